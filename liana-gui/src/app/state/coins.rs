@@ -4,7 +4,14 @@ use std::{cmp::Ordering, collections::HashSet};
 
 use iced::Task;
 
-use liana_ui::widget::Element;
+use liana::{
+    miniscript::bitcoin::{OutPoint, Txid},
+    spend::{Feerate, SpendCreationError, MAX_FEERATE, MIN_FEERATE},
+};
+use liana_ui::{
+    component::{form, modal::Modal},
+    widget::Element,
+};
 use lianad::commands::CoinStatus;
 
 use crate::daemon::model::LabelsLoader;
@@ -19,7 +26,7 @@ use crate::{
         wallet::Wallet,
     },
     daemon::{
-        model::{Coin, LabelItem, Labelled},
+        model::{Coin, CreateSpendResult, FeeEstimate, LabelItem, Labelled},
         Daemon,
     },
 };
@@ -52,6 +59,7 @@ pub struct CoinsPanel {
     warning: Option<Error>,
     /// timelock value to pass for the heir to consume a coin.
     timelock: u16,
+    cpfp_modal: Option<CreateCpfpModal>,
 }
 
 impl CoinsPanel {
@@ -62,6 +70,7 @@ impl CoinsPanel {
             selected: Vec::new(),
             warning: None,
             timelock,
+            cpfp_modal: None,
         };
         panel.update_coins(coins);
         panel
@@ -93,7 +102,7 @@ impl CoinsPanel {
 
 impl State for CoinsPanel {
     fn view<'a>(&'a self, cache: &'a Cache) -> Element<'a, view::Message> {
-        view::dashboard(
+        let content = view::dashboard(
             &Menu::Coins,
             cache,
             self.warning.as_ref(),
@@ -105,7 +114,12 @@ impl State for CoinsPanel {
                 &self.coins.labels,
                 self.labels_edited.cache(),
             ),
-        )
+        );
+        if let Some(modal) = &self.cpfp_modal {
+            modal.view(content)
+        } else {
+            content
+        }
     }
 
     fn update(
@@ -148,7 +162,27 @@ impl State for CoinsPanel {
                     self.selected.push(i);
                 }
             }
-            _ => {}
+            Message::View(view::Message::CreateCpfp(view::CreateCpfpMessage::New(outpoint))) => {
+                self.cpfp_modal = Some(CreateCpfpModal::new(outpoint));
+                return Task::perform(
+                    async move {
+                        daemon
+                            .estimate_fees()
+                            .await
+                            .map(|res| res.estimates)
+                            .map_err(|e| e.into())
+                    },
+                    Message::FeeEstimates,
+                );
+            }
+            Message::View(view::Message::CreateCpfp(view::CreateCpfpMessage::Cancel)) => {
+                self.cpfp_modal = None;
+            }
+            _ => {
+                if let Some(modal) = &mut self.cpfp_modal {
+                    return modal.update(daemon, message);
+                }
+            }
         };
         Task::none()
     }
@@ -158,6 +192,7 @@ impl State for CoinsPanel {
         daemon: Arc<dyn Daemon + Sync + Send>,
         _wallet: Arc<Wallet>,
     ) -> Task<Message> {
+        self.cpfp_modal = None;
         let daemon1 = daemon.clone();
         let daemon2 = daemon.clone();
         Task::batch(vec![
@@ -198,6 +233,109 @@ impl From<CoinsPanel> for Box<dyn State> {
     }
 }
 
+#[derive(Debug)]
+pub struct CreateCpfpModal {
+    /// Unconfirmed coin to spend.
+    outpoint: OutPoint,
+    /// Feerate form value.
+    feerate_val: form::Value<String>,
+    /// Parsed feerate.
+    feerate: Option<Feerate>,
+    /// Estimated feerates the user can pick from.
+    fee_estimates: Vec<FeeEstimate>,
+    /// Child transaction ID.
+    cpfp_txid: Option<Txid>,
+
+    processing: bool,
+    warning: Option<Error>,
+}
+
+impl CreateCpfpModal {
+    fn new(outpoint: OutPoint) -> Self {
+        Self {
+            outpoint,
+            feerate_val: form::Value::default(),
+            feerate: None,
+            fee_estimates: Vec::new(),
+            cpfp_txid: None,
+            processing: false,
+            warning: None,
+        }
+    }
+
+    fn update(&mut self, daemon: Arc<dyn Daemon + Sync + Send>, message: Message) -> Task<Message> {
+        match message {
+            Message::View(view::Message::CreateCpfp(view::CreateCpfpMessage::FeerateEdited(s))) => {
+                self.warning = None;
+                self.feerate = s
+                    .parse::<Feerate>()
+                    .ok()
+                    .filter(|value| *value >= MIN_FEERATE && *value <= MAX_FEERATE);
+                self.feerate_val.valid = self.feerate.is_some();
+                self.feerate_val.value = s; // save form value even if it cannot be parsed
+            }
+            Message::FeeEstimates(res) => match res {
+                Ok(estimates) => self.fee_estimates = estimates,
+                Err(e) => tracing::warn!("Failed to get fee estimates: {}", e),
+            },
+            Message::CpfpPsbt(res) => {
+                self.processing = false;
+                match res {
+                    Ok(txid) => self.cpfp_txid = Some(txid),
+                    Err(e) => self.warning = Some(e),
+                }
+            }
+            Message::View(view::Message::CreateCpfp(view::CreateCpfpMessage::Confirm)) => {
+                if let Some(feerate) = self.feerate {
+                    self.warning = None;
+                    self.processing = true;
+                    return Task::perform(cpfp(daemon, self.outpoint, feerate), Message::CpfpPsbt);
+                }
+            }
+            _ => {}
+        }
+        Task::none()
+    }
+
+    fn view<'a>(&'a self, content: Element<'a, view::Message>) -> Element<'a, view::Message> {
+        let modal = Modal::new(
+            content,
+            view::coins::create_cpfp_modal(
+                &self.feerate_val,
+                &self.fee_estimates,
+                self.cpfp_txid,
+                self.warning.as_ref(),
+            ),
+        );
+        if self.processing {
+            modal
+        } else {
+            modal.on_blur(Some(view::Message::CreateCpfp(
+                view::CreateCpfpMessage::Cancel,
+            )))
+        }
+        .into()
+    }
+}
+
+async fn cpfp(
+    daemon: Arc<dyn Daemon + Sync + Send>,
+    outpoint: OutPoint,
+    feerate: Feerate,
+) -> Result<Txid, Error> {
+    let psbt = match daemon.create_cpfp(&outpoint, &[], feerate).await? {
+        CreateSpendResult::Success { psbt, .. } => psbt,
+        CreateSpendResult::InsufficientFunds { missing } => {
+            return Err(
+                SpendCreationError::CoinSelection(liana::spend::InsufficientFunds { missing })
+                    .into(),
+            );
+        }
+    };
+    daemon.update_spend_tx(&psbt).await?;
+    Ok(psbt.unsigned_tx.compute_txid())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
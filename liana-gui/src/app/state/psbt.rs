@@ -1,6 +1,5 @@
 use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
-use std::str::FromStr;
 use std::sync::Arc;
 
 use iced::Subscription;
@@ -9,6 +8,7 @@ use iced::Task;
 use liana::{
     descriptors::LianaPolicy,
     miniscript::bitcoin::{bip32::Fingerprint, psbt::Psbt, Network, Txid},
+    psbt_v2,
 };
 use lianad::commands::CoinStatus;
 
@@ -691,7 +691,8 @@ impl Action for UpdateAction {
                     Ok(()) => {
                         self.success = true;
                         self.error = None;
-                        let psbt = Psbt::from_str(&self.updated.value).expect("Already checked");
+                        let psbt =
+                            psbt_v2::from_base64(&self.updated.value).expect("Already checked");
                         merge_signatures(&mut tx.psbt, &psbt);
                         tx.sigs = self
                             .wallet
@@ -704,7 +705,7 @@ impl Action for UpdateAction {
             }
             Message::View(view::Message::ImportSpend(view::ImportSpendMessage::PsbtEdited(s))) => {
                 self.updated.value = s;
-                if let Ok(psbt) = Psbt::from_str(&self.updated.value) {
+                if let Ok(psbt) = psbt_v2::from_base64(&self.updated.value) {
                     self.updated.valid =
                         tx.psbt.unsigned_tx.compute_txid() == psbt.unsigned_tx.compute_txid();
                 } else {
@@ -714,7 +715,7 @@ impl Action for UpdateAction {
             Message::View(view::Message::ImportSpend(view::ImportSpendMessage::Confirm)) => {
                 self.processing = true;
                 self.error = None;
-                if let Ok(updated) = Psbt::from_str(&self.updated.value) {
+                if let Ok(updated) = psbt_v2::from_base64(&self.updated.value) {
                     return Task::perform(
                         async move { daemon.update_spend_tx(&updated).await.map_err(|e| e.into()) },
                         Message::Updated,
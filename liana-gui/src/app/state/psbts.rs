@@ -1,9 +1,8 @@
-use std::str::FromStr;
 use std::sync::Arc;
 
 use iced::{Subscription, Task};
 
-use liana::miniscript::bitcoin::psbt::Psbt;
+use liana::psbt_v2;
 use liana_ui::{
     component::{form, modal},
     widget::Element,
@@ -206,20 +205,34 @@ impl ImportPsbtModal {
             }
             Message::View(view::Message::ImportSpend(view::ImportSpendMessage::PsbtEdited(s))) => {
                 self.imported.value = s;
-                self.imported.valid = Psbt::from_str(&self.imported.value).ok().is_some();
+                self.imported.valid = psbt_v2::from_base64(&self.imported.value).ok().is_some();
             }
+            Message::View(view::Message::ImportSpend(view::ImportSpendMessage::ImportFile)) => {
+                return Task::perform(import_psbt_file(), |res| {
+                    Message::View(view::Message::ImportSpend(
+                        view::ImportSpendMessage::FileImported(res),
+                    ))
+                });
+            }
+            Message::View(view::Message::ImportSpend(view::ImportSpendMessage::FileImported(
+                res,
+            ))) => match res {
+                Some(Ok(psbt)) => {
+                    self.imported.value = psbt;
+                    self.imported.valid = true;
+                    self.error = None;
+                }
+                Some(Err(e)) => self.error = Some(Error::Unexpected(e)),
+                None => {}
+            },
             Message::View(view::Message::ImportSpend(view::ImportSpendMessage::Confirm)) => {
                 if self.imported.valid {
                     self.processing = true;
                     self.error = None;
-                    let imported = Psbt::from_str(&self.imported.value).expect("Already checked");
+                    let imported =
+                        psbt_v2::from_base64(&self.imported.value).expect("Already checked");
                     return Task::perform(
-                        async move {
-                            daemon
-                                .update_spend_tx(&imported)
-                                .await
-                                .map_err(|e| e.into())
-                        },
+                        async move { daemon.import_psbt(&imported).await.map_err(|e| e.into()) },
                         Message::Updated,
                     );
                 }
@@ -230,3 +243,22 @@ impl ImportPsbtModal {
         Task::none()
     }
 }
+
+// Ask the user for a PSBT file, either binary or base64-encoded, and read it as a base64 string.
+async fn import_psbt_file() -> Option<Result<String, String>> {
+    let file = rfd::AsyncFileDialog::new()
+        .set_title("Choose a PSBT file to import...")
+        .add_filter("PSBT", &["psbt", "txt"])
+        .pick_file()
+        .await?;
+    let content = file.read().await;
+    let psbt = psbt_v2::deserialize(&content).or_else(|_| {
+        std::str::from_utf8(&content)
+            .map_err(|_| ())
+            .and_then(|s| psbt_v2::from_base64(s.trim()).map_err(|_| ()))
+    });
+    Some(
+        psbt.map(|psbt| psbt.to_string())
+            .map_err(|_| "The file does not contain a valid PSBT.".to_string()),
+    )
+}
@@ -1,12 +1,17 @@
 use std::collections::HashSet;
 use std::str::FromStr;
 use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use iced::Task;
 
 use liana::miniscript::bitcoin::{
     bip32::{DerivationPath, Fingerprint},
-    secp256k1,
+    secp256k1, Sequence,
+};
+use liana::{
+    descriptors::PathCondition,
+    spend::{Feerate, MIN_FEERATE},
 };
 use liana_ui::{component::form, widget::Element};
 use lianad::commands::CoinStatus;
@@ -19,7 +24,7 @@ use crate::{
         message::Message,
         state::psbt,
         state::{redirect, State},
-        view,
+        view::{self, recovery::RecoveryTimelock},
         wallet::Wallet,
     },
     daemon::{
@@ -80,6 +85,7 @@ impl State for RecoveryPanel {
                                 &path.origins,
                                 path.total_amount,
                                 path.number_of_coins,
+                                path.timelock,
                                 &self.wallet.keys_aliases,
                                 self.selected_path == Some(i),
                             ))
@@ -140,23 +146,42 @@ impl State for RecoveryPanel {
                 }
                 view::Message::CreateSpend(view::CreateSpendMessage::FeerateEdited(feerate)) => {
                     self.feerate.value = feerate;
-                    self.feerate.valid =
-                        self.feerate.value.parse::<u64>().is_ok() && self.feerate.value != "0";
+                    self.feerate.valid = self
+                        .feerate
+                        .value
+                        .parse::<Feerate>()
+                        .is_ok_and(|feerate| feerate >= MIN_FEERATE);
                 }
                 view::Message::Next => {
                     let address = Address::from_str(&self.recipient.value).expect("Checked before");
-                    let feerate_vb = self.feerate.value.parse::<u64>().expect("Checked before");
+                    let feerate = self
+                        .feerate
+                        .value
+                        .parse::<Feerate>()
+                        .expect("Checked before");
                     self.warning = None;
                     let desc = self.wallet.main_descriptor.clone();
-                    let sequence = self
+                    let (sequence, absolute_timelock) = match self
                         .recovery_paths
                         .get(self.selected_path.expect("A path must be selected"))
-                        .map(|p| p.sequence);
+                        .map(|p| p.timelock)
+                    {
+                        Some(RecoveryTimelock::Relative(sequence)) => (
+                            Some(Sequence::from_height(sequence).to_consensus_u32()),
+                            None,
+                        ),
+                        Some(RecoveryTimelock::RelativeTime(intervals)) => (
+                            Some(Sequence::from_512_second_intervals(intervals).to_consensus_u32()),
+                            None,
+                        ),
+                        Some(RecoveryTimelock::Absolute(tl)) => (None, Some(tl.to_consensus_u32())),
+                        None => (None, None),
+                    };
                     let network = cache.network;
                     return Task::perform(
                         async move {
                             let psbt = daemon
-                                .create_recovery(address, feerate_vb, sequence)
+                                .create_recovery(address, feerate, sequence, absolute_timelock)
                                 .await?;
                             let outpoints: Vec<_> = psbt
                                 .unsigned_tx
@@ -228,24 +253,60 @@ impl From<RecoveryPanel> for Box<dyn State> {
 
 pub struct RecoveryPath {
     threshold: usize,
-    sequence: u16,
+    timelock: RecoveryTimelock,
     origins: Vec<(Fingerprint, HashSet<DerivationPath>)>,
     total_amount: Amount,
     number_of_coins: usize,
 }
 
 fn recovery_paths(wallet: &Wallet, coins: &[Coin], blockheight: i32) -> Vec<RecoveryPath> {
-    wallet
-        .main_descriptor
-        .policy()
-        .recovery_paths()
+    let policy = wallet.main_descriptor.policy();
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    policy
+        .conditional_paths()
         .iter()
-        .map(|(&sequence, path)| {
+        .filter_map(|(cond, path)| match *cond {
+            PathCondition::RelativeBlocks(sequence) => {
+                Some((RecoveryTimelock::Relative(sequence), path))
+            }
+            PathCondition::RelativeTime(intervals) => {
+                Some((RecoveryTimelock::RelativeTime(intervals), path))
+            }
+            PathCondition::Absolute(tl) => Some((RecoveryTimelock::Absolute(tl), path)),
+            PathCondition::HashLock(_) => None,
+        })
+        .map(|(timelock, path)| {
             let (number_of_coins, total_amount) = coins
                 .iter()
                 .filter(|coin| {
                     coin.spend_info.is_none()
-                        && remaining_sequence(coin, blockheight as u32, sequence) <= 1
+                        && match timelock {
+                            RecoveryTimelock::Relative(sequence) => {
+                                remaining_sequence(coin, blockheight as u32, sequence) <= 1
+                            }
+                            // We don't have the confirmation time of the coins, estimate it from
+                            // the number of confirmations. The daemon checks it precisely.
+                            RecoveryTimelock::RelativeTime(intervals) => {
+                                coin.block_height
+                                    .map(|h| (blockheight - h + 1) as i64 * 600)
+                                    .unwrap_or(0)
+                                    >= intervals as i64 * 512
+                            }
+                            // The daemon checks a timestamp against the median time past of the
+                            // tip, which lags behind the current time. It will error if we are
+                            // too early.
+                            RecoveryTimelock::Absolute(tl) => {
+                                coin.block_height.is_some()
+                                    && if tl.is_block_height() {
+                                        tl.to_consensus_u32() as i64 <= blockheight as i64
+                                    } else {
+                                        (tl.to_consensus_u32() as u64) < now
+                                    }
+                            }
+                        }
                 })
                 .fold(
                     (0, Amount::from_sat(0)),
@@ -258,7 +319,7 @@ fn recovery_paths(wallet: &Wallet, coins: &[Coin], blockheight: i32) -> Vec<Reco
             RecoveryPath {
                 total_amount,
                 number_of_coins,
-                sequence,
+                timelock,
                 threshold,
                 origins: origins.into_iter().collect(),
             }
@@ -1,6 +1,6 @@
 use std::collections::HashSet;
 use std::convert::From;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use iced::{Subscription, Task};
@@ -8,6 +8,7 @@ use iced::{Subscription, Task};
 use liana::{
     descriptors::LianaDescriptor,
     miniscript::bitcoin::{bip32::Fingerprint, Network},
+    signer::HotSigner,
 };
 
 use liana_ui::{
@@ -32,6 +33,7 @@ pub struct WalletSettingsState {
     modal: Option<RegisterWalletModal>,
     processing: bool,
     updated: bool,
+    hot_signer_password: String,
 }
 
 impl WalletSettingsState {
@@ -45,6 +47,7 @@ impl WalletSettingsState {
             modal: None,
             processing: false,
             updated: false,
+            hot_signer_password: String::new(),
         }
     }
 
@@ -84,6 +87,8 @@ impl State for WalletSettingsState {
             &self.keys_aliases,
             self.processing,
             self.updated,
+            self.wallet.signer.as_ref().map(|s| s.fingerprint),
+            &self.hot_signer_password,
         );
         if let Some(m) = &self.modal {
             modal::Modal::new(content, m.view())
@@ -116,6 +121,7 @@ impl State for WalletSettingsState {
                 } else {
                     match res {
                         Ok(wallet) => {
+                            self.hot_signer_password = String::new();
                             self.keys_aliases = Self::keys_aliases(&wallet);
                             self.wallet = wallet;
                             self.updated = true;
@@ -155,6 +161,44 @@ impl State for WalletSettingsState {
                     Message::WalletUpdated,
                 )
             }
+            Message::View(view::Message::Settings(
+                view::SettingsMessage::HotSignerPasswordEdited(password),
+            )) => {
+                self.hot_signer_password = password;
+                Task::none()
+            }
+            Message::View(view::Message::Settings(view::SettingsMessage::UnlockHotSigner)) => {
+                self.processing = true;
+                self.updated = false;
+                let (data_dir, network, wallet, password) = (
+                    self.data_dir.clone(),
+                    cache.network,
+                    self.wallet.clone(),
+                    self.hot_signer_password.clone(),
+                );
+                Task::perform(
+                    async move { unlock_hot_signer(&data_dir, network, &wallet, &password) },
+                    Message::WalletUpdated,
+                )
+            }
+            Message::View(view::Message::Settings(view::SettingsMessage::EncryptHotSigners)) => {
+                self.processing = true;
+                self.updated = false;
+                let (data_dir, network, wallet, password) = (
+                    self.data_dir.clone(),
+                    cache.network,
+                    self.wallet.clone(),
+                    self.hot_signer_password.clone(),
+                );
+                Task::perform(
+                    async move {
+                        HotSigner::encrypt_datadir(&data_dir, network, &password)
+                            .map_err(|e| Error::Unexpected(e.to_string()))?;
+                        unlock_hot_signer(&data_dir, network, &wallet, &password)
+                    },
+                    Message::WalletUpdated,
+                )
+            }
             Message::View(view::Message::Close) => {
                 self.modal = None;
                 Task::none()
@@ -369,6 +413,19 @@ async fn register_wallet(
     Ok(wallet)
 }
 
+// Load the hot signer of the wallet, decrypting its mnemonic with the given password.
+fn unlock_hot_signer(
+    data_dir: &Path,
+    network: Network,
+    wallet: &Wallet,
+    password: &str,
+) -> Result<Arc<Wallet>, Error> {
+    let wallet = wallet
+        .clone()
+        .load_hotsigners_with_password(data_dir, network, Some(password))?;
+    Ok(Arc::new(wallet))
+}
+
 async fn update_keys_aliases(
     data_dir: PathBuf,
     network: Network,
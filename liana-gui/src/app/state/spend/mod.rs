@@ -124,6 +124,7 @@ impl State for CreateSpendPanel {
     ) -> Task<Message> {
         let daemon1 = daemon.clone();
         let daemon2 = daemon.clone();
+        let daemon3 = daemon.clone();
         Task::batch(vec![
             Task::perform(
                 async move {
@@ -151,6 +152,16 @@ impl State for CreateSpendPanel {
                 },
                 Message::Labels,
             ),
+            Task::perform(
+                async move {
+                    daemon3
+                        .estimate_fees()
+                        .await
+                        .map(|res| res.estimates)
+                        .map_err(|e| e.into())
+                },
+                Message::FeeEstimates,
+            ),
         ])
     }
 }
@@ -8,20 +8,25 @@ use std::{
 
 use iced::{Subscription, Task};
 use liana::{
+    batch,
     descriptors::LianaDescriptor,
     miniscript::bitcoin::{
-        address, psbt::Psbt, secp256k1, Address, Amount, Denomination, Network, OutPoint,
+        hex::FromHex, psbt::Psbt, secp256k1, Address, Amount, Denomination, Network, OutPoint,
+        ScriptBuf,
+    },
+    spend::{
+        Feerate, SpendCreationError, SpendDestination, MAX_DATA_CARRIER_SIZE, MAX_FEERATE,
+        MIN_FEERATE,
     },
-    spend::{SpendCreationError, MAX_FEERATE},
 };
-use lianad::commands::ListCoinsEntry;
+use lianad::commands::{Destination, ListCoinsEntry};
 
 use liana_ui::{component::form, widget::Element};
 
 use crate::{
     app::{cache::Cache, error::Error, message::Message, state::psbt, view, wallet::Wallet},
     daemon::{
-        model::{coin_is_owned, remaining_sequence, Coin, CreateSpendResult, SpendTx},
+        model::{coin_is_owned, remaining_sequence, Coin, CreateSpendResult, FeeEstimate, SpendTx},
         Daemon,
     },
 };
@@ -88,6 +93,10 @@ pub struct DefineSpend {
     batch_label: form::Value<String>,
     amount_left_to_select: Option<Amount>,
     feerate: form::Value<String>,
+    /// Feerates the user can pick from, as estimated by the Bitcoin backend.
+    fee_estimates: Vec<FeeEstimate>,
+    /// Data to embed in the transaction in a data-carrier output, as hex.
+    data: form::Value<String>,
     generated: Option<(Psbt, Vec<String>)>,
     warning: Option<Error>,
 }
@@ -125,6 +134,8 @@ impl DefineSpend {
             is_valid: false,
             is_duplicate: false,
             feerate: form::Value::default(),
+            fee_estimates: Vec::new(),
+            data: form::Value::default(),
             amount_left_to_select: None,
             warning: None,
         }
@@ -171,9 +182,20 @@ impl DefineSpend {
         self.feerate.valid
             && !self.feerate.value.is_empty()
             && (self.batch_label.valid || self.recipients.len() < 2)
-            // Recipients will be empty for self-send.
+            && self.data.valid
+            // Recipients will be empty for self-send. Only an address can receive the max amount.
             && self.recipients.iter().enumerate().all(|(i, r)|
-            r.valid() || (is_redraft && self.send_max_to_recipient == Some(i) && r.address_valid()))
+            (self.send_max_to_recipient != Some(i) || r.is_address())
+            && (r.valid() || (is_redraft && self.send_max_to_recipient == Some(i) && r.address_valid())))
+    }
+
+    // The data-carrier output to add to the transaction, if any.
+    fn data_destination(&self) -> Option<(Destination, u64)> {
+        if self.data.value.is_empty() {
+            return None;
+        }
+        let data = Vec::from_hex(&self.data.value).expect("Checked before");
+        Some((Destination::Data(data), 0))
     }
 
     fn exists_duplicate(&self) -> bool {
@@ -221,7 +243,7 @@ impl DefineSpend {
             return;
         }
 
-        let destinations: HashMap<Address<address::NetworkUnchecked>, u64> = self
+        let destinations: HashMap<Destination, u64> = self
             .recipients
             .iter()
             .enumerate()
@@ -235,11 +257,12 @@ impl DefineSpend {
                     None
                 } else {
                     Some((
-                        Address::from_str(&recipient.address.value).expect("Checked before"),
+                        recipient.destination().expect("Checked before"),
                         recipient.amount().expect("Checked before"),
                     ))
                 }
             })
+            .chain(self.data_destination())
             .collect();
 
         let recipient_with_max = if let Some(i) = self.send_max_to_recipient {
@@ -310,13 +333,17 @@ impl DefineSpend {
                 .clone()
         };
 
-        let feerate_vb = self.feerate.value.parse::<u64>().expect("Checked before");
+        let feerate = self
+            .feerate
+            .value
+            .parse::<Feerate>()
+            .expect("Checked before");
         match tokio::runtime::Handle::current().block_on(async {
             daemon
                 .create_spend_tx(
                     &outpoints,
                     &destinations,
-                    feerate_vb,
+                    feerate,
                     Some(change_address.clone()),
                 )
                 .await
@@ -423,6 +450,45 @@ impl Step for DefineSpend {
                     view::CreateSpendMessage::AddRecipient => {
                         self.recipients.push(Recipient::default());
                     }
+                    view::CreateSpendMessage::ImportRecipients => {
+                        let network = self.network;
+                        return Task::perform(import_recipients(network), |res| {
+                            Message::View(view::Message::CreateSpend(
+                                view::CreateSpendMessage::RecipientsImported(res),
+                            ))
+                        });
+                    }
+                    view::CreateSpendMessage::RecipientsImported(res) => match res {
+                        Some(Ok(payments)) => {
+                            // The imported payments replace the recipients left empty.
+                            self.recipients.retain(|r| {
+                                !r.address.value.is_empty() || !r.amount.value.is_empty()
+                            });
+                            self.send_max_to_recipient = self
+                                .send_max_to_recipient
+                                .filter(|i| *i < self.recipients.len());
+                            for payment in payments {
+                                let mut recipient = Recipient::default();
+                                let i = self.recipients.len();
+                                for (field, value) in [
+                                    ("address", payment.address.to_string()),
+                                    ("amount", payment.amount.to_string_in(Denomination::Bitcoin)),
+                                    ("label", payment.label.unwrap_or_default()),
+                                ] {
+                                    recipient.update(
+                                        self.network,
+                                        view::CreateSpendMessage::RecipientEdited(i, field, value),
+                                    );
+                                }
+                                self.recipients.push(recipient);
+                            }
+                            self.warning = None;
+                        }
+                        Some(Err(e)) => {
+                            self.warning = Some(Error::Unexpected(e));
+                        }
+                        None => {}
+                    },
                     view::CreateSpendMessage::DeleteRecipient(i) => {
                         self.recipients.remove(i);
                         if self.recipients.len() < 2 {
@@ -452,9 +518,9 @@ impl Step for DefineSpend {
                     }
 
                     view::CreateSpendMessage::FeerateEdited(s) => {
-                        if let Ok(value) = s.parse::<u64>() {
+                        if let Ok(value) = s.parse::<Feerate>() {
                             self.feerate.value = s;
-                            self.feerate.valid = value != 0 && value <= MAX_FEERATE;
+                            self.feerate.valid = (MIN_FEERATE..=MAX_FEERATE).contains(&value);
                         } else if s.is_empty() {
                             self.feerate.value = "".to_string();
                             self.feerate.valid = true;
@@ -463,6 +529,16 @@ impl Step for DefineSpend {
                         }
                         self.warning = None;
                     }
+                    view::CreateSpendMessage::DataEdited(s) => {
+                        self.data.valid = s.is_empty()
+                            || Vec::from_hex(&s)
+                                .ok()
+                                .and_then(|data| SpendDestination::data(&data).ok())
+                                .map(|dest| dest.script_pubkey().len() <= MAX_DATA_CARRIER_SIZE)
+                                .unwrap_or(false);
+                        self.data.value = s;
+                        self.warning = None;
+                    }
                     view::CreateSpendMessage::Generate => {
                         let inputs: Vec<OutPoint> = self
                             .coins
@@ -477,25 +553,24 @@ impl Step for DefineSpend {
                                 },
                             )
                             .collect();
-                        let mut outputs: HashMap<Address<address::NetworkUnchecked>, u64> =
-                            HashMap::new();
+                        let mut outputs: HashMap<Destination, u64> = HashMap::new();
                         for recipient in &self.recipients {
                             outputs.insert(
-                                Address::from_str(&recipient.address.value)
-                                    .expect("Checked before"),
+                                recipient.destination().expect("Checked before"),
                                 recipient.amount().expect("Checked before"),
                             );
                         }
-                        let feerate_vb = self.feerate.value.parse::<u64>().unwrap_or(0);
+                        outputs.extend(self.data_destination());
+                        let feerate = self.feerate.value.parse::<Feerate>().unwrap_or_default();
                         self.warning = None;
                         return Task::perform(
                             async move {
                                 daemon
-                                    .create_spend_tx(&inputs, &outputs, feerate_vb, None)
+                                    .create_spend_tx(&inputs, &outputs, feerate, None)
                                     .await
                                     .map_err(|e| e.into())
                                     .and_then(|res| match res {
-                                        CreateSpendResult::Success { psbt, warnings } => {
+                                        CreateSpendResult::Success { psbt, warnings, .. } => {
                                             Ok((psbt, warnings))
                                         }
                                         CreateSpendResult::InsufficientFunds { missing } => {
@@ -550,6 +625,13 @@ impl Step for DefineSpend {
                 }
                 Err(e) => self.warning = Some(e),
             },
+            Message::FeeEstimates(res) => match res {
+                Ok(estimates) => {
+                    self.fee_estimates = estimates;
+                }
+                // The estimates are only a convenience, the user can still set a feerate.
+                Err(e) => tracing::warn!("Failed to get fee estimates: {}", e),
+            },
             Message::Coins(res) => match res {
                 Ok(coins) => {
                     let selected: HashSet<OutPoint> =
@@ -600,10 +682,7 @@ impl Step for DefineSpend {
                     .iter()
                     .find(|recipient| {
                         !recipient.label.value.is_empty()
-                            && Address::from_str(&recipient.address.value)
-                                .unwrap()
-                                .assume_checked()
-                                .matches_script_pubkey(&output.script_pubkey)
+                            && recipient.script_pubkey().as_ref() == Some(&output.script_pubkey)
                             && output.value.to_sat() == recipient.amount().unwrap()
                     })
                     .map(|recipient| recipient.label.value.to_string())
@@ -646,11 +725,27 @@ impl Step for DefineSpend {
             &self.batch_label,
             self.amount_left_to_select.as_ref(),
             &self.feerate,
+            &self.fee_estimates,
+            &self.data,
             self.warning.as_ref(),
         )
     }
 }
 
+// Ask the user for a CSV or JSON file of payments and read them from it.
+async fn import_recipients(network: Network) -> Option<Result<Vec<batch::Payment>, String>> {
+    let file = rfd::AsyncFileDialog::new()
+        .set_title("Choose a file with the payments to import...")
+        .add_filter("Payments", &["csv", "json"])
+        .pick_file()
+        .await?;
+    let content = match String::from_utf8(file.read().await) {
+        Ok(content) => content,
+        Err(_) => return Some(Err("The payments file is not valid UTF-8.".to_string())),
+    };
+    Some(batch::parse_batch(&content, network).map_err(|e| e.to_string()))
+}
+
 #[derive(Default, Clone)]
 struct Recipient {
     label: form::Value<String>,
@@ -659,6 +754,27 @@ struct Recipient {
 }
 
 impl Recipient {
+    // The destination of this payment: either an address or a raw script. Data-carrier outputs
+    // are set separately.
+    fn destination(&self) -> Option<Destination> {
+        match Destination::from_str(&self.address.value) {
+            Ok(Destination::Data(_)) | Err(_) => None,
+            Ok(destination) => Some(destination),
+        }
+    }
+
+    fn is_address(&self) -> bool {
+        matches!(self.destination(), Some(Destination::Address(_)))
+    }
+
+    fn script_pubkey(&self) -> Option<ScriptBuf> {
+        match self.destination()? {
+            Destination::Address(address) => Some(address.assume_checked().script_pubkey()),
+            Destination::Script(script) => Some(script),
+            Destination::Data(_) => None,
+        }
+    }
+
     fn amount(&self) -> Result<u64, Error> {
         if self.amount.value.is_empty() {
             return Err(Error::Unexpected("Amount should be non-zero".to_string()));
@@ -671,12 +787,15 @@ impl Recipient {
             return Err(Error::Unexpected("Amount should be non-zero".to_string()));
         }
 
-        if amount.to_sat() < DUST_OUTPUT_SATS {
+        // Raw scripts are only subject to the dust threshold of the network.
+        if amount.to_sat() < DUST_OUTPUT_SATS
+            && !matches!(self.destination(), Some(Destination::Script(_)))
+        {
             return Err(Error::Unexpected("Amount should be non-zero".to_string()));
         }
 
-        if let Ok(address) = Address::from_str(&self.address.value) {
-            if amount <= address.assume_checked().script_pubkey().minimal_non_dust() {
+        if let Some(script_pubkey) = self.script_pubkey() {
+            if amount <= script_pubkey.minimal_non_dust() {
                 return Err(Error::Unexpected(
                     "Amount must be superior to script dust value".to_string(),
                 ));
@@ -701,8 +820,11 @@ impl Recipient {
         match message {
             view::CreateSpendMessage::RecipientEdited(_, "address", address) => {
                 self.address.value = address;
-                if let Ok(address) = Address::from_str(&self.address.value) {
-                    self.address.valid = address.is_valid_for_network(network);
+                if let Some(destination) = self.destination() {
+                    self.address.valid = match destination {
+                        Destination::Address(address) => address.is_valid_for_network(network),
+                        _ => true,
+                    };
                     if !self.amount.value.is_empty() {
                         self.amount.valid = self.amount().is_ok();
                     }
@@ -8,7 +8,7 @@ use std::{
 use iced::Task;
 use liana::{
     miniscript::bitcoin::{OutPoint, Txid},
-    spend::{SpendCreationError, MAX_FEERATE},
+    spend::{Feerate, SpendCreationError, MAX_FEERATE},
 };
 use liana_ui::{
     component::{form, modal::Modal},
@@ -32,7 +32,7 @@ use crate::{
 };
 
 use crate::daemon::{
-    model::{CreateSpendResult, HistoryTransaction, LabelItem, Labelled},
+    model::{CreateSpendResult, FeeEstimate, HistoryTransaction, LabelItem, Labelled},
     Daemon,
 };
 
@@ -148,6 +148,19 @@ impl State for TransactionsPanel {
                 Ok(descendant_txids) => {
                     let modal = CreateRbfModal::new(*tx, is_cancel, descendant_txids);
                     self.modal = TransactionsModal::CreateRbf(modal);
+                    // For cancel, we let `rbfpsbt` set the feerate.
+                    if !is_cancel {
+                        return Task::perform(
+                            async move {
+                                daemon
+                                    .estimate_fees()
+                                    .await
+                                    .map(|res| res.estimates)
+                                    .map_err(|e| e.into())
+                            },
+                            Message::FeeEstimates,
+                        );
+                    }
                 }
                 Err(e) => {
                     self.warning = e.into();
@@ -342,14 +355,16 @@ pub struct CreateRbfModal {
     /// Whether to cancel or bump fee.
     is_cancel: bool,
     /// Min feerate required for RBF.
-    min_feerate_vb: u64,
+    min_feerate: Feerate,
     /// IDs of any transactions from this wallet that are direct descendants of
     /// the transaction to be replaced.
     descendant_txids: HashSet<Txid>,
     /// Feerate form value.
     feerate_val: form::Value<String>,
     /// Parsed feerate.
-    feerate_vb: Option<u64>,
+    feerate: Option<Feerate>,
+    /// Estimated feerates above the minimum, the user can pick from.
+    fee_estimates: Vec<FeeEstimate>,
     /// Replacement transaction ID.
     replacement_txid: Option<Txid>,
 
@@ -363,28 +378,26 @@ impl CreateRbfModal {
         is_cancel: bool,
         descendant_txids: HashSet<Txid>,
     ) -> Self {
-        let prev_feerate_vb = tx
-            .fee_amount
-            .expect("rbf should only be used on a transaction with fee amount set")
-            .to_sat()
-            .checked_div(tx.tx.vsize().try_into().expect("vsize must fit in u64"))
-            .expect("transaction vsize must be positive");
-        let min_feerate_vb = prev_feerate_vb.checked_add(1).unwrap();
+        let prev_feerate = Feerate::from_fee(
+            tx.fee_amount
+                .expect("rbf should only be used on a transaction with fee amount set")
+                .to_sat(),
+            tx.tx.vsize().try_into().expect("vsize must fit in u64"),
+        )
+        .expect("transaction vsize must be positive");
+        let min_feerate = prev_feerate.saturating_add_sat_per_vb(1);
         Self {
             tx,
             is_cancel,
-            min_feerate_vb,
+            min_feerate,
             descendant_txids,
             feerate_val: form::Value {
                 valid: true,
-                value: min_feerate_vb.to_string(),
+                value: min_feerate.to_string(),
             },
             // For cancel, we let `rbfpsbt` set the feerate.
-            feerate_vb: if is_cancel {
-                None
-            } else {
-                Some(min_feerate_vb)
-            },
+            feerate: if is_cancel { None } else { Some(min_feerate) },
+            fee_estimates: Vec::new(),
             replacement_txid: None,
             warning: None,
             processing: false,
@@ -400,19 +413,28 @@ impl CreateRbfModal {
         match message {
             Message::View(view::Message::CreateRbf(view::CreateRbfMessage::FeerateEdited(s))) => {
                 self.warning = None;
-                if let Ok(value) = s.parse::<u64>() {
-                    self.feerate_val.valid = value >= self.min_feerate_vb && value <= MAX_FEERATE;
+                if let Ok(value) = s.parse::<Feerate>() {
+                    self.feerate_val.valid = value >= self.min_feerate && value <= MAX_FEERATE;
                     if self.feerate_val.valid {
-                        self.feerate_vb = Some(value);
+                        self.feerate = Some(value);
                     }
                 } else {
                     self.feerate_val.valid = false;
                 }
                 if !self.feerate_val.valid {
-                    self.feerate_vb = None;
+                    self.feerate = None;
                 }
                 self.feerate_val.value = s; // save form value even if it cannot be parsed
             }
+            Message::FeeEstimates(res) => match res {
+                Ok(estimates) => {
+                    self.fee_estimates = estimates
+                        .into_iter()
+                        .filter(|estimate| estimate.feerate >= self.min_feerate)
+                        .collect();
+                }
+                Err(e) => tracing::warn!("Failed to get fee estimates: {}", e),
+            },
             Message::RbfPsbt(res) => {
                 self.processing = false;
                 match res {
@@ -426,7 +448,7 @@ impl CreateRbfModal {
                 self.warning = None;
                 self.processing = true;
                 return Task::perform(
-                    rbf(daemon, self.tx.clone(), self.is_cancel, self.feerate_vb),
+                    rbf(daemon, self.tx.clone(), self.is_cancel, self.feerate),
                     Message::RbfPsbt,
                 );
             }
@@ -441,6 +463,7 @@ impl CreateRbfModal {
                 self.is_cancel,
                 &self.descendant_txids,
                 &self.feerate_val,
+                &self.fee_estimates,
                 self.replacement_txid,
                 self.warning.as_ref(),
             ),
@@ -460,13 +483,10 @@ async fn rbf(
     daemon: Arc<dyn Daemon + Sync + Send>,
     previous_tx: model::HistoryTransaction,
     is_cancel: bool,
-    feerate_vb: Option<u64>,
+    feerate: Option<Feerate>,
 ) -> Result<Txid, Error> {
     let previous_txid = previous_tx.tx.compute_txid();
-    let psbt = match daemon
-        .rbf_psbt(&previous_txid, is_cancel, feerate_vb)
-        .await?
-    {
+    let psbt = match daemon.rbf_psbt(&previous_txid, is_cancel, feerate).await? {
         CreateSpendResult::Success { psbt, .. } => psbt,
         CreateSpendResult::InsufficientFunds { missing } => {
             return Err(
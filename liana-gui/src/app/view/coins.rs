@@ -3,7 +3,7 @@ use std::collections::HashMap;
 use iced::{widget::Space, Alignment, Length};
 
 use liana_ui::{
-    component::{amount::*, badge, button, form, text::*},
+    component::{amount::*, badge, button, card, form, text::*},
     icon, theme,
     widget::*,
 };
@@ -11,10 +11,16 @@ use liana_ui::{
 use crate::{
     app::{
         cache::Cache,
+        error::Error,
         menu::Menu,
-        view::{label, message::Message},
+        view::{
+            label,
+            message::{CreateCpfpMessage, Message},
+            spend::feerate_presets,
+            warning::warn,
+        },
     },
-    daemon::model::{remaining_sequence, Coin},
+    daemon::model::{remaining_sequence, Coin, FeeEstimate, Txid},
 };
 
 pub fn coins_view<'a>(
@@ -298,12 +304,28 @@ fn coin_list_view<'a>(
                                 .spacing(5)
                         } else {
                             Column::new().push(
-                                Row::new().push(Space::with_width(Length::Fill)).push(
-                                    button::secondary(Some(icon::arrow_repeat()), "Refresh coin")
-                                        .on_press(Message::Menu(Menu::RefreshCoins(vec![
-                                            coin.outpoint,
-                                        ]))),
-                                ),
+                                Row::new()
+                                    .spacing(10)
+                                    .push(Space::with_width(Length::Fill))
+                                    .push_maybe(if coin.block_height.is_none() {
+                                        Some(
+                                            button::secondary(Some(icon::up_icon()), "Speed up")
+                                                .on_press(Message::CreateCpfp(
+                                                    CreateCpfpMessage::New(coin.outpoint),
+                                                )),
+                                        )
+                                    } else {
+                                        None
+                                    })
+                                    .push(
+                                        button::secondary(
+                                            Some(icon::arrow_repeat()),
+                                            "Refresh coin",
+                                        )
+                                        .on_press(
+                                            Message::Menu(Menu::RefreshCoins(vec![coin.outpoint])),
+                                        ),
+                                    ),
                             )
                         }),
                 )
@@ -314,6 +336,70 @@ fn coin_list_view<'a>(
     .style(theme::card::simple)
 }
 
+pub fn create_cpfp_modal<'a>(
+    feerate: &form::Value<String>,
+    fee_estimates: &[FeeEstimate],
+    cpfp_txid: Option<Txid>,
+    warning: Option<&'a Error>,
+) -> Element<'a, Message> {
+    let mut confirm_button = button::secondary(None, "Confirm").width(Length::Fixed(200.0));
+    if feerate.valid && !feerate.value.is_empty() {
+        confirm_button = confirm_button.on_press(Message::CreateCpfp(CreateCpfpMessage::Confirm));
+    }
+    card::simple(
+        Column::new()
+            .spacing(10)
+            .push(Container::new(h4_bold("Speed up incoming transaction")).width(Length::Fill))
+            .push(Row::new().push(text(
+                "Spend this unconfirmed coin back to your wallet with a high enough \
+                feerate to incentivize the confirmation of the transaction that created it. \
+                The feerate must be higher than the one of the unconfirmed transaction. \
+                Other coins may be used as inputs.",
+            )))
+            .push(
+                Row::new()
+                    .push(Container::new(p1_bold("Feerate")).padding(10))
+                    .spacing(10)
+                    .push(
+                        form::Form::new_trimmed("", feerate, move |msg| {
+                            Message::CreateCpfp(CreateCpfpMessage::FeerateEdited(msg))
+                        })
+                        .warning(
+                            "Feerate must be between 1 and 1000 sats/vbyte, with up to 3 decimals",
+                        )
+                        .size(P1_SIZE)
+                        .padding(10),
+                    )
+                    .push(feerate_presets(fee_estimates, |feerate| {
+                        Message::CreateCpfp(CreateCpfpMessage::FeerateEdited(feerate))
+                    }))
+                    .width(Length::Fill),
+            )
+            .push(warn(warning))
+            .push(Row::new().push(if cpfp_txid.is_none() {
+                Row::new().push(confirm_button)
+            } else {
+                Row::new()
+                    .spacing(10)
+                    .align_y(Alignment::Center)
+                    .push(icon::circle_check_icon().style(theme::text::secondary))
+                    .push(
+                        text("PSBT created successfully and ready to be signed")
+                            .style(theme::text::success),
+                    )
+            }))
+            .push_maybe(cpfp_txid.map(|id| {
+                Row::new().push(
+                    button::secondary(None, "Go to PSBT")
+                        .width(Length::Fixed(200.0))
+                        .on_press(Message::Menu(Menu::PsbtPreSelected(id))),
+                )
+            })),
+    )
+    .width(Length::Fixed(800.0))
+    .into()
+}
+
 pub fn coin_sequence_label<'a, T: 'a>(seq: u32, timelock: u32) -> Container<'a, T> {
     if seq == 0 {
         Container::new(
@@ -1,5 +1,8 @@
 use crate::{app::menu::Menu, export::ExportMessage, node::bitcoind::RpcAuthType};
-use liana::miniscript::bitcoin::{bip32::Fingerprint, OutPoint};
+use liana::{
+    batch::Payment,
+    miniscript::bitcoin::{bip32::Fingerprint, OutPoint},
+};
 
 #[derive(Debug, Clone)]
 pub enum Message {
@@ -18,6 +21,7 @@ pub enum Message {
     Previous,
     SelectHardwareWallet(usize),
     CreateRbf(CreateRbfMessage),
+    CreateCpfp(CreateCpfpMessage),
     ShowQrCode(usize),
     Export(ExportMessage),
 }
@@ -32,11 +36,16 @@ pub enum LabelMessage {
 #[derive(Debug, Clone)]
 pub enum CreateSpendMessage {
     AddRecipient,
+    /// Import the recipients from a CSV or JSON file.
+    ImportRecipients,
+    /// The recipients read from the file, or an error. `None` if no file was chosen.
+    RecipientsImported(Option<Result<Vec<Payment>, String>>),
     BatchLabelEdited(String),
     DeleteRecipient(usize),
     SelectCoin(usize),
     RecipientEdited(usize, &'static str, String),
     FeerateEdited(String),
+    DataEdited(String),
     SelectPath(usize),
     Generate,
     SendMaxToRecipient(usize),
@@ -47,6 +56,9 @@ pub enum CreateSpendMessage {
 pub enum ImportSpendMessage {
     Import,
     PsbtEdited(String),
+    ImportFile,
+    /// The base64-encoded PSBT read from a file, if one was picked.
+    FileImported(Option<Result<String, String>>),
     Confirm,
 }
 
@@ -77,6 +89,11 @@ pub enum SettingsMessage {
     RegisterWallet,
     FingerprintAliasEdited(Fingerprint, String),
     Save,
+    HotSignerPasswordEdited(String),
+    /// Load the hot signer from its mnemonic encrypted with the password.
+    UnlockHotSigner,
+    /// Encrypt the plaintext mnemonics of the hot signers with the password.
+    EncryptHotSigners,
 }
 
 #[derive(Debug, Clone)]
@@ -102,3 +119,12 @@ pub enum CreateRbfMessage {
     Cancel,
     Confirm,
 }
+
+#[derive(Debug, Clone)]
+pub enum CreateCpfpMessage {
+    /// Bump the feerate of the unconfirmed transaction creating this coin.
+    New(OutPoint),
+    FeerateEdited(String),
+    Cancel,
+    Confirm,
+}
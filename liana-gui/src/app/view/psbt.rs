@@ -11,6 +11,7 @@ use liana::{
         bip32::Fingerprint, blockdata::transaction::TxOut, Address, Network, OutPoint, Transaction,
         Txid,
     },
+    psbt_v2,
 };
 
 use liana_ui::{
@@ -58,7 +59,7 @@ pub fn psbt_view<'a>(
                     .align_y(Alignment::Center)
                     .spacing(10)
                     .push(Container::new(h3("PSBT")).width(Length::Fill))
-                    .push_maybe(if !tx.sigs.recovery_paths().is_empty() {
+                    .push_maybe(if tx.sigs.last_available_recovery_path().is_some() {
                         Some(badge::recovery())
                     } else {
                         None
@@ -343,6 +344,16 @@ pub fn spend_overview_view<'a>(
                                                 )
                                                 .on_press(Message::Clipboard(tx.psbt.to_string())),
                                             )
+                                            .push(
+                                                button::secondary(
+                                                    Some(icon::clipboard_icon()),
+                                                    "Copy as PSBTv2",
+                                                )
+                                                .on_press(Message::Clipboard(psbt_v2::to_base64(
+                                                    &tx.psbt,
+                                                    psbt_v2::PsbtVersion::V2,
+                                                ))),
+                                            )
                                             .push(
                                                 button::secondary(
                                                     Some(icon::import_icon()),
@@ -491,18 +502,21 @@ pub fn signatures<'a>(
                             .padding(15)
                             .spacing(10)
                             .push(text("Finalizing this transaction requires:"))
-                            .push_maybe(if tx.sigs.recovery_paths().is_empty() {
-                                Some(path_view(
-                                    desc_info.primary_path(),
-                                    tx.sigs.primary_path(),
-                                    keys_aliases,
-                                ))
-                            } else {
-                                tx.sigs.recovery_paths().iter().last().map(|(seq, path)| {
-                                    let keys = &desc_info.recovery_paths()[seq];
-                                    path_view(keys, path, keys_aliases)
-                                })
-                            }),
+                            .push(
+                                if let Some((cond, path)) = tx.sigs.last_available_recovery_path() {
+                                    path_view(
+                                        &desc_info.conditional_paths()[cond],
+                                        path,
+                                        keys_aliases,
+                                    )
+                                } else {
+                                    path_view(
+                                        desc_info.primary_path(),
+                                        tx.sigs.primary_path(),
+                                        keys_aliases,
+                                    )
+                                },
+                            ),
                     )
                 },
             ))
@@ -912,6 +926,7 @@ fn payment_view<'a>(
     let addr = Address::from_script(&output.script_pubkey, network)
         .ok()
         .map(|a| a.to_string());
+    let has_address = addr.is_some();
     let outpoint = OutPoint {
         txid,
         vout: i as u32,
@@ -977,6 +992,22 @@ fn payment_view<'a>(
                         )
                 }))
         }))
+        .push_maybe(if !has_address {
+            // This output doesn't pay to an address, for instance it carries data.
+            Some(
+                Row::new()
+                    .align_y(Alignment::Center)
+                    .width(Length::Fill)
+                    .spacing(5)
+                    .push(p1_bold("Script:").style(theme::text::secondary))
+                    .push(
+                        p2_regular(output.script_pubkey.to_asm_string())
+                            .style(theme::text::secondary),
+                    ),
+            )
+        } else {
+            None
+        })
         .into()
 }
 
@@ -32,16 +32,27 @@ pub fn import_psbt_view<'a>(
                     .size(P1_SIZE)
                     .padding(10),
                 )
-                .push(Row::new().push(Space::with_width(Length::Fill)).push(
-                    if imported.valid && !imported.value.is_empty() && !processing {
-                        button::secondary(None, "Import")
-                            .on_press(Message::ImportSpend(ImportSpendMessage::Confirm))
-                    } else if processing {
-                        button::secondary(None, "Processing...")
-                    } else {
-                        button::secondary(None, "Import")
-                    },
-                )),
+                .push(
+                    Row::new()
+                        .spacing(10)
+                        .push(
+                            button::secondary(Some(icon::import_icon()), "From file")
+                                .on_press_maybe((!processing).then_some(Message::ImportSpend(
+                                    ImportSpendMessage::ImportFile,
+                                ))),
+                        )
+                        .push(Space::with_width(Length::Fill))
+                        .push(
+                            if imported.valid && !imported.value.is_empty() && !processing {
+                                button::secondary(None, "Import")
+                                    .on_press(Message::ImportSpend(ImportSpendMessage::Confirm))
+                            } else if processing {
+                                button::secondary(None, "Processing...")
+                            } else {
+                                button::secondary(None, "Import")
+                            },
+                        ),
+                ),
         ))
         .max_width(400)
         .into()
@@ -102,7 +113,7 @@ fn spend_tx_list_view(i: usize, tx: &SpendTx) -> Element<'_, Message> {
                         } else {
                             badge::spend()
                         })
-                        .push(if !tx.sigs.recovery_paths().is_empty() {
+                        .push(if tx.sigs.last_available_recovery_path().is_some() {
                             badge::recovery()
                         } else {
                             let sigs = tx.sigs.primary_path();
@@ -5,9 +5,13 @@ use iced::{
     Alignment, Length,
 };
 
-use liana::miniscript::bitcoin::{
-    bip32::{DerivationPath, Fingerprint},
-    Amount,
+use chrono::{DateTime, Utc};
+use liana::miniscript::{
+    bitcoin::{
+        bip32::{DerivationPath, Fingerprint},
+        Amount,
+    },
+    AbsLockTime,
 };
 
 use liana_ui::{
@@ -133,12 +137,25 @@ pub fn recovery<'a>(
     )
 }
 
+/// The timelock behind which a recovery path is locked.
+#[derive(Debug, Clone, Copy)]
+pub enum RecoveryTimelock {
+    /// A number of blocks after the coin was confirmed.
+    Relative(u16),
+    /// A number of 512-second intervals after the coin was confirmed.
+    RelativeTime(u16),
+    /// A block height or a date, for all coins.
+    Absolute(AbsLockTime),
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn recovery_path_view<'a>(
     index: usize,
     threshold: usize,
     origins: &'a [(Fingerprint, HashSet<DerivationPath>)],
     total_amount: Amount,
     number_of_coins: usize,
+    timelock: RecoveryTimelock,
     key_aliases: &'a HashMap<Fingerprint, String>,
     selected: bool,
 ) -> Element<'a, Message> {
@@ -181,7 +198,23 @@ pub fn recovery_path_view<'a>(
                                         .style(theme::pill::simple)
                                 })
                             },
-                        )),
+                        ))
+                        .push_maybe(match timelock {
+                            RecoveryTimelock::Relative(_) => None,
+                            RecoveryTimelock::RelativeTime(intervals) => Some(text(format!(
+                                "about {} days after confirmation",
+                                (intervals as u64 * 512 + 43_200) / 86_400
+                            ))),
+                            RecoveryTimelock::Absolute(tl) if tl.is_block_height() => {
+                                Some(text(format!("after block {}", tl.to_consensus_u32())))
+                            }
+                            RecoveryTimelock::Absolute(tl) => Some(text(format!(
+                                "after {}",
+                                DateTime::<Utc>::from_timestamp(tl.to_consensus_u32() as i64, 0)
+                                    .expect("Correct timestamp")
+                                    .format("%b. %d, %Y - %T")
+                            ))),
+                        }),
                 )
                 .push(
                     Row::new()
@@ -1,6 +1,8 @@
 use std::collections::HashSet;
 use std::str::FromStr;
 
+use chrono::{DateTime, Utc};
+
 use iced::{
     alignment,
     widget::{radio, scrollable, tooltip as iced_tooltip, Space},
@@ -8,7 +10,7 @@ use iced::{
 };
 
 use liana::{
-    descriptors::{LianaDescriptor, LianaPolicy},
+    descriptors::{ConditionExplanation, LianaDescriptor},
     miniscript::bitcoin::{bip32::Fingerprint, Network},
 };
 use lianad::config::BitcoindRpcAuth;
@@ -898,6 +900,7 @@ fn is_ok_and<T, E>(res: &Result<T, E>, f: impl FnOnce(&T) -> bool) -> bool {
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn wallet_settings<'a>(
     cache: &'a Cache,
     warning: Option<&Error>,
@@ -905,6 +908,8 @@ pub fn wallet_settings<'a>(
     keys_aliases: &'a [(Fingerprint, form::Value<String>)],
     processing: bool,
     updated: bool,
+    hot_signer: Option<Fingerprint>,
+    hot_signer_password: &'a str,
 ) -> Element<'a, Message> {
     dashboard(
         &Menu::Settings,
@@ -964,9 +969,11 @@ pub fn wallet_settings<'a>(
                 )
                 .width(Length::Fill),
             )
-            .push(
-                card::simple(display_policy(descriptor.policy(), keys_aliases)).width(Length::Fill),
-            )
+            .push(card::simple(display_policy(
+                descriptor,
+                keys_aliases,
+                cache.blockheight,
+            )).width(Length::Fill))
             .push(
                 card::simple(
                     Column::new()
@@ -1024,40 +1031,94 @@ pub fn wallet_settings<'a>(
                         .spacing(10),
                 )
                 .width(Length::Fill),
+            )
+            .push(
+                card::simple(
+                    Column::new()
+                        .spacing(10)
+                        .push(text("Hot signer:").bold())
+                        .push(match hot_signer {
+                            Some(fg) => p1_regular(format!("The key {} is stored on this computer.", fg)),
+                            None => p1_regular("No key stored on this computer is loaded. If its mnemonic is encrypted, enter the password to unlock it.")
+                                .style(theme::text::secondary),
+                        })
+                        .push(p2_regular("The mnemonics stored on this computer can be encrypted with a password, which will then be required to unlock them each time the wallet is opened.").style(theme::text::secondary))
+                        .push(
+                            Row::new()
+                                .spacing(10)
+                                .align_y(Alignment::Center)
+                                .push(
+                                    TextInput::new("Password", hot_signer_password)
+                                        .on_input(|p| {
+                                            Message::Settings(
+                                                SettingsMessage::HotSignerPasswordEdited(p),
+                                            )
+                                        })
+                                        .secure(true)
+                                        .size(P1_SIZE)
+                                        .padding(10),
+                                )
+                                .push(
+                                    button::secondary(None, "Unlock").on_press_maybe(
+                                        (!processing
+                                            && hot_signer.is_none()
+                                            && !hot_signer_password.is_empty())
+                                        .then_some(Message::Settings(
+                                            SettingsMessage::UnlockHotSigner,
+                                        )),
+                                    ),
+                                )
+                                .push(
+                                    button::secondary(None, "Encrypt").on_press_maybe(
+                                        (!processing && !hot_signer_password.is_empty())
+                                            .then_some(Message::Settings(
+                                                SettingsMessage::EncryptHotSigners,
+                                            )),
+                                    ),
+                                ),
+                        ),
+                )
+                .width(Length::Fill),
             ),
     )
 }
 
-fn display_policy(
-    policy: LianaPolicy,
-    keys_aliases: &[(Fingerprint, form::Value<String>)],
-) -> Element<'_, Message> {
-    let (primary_threshold, primary_keys) = policy.primary_path().thresh_origins();
-    let recovery_paths = policy.recovery_paths();
-
-    // The iteration over an HashMap keys can have a different order at each refresh
-    let mut primary_keys: Vec<Fingerprint> = primary_keys.into_keys().collect();
-    primary_keys.sort();
+fn display_policy<'a>(
+    descriptor: &LianaDescriptor,
+    keys_aliases: &'a [(Fingerprint, form::Value<String>)],
+    blockheight: i32,
+) -> Element<'a, Message> {
+    let mut col = Column::new();
+    let mut recovery_index = 0;
+    let mut explanation = descriptor.explain();
+    // The block height is unknown until the first poll of the backend.
+    if blockheight > 0 {
+        explanation.estimate_dates(blockheight as u32, Utc::now().timestamp() as u64);
+    }
+    for path in explanation.paths {
+        // A signer may appear more than once in a path, and the keys order can differ at each
+        // refresh. Display each signer once, sorted by fingerprint.
+        let mut keys: Vec<Fingerprint> = path.keys.iter().map(|k| k.fingerprint).collect();
+        keys.sort();
+        keys.dedup();
 
-    let mut col = Column::new().push(
-        Row::new()
+        let row = Row::new()
             .spacing(5)
             .push(
                 text(format!(
                     "{} signature{}",
-                    primary_threshold,
-                    if primary_threshold > 1 { "s" } else { "" }
+                    path.threshold,
+                    if path.threshold > 1 { "s" } else { "" }
                 ))
                 .bold(),
             )
-            .push(if primary_keys.len() > 1 {
-                text(format!("out of {} by", primary_keys.len()))
+            .push(if keys.len() > 1 {
+                text(format!("out of {} by", keys.len()))
             } else {
                 text("by")
             })
             .push(
-                primary_keys
-                    .iter()
+                keys.iter()
                     .enumerate()
                     .fold(Row::new().spacing(5), |row, (i, k)| {
                         let content = if let Some(alias) = keys_aliases
@@ -1076,79 +1137,73 @@ fn display_policy(
                         } else {
                             Container::new(text(format!("[{}]", k)).bold())
                         };
-                        if primary_keys.len() == 1 || i == primary_keys.len() - 1 {
+                        if keys.len() == 1 || i == keys.len() - 1 {
                             row.push(content)
-                        } else if i <= primary_keys.len() - 2 {
+                        } else if i <= keys.len() - 2 {
                             row.push(content).push(text("and"))
                         } else {
                             row.push(content).push(text(","))
                         }
                     }),
-            )
-            .push(text("can always spend this wallet's funds (Primary path)")),
-    );
-    for (i, (sequence, recovery_path)) in recovery_paths.iter().enumerate() {
-        let (threshold, recovery_keys) = recovery_path.thresh_origins();
+            );
 
-        // The iteration over an HashMap keys can have a different order at each refresh
-        let mut recovery_keys: Vec<Fingerprint> = recovery_keys.into_keys().collect();
-        recovery_keys.sort();
-
-        col = col.push(
-            Row::new()
-                .spacing(5)
-                .push(
+        let row = match path.condition {
+            ConditionExplanation::None => {
+                col =
+                    col.push(row.push(text("can always spend this wallet's funds (Primary path)")));
+                continue;
+            }
+            ConditionExplanation::RelativeBlocks { blocks, .. } => {
+                row.push(text("can spend coins inactive for")).push(
                     text(format!(
-                        "{} signature{}",
-                        threshold,
-                        if threshold > 1 { "s" } else { "" }
+                        "{} blocks (~{})",
+                        blocks,
+                        expire_message_units(blocks as u32).join(",")
                     ))
                     .bold(),
                 )
-                .push(if recovery_keys.len() > 1 {
-                    text(format!("out of {} by", recovery_keys.len()))
-                } else {
-                    text("by")
-                })
-                .push(recovery_keys.iter().enumerate().fold(
-                    Row::new().spacing(5),
-                    |row, (i, k)| {
-                        let content = if let Some(alias) = keys_aliases
-                            .iter()
-                            .find(|(fg, a)| fg == k && !a.value.is_empty())
-                            .map(|(_, f)| &f.value)
-                        {
-                            Container::new(
-                                iced_tooltip::Tooltip::new(
-                                    text(alias).bold(),
-                                    text(k.to_string()),
-                                    iced_tooltip::Position::Bottom,
-                                )
-                                .style(theme::card::simple),
-                            )
-                        } else {
-                            Container::new(text(format!("[{}]", k)).bold())
-                        };
-                        if recovery_keys.len() == 1 || i == recovery_keys.len() - 1 {
-                            row.push(content)
-                        } else if i <= recovery_keys.len() - 2 {
-                            row.push(content).push(text("and"))
-                        } else {
-                            row.push(content).push(text(","))
-                        }
-                    },
-                ))
-                .push(text("can spend coins inactive for"))
-                .push(
+            }
+            ConditionExplanation::RelativeTime { seconds, .. } => {
+                row.push(text("can spend coins inactive for")).push(
                     text(format!(
-                        "{} blocks (~{})",
-                        sequence,
-                        expire_message_units(*sequence as u32).join(",")
+                        "~{}",
+                        // The helper takes a number of 10-minute blocks.
+                        expire_message_units((seconds / 600) as u32).join(",")
                     ))
                     .bold(),
                 )
-                .push(text(format!("(Recovery path #{})", i + 1))),
-        );
+            }
+            ConditionExplanation::AbsoluteHeight {
+                height,
+                approx_timestamp,
+            } => row
+                .push(text("can spend coins from block"))
+                .push(text(height.to_string()).bold())
+                .push_maybe(approx_timestamp.map(|timestamp| {
+                    text(format!(
+                        "(~{})",
+                        DateTime::<Utc>::from_timestamp(timestamp as i64, 0)
+                            .expect("Correct timestamp")
+                            .format("%b. %d, %Y")
+                    ))
+                })),
+            ConditionExplanation::AbsoluteTime { timestamp } => {
+                row.push(text("can spend coins after")).push(
+                    text(
+                        DateTime::<Utc>::from_timestamp(timestamp as i64, 0)
+                            .expect("Correct timestamp")
+                            .format("%b. %d, %Y - %T")
+                            .to_string(),
+                    )
+                    .bold(),
+                )
+            }
+            ConditionExplanation::Hashlock { hash } => row
+                .push(text("can spend coins by revealing the preimage of"))
+                .push(text(hash.to_string()).bold()),
+        };
+        recovery_index += 1;
+        col = col.push(row.push(text(format!("(Recovery path #{})", recovery_index))));
     }
     Column::new()
         .spacing(10)
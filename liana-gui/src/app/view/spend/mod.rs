@@ -24,7 +24,7 @@ use crate::{
         menu::Menu,
         view::{coins, dashboard, message::*, psbt},
     },
-    daemon::model::{remaining_sequence, Coin, SpendTx},
+    daemon::model::{remaining_sequence, Coin, FeeEstimate, SpendTx},
 };
 
 #[allow(clippy::too_many_arguments)]
@@ -119,6 +119,8 @@ pub fn create_spend_tx<'a>(
     batch_label: &form::Value<String>,
     amount_left: Option<&Amount>,
     feerate: &form::Value<String>,
+    fee_estimates: &[FeeEstimate],
+    data: &form::Value<String>,
     error: Option<&Error>,
 ) -> Element<'a, Message> {
     let is_self_send = recipients.is_empty();
@@ -149,6 +151,7 @@ pub fn create_spend_tx<'a>(
                     .push(Column::with_children(recipients).spacing(10))
                     .push(
                         Row::new()
+                            .spacing(10)
                             .push_maybe(if duplicate {
                                 Some(
                                     Container::new(
@@ -161,6 +164,16 @@ pub fn create_spend_tx<'a>(
                                 None
                             })
                             .push(Space::with_width(Length::Fill))
+                            .push_maybe(if is_self_send {
+                                None
+                            } else {
+                                Some(
+                                    button::secondary(Some(icon::import_icon()), "Import payments")
+                                        .on_press(Message::CreateSpend(
+                                            CreateSpendMessage::ImportRecipients,
+                                        )),
+                                )
+                            })
                             .push_maybe(if is_self_send {
                                 None
                             } else {
@@ -174,6 +187,26 @@ pub fn create_spend_tx<'a>(
                     )
                     .spacing(20),
             )
+            .push_maybe(if is_self_send {
+                None
+            } else {
+                Some(
+                    Row::new()
+                        .align_y(Alignment::Start)
+                        .spacing(10)
+                        .push(Container::new(p1_bold("Data")).padding(10))
+                        .push(
+                            form::Form::new_trimmed(
+                                "Data to embed in the transaction (optional, in hex)",
+                                data,
+                                |msg| Message::CreateSpend(CreateSpendMessage::DataEdited(msg)),
+                            )
+                            .warning("Data must be hex-encoded and at most 80 bytes long")
+                            .size(P1_SIZE)
+                            .padding(10),
+                        ),
+                )
+            })
             .push(
                 Row::new()
                     .push(
@@ -189,15 +222,20 @@ pub fn create_spend_tx<'a>(
                                     },
                                 )
                                 .warning(
-                                    "Feerate must be an integer less than \
-                                    or equal to 1000 sats/vbyte",
+                                    "Feerate must be at least 1 and at most 1000 sats/vbyte, \
+                                    with up to 3 decimals",
                                 )
                                 .size(P1_SIZE)
                                 .padding(10),
                             )
                             .width(Length::FillPortion(1)),
                     )
-                    .push(Space::with_width(Length::FillPortion(1))),
+                    .push(
+                        Container::new(feerate_presets(fee_estimates, |feerate| {
+                            Message::CreateSpend(CreateSpendMessage::FeerateEdited(feerate))
+                        }))
+                        .width(Length::FillPortion(1)),
+                    ),
             )
             .push(
                 Container::new(
@@ -314,6 +352,39 @@ pub fn create_spend_tx<'a>(
     )
 }
 
+/// Buttons to set the feerate to one of the given estimates. Estimates the Bitcoin backend could
+/// not make, which are only the minimum feerate, are not shown.
+pub fn feerate_presets<'a>(
+    estimates: &[FeeEstimate],
+    on_select: impl Fn(String) -> Message,
+) -> Row<'a, Message> {
+    estimates.iter().filter(|estimate| estimate.estimated).fold(
+        Row::new().spacing(10).align_y(Alignment::Center),
+        |row, estimate| {
+            let target = match estimate.target {
+                1 => "Next block".to_string(),
+                6 => "1 hour".to_string(),
+                144 => "1 day".to_string(),
+                blocks => format!("{} blocks", blocks),
+            };
+            row.push(
+                Button::new(
+                    Column::new()
+                        .align_x(Alignment::Center)
+                        .push(p2_medium(target))
+                        .push(
+                            p2_regular(format!("{} sats/vbyte", estimate.feerate))
+                                .style(theme::text::secondary),
+                        ),
+                )
+                .padding(5)
+                .style(theme::button::secondary)
+                .on_press(on_select(estimate.feerate.to_string())),
+            )
+        },
+    )
+}
+
 pub fn recipient_view<'a>(
     index: usize,
     address: &'a form::Value<String>,
@@ -343,10 +414,12 @@ pub fn recipient_view<'a>(
                             .width(Length::Fixed(110.0)),
                     )
                     .push(
-                        form::Form::new_trimmed("Address", address, move |msg| {
-                            CreateSpendMessage::RecipientEdited(index, "address", msg)
-                        })
-                        .warning("Invalid address (maybe it is for another network?)")
+                        form::Form::new_trimmed(
+                            "Address or raw(<script hex>)",
+                            address,
+                            move |msg| CreateSpendMessage::RecipientEdited(index, "address", msg),
+                        )
+                        .warning("Invalid address (maybe it is for another network?) or script")
                         .size(P1_SIZE)
                         .padding(10),
                     ),
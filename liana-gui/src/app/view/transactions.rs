@@ -24,7 +24,7 @@ use crate::{
             warning::warn,
         },
     },
-    daemon::model::{HistoryTransaction, Txid},
+    daemon::model::{FeeEstimate, HistoryTransaction, Txid},
     export::ExportMessage,
 };
 
@@ -173,6 +173,7 @@ pub fn create_rbf_modal<'a>(
     is_cancel: bool,
     descendant_txids: &HashSet<Txid>,
     feerate: &form::Value<String>,
+    fee_estimates: &[FeeEstimate],
     replacement_txid: Option<Txid>,
     warning: Option<&'a Error>,
 ) -> Element<'a, Message> {
@@ -259,11 +260,14 @@ pub fn create_rbf_modal<'a>(
                             })
                             .warning(
                                 "Feerate must be greater than previous value and \
-                                less than or equal to 1000 sats/vbyte",
+                                less than or equal to 1000 sats/vbyte, with up to 3 decimals",
                             )
                             .size(P1_SIZE)
                             .padding(10),
                         )
+                        .push(super::spend::feerate_presets(fee_estimates, |feerate| {
+                            Message::CreateRbf(CreateRbfMessage::FeerateEdited(feerate))
+                        }))
                         .width(Length::Fill),
                 )
             } else {
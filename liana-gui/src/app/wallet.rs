@@ -6,7 +6,10 @@ use crate::{
     app::settings, daemon::DaemonBackend, hw::HardwareWalletConfig, node::NodeType, signer::Signer,
 };
 
-use liana::{miniscript::bitcoin, signer::HotSigner};
+use liana::{
+    miniscript::bitcoin,
+    signer::{HotSigner, SecretRequest},
+};
 
 use liana::descriptors::LianaDescriptor;
 use liana::miniscript::bitcoin::bip32::Fingerprint;
@@ -73,7 +76,7 @@ impl Wallet {
         for (fingerprint, _) in info.primary_path().thresh_origins().1.iter() {
             descriptor_keys.insert(*fingerprint);
         }
-        for path in info.recovery_paths().values() {
+        for path in info.conditional_paths().values() {
             for (fingerprint, _) in path.thresh_origins().1.iter() {
                 descriptor_keys.insert(*fingerprint);
             }
@@ -141,7 +144,27 @@ impl Wallet {
         datadir_path: &Path,
         network: bitcoin::Network,
     ) -> Result<Self, WalletError> {
-        let hot_signers = match HotSigner::from_datadir(datadir_path, network) {
+        self.load_hotsigners_with_password(datadir_path, network, None)
+    }
+
+    /// Load the hot signer of this wallet from the datadir, decrypting the encrypted mnemonics
+    /// with the given password if any.
+    pub fn load_hotsigners_with_password(
+        self,
+        datadir_path: &Path,
+        network: bitcoin::Network,
+        password: Option<&str>,
+    ) -> Result<Self, WalletError> {
+        // Skip the mnemonics whose secret we don't have, without preventing to use the wallet
+        // with the other signers. The GUI can't prompt for a BIP39 passphrase yet.
+        let res = HotSigner::from_datadir_with_secrets(datadir_path, network, |req| match req {
+            SecretRequest::Password(_) if password.is_some() => password.map(|p| p.to_string()),
+            _ => {
+                tracing::warn!("Not loading hot signer: {:?} is required", req);
+                None
+            }
+        });
+        let hot_signers = match res {
             Ok(signers) => signers,
             Err(e) => match e {
                 liana::signer::SignerError::MnemonicStorage(e) => {
@@ -12,9 +12,12 @@ use tracing::{error, info};
 pub mod error;
 pub mod jsonrpc;
 
-use liana::miniscript::bitcoin::{address, psbt::Psbt, Address, Network, OutPoint, Txid};
+use liana::{
+    miniscript::bitcoin::{address, psbt::Psbt, Address, Network, OutPoint, Txid},
+    spend::Feerate,
+};
 use lianad::{
-    commands::{CoinStatus, CreateRecoveryResult, LabelItem},
+    commands::{CoinStatus, CreateRecoveryResult, Destination, LabelItem},
     config::Config,
 };
 
@@ -81,6 +84,10 @@ impl<C: Client + Send + Sync + Debug> Daemon for Lianad<C> {
         self.call("getnewaddress", Option::<Request>::None)
     }
 
+    async fn estimate_fees(&self) -> Result<EstimateFeesResult, DaemonError> {
+        self.call("estimatefees", Option::<Request>::None)
+    }
+
     async fn list_coins(
         &self,
         statuses: &[CoinStatus],
@@ -102,30 +109,45 @@ impl<C: Client + Send + Sync + Debug> Daemon for Lianad<C> {
     async fn create_spend_tx(
         &self,
         coins_outpoints: &[OutPoint],
-        destinations: &HashMap<Address<address::NetworkUnchecked>, u64>,
-        feerate_vb: u64,
+        destinations: &HashMap<Destination, u64>,
+        feerate: Feerate,
         change_address: Option<Address<address::NetworkUnchecked>>,
     ) -> Result<CreateSpendResult, DaemonError> {
-        let mut input = vec![
-            json!(destinations),
-            json!(coins_outpoints),
-            json!(feerate_vb),
-        ];
+        let mut input = vec![json!(destinations), json!(coins_outpoints), json!(feerate)];
         if let Some(change_address) = change_address {
             input.push(json!(change_address));
         }
         self.call("createspend", Some(input))
     }
 
+    async fn create_cpfp(
+        &self,
+        outpoint: &OutPoint,
+        extra_outpoints: &[OutPoint],
+        feerate: Feerate,
+    ) -> Result<CreateSpendResult, DaemonError> {
+        self.call(
+            "createcpfp",
+            Some(vec![
+                json!(outpoint.to_string()),
+                json!(feerate),
+                json!(extra_outpoints
+                    .iter()
+                    .map(|op| op.to_string())
+                    .collect::<Vec<String>>()),
+            ]),
+        )
+    }
+
     async fn rbf_psbt(
         &self,
         txid: &Txid,
         is_cancel: bool,
-        feerate_vb: Option<u64>,
+        feerate: Option<Feerate>,
     ) -> Result<CreateSpendResult, DaemonError> {
         let mut input = vec![json!(txid.to_string()), json!(is_cancel)];
-        if let Some(feerate_vb) = feerate_vb {
-            input.push(json!(feerate_vb));
+        if let Some(feerate) = feerate {
+            input.push(json!(feerate));
         }
         self.call("rbfpsbt", Some(input))
     }
@@ -136,6 +158,12 @@ impl<C: Client + Send + Sync + Debug> Daemon for Lianad<C> {
         Ok(())
     }
 
+    async fn import_psbt(&self, psbt: &Psbt) -> Result<(), DaemonError> {
+        let _res: serde_json::value::Value =
+            self.call("importpsbt", Some(vec![psbt.to_string()]))?;
+        Ok(())
+    }
+
     async fn delete_spend_tx(&self, txid: &Txid) -> Result<(), DaemonError> {
         let _res: serde_json::value::Value =
             self.call("delspendtx", Some(vec![txid.to_string()]))?;
@@ -172,12 +200,18 @@ impl<C: Client + Send + Sync + Debug> Daemon for Lianad<C> {
     async fn create_recovery(
         &self,
         address: Address<address::NetworkUnchecked>,
-        feerate_vb: u64,
-        sequence: Option<u16>,
+        feerate: Feerate,
+        sequence: Option<u32>,
+        absolute_timelock: Option<u32>,
     ) -> Result<Psbt, DaemonError> {
         let res: CreateRecoveryResult = self.call(
             "createrecovery",
-            Some(vec![json!(address), json!(feerate_vb), json!(sequence)]),
+            Some(vec![
+                json!(address),
+                json!(feerate),
+                json!(sequence),
+                json!(absolute_timelock),
+            ]),
         )?;
         Ok(res.psbt)
     }
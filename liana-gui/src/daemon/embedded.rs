@@ -4,9 +4,12 @@ use tokio::sync::Mutex;
 
 use super::{model::*, node, Daemon, DaemonBackend, DaemonError};
 use async_trait::async_trait;
-use liana::miniscript::bitcoin::{address, psbt::Psbt, Address, Network, OutPoint, Txid};
+use liana::{
+    miniscript::bitcoin::{address, psbt::Psbt, Address, Network, OutPoint, Txid},
+    spend::Feerate,
+};
 use lianad::{
-    commands::{CoinStatus, LabelItem},
+    commands::{CoinStatus, CreateSpendOptions, Destination, LabelItem, Recipient},
     config::Config,
     DaemonControl, DaemonHandle,
 };
@@ -97,6 +100,10 @@ impl Daemon for EmbeddedDaemon {
         self.command(|daemon| Ok(daemon.get_new_address())).await
     }
 
+    async fn estimate_fees(&self) -> Result<EstimateFeesResult, DaemonError> {
+        self.command(|daemon| Ok(daemon.estimate_fees())).await
+    }
+
     async fn list_coins(
         &self,
         statuses: &[CoinStatus],
@@ -133,13 +140,37 @@ impl Daemon for EmbeddedDaemon {
     async fn create_spend_tx(
         &self,
         coins_outpoints: &[OutPoint],
-        destinations: &HashMap<Address<address::NetworkUnchecked>, u64>,
-        feerate_vb: u64,
+        destinations: &HashMap<Destination, u64>,
+        feerate: Feerate,
         change_address: Option<Address<address::NetworkUnchecked>>,
     ) -> Result<CreateSpendResult, DaemonError> {
+        let recipients: Vec<Recipient> = destinations
+            .iter()
+            .map(|(destination, amount)| Recipient::new(destination.clone(), *amount))
+            .collect();
         self.command(|daemon| {
             daemon
-                .create_spend(destinations, coins_outpoints, feerate_vb, change_address)
+                .create_spend(
+                    &recipients,
+                    coins_outpoints,
+                    feerate,
+                    change_address,
+                    CreateSpendOptions::default(),
+                )
+                .map_err(|e| DaemonError::Unexpected(e.to_string()))
+        })
+        .await
+    }
+
+    async fn create_cpfp(
+        &self,
+        outpoint: &OutPoint,
+        extra_outpoints: &[OutPoint],
+        feerate: Feerate,
+    ) -> Result<CreateSpendResult, DaemonError> {
+        self.command(|daemon| {
+            daemon
+                .create_cpfp(outpoint, extra_outpoints, feerate)
                 .map_err(|e| DaemonError::Unexpected(e.to_string()))
         })
         .await
@@ -149,11 +180,11 @@ impl Daemon for EmbeddedDaemon {
         &self,
         txid: &Txid,
         is_cancel: bool,
-        feerate_vb: Option<u64>,
+        feerate: Option<Feerate>,
     ) -> Result<CreateSpendResult, DaemonError> {
         self.command(|daemon| {
             daemon
-                .rbf_psbt(txid, is_cancel, feerate_vb)
+                .rbf_psbt(txid, is_cancel, feerate)
                 .map_err(|e| DaemonError::Unexpected(e.to_string()))
         })
         .await
@@ -162,7 +193,17 @@ impl Daemon for EmbeddedDaemon {
     async fn update_spend_tx(&self, psbt: &Psbt) -> Result<(), DaemonError> {
         self.command(|daemon| {
             daemon
-                .update_spend(psbt.clone())
+                .update_spend(psbt.clone(), &HashMap::new())
+                .map_err(|e| DaemonError::Unexpected(e.to_string()))
+        })
+        .await
+    }
+
+    async fn import_psbt(&self, psbt: &Psbt) -> Result<(), DaemonError> {
+        self.command(|daemon| {
+            daemon
+                .import_psbt(psbt.clone())
+                .map(|_| ())
                 .map_err(|e| DaemonError::Unexpected(e.to_string()))
         })
         .await
@@ -197,12 +238,13 @@ impl Daemon for EmbeddedDaemon {
     async fn create_recovery(
         &self,
         address: Address<address::NetworkUnchecked>,
-        feerate_vb: u64,
-        sequence: Option<u16>,
+        feerate: Feerate,
+        sequence: Option<u32>,
+        absolute_timelock: Option<u32>,
     ) -> Result<Psbt, DaemonError> {
         self.command(|daemon| {
             daemon
-                .create_recovery(address, feerate_vb, sequence)
+                .create_recovery(address, feerate, sequence, absolute_timelock, false)
                 .map(|res| res.psbt)
                 .map_err(|e| DaemonError::Unexpected(e.to_string()))
         })
@@ -11,11 +11,14 @@ use std::path::Path;
 
 use async_trait::async_trait;
 
-use liana::miniscript::bitcoin::{
-    address, bip32::Fingerprint, psbt::Psbt, secp256k1, Address, Network, OutPoint, Txid,
+use liana::{
+    miniscript::bitcoin::{
+        address, bip32::Fingerprint, psbt::Psbt, secp256k1, Address, Network, OutPoint, Txid,
+    },
+    spend::Feerate,
 };
 use lianad::{
-    commands::{CoinStatus, LabelItem, TransactionInfo},
+    commands::{CoinStatus, Destination, LabelItem, TransactionInfo},
     config::Config,
     StartupError,
 };
@@ -90,15 +93,15 @@ pub trait Daemon: Debug {
     async fn create_spend_tx(
         &self,
         coins_outpoints: &[OutPoint],
-        destinations: &HashMap<Address<address::NetworkUnchecked>, u64>,
-        feerate_vb: u64,
+        destinations: &HashMap<Destination, u64>,
+        feerate: Feerate,
         change_address: Option<Address<address::NetworkUnchecked>>,
     ) -> Result<model::CreateSpendResult, DaemonError>;
     async fn rbf_psbt(
         &self,
         txid: &Txid,
         is_cancel: bool,
-        feerate_vb: Option<u64>,
+        feerate: Option<Feerate>,
     ) -> Result<model::CreateSpendResult, DaemonError>;
     async fn update_spend_tx(&self, psbt: &Psbt) -> Result<(), DaemonError>;
     async fn delete_spend_tx(&self, txid: &Txid) -> Result<(), DaemonError>;
@@ -113,8 +116,9 @@ pub trait Daemon: Debug {
     async fn create_recovery(
         &self,
         address: Address<address::NetworkUnchecked>,
-        feerate_vb: u64,
-        sequence: Option<u16>,
+        feerate: Feerate,
+        sequence: Option<u32>,
+        absolute_timelock: Option<u32>,
     ) -> Result<Psbt, DaemonError>;
     async fn list_txs(&self, txid: &[Txid]) -> Result<model::ListTransactionsResult, DaemonError>;
     async fn get_labels(
@@ -129,6 +133,31 @@ pub trait Daemon: Debug {
         Ok(())
     }
 
+    /// Import a PSBT created by another wallet as a draft, filling in the information signers
+    /// need. Backends which cannot validate it just store it.
+    async fn import_psbt(&self, psbt: &Psbt) -> Result<(), DaemonError> {
+        self.update_spend_tx(psbt).await
+    }
+
+    /// Get fee estimates for a few confirmation targets. Backends which cannot estimate fees
+    /// return no estimate.
+    async fn estimate_fees(&self) -> Result<model::EstimateFeesResult, DaemonError> {
+        Ok(model::EstimateFeesResult {
+            estimates: Vec::new(),
+        })
+    }
+
+    /// Create a transaction spending the given unconfirmed coin (and optional extra coins)
+    /// back to the wallet so as to bump the feerate of its parent. Not all backends support it.
+    async fn create_cpfp(
+        &self,
+        _outpoint: &OutPoint,
+        _extra_outpoints: &[OutPoint],
+        _feerate: Feerate,
+    ) -> Result<model::CreateSpendResult, DaemonError> {
+        Err(DaemonError::ClientNotSupported)
+    }
+
     // List spend transactions, optionally filtered to the specified `txids`.
     // Set `txids` to `None` for no filter (passing an empty slice returns no transactions).
     async fn list_spend_transactions(
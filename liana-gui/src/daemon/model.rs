@@ -1,7 +1,7 @@
 use std::cmp::Ordering;
 use std::collections::{HashMap, HashSet};
 
-use liana::descriptors::LianaDescriptor;
+use liana::descriptors::{LianaDescriptor, PathCondition};
 pub use liana::{
     descriptors::{LianaPolicy, PartialSpendInfo, PathSpendInfo},
     miniscript::bitcoin::{
@@ -11,8 +11,9 @@ pub use liana::{
     },
 };
 pub use lianad::commands::{
-    CreateSpendResult, GetAddressResult, GetInfoResult, GetLabelsResult, LabelItem, ListCoinsEntry,
-    ListCoinsResult, ListSpendEntry, ListSpendResult, ListTransactionsResult, TransactionInfo,
+    CreateSpendResult, EstimateFeesResult, FeeEstimate, GetAddressResult, GetInfoResult,
+    GetLabelsResult, LabelItem, ListCoinsEntry, ListCoinsResult, ListSpendEntry, ListSpendResult,
+    ListTransactionsResult, TransactionInfo,
 };
 
 pub type Coin = ListCoinsEntry;
@@ -199,8 +200,10 @@ impl SpendTx {
             return Some(path);
         }
         self.sigs
-            .recovery_paths()
-            .values()
+            .conditional_paths()
+            .iter()
+            .filter(|(cond, _)| !matches!(cond, PathCondition::HashLock(_)))
+            .map(|(_, path)| path)
             .find(|&path| path.sigs_count >= path.threshold)
     }
 
@@ -210,7 +213,7 @@ impl SpendTx {
             signers.insert(*fg);
         }
 
-        for path in self.sigs.recovery_paths().values() {
+        for path in self.sigs.conditional_paths().values() {
             for fg in path.signed_pubkeys.keys() {
                 signers.insert(*fg);
             }
@@ -267,9 +270,9 @@ impl Labelled for SpendTx {
                 txid,
                 vout: vout as u32,
             }));
-            items.push(LabelItem::Address(
-                Address::from_script(&output.script_pubkey, self.network).unwrap(),
-            ));
+            if let Ok(address) = Address::from_script(&output.script_pubkey, self.network) {
+                items.push(LabelItem::Address(address));
+            }
         }
         items
     }
@@ -48,6 +48,8 @@ pub enum DescriptorTemplate {
     SimpleInheritance,
     Custom,
     MultisigSecurity,
+    DecayingMultisig,
+    ExpandingMultisig,
 }
 
 #[derive(Clone)]
@@ -43,7 +43,27 @@ pub enum Message {
     HardwareWallets(HardwareWalletMessage),
     WalletRegistered(Result<(Fingerprint, Option<[u8; 32]>), Error>),
     MnemonicWord(usize, String),
+    MnemonicWordCount(usize),
+    MnemonicPassphrase(String),
     ImportMnemonic(bool),
+    Slip39Shares(Slip39Shares),
+}
+
+#[derive(Debug, Clone)]
+pub enum Slip39Shares {
+    /// Whether to use SLIP-39 shares instead of a single BIP39 mnemonic.
+    UseShares(bool),
+    GroupThresholdSelected(u8),
+    /// The member threshold of the group at this index.
+    ThresholdSelected(usize, u8),
+    /// The member count of the group at this index.
+    CountSelected(usize, u8),
+    AddGroup,
+    RemoveGroup(usize),
+    Generate,
+    ShareEdited(usize, String),
+    AddShare,
+    RemoveShare(usize),
 }
 
 #[derive(Debug, Clone)]
@@ -113,6 +133,8 @@ pub enum InternalBitcoindMsg {
 pub enum DefineDescriptor {
     ChangeTemplate(context::DescriptorTemplate),
     ImportDescriptor(String),
+    /// The BIP-129 token to decrypt an imported descriptor record with.
+    BsmsTokenEdited(String),
     KeysEdited(Vec<(usize, usize)>, Key),
     KeysEdit(Vec<(usize, usize)>),
     Path(usize, DefinePath),
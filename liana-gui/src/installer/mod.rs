@@ -118,7 +118,7 @@ impl Installer {
             hws: HardwareWallets::new(destination_path.clone(), network),
             steps: match user_flow {
                 UserFlow::CreateWallet => vec![
-                    ChooseDescriptorTemplate::default().into(),
+                    ChooseDescriptorTemplate::new(network, signer.clone()).into(),
                     DescriptorTemplateDescription::default().into(),
                     DefineDescriptor::new(network, signer.clone()).into(),
                     BackupMnemonic::new(signer.clone()).into(),
@@ -4,4 +4,6 @@ pub const DEFINE_DESCRIPTOR_FINGERPRINT_TOOLTIP: &str =
     "The alias is applied on all the keys derived from the same seed";
 pub const REGISTER_DESCRIPTOR_HELP: &str = "To be used with the wallet, a signing device needs the descriptor. If the descriptor contains one or more keys imported from an external signing device, the descriptor must be registered on it. Registration confirms that the device is able to handle the policy. Registration on a device is not a substitute for backing up the descriptor.";
 pub const MNEMONIC_HELP: &str = "A hot key generated on this computer was used for creating this wallet. It needs to be backed up. \n Keep it in a safe place. Never share it with anyone.";
+pub const SLIP39_BACKUP_HELP: &str = "The key can be split into several shares, a number of which are needed to recover it. Shares can be organized in groups, for instance one per family member or trusted party, a number of which are needed. Give each share to a different person or keep them in different places. A single share reveals nothing about the key. \n These shares are specific to Liana: they encode the mnemonic of the key, and not its master secret like other SLIP-39 wallets do. They can only be used to recover the key in Liana. A passphrase, if any, is not part of the shares and must be backed up separately.";
+pub const SLIP39_RECOVER_HELP: &str = "Enter the SLIP-39 shares needed to recover the key, one per line. Only shares created by Liana can be used: the shares created by other SLIP-39 wallets would recover a different key.";
 pub const RECOVER_MNEMONIC_HELP: &str = "If you were using a hot key (a key stored on the computer) in your wallet, you will need to recover it from mnemonics to be able to sign transactions again. Otherwise you can directly go the next step.";
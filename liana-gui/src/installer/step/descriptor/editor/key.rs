@@ -3,11 +3,12 @@ use std::str::FromStr;
 use std::sync::{Arc, Mutex};
 
 use iced::{Subscription, Task};
+use liana::bsms;
 use liana::miniscript::bitcoin::bip32::Xpub;
 use liana::miniscript::{
     bitcoin::{
         bip32::{DerivationPath, Fingerprint},
-        Network,
+        secp256k1, Network,
     },
     descriptor::{DerivPaths, DescriptorMultiXKey, DescriptorPublicKey, DescriptorXKey, Wildcard},
 };
@@ -277,7 +278,26 @@ impl super::DescriptorEditModal for EditXpubModal {
                     self.form_name.value = name;
                 }
                 message::ImportKeyModal::XPubEdited(s) => {
-                    if let Ok(DescriptorPublicKey::XPub(key)) = DescriptorPublicKey::from_str(&s) {
+                    // The key may also be given as a BIP-129 key record of a setup without
+                    // encryption. Its signature is then verified and its description used as alias.
+                    let (key, alias) = if s.trim().starts_with(bsms::BSMS_VERSION) {
+                        let secp = secp256k1::Secp256k1::verification_only();
+                        bsms::KeyRecord::from_str(&s)
+                            .ok()
+                            .filter(|record| {
+                                !record.token().is_encrypted() && record.verify(&secp).is_ok()
+                            })
+                            .map(|record| {
+                                (
+                                    Some(DescriptorPublicKey::XPub(record.descriptor_xkey())),
+                                    Some(record.description().to_string()),
+                                )
+                            })
+                            .unwrap_or((None, None))
+                    } else {
+                        (DescriptorPublicKey::from_str(&s).ok(), None)
+                    };
+                    if let Some(DescriptorPublicKey::XPub(key)) = key {
                         self.chosen_signer = None;
                         if !key.derivation_path.is_master() {
                             self.form_xpub.valid = false;
@@ -297,7 +317,7 @@ impl super::DescriptorEditModal for EditXpubModal {
                                     device_kind: None,
                                     device_version: None,
                                 });
-                                self.form_name.value = "".to_string();
+                                self.form_name.value = alias.unwrap_or_default();
                                 self.form_name.valid = true;
                             }
                         } else {
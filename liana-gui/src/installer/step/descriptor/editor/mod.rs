@@ -82,6 +82,11 @@ impl Path {
         self
     }
 
+    pub fn with_sequence(mut self, sequence: u16) -> Self {
+        self.sequence = sequence;
+        self
+    }
+
     pub fn with_threshold(mut self, t: usize) -> Self {
         self.threshold = if t > self.keys.len() {
             self.keys.len()
@@ -187,6 +192,24 @@ impl DefineDescriptor {
                         Path::new_recovery_path().with_n_keys(3).with_threshold(2),
                     ];
                 }
+                DescriptorTemplate::DecayingMultisig => {
+                    self.paths = vec![
+                        Path::new_primary_path().with_n_keys(3).with_threshold(2),
+                        Path::new_recovery_path().with_n_keys(3).with_threshold(1),
+                    ];
+                }
+                DescriptorTemplate::ExpandingMultisig => {
+                    // Each recovery path adds a key and must have its own timelock, the last one
+                    // defaults to the longest.
+                    self.paths = vec![
+                        Path::new_primary_path().with_n_keys(2).with_threshold(2),
+                        Path::new_recovery_path()
+                            .with_n_keys(3)
+                            .with_threshold(2)
+                            .with_sequence(26_280),
+                        Path::new_recovery_path().with_n_keys(4).with_threshold(1),
+                    ];
+                }
                 DescriptorTemplate::Custom => {
                     self.paths = vec![Path::new_primary_path(), Path::new_recovery_path()];
                 }
@@ -198,6 +221,19 @@ impl DefineDescriptor {
 
 impl Step for DefineDescriptor {
     fn load_context(&mut self, ctx: &Context) {
+        // The hot signer may have been generated anew since, forget about the previous one.
+        let signer_fingerprint = self.signer.lock().unwrap().fingerprint();
+        if signer_fingerprint != self.signer_fingerprint {
+            self.keys.remove(&self.signer_fingerprint);
+            for path in &mut self.paths {
+                for key in &mut path.keys {
+                    if *key == Some(self.signer_fingerprint) {
+                        *key = None;
+                    }
+                }
+            }
+            self.signer_fingerprint = signer_fingerprint;
+        }
         self.load_template(ctx.descriptor_template)
     }
     // form value is set as valid each time it is edited.
@@ -491,6 +527,35 @@ impl Step for DefineDescriptor {
                     self.valid(),
                 )
             }
+            DescriptorTemplate::DecayingMultisig => {
+                view::editor::template::decaying_multisig::decaying_multisig_template(
+                    progress,
+                    self.use_taproot,
+                    self.path_keys(&self.paths[0]),
+                    self.paths[0].threshold,
+                    self.path_keys(&self.paths[1]),
+                    self.paths[1].sequence,
+                    self.paths[1].threshold,
+                    self.valid(),
+                )
+            }
+            DescriptorTemplate::ExpandingMultisig => {
+                view::editor::template::expanding_multisig::expanding_multisig_template(
+                    progress,
+                    self.use_taproot,
+                    self.path_keys(&self.paths[0]),
+                    self.paths[1..]
+                        .iter()
+                        .map(|p| view::editor::template::custom::Path {
+                            keys: self.path_keys(p),
+                            sequence: p.sequence,
+                            duplicate_sequence: p.duplicate_sequence,
+                            threshold: p.threshold,
+                        })
+                        .collect(),
+                    self.valid(),
+                )
+            }
             DescriptorTemplate::Custom => view::editor::template::custom::custom_template(
                 progress,
                 self.use_taproot,
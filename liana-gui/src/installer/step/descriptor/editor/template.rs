@@ -1,5 +1,8 @@
+use std::sync::{Arc, Mutex};
+
 use iced::Task;
 
+use liana::miniscript::bitcoin::Network;
 use liana_ui::widget::Element;
 
 use crate::{
@@ -10,11 +13,27 @@ use crate::{
         step::{Context, Step},
         view,
     },
+    signer::Signer,
 };
 
-#[derive(Default)]
 pub struct ChooseDescriptorTemplate {
     template: DescriptorTemplate,
+    network: Network,
+    // The hot signer is generated anew if another number of words is chosen for its mnemonic.
+    signer: Arc<Mutex<Signer>>,
+    word_count: usize,
+}
+
+impl ChooseDescriptorTemplate {
+    pub fn new(network: Network, signer: Arc<Mutex<Signer>>) -> Self {
+        let word_count = signer.lock().unwrap().mnemonic().len();
+        Self {
+            template: DescriptorTemplate::default(),
+            network,
+            signer,
+            word_count,
+        }
+    }
 }
 
 impl From<ChooseDescriptorTemplate> for Box<dyn Step> {
@@ -24,11 +43,24 @@ impl From<ChooseDescriptorTemplate> for Box<dyn Step> {
 }
 impl Step for ChooseDescriptorTemplate {
     fn update(&mut self, _hws: &mut HardwareWallets, message: Message) -> Task<Message> {
-        if let Message::SelectDescriptorTemplate(template) = message {
-            self.template = template;
-            Task::perform(async move {}, |_| Message::Next)
-        } else {
-            Task::none()
+        match message {
+            Message::SelectDescriptorTemplate(template) => {
+                self.template = template;
+                Task::perform(async move {}, |_| Message::Next)
+            }
+            Message::MnemonicWordCount(count) => {
+                if count != self.word_count {
+                    match Signer::generate_with(self.network, count) {
+                        Ok(signer) => {
+                            *self.signer.lock().unwrap() = signer;
+                            self.word_count = count;
+                        }
+                        Err(e) => tracing::error!("Failed to generate hot signer: {}", e),
+                    }
+                }
+                Task::none()
+            }
+            _ => Task::none(),
         }
     }
 
@@ -43,7 +75,7 @@ impl Step for ChooseDescriptorTemplate {
         progress: (usize, usize),
         _email: Option<&'a str>,
     ) -> Element<Message> {
-        view::editor::template::choose_descriptor_template(progress)
+        view::editor::template::choose_descriptor_template(progress, self.word_count)
     }
 }
 
@@ -76,6 +108,12 @@ impl Step for DescriptorTemplateDescription {
             DescriptorTemplate::MultisigSecurity => {
                 view::editor::template::multisig_security_wallet::multisig_security_template_description(progress)
             }
+            DescriptorTemplate::DecayingMultisig => {
+                view::editor::template::decaying_multisig::decaying_multisig_template_description(progress)
+            }
+            DescriptorTemplate::ExpandingMultisig => {
+                view::editor::template::expanding_multisig::expanding_multisig_template_description(progress)
+            }
             DescriptorTemplate::Custom => {
                 view::editor::template::custom::custom_template_description(progress)
             }
@@ -7,8 +7,9 @@ use std::{
 
 use iced::{Subscription, Task};
 use liana::{
+    bsms,
     descriptors::LianaDescriptor,
-    miniscript::bitcoin::{bip32::Fingerprint, Network},
+    miniscript::bitcoin::{bip32::Fingerprint, secp256k1, Network},
 };
 
 use liana_ui::{component::form, widget::Element};
@@ -28,6 +29,8 @@ use crate::{
 pub struct ImportDescriptor {
     network: Network,
     imported_descriptor: form::Value<String>,
+    // The token to decrypt a BIP-129 descriptor record, if it is encrypted.
+    bsms_token: form::Value<String>,
     wrong_network: bool,
     error: Option<String>,
 }
@@ -37,14 +40,49 @@ impl ImportDescriptor {
         Self {
             network,
             imported_descriptor: form::Value::default(),
+            bsms_token: form::Value::default(),
             wrong_network: false,
             error: None,
         }
     }
 
+    // Parse the imported descriptor, which may also be given as a BIP-129 descriptor record,
+    // encrypted if a token was given. In this case the first address in the record is checked
+    // against the descriptor.
+    fn parse_descriptor(&mut self, network: Network) -> Option<LianaDescriptor> {
+        let value = self.imported_descriptor.value.trim();
+        let token = self.bsms_token.value.trim();
+        let secp = secp256k1::Secp256k1::verification_only();
+        let record = if value.starts_with(bsms::BSMS_VERSION) {
+            bsms::DescriptorRecord::parse(value, network, &secp)
+        } else if !token.is_empty() {
+            match bsms::Token::from_str(token) {
+                Ok(token) => bsms::DescriptorRecord::decrypt(&token, value, network, &secp),
+                Err(_) => {
+                    self.bsms_token.valid = false;
+                    self.error = None;
+                    return None;
+                }
+            }
+        } else {
+            self.error = None;
+            return LianaDescriptor::from_str(value).ok();
+        };
+        match record {
+            Ok(record) => {
+                self.error = None;
+                Some(record.descriptor().clone())
+            }
+            Err(e) => {
+                self.error = Some(e.to_string());
+                None
+            }
+        }
+    }
+
     fn check_descriptor(&mut self, network: Network) -> Option<LianaDescriptor> {
         if !self.imported_descriptor.value.is_empty() {
-            if let Ok(desc) = LianaDescriptor::from_str(&self.imported_descriptor.value) {
+            if let Some(desc) = self.parse_descriptor(network) {
                 if network == Network::Bitcoin {
                     self.imported_descriptor.valid = desc.all_xpubs_net_is(network);
                 } else {
@@ -64,6 +102,7 @@ impl ImportDescriptor {
             }
         } else {
             self.wrong_network = false;
+            self.error = None;
             self.imported_descriptor.valid = true;
             None
         }
@@ -78,11 +117,17 @@ impl Step for ImportDescriptor {
     // form value is set as valid each time it is edited.
     // Verification of the values is happening when the user click on Next button.
     fn update(&mut self, _hws: &mut HardwareWallets, message: Message) -> Task<Message> {
-        if let Message::DefineDescriptor(message::DefineDescriptor::ImportDescriptor(desc)) =
-            message
-        {
-            self.imported_descriptor.value = desc;
-            self.check_descriptor(self.network);
+        match message {
+            Message::DefineDescriptor(message::DefineDescriptor::ImportDescriptor(desc)) => {
+                self.imported_descriptor.value = desc;
+                self.check_descriptor(self.network);
+            }
+            Message::DefineDescriptor(message::DefineDescriptor::BsmsTokenEdited(token)) => {
+                self.bsms_token.value = token;
+                self.bsms_token.valid = true;
+                self.check_descriptor(self.network);
+            }
+            _ => {}
         }
         Task::none()
     }
@@ -110,6 +155,7 @@ impl Step for ImportDescriptor {
             progress,
             email,
             &self.imported_descriptor,
+            &self.bsms_token,
             self.wrong_network,
             self.error.as_ref(),
         )
@@ -2,18 +2,61 @@ use std::collections::HashSet;
 use std::sync::{Arc, Mutex};
 
 use iced::Task;
-use liana::{bip39, signer::HotSigner};
+use liana::{bip39, signer::HotSigner, slip39};
 
 use liana_ui::widget::Element;
 
 use crate::{
     hw::HardwareWallets,
-    installer::{context::Context, message::Message, step::Step, view},
+    installer::{
+        context::Context,
+        message::{Message, Slip39Shares},
+        step::Step,
+        view,
+    },
     signer::Signer,
 };
 
+/// The possible number of SLIP-39 shares, and thresholds.
+pub const SLIP39_SHARE_COUNTS: [u8; 16] = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16];
+
+/// The backup of the hot key as groups of SLIP-39 shares.
+pub struct Slip39Backup {
+    pub use_shares: bool,
+    /// The number of groups needed to recover the key.
+    pub group_threshold: u8,
+    pub groups: Vec<slip39::Group>,
+    /// The shares of each group.
+    pub shares: Vec<Vec<String>>,
+    pub error: Option<String>,
+}
+
+impl Slip39Backup {
+    // The parameters changed, the previously generated shares are now irrelevant.
+    fn reset_shares(&mut self) {
+        self.shares = Vec::new();
+        self.error = None;
+    }
+}
+
+impl Default for Slip39Backup {
+    fn default() -> Self {
+        Self {
+            use_shares: false,
+            group_threshold: 1,
+            groups: vec![slip39::Group {
+                member_threshold: 2,
+                member_count: 3,
+            }],
+            shares: Vec::new(),
+            error: None,
+        }
+    }
+}
+
 pub struct BackupMnemonic {
-    words: [&'static str; 12],
+    words: Vec<&'static str>,
+    slip39: Slip39Backup,
     done: bool,
     signer: Arc<Mutex<Signer>>,
 }
@@ -24,6 +67,7 @@ impl BackupMnemonic {
         Self {
             done: false,
             words,
+            slip39: Slip39Backup::default(),
             signer,
         }
     }
@@ -36,9 +80,81 @@ impl From<BackupMnemonic> for Box<dyn Step> {
 }
 
 impl Step for BackupMnemonic {
+    fn load_context(&mut self, _ctx: &Context) {
+        // The hot signer may have been generated anew with another number of words.
+        let words = self.signer.lock().unwrap().mnemonic();
+        if words != self.words {
+            self.words = words;
+            self.slip39 = Slip39Backup::default();
+            self.done = false;
+        }
+    }
     fn update(&mut self, _hws: &mut HardwareWallets, message: Message) -> Task<Message> {
-        if let Message::UserActionDone(done) = message {
-            self.done = done;
+        match message {
+            Message::UserActionDone(done) => self.done = done,
+            Message::Slip39Shares(msg) => {
+                // Any change to the backup means it has to be done again.
+                self.done = false;
+                match msg {
+                    Slip39Shares::UseShares(use_shares) => self.slip39.use_shares = use_shares,
+                    Slip39Shares::GroupThresholdSelected(threshold) => {
+                        self.slip39.group_threshold = threshold.min(self.slip39.groups.len() as u8);
+                        self.slip39.reset_shares();
+                    }
+                    Slip39Shares::ThresholdSelected(i, threshold) => {
+                        if let Some(group) = self.slip39.groups.get_mut(i) {
+                            group.member_threshold = threshold;
+                            group.member_count = group.member_count.max(threshold);
+                        }
+                        self.slip39.reset_shares();
+                    }
+                    Slip39Shares::CountSelected(i, count) => {
+                        if let Some(group) = self.slip39.groups.get_mut(i) {
+                            group.member_count = count;
+                            group.member_threshold = group.member_threshold.min(count);
+                        }
+                        self.slip39.reset_shares();
+                    }
+                    Slip39Shares::AddGroup => {
+                        if self.slip39.groups.len() < slip39::MAX_SHARE_COUNT as usize {
+                            self.slip39.groups.push(slip39::Group {
+                                member_threshold: 1,
+                                member_count: 1,
+                            });
+                        }
+                        self.slip39.reset_shares();
+                    }
+                    Slip39Shares::RemoveGroup(i) => {
+                        if i < self.slip39.groups.len() && self.slip39.groups.len() > 1 {
+                            self.slip39.groups.remove(i);
+                        }
+                        self.slip39.group_threshold = self
+                            .slip39
+                            .group_threshold
+                            .min(self.slip39.groups.len() as u8);
+                        self.slip39.reset_shares();
+                    }
+                    Slip39Shares::Generate => {
+                        match self
+                            .signer
+                            .lock()
+                            .unwrap()
+                            .slip39_shares(self.slip39.group_threshold, &self.slip39.groups)
+                        {
+                            Ok(shares) => {
+                                self.slip39.shares = shares;
+                                self.slip39.error = None;
+                            }
+                            Err(e) => {
+                                self.slip39.shares = Vec::new();
+                                self.slip39.error = Some(e.to_string());
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            _ => {}
         }
         Task::none()
     }
@@ -57,13 +173,19 @@ impl Step for BackupMnemonic {
         progress: (usize, usize),
         email: Option<&'a str>,
     ) -> Element<Message> {
-        view::backup_mnemonic(progress, email, &self.words, self.done)
+        view::backup_mnemonic(progress, email, &self.words, &self.slip39, self.done)
     }
 }
 
+/// The number of words of the mnemonics which can be imported.
+pub const MNEMONIC_WORD_COUNTS: [usize; 3] = [12, 18, 24];
+
 pub struct RecoverMnemonic {
     language: bip39::Language,
-    words: [(String, bool); 12],
+    words: Vec<(String, bool)>,
+    // Set when recovering from SLIP-39 shares instead of a BIP39 mnemonic.
+    slip39_shares: Option<Vec<String>>,
+    passphrase: String,
     current: usize,
     suggestions: Vec<String>,
     error: Option<String>,
@@ -75,7 +197,9 @@ impl Default for RecoverMnemonic {
     fn default() -> Self {
         Self {
             language: bip39::Language::English,
-            words: Default::default(),
+            words: vec![Default::default(); MNEMONIC_WORD_COUNTS[0]],
+            slip39_shares: None,
+            passphrase: String::new(),
             current: 0,
             suggestions: Vec::new(),
             error: None,
@@ -113,6 +237,50 @@ impl Step for RecoverMnemonic {
                     *word = value;
                 }
             }
+            Message::MnemonicWordCount(count) => {
+                self.words.resize(count, Default::default());
+                self.current = self.current.min(count - 1);
+                self.suggestions = Vec::new();
+                self.error = None;
+            }
+            Message::MnemonicPassphrase(passphrase) => {
+                self.passphrase = passphrase;
+                self.error = None;
+            }
+            Message::Slip39Shares(msg) => {
+                self.error = None;
+                match msg {
+                    Slip39Shares::UseShares(use_shares) => {
+                        self.slip39_shares = if use_shares {
+                            Some(vec![String::new()])
+                        } else {
+                            None
+                        };
+                    }
+                    Slip39Shares::ShareEdited(i, value) => {
+                        if let Some(share) = self
+                            .slip39_shares
+                            .as_mut()
+                            .and_then(|shares| shares.get_mut(i))
+                        {
+                            *share = value;
+                        }
+                    }
+                    Slip39Shares::AddShare => {
+                        if let Some(shares) = self.slip39_shares.as_mut() {
+                            shares.push(String::new());
+                        }
+                    }
+                    Slip39Shares::RemoveShare(i) => {
+                        if let Some(shares) = self.slip39_shares.as_mut() {
+                            if i < shares.len() && shares.len() > 1 {
+                                shares.remove(i);
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
             Message::ImportMnemonic(recover) => self.recover = recover,
             Message::Skip => {
                 self.skip = true;
@@ -130,13 +298,26 @@ impl Step for RecoverMnemonic {
             return true;
         }
 
-        let words: Vec<String> = self
-            .words
-            .iter()
-            .filter_map(|(s, valid)| if *valid { Some(s.clone()) } else { None })
-            .collect();
-
-        let seed = match HotSigner::from_str(ctx.bitcoin_config.network, &words.join(" ")) {
+        let seed = if let Some(shares) = &self.slip39_shares {
+            let shares: Vec<&str> = shares
+                .iter()
+                .map(|s| s.trim())
+                .filter(|s| !s.is_empty())
+                .collect();
+            HotSigner::from_slip39_shares(ctx.bitcoin_config.network, &shares, &self.passphrase)
+        } else {
+            let words: Vec<String> = self
+                .words
+                .iter()
+                .filter_map(|(s, valid)| if *valid { Some(s.clone()) } else { None })
+                .collect();
+            HotSigner::from_str_with_passphrase(
+                ctx.bitcoin_config.network,
+                &words.join(" "),
+                &self.passphrase,
+            )
+        };
+        let seed = match seed {
             Ok(seed) => seed,
             Err(e) => {
                 self.error = Some(e.to_string());
@@ -153,7 +334,7 @@ impl Step for RecoverMnemonic {
             for (fingerprint, _) in info.primary_path().thresh_origins().1.iter() {
                 descriptor_keys.insert(*fingerprint);
             }
-            for (_, path) in info.recovery_paths().iter() {
+            for path in info.conditional_paths().values() {
                 for (fingerprint, _) in path.thresh_origins().1.iter() {
                     descriptor_keys.insert(*fingerprint);
                 }
@@ -178,6 +359,8 @@ impl Step for RecoverMnemonic {
             progress,
             email,
             &self.words,
+            self.slip39_shares.as_deref(),
+            &self.passphrase,
             self.current,
             &self.suggestions,
             self.recover,
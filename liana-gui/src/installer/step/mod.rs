@@ -17,7 +17,9 @@ pub use descriptor::{
 };
 
 pub use backend::{ChooseBackend, ImportRemoteWallet, RemoteBackendLogin};
-pub use mnemonic::{BackupMnemonic, RecoverMnemonic};
+pub use mnemonic::{
+    BackupMnemonic, RecoverMnemonic, Slip39Backup, MNEMONIC_WORD_COUNTS, SLIP39_SHARE_COUNTS,
+};
 pub use share_xpubs::ShareXpubs;
 
 use std::path::PathBuf;
@@ -1,9 +1,12 @@
 use std::sync::{Arc, Mutex};
 
 use iced::{Subscription, Task};
-use liana::miniscript::bitcoin::{
-    bip32::{ChildNumber, Fingerprint},
-    Network,
+use liana::{
+    bsms,
+    miniscript::bitcoin::{
+        bip32::{ChildNumber, Fingerprint},
+        Network,
+    },
 };
 
 use liana_ui::widget::Element;
@@ -31,8 +34,10 @@ pub struct HardwareWalletXpubs {
 pub struct SignerXpubs {
     signer: Arc<Mutex<Signer>>,
     xpubs: Vec<String>,
+    // The BIP-129 key record for the xpub, for a coordinator using the BSMS setup.
+    bsms_record: Option<String>,
     next_account: ChildNumber,
-    words: [&'static str; 12],
+    words: Vec<&'static str>,
     did_backup: bool,
 }
 
@@ -43,6 +48,7 @@ impl SignerXpubs {
             words,
             signer,
             xpubs: Vec::new(),
+            bsms_record: None,
             next_account: ChildNumber::from_hardened_idx(0).unwrap(),
             did_backup: false,
         }
@@ -59,10 +65,24 @@ impl SignerXpubs {
             derivation_path.to_string().trim_start_matches("m/"),
             signer.get_extended_pubkey(&derivation_path)
         )];
+        self.bsms_record = Some(
+            signer
+                .bsms_key_record(
+                    bsms::Token::no_encryption(),
+                    &derivation_path,
+                    "Liana hot signer".to_string(),
+                )
+                .to_string(),
+        );
     }
 
     pub fn view(&self) -> Element<Message> {
-        view::signer_xpubs(&self.xpubs, &self.words, self.did_backup)
+        view::signer_xpubs(
+            &self.xpubs,
+            self.bsms_record.as_ref(),
+            &self.words,
+            self.did_backup,
+        )
     }
 }
 
@@ -304,7 +304,7 @@ pub fn edit_key_modal<'a>(
                                     .push(
                                         Row::new()
                                             .align_y(Alignment::Center)
-                                            .push(p1_regular("Enter an extended public key:").width(Length::Fill))
+                                            .push(p1_regular("Enter an extended public key or a BIP-129 key record:").width(Length::Fill))
                                             .push(image::success_mark_icon().width(Length::Fixed(50.0)))
                                     )
                                     .push(
@@ -318,9 +318,9 @@ pub fn edit_key_modal<'a>(
                                                                 message::ImportKeyModal::XPubEdited(msg),),)
                                                     })
                                                     .warning(if network == bitcoin::Network::Bitcoin {
-                                                        "Please enter correct xpub with origin and without appended derivation path"
+                                                        "Please enter correct xpub with origin and without appended derivation path, or a valid BIP-129 key record"
                                                     } else {
-                                                        "Please enter correct tpub with origin and without appended derivation path"
+                                                        "Please enter correct tpub with origin and without appended derivation path, or a valid BIP-129 key record"
                                                     })
                                                     .size(text::P1_SIZE)
                                                     .padding(10),
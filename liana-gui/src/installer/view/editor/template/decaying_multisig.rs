@@ -0,0 +1,221 @@
+use iced::{alignment, widget::Space, Alignment, Length};
+
+use liana_ui::{
+    color,
+    component::{
+        button, collapse,
+        text::{h3, p1_regular, text, Text, H3_SIZE},
+    },
+    icon, theme,
+    widget::*,
+};
+
+use crate::installer::{
+    context,
+    message::{self, Message},
+    step::descriptor::editor::key::Key,
+    view::{
+        editor::{
+            define_descriptor_advanced_settings, defined_key, path, undefined_key,
+            uneditable_defined_key,
+        },
+        layout,
+    },
+};
+
+pub fn decaying_multisig_template_description(
+    progress: (usize, usize),
+) -> Element<'static, Message> {
+    layout(
+        progress,
+        None,
+        "Introduction",
+        Column::new()
+            .align_x(Alignment::Start)
+            .push(h3("Decaying multisig wallet"))
+            .max_width(800.0)
+            .push(Container::new(
+                p1_regular("For this setup you will need 3 keys. For security reasons, we suggest you use a separate Hardware Wallet for each key.")
+                .style(theme::text::secondary)
+                .align_x(alignment::Horizontal::Left)
+            ).align_x(alignment::Horizontal::Left).width(Length::Fill))
+            .push(Row::new()
+                .spacing(30)
+                .push(
+                    Row::new()
+                    .align_y(Alignment::Center)
+                    .spacing(10)
+                    .push(icon::round_key_icon().size(H3_SIZE).style(theme::text::success))
+                    .push(p1_regular("Key #1").bold())
+                ).push(
+                    Row::new()
+                    .align_y(Alignment::Center)
+                    .spacing(10)
+                    .push(icon::round_key_icon().size(H3_SIZE).style(theme::text::success))
+                    .push(p1_regular("Key #2").bold())
+                ).push(
+                    Row::new()
+                    .align_y(Alignment::Center)
+                    .spacing(10)
+                    .push(icon::round_key_icon().size(H3_SIZE).style(theme::text::success))
+                    .push(p1_regular("Key #3").bold())
+            ))
+            .push(Container::new(
+                p1_regular("Any 2 of the 3 keys will always be able to spend. In case two of your keys become unavailable, after a period of inactivity you will be able to recover your funds using any single one of the 3 keys (1-of-3 multisig).")
+                .style(theme::text::secondary)
+                .align_x(alignment::Horizontal::Left)
+            ).align_x(alignment::Horizontal::Left).width(Length::Fill))
+            .push(Row::new().push(Space::with_width(Length::Fill)).push(button::primary(None, "Next").width(Length::Fixed(200.0)).on_press(Message::Next)))
+            .push(Space::with_height(50.0))
+            .spacing(20),
+        true,
+        Some(Message::Previous),
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn decaying_multisig_template<'a>(
+    progress: (usize, usize),
+    use_taproot: bool,
+    primary_keys: Vec<Option<&'a Key>>,
+    primary_threshold: usize,
+    recovery_keys: Vec<Option<&'a Key>>,
+    sequence: u16,
+    threshold: usize,
+    valid: bool,
+) -> Element<'a, Message> {
+    layout(
+        progress,
+        None,
+        "Set keys",
+        Column::new()
+            .align_x(Alignment::Start)
+            .max_width(1000.0)
+            .push(collapse::Collapse::new(
+                || {
+                    Button::new(
+                        Row::new()
+                            .align_y(Alignment::Center)
+                            .spacing(10)
+                            .push(text("Advanced settings").small().bold())
+                            .push(icon::collapse_icon()),
+                    )
+                    .style(theme::button::transparent)
+                },
+                || {
+                    Button::new(
+                        Row::new()
+                            .align_y(Alignment::Center)
+                            .spacing(10)
+                            .push(text("Advanced settings").small().bold())
+                            .push(icon::collapsed_icon()),
+                    )
+                    .style(theme::button::transparent)
+                },
+                move || define_descriptor_advanced_settings(use_taproot),
+            ))
+            .push(
+                path(
+                    color::GREEN,
+                    None,
+                    0,
+                    false,
+                    primary_threshold,
+                    primary_keys
+                        .iter()
+                        .enumerate()
+                        .map(|(i, primary_key)| {
+                            if let Some(key) = primary_key {
+                                defined_key(
+                                    &key.name,
+                                    color::GREEN,
+                                    format!("Key #{}", i + 1),
+                                    if use_taproot && !key.is_compatible_taproot {
+                                        Some("This device does not support Taproot")
+                                    } else {
+                                        None
+                                    },
+                                    true,
+                                )
+                            } else {
+                                undefined_key(
+                                    color::GREEN,
+                                    format!("Key #{}", i + 1),
+                                    !primary_keys[0..i].iter().any(|k| k.is_none()),
+                                    true,
+                                )
+                            }
+                            .map(move |msg| message::DefinePath::Key(i, msg))
+                        })
+                        .collect(),
+                    true,
+                )
+                .map(move |msg| {
+                    // The same keys are used in both paths.
+                    if let message::DefinePath::Key(i, message::DefineKey::Edit) = msg {
+                        Message::DefineDescriptor(message::DefineDescriptor::KeysEdit(vec![
+                            (0, i),
+                            (1, i),
+                        ]))
+                    } else {
+                        Message::DefineDescriptor(message::DefineDescriptor::Path(0, msg))
+                    }
+                }),
+            )
+            .push(
+                path(
+                    color::ORANGE,
+                    None,
+                    sequence,
+                    false,
+                    threshold,
+                    recovery_keys
+                        .iter()
+                        .enumerate()
+                        .map(|(j, recovery_key)| {
+                            if let Some(key) = recovery_key {
+                                uneditable_defined_key(
+                                    &key.name,
+                                    color::GREEN,
+                                    format!("Key #{}", j + 1),
+                                    if use_taproot && !key.is_compatible_taproot {
+                                        Some("This device does not support Taproot")
+                                    } else {
+                                        None
+                                    },
+                                )
+                            } else {
+                                // Keys are set from the primary path.
+                                undefined_key(color::GREEN, format!("Key #{}", j + 1), false, true)
+                            }
+                            .map(move |msg| message::DefinePath::Key(j, msg))
+                        })
+                        .collect(),
+                    true,
+                )
+                .map(|msg| Message::DefineDescriptor(message::DefineDescriptor::Path(1, msg))),
+            )
+            .push(
+                Row::new()
+                    .push(
+                        button::secondary(None, "Customize")
+                            .width(Length::Fixed(200.0))
+                            .on_press(Message::DefineDescriptor(
+                                message::DefineDescriptor::ChangeTemplate(
+                                    context::DescriptorTemplate::Custom,
+                                ),
+                            )),
+                    )
+                    .push(Space::with_width(Length::Fill))
+                    .push(
+                        button::primary(None, "Continue")
+                            .width(Length::Fixed(200.0))
+                            .on_press_maybe(if valid { Some(Message::Next) } else { None }),
+                    ),
+            )
+            .push(Space::with_height(100.0))
+            .spacing(20),
+        true,
+        Some(Message::Previous),
+    )
+}
@@ -0,0 +1,275 @@
+use iced::{alignment, widget::Space, Alignment, Length};
+
+use liana_ui::{
+    color,
+    component::{
+        button, collapse,
+        text::{h3, p1_regular, text, Text, H3_SIZE},
+    },
+    icon, theme,
+    widget::*,
+};
+
+use crate::installer::{
+    context,
+    message::{self, Message},
+    step::descriptor::editor::key::Key,
+    view::{
+        editor::{
+            define_descriptor_advanced_settings, defined_key, path, template::custom::Path,
+            undefined_key, uneditable_defined_key,
+        },
+        layout,
+    },
+};
+
+/// Name of the key introduced by the recovery path at the given index.
+fn added_key_name(recovery_index: usize) -> String {
+    if recovery_index == 0 {
+        "Recovery key".to_string()
+    } else {
+        "Heir key".to_string()
+    }
+}
+
+/// Name of the key at the given position in any of the paths.
+fn key_name(j: usize) -> String {
+    if j < 2 {
+        format!("Primary key #{}", j + 1)
+    } else {
+        added_key_name(j - 2)
+    }
+}
+
+pub fn expanding_multisig_template_description(
+    progress: (usize, usize),
+) -> Element<'static, Message> {
+    layout(
+        progress,
+        None,
+        "Introduction",
+        Column::new()
+            .align_x(Alignment::Start)
+            .push(h3("Expanding multisig wallet"))
+            .max_width(800.0)
+            .push(Container::new(
+                p1_regular("For this setup you will need 4 keys. For security reasons, we suggest you use a separate Hardware Wallet for each key.")
+                .style(theme::text::secondary)
+                .align_x(alignment::Horizontal::Left)
+            ).align_x(alignment::Horizontal::Left).width(Length::Fill))
+            .push(Row::new()
+                .spacing(30)
+                .push(
+                    Row::new()
+                    .align_y(Alignment::Center)
+                    .spacing(10)
+                    .push(icon::round_key_icon().size(H3_SIZE).style(theme::text::success))
+                    .push(p1_regular("Primary key #1").bold())
+                ).push(
+                    Row::new()
+                    .align_y(Alignment::Center)
+                    .spacing(10)
+                    .push(icon::round_key_icon().size(H3_SIZE).style(theme::text::success))
+                    .push(p1_regular("Primary key #2").bold())
+                ).push(
+                    Row::new()
+                    .align_y(Alignment::Center)
+                    .spacing(10)
+                    .push(icon::round_key_icon().size(H3_SIZE).style(theme::text::success))
+                    .push(p1_regular("Recovery key").bold())
+                ).push(
+                    Row::new()
+                    .align_y(Alignment::Center)
+                    .spacing(10)
+                    .push(icon::round_key_icon().size(H3_SIZE).style(theme::text::success))
+                    .push(p1_regular("Heir key").bold())
+            ))
+            .push(Container::new(
+                p1_regular("The two primary keys will always be able to spend together. After a period of inactivity, any 2 of the 2 primary keys and the recovery key will be able to spend (2-of-3 multisig). After a longer period of inactivity, any single one of the 4 keys will be able to spend (1-of-4).")
+                .style(theme::text::secondary)
+                .align_x(alignment::Horizontal::Left)
+            ).align_x(alignment::Horizontal::Left).width(Length::Fill))
+            .push(Row::new().push(Space::with_width(Length::Fill)).push(button::primary(None, "Next").width(Length::Fixed(200.0)).on_press(Message::Next)))
+            .push(Space::with_height(50.0))
+            .spacing(20),
+        true,
+        Some(Message::Previous),
+    )
+}
+
+pub fn expanding_multisig_template<'a>(
+    progress: (usize, usize),
+    use_taproot: bool,
+    primary_keys: Vec<Option<&'a Key>>,
+    recovery_paths: Vec<Path<'a>>,
+    valid: bool,
+) -> Element<'a, Message> {
+    let n_recovery_paths = recovery_paths.len();
+    layout(
+        progress,
+        None,
+        "Set keys",
+        Column::new()
+            .align_x(Alignment::Start)
+            .max_width(1000.0)
+            .push(collapse::Collapse::new(
+                || {
+                    Button::new(
+                        Row::new()
+                            .align_y(Alignment::Center)
+                            .spacing(10)
+                            .push(text("Advanced settings").small().bold())
+                            .push(icon::collapse_icon()),
+                    )
+                    .style(theme::button::transparent)
+                },
+                || {
+                    Button::new(
+                        Row::new()
+                            .align_y(Alignment::Center)
+                            .spacing(10)
+                            .push(text("Advanced settings").small().bold())
+                            .push(icon::collapsed_icon()),
+                    )
+                    .style(theme::button::transparent)
+                },
+                move || define_descriptor_advanced_settings(use_taproot),
+            ))
+            .push(
+                path(
+                    color::GREEN,
+                    None,
+                    0,
+                    false,
+                    2,
+                    primary_keys
+                        .iter()
+                        .enumerate()
+                        .map(|(i, primary_key)| {
+                            if let Some(key) = primary_key {
+                                defined_key(
+                                    &key.name,
+                                    color::GREEN,
+                                    key_name(i),
+                                    if use_taproot && !key.is_compatible_taproot {
+                                        Some("This device does not support Taproot")
+                                    } else {
+                                        None
+                                    },
+                                    true,
+                                )
+                            } else {
+                                undefined_key(
+                                    color::GREEN,
+                                    key_name(i),
+                                    !primary_keys[0..i].iter().any(|k| k.is_none()),
+                                    true,
+                                )
+                            }
+                            .map(move |msg| message::DefinePath::Key(i, msg))
+                        })
+                        .collect(),
+                    true,
+                )
+                .map(move |msg| {
+                    // The primary keys are part of every path.
+                    if let message::DefinePath::Key(i, message::DefineKey::Edit) = msg {
+                        Message::DefineDescriptor(message::DefineDescriptor::KeysEdit(
+                            (0..=n_recovery_paths).map(|p| (p, i)).collect(),
+                        ))
+                    } else {
+                        Message::DefineDescriptor(message::DefineDescriptor::Path(0, msg))
+                    }
+                }),
+            )
+            .push(recovery_paths.into_iter().enumerate().fold(
+                Column::new().spacing(20),
+                |col, (r, recovery_path)| {
+                    let n_keys = recovery_path.keys.len();
+                    col.push(
+                        path(
+                            color::ORANGE,
+                            None,
+                            recovery_path.sequence,
+                            recovery_path.duplicate_sequence,
+                            recovery_path.threshold,
+                            recovery_path
+                                .keys
+                                .iter()
+                                .enumerate()
+                                .map(|(j, recovery_key)| {
+                                    // Only the last key is introduced by this path, the other
+                                    // ones are set from the previous paths.
+                                    let added = j + 1 == n_keys;
+                                    let key_color =
+                                        if j < 2 { color::GREEN } else { color::ORANGE };
+                                    if let Some(key) = recovery_key {
+                                        let warning = if use_taproot && !key.is_compatible_taproot {
+                                            Some("This device does not support Taproot")
+                                        } else {
+                                            None
+                                        };
+                                        if added {
+                                            defined_key(
+                                                &key.name,
+                                                key_color,
+                                                key_name(j),
+                                                warning,
+                                                true,
+                                            )
+                                        } else {
+                                            uneditable_defined_key(
+                                                &key.name,
+                                                key_color,
+                                                key_name(j),
+                                                warning,
+                                            )
+                                        }
+                                    } else {
+                                        undefined_key(key_color, key_name(j), added, true)
+                                    }
+                                    .map(move |msg| message::DefinePath::Key(j, msg))
+                                })
+                                .collect(),
+                            true,
+                        )
+                        .map(move |msg| {
+                            // The key introduced by this path is also part of the following ones.
+                            if let message::DefinePath::Key(j, message::DefineKey::Edit) = msg {
+                                Message::DefineDescriptor(message::DefineDescriptor::KeysEdit(
+                                    (r + 1..=n_recovery_paths).map(|p| (p, j)).collect(),
+                                ))
+                            } else {
+                                Message::DefineDescriptor(message::DefineDescriptor::Path(
+                                    r + 1,
+                                    msg,
+                                ))
+                            }
+                        }),
+                    )
+                },
+            ))
+            .push(
+                Row::new()
+                    .push(
+                        button::secondary(None, "Customize")
+                            .width(Length::Fixed(200.0))
+                            .on_press(Message::DefineDescriptor(
+                                message::DefineDescriptor::ChangeTemplate(
+                                    context::DescriptorTemplate::Custom,
+                                ),
+                            )),
+                    )
+                    .push(Space::with_width(Length::Fill))
+                    .push(
+                        button::primary(None, "Continue")
+                            .width(Length::Fixed(200.0))
+                            .on_press_maybe(if valid { Some(Message::Next) } else { None }),
+                    ),
+            )
+            .push(Space::with_height(100.0))
+            .spacing(20),
+        true,
+        Some(Message::Previous),
+    )
+}
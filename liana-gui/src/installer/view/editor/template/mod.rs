@@ -1,8 +1,10 @@
 pub mod custom;
+pub mod decaying_multisig;
+pub mod expanding_multisig;
 pub mod inheritance;
 pub mod multisig_security_wallet;
 
-use iced::{Alignment, Length};
+use iced::{widget::radio, Alignment, Length};
 
 use liana_ui::{
     component::text::{h3, p2_regular},
@@ -11,9 +13,12 @@ use liana_ui::{
 };
 
 use crate::installer::context;
-use crate::installer::{message::Message, view::layout};
+use crate::installer::{message::Message, step::MNEMONIC_WORD_COUNTS, view::layout};
 
-pub fn choose_descriptor_template(progress: (usize, usize)) -> Element<'static, Message> {
+pub fn choose_descriptor_template(
+    progress: (usize, usize),
+    word_count: usize,
+) -> Element<'static, Message> {
     layout(
         progress,
         None,
@@ -53,6 +58,38 @@ pub fn choose_descriptor_template(progress: (usize, usize)) -> Element<'static,
                 ).style(theme::button::secondary)
                 .width(Length::Fill),
             )
+            .push(
+                Button::new(
+                    Column::new()
+                        .align_x(Alignment::Start)
+                        .push(h3("Decaying multisig"))
+                        .push(p2_regular("Two out of three keys required to spend, a single one after a period of inactivity.").style(theme::text::secondary))
+                        .width(Length::Fill)
+                )
+                .padding(15)
+                .on_press(
+                        Message::SelectDescriptorTemplate(
+                            context::DescriptorTemplate::DecayingMultisig,
+                        )
+                ).style(theme::button::secondary)
+                .width(Length::Fill),
+            )
+            .push(
+                Button::new(
+                    Column::new()
+                        .align_x(Alignment::Start)
+                        .push(h3("Expanding multisig with inheritance"))
+                        .push(p2_regular("Two keys required to spend, a recovery key added after a period of inactivity and an heir key after a longer one.").style(theme::text::secondary))
+                        .width(Length::Fill)
+                )
+                .padding(15)
+                .on_press(
+                        Message::SelectDescriptorTemplate(
+                            context::DescriptorTemplate::ExpandingMultisig,
+                        )
+                ).style(theme::button::secondary)
+                .width(Length::Fill),
+            )
             .push(
                 Button::new(
                     Column::new()
@@ -69,6 +106,22 @@ pub fn choose_descriptor_template(progress: (usize, usize)) -> Element<'static,
                 ).style(theme::button::secondary)
                 .width(Length::Fill),
             )
+            .push(
+                Column::new()
+                    .spacing(10)
+                    .push(p2_regular("Number of words of the mnemonic of a key generated on this computer:").style(theme::text::secondary))
+                    .push(MNEMONIC_WORD_COUNTS.iter().fold(
+                        Row::new().spacing(10),
+                        |row, count| {
+                            row.push(radio(
+                                format!("{} words", count),
+                                *count,
+                                Some(word_count),
+                                Message::MnemonicWordCount,
+                            ))
+                        },
+                    )),
+            )
             .spacing(20),
         true,
         Some(Message::Previous),
@@ -1,7 +1,10 @@
 pub mod editor;
 
 use async_hwi::utils::extract_keys_and_template;
-use iced::widget::{checkbox, radio, scrollable, scrollable::Scrollbar, Button, Space, TextInput};
+use chrono::{DateTime, Utc};
+use iced::widget::{
+    checkbox, pick_list, radio, scrollable, scrollable::Scrollbar, Button, Space, TextInput,
+};
 use iced::{
     alignment,
     widget::{progress_bar, tooltip as iced_tooltip},
@@ -16,7 +19,7 @@ use std::path::PathBuf;
 use std::{collections::HashSet, str::FromStr};
 
 use liana::{
-    descriptors::{LianaDescriptor, LianaPolicy},
+    descriptors::{LianaDescriptor, LianaPolicy, PathCondition},
     miniscript::bitcoin::{self, bip32::Fingerprint},
 };
 use liana_ui::{
@@ -33,7 +36,9 @@ use crate::{
     installer::{
         message::{self, DefineBitcoind, DefineNode, Message},
         prompt,
-        step::{DownloadState, InstallState},
+        step::{
+            DownloadState, InstallState, Slip39Backup, MNEMONIC_WORD_COUNTS, SLIP39_SHARE_COUNTS,
+        },
         view::editor::duration_from_sequence,
         Error,
     },
@@ -262,11 +267,12 @@ pub fn import_descriptor<'a>(
     progress: (usize, usize),
     email: Option<&'a str>,
     imported_descriptor: &form::Value<String>,
+    bsms_token: &form::Value<String>,
     wrong_network: bool,
     error: Option<&String>,
 ) -> Element<'a, Message> {
     let col_descriptor = Column::new()
-        .push(text("Descriptor:").bold())
+        .push(text("Descriptor or BIP-129 descriptor record:").bold())
         .push(
             form::Form::new_trimmed("Descriptor", imported_descriptor, |msg| {
                 Message::DefineDescriptor(message::DefineDescriptor::ImportDescriptor(msg))
@@ -279,6 +285,15 @@ pub fn import_descriptor<'a>(
             .size(text::P1_SIZE)
             .padding(10),
         )
+        .push(text("BIP-129 token, if the descriptor record is encrypted:").small())
+        .push(
+            form::Form::new_trimmed("Token", bsms_token, |msg| {
+                Message::DefineDescriptor(message::DefineDescriptor::BsmsTokenEdited(msg))
+            })
+            .warning("Invalid token. Must be 16 or 32 hex characters.")
+            .size(text::P1_SIZE)
+            .padding(10),
+        )
         .spacing(10);
     layout(
         progress,
@@ -314,7 +329,8 @@ const BACKUP_WARNING: &str =
 
 pub fn signer_xpubs<'a>(
     xpubs: &'a [String],
-    words: &'a [&'static str; 12],
+    bsms_record: Option<&'a String>,
+    words: &'a [&'static str],
     did_backup: bool,
 ) -> Element<'a, Message> {
     Container::new(
@@ -401,6 +417,45 @@ pub fn signer_xpubs<'a>(
                 }))
             } else {
                 None
+            })
+            .push_maybe(if did_backup {
+                bsms_record.map(|record| {
+                    Column::new()
+                        .padding(15)
+                        .spacing(5)
+                        .push(
+                            text("BIP-129 key record, for a coordinator using the BSMS setup:")
+                                .small(),
+                        )
+                        .push(
+                            Row::new()
+                                .spacing(5)
+                                .align_y(Alignment::Center)
+                                .push(
+                                    Container::new(
+                                        scrollable(
+                                            Container::new(text(record).small()).padding(10),
+                                        )
+                                        .direction(
+                                            scrollable::Direction::Horizontal(
+                                                Scrollbar::new().width(5).scroller_width(5),
+                                            ),
+                                        ),
+                                    )
+                                    .width(Length::Fill),
+                                )
+                                .push(
+                                    Container::new(
+                                        button::secondary(Some(icon::clipboard_icon()), "Copy")
+                                            .on_press(Message::Clibpboard(record.clone()))
+                                            .width(Length::Shrink),
+                                    )
+                                    .padding(10),
+                                ),
+                        )
+                })
+            } else {
+                None
             }),
     )
     .style(theme::card::simple)
@@ -778,7 +833,6 @@ fn display_policy(
     // The iteration over an HashMap keys can have a different order at each refresh
     let mut primary_keys: Vec<Fingerprint> = primary_keys.into_keys().collect();
     primary_keys.sort();
-    let recovery_paths = policy.recovery_paths();
     let mut col = Column::new().push(
         Row::new()
             .spacing(5)
@@ -823,12 +877,12 @@ fn display_policy(
             )
             .push(text("can always spend this wallet's funds (Primary path)")),
     );
-    for (i, (sequence, recovery_path)) in recovery_paths.iter().enumerate() {
+    for (i, (condition, recovery_path)) in policy.conditional_paths().iter().enumerate() {
         let (threshold, recovery_keys) = recovery_path.thresh_origins();
         // The iteration over an HashMap keys can have a different order at each refresh
         let mut recovery_keys: Vec<Fingerprint> = recovery_keys.into_keys().collect();
         recovery_keys.sort();
-        col = col.push(
+        let row =
             Row::new()
                 .spacing(5)
                 .push(
@@ -867,18 +921,45 @@ fn display_policy(
                             row.push(content).push(text(","))
                         }
                     },
-                ))
-                .push(text("can spend coins inactive for"))
-                .push(
+                ));
+        let row = match *condition {
+            PathCondition::RelativeBlocks(sequence) => {
+                row.push(text("can spend coins inactive for")).push(
                     text(format!(
                         "{} blocks (~{})",
                         sequence,
-                        expire_message_units(*sequence as u32).join(",")
+                        expire_message_units(sequence as u32).join(",")
                     ))
                     .bold(),
                 )
-                .push(text(format!("(Recovery path #{})", i + 1))),
-        );
+            }
+            PathCondition::RelativeTime(intervals) => {
+                row.push(text("can spend coins inactive for")).push(
+                    text(format!(
+                        "~{}",
+                        // The helper takes a number of 10-minute blocks.
+                        expire_message_units(intervals as u32 * 512 / 600).join(",")
+                    ))
+                    .bold(),
+                )
+            }
+            PathCondition::Absolute(timelock) if timelock.is_block_height() => row
+                .push(text("can spend coins from block"))
+                .push(text(timelock.to_consensus_u32().to_string()).bold()),
+            PathCondition::Absolute(timelock) => row.push(text("can spend coins after")).push(
+                text(
+                    DateTime::<Utc>::from_timestamp(timelock.to_consensus_u32() as i64, 0)
+                        .expect("Correct timestamp")
+                        .format("%b. %d, %Y - %T")
+                        .to_string(),
+                )
+                .bold(),
+            ),
+            PathCondition::HashLock(hash) => row
+                .push(text("can spend coins by revealing the preimage of"))
+                .push(text(hash.to_string()).bold()),
+        };
+        col = col.push(row.push(text(format!("(Recovery path #{})", i + 1))));
     }
     Column::new()
         .spacing(10)
@@ -1688,7 +1769,8 @@ pub fn key_list_view<'a>(
 pub fn backup_mnemonic<'a>(
     progress: (usize, usize),
     email: Option<&'a str>,
-    words: &'a [&'static str; 12],
+    words: &'a [&'static str],
+    slip39: &'a Slip39Backup,
     done: bool,
 ) -> Element<'a, Message> {
     layout(
@@ -1697,7 +1779,10 @@ pub fn backup_mnemonic<'a>(
         "Backup your mnemonic",
         Column::new()
             .push(text(prompt::MNEMONIC_HELP))
-            .push(
+            .push(backup_kind_selection(slip39.use_shares))
+            .push(if slip39.use_shares {
+                slip39_shares_backup(slip39)
+            } else {
                 words
                     .iter()
                     .enumerate()
@@ -1711,9 +1796,23 @@ pub fn backup_mnemonic<'a>(
                                 )
                                 .push(text(*w).bold()),
                         )
-                    }),
-            )
-            .push(checkbox("I have backed up my mnemonic", done).on_toggle(Message::UserActionDone))
+                    })
+            })
+            .push_maybe(if !slip39.use_shares || !slip39.shares.is_empty() {
+                Some(
+                    checkbox(
+                        if slip39.use_shares {
+                            "I have distributed the shares"
+                        } else {
+                            "I have backed up my mnemonic"
+                        },
+                        done,
+                    )
+                    .on_toggle(Message::UserActionDone),
+                )
+            } else {
+                None
+            })
             .push(if done {
                 button::secondary(None, "Next")
                     .on_press(Message::Next)
@@ -1728,15 +1827,187 @@ pub fn backup_mnemonic<'a>(
     )
 }
 
+fn backup_kind_selection<'a>(use_shares: bool) -> Row<'a, Message> {
+    Row::new()
+        .spacing(20)
+        .push(radio(
+            "Single mnemonic (BIP39)",
+            false,
+            Some(use_shares),
+            |b| Message::Slip39Shares(message::Slip39Shares::UseShares(b)),
+        ))
+        .push(radio(
+            "Shamir shares (SLIP-39)",
+            true,
+            Some(use_shares),
+            |b| Message::Slip39Shares(message::Slip39Shares::UseShares(b)),
+        ))
+}
+
+fn slip39_shares_backup(slip39: &Slip39Backup) -> Column<Message> {
+    let n_groups = slip39.groups.len();
+    Column::new()
+        .spacing(20)
+        .push(text(prompt::SLIP39_BACKUP_HELP))
+        .push_maybe(if n_groups > 1 {
+            Some(
+                Row::new()
+                    .spacing(10)
+                    .align_y(Alignment::Center)
+                    .push(text("Require"))
+                    .push(
+                        pick_list(
+                            &SLIP39_SHARE_COUNTS[..n_groups],
+                            Some(slip39.group_threshold),
+                            |t| {
+                                Message::Slip39Shares(
+                                    message::Slip39Shares::GroupThresholdSelected(t),
+                                )
+                            },
+                        )
+                        .style(theme::pick_list::primary),
+                    )
+                    .push(text(format!(
+                        "out of {} groups to recover the key.",
+                        n_groups
+                    ))),
+            )
+        } else {
+            None
+        })
+        .push(slip39.groups.iter().enumerate().fold(
+            Column::new().spacing(10),
+            |acc, (i, group)| {
+                acc.push(
+                    Row::new()
+                        .spacing(10)
+                        .align_y(Alignment::Center)
+                        .push_maybe(if n_groups > 1 {
+                            Some(
+                                Container::new(text(format!("Group #{}", i + 1)).small())
+                                    .width(Length::Fixed(80.0)),
+                            )
+                        } else {
+                            None
+                        })
+                        .push(text("Require"))
+                        .push(
+                            pick_list(
+                                &SLIP39_SHARE_COUNTS[..],
+                                Some(group.member_threshold),
+                                move |t| {
+                                    Message::Slip39Shares(message::Slip39Shares::ThresholdSelected(
+                                        i, t,
+                                    ))
+                                },
+                            )
+                            .style(theme::pick_list::primary),
+                        )
+                        .push(text("out of"))
+                        .push(
+                            pick_list(
+                                &SLIP39_SHARE_COUNTS[..],
+                                Some(group.member_count),
+                                move |c| {
+                                    Message::Slip39Shares(message::Slip39Shares::CountSelected(
+                                        i, c,
+                                    ))
+                                },
+                            )
+                            .style(theme::pick_list::primary),
+                        )
+                        .push(text(if n_groups > 1 {
+                            "shares to recover the group."
+                        } else {
+                            "shares to recover the key."
+                        }))
+                        .push_maybe(if n_groups > 1 {
+                            Some(
+                                Button::new(icon::trash_icon())
+                                    .style(theme::button::secondary)
+                                    .on_press(Message::Slip39Shares(
+                                        message::Slip39Shares::RemoveGroup(i),
+                                    )),
+                            )
+                        } else {
+                            None
+                        }),
+                )
+            },
+        ))
+        .push(
+            Row::new()
+                .spacing(10)
+                .push(
+                    button::secondary(Some(icon::plus_icon()), "Add group").on_press_maybe(
+                        if n_groups < SLIP39_SHARE_COUNTS.len() {
+                            Some(Message::Slip39Shares(message::Slip39Shares::AddGroup))
+                        } else {
+                            None
+                        },
+                    ),
+                )
+                .push(
+                    button::secondary(None, "Generate shares")
+                        .on_press(Message::Slip39Shares(message::Slip39Shares::Generate)),
+                ),
+        )
+        .push_maybe(
+            slip39
+                .error
+                .as_ref()
+                .map(|e| card::invalid(text(e).style(theme::text::error))),
+        )
+        .push(slip39.shares.iter().enumerate().fold(
+            Column::new().spacing(20),
+            |acc, (g, shares)| {
+                acc.push(
+                    shares.iter().enumerate().fold(
+                        Column::new()
+                            .spacing(10)
+                            .push_maybe(if slip39.shares.len() > 1 {
+                                Some(text(format!("Group #{}", g + 1)).bold())
+                            } else {
+                                None
+                            }),
+                        |acc, (i, share)| {
+                            acc.push(
+                                Row::new()
+                                    .spacing(10)
+                                    .align_y(Alignment::Center)
+                                    .push(
+                                        Container::new(text(format!("Share #{}", i + 1)).small())
+                                            .width(Length::Fixed(80.0)),
+                                    )
+                                    .push(
+                                        Container::new(text(share).bold())
+                                            .width(Length::Fixed(600.0)),
+                                    ),
+                            )
+                        },
+                    ),
+                )
+            },
+        ))
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn recover_mnemonic<'a>(
     progress: (usize, usize),
     email: Option<&'a str>,
-    words: &'a [(String, bool); 12],
+    words: &'a [(String, bool)],
+    slip39_shares: Option<&'a [String]>,
+    passphrase: &'a str,
     current: usize,
     suggestions: &'a [String],
     recover: bool,
     error: Option<&'a String>,
 ) -> Element<'a, Message> {
+    let can_recover = if let Some(shares) = slip39_shares {
+        shares.iter().any(|s| !s.trim().is_empty())
+    } else {
+        words.iter().all(|(_, valid)| *valid)
+    };
     layout(
         progress,
         email,
@@ -1747,53 +2018,27 @@ pub fn recover_mnemonic<'a>(
                 Some(
                     Column::new()
                         .align_x(Alignment::Center)
+                        .spacing(20)
+                        .push(backup_kind_selection(slip39_shares.is_some()))
+                        .push(if let Some(shares) = slip39_shares {
+                            slip39_shares_input(shares)
+                        } else {
+                            mnemonic_words_input(words, current, suggestions)
+                        })
                         .push(
-                            Container::new(if !suggestions.is_empty() {
-                                suggestions.iter().fold(Row::new().spacing(5), |row, sugg| {
-                                    row.push(
-                                        Button::new(text(sugg))
-                                            .style(theme::button::secondary)
-                                            .on_press(Message::MnemonicWord(
-                                                current,
-                                                sugg.to_string(),
-                                            )),
+                            Column::new()
+                                .spacing(5)
+                                .push(text("BIP39 passphrase (leave empty if none):").small())
+                                .push(
+                                    Container::new(
+                                        TextInput::new("Passphrase", passphrase)
+                                            .on_input(Message::MnemonicPassphrase)
+                                            .secure(true),
                                     )
-                                })
-                            } else {
-                                Row::new()
-                            })
-                            // Fixed height in order to not move words list
-                            .height(Length::Fixed(50.0)),
+                                    .width(Length::Fixed(300.0)),
+                                ),
                         )
-                        .push(words.iter().enumerate().fold(
-                            Column::new().spacing(5),
-                            |acc, (i, (word, valid))| {
-                                acc.push(
-                                    Row::new()
-                                        .spacing(10)
-                                        .align_y(Alignment::Center)
-                                        .push(
-                                            Container::new(text(format!("#{}", i + 1)).small())
-                                                .width(Length::Fixed(50.0)),
-                                        )
-                                        .push(
-                                            Container::new(TextInput::new("", word).on_input(
-                                                move |msg| Message::MnemonicWord(i, msg),
-                                            ))
-                                            .width(Length::Fixed(100.0)),
-                                        )
-                                        .push_maybe(if *valid {
-                                            Some(
-                                                icon::circle_check_icon()
-                                                    .style(theme::text::success),
-                                            )
-                                        } else {
-                                            None
-                                        }),
-                                )
-                            },
-                        ))
-                        .push(Space::with_height(Length::Fixed(50.0)))
+                        .push(Space::with_height(Length::Fixed(30.0)))
                         .push_maybe(
                             error.map(|e| card::invalid(text(e).style(theme::text::error))),
                         ),
@@ -1822,15 +2067,13 @@ pub fn recover_mnemonic<'a>(
                             .on_press(Message::ImportMnemonic(false))
                             .width(Length::Fixed(200.0)),
                     )
-                    .push(
-                        if words.iter().any(|(_, valid)| !valid) || error.is_some() {
-                            button::secondary(None, "Next").width(Length::Fixed(200.0))
-                        } else {
-                            button::secondary(None, "Next")
-                                .on_press(Message::Next)
-                                .width(Length::Fixed(200.0))
-                        },
-                    )
+                    .push(if !can_recover || error.is_some() {
+                        button::secondary(None, "Next").width(Length::Fixed(200.0))
+                    } else {
+                        button::secondary(None, "Next")
+                            .on_press(Message::Next)
+                            .width(Length::Fixed(200.0))
+                    })
             })
             .spacing(50),
         true,
@@ -1838,6 +2081,113 @@ pub fn recover_mnemonic<'a>(
     )
 }
 
+fn mnemonic_words_input<'a>(
+    words: &'a [(String, bool)],
+    current: usize,
+    suggestions: &'a [String],
+) -> Column<'a, Message> {
+    Column::new()
+        .align_x(Alignment::Center)
+        .push(
+            MNEMONIC_WORD_COUNTS
+                .iter()
+                .fold(Row::new().spacing(10), |row, count| {
+                    row.push(radio(
+                        format!("{} words", count),
+                        *count,
+                        Some(words.len()),
+                        Message::MnemonicWordCount,
+                    ))
+                }),
+        )
+        .push(
+            Container::new(if !suggestions.is_empty() {
+                suggestions.iter().fold(Row::new().spacing(5), |row, sugg| {
+                    row.push(
+                        Button::new(text(sugg))
+                            .style(theme::button::secondary)
+                            .on_press(Message::MnemonicWord(current, sugg.to_string())),
+                    )
+                })
+            } else {
+                Row::new()
+            })
+            // Fixed height in order to not move words list
+            .height(Length::Fixed(50.0)),
+        )
+        .push(
+            words
+                .iter()
+                .enumerate()
+                .fold(Column::new().spacing(5), |acc, (i, (word, valid))| {
+                    acc.push(
+                        Row::new()
+                            .spacing(10)
+                            .align_y(Alignment::Center)
+                            .push(
+                                Container::new(text(format!("#{}", i + 1)).small())
+                                    .width(Length::Fixed(50.0)),
+                            )
+                            .push(
+                                Container::new(
+                                    TextInput::new("", word)
+                                        .on_input(move |msg| Message::MnemonicWord(i, msg)),
+                                )
+                                .width(Length::Fixed(100.0)),
+                            )
+                            .push_maybe(if *valid {
+                                Some(icon::circle_check_icon().style(theme::text::success))
+                            } else {
+                                None
+                            }),
+                    )
+                }),
+        )
+}
+
+fn slip39_shares_input(shares: &[String]) -> Column<Message> {
+    shares
+        .iter()
+        .enumerate()
+        .fold(
+            Column::new()
+                .spacing(10)
+                .push(text(prompt::SLIP39_RECOVER_HELP)),
+            |acc, (i, share)| {
+                acc.push(
+                    Row::new()
+                        .spacing(10)
+                        .align_y(Alignment::Center)
+                        .push(
+                            Container::new(text(format!("Share #{}", i + 1)).small())
+                                .width(Length::Fixed(80.0)),
+                        )
+                        .push(
+                            Container::new(TextInput::new("", share).on_input(move |s| {
+                                Message::Slip39Shares(message::Slip39Shares::ShareEdited(i, s))
+                            }))
+                            .width(Length::Fixed(600.0)),
+                        )
+                        .push_maybe(if shares.len() > 1 {
+                            Some(
+                                Button::new(icon::trash_icon())
+                                    .style(theme::button::secondary)
+                                    .on_press(Message::Slip39Shares(
+                                        message::Slip39Shares::RemoveShare(i),
+                                    )),
+                            )
+                        } else {
+                            None
+                        }),
+                )
+            },
+        )
+        .push(
+            button::secondary(Some(icon::plus_icon()), "Add share")
+                .on_press(Message::Slip39Shares(message::Slip39Shares::AddShare)),
+        )
+}
+
 pub fn choose_backend(progress: (usize, usize)) -> Element<'static, Message> {
     layout(
         progress,
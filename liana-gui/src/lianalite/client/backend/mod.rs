@@ -2,6 +2,7 @@ pub mod api;
 
 use std::{
     collections::{HashMap, HashSet},
+    convert::TryInto,
     path::Path,
     sync::Arc,
 };
@@ -11,9 +12,10 @@ use chrono::Utc;
 use liana::{
     descriptors::LianaDescriptor,
     miniscript::bitcoin::{address, psbt::Psbt, Address, Network, OutPoint, Txid},
+    spend::Feerate,
 };
 use lianad::{
-    commands::{CoinStatus, GetInfoDescriptors, LCSpendInfo, LabelItem},
+    commands::{CoinStatus, Destination, GetInfoDescriptors, LCSpendInfo, LabelItem},
     config::Config,
 };
 use reqwest::{Error, IntoUrl, Method, RequestBuilder, Response};
@@ -41,6 +43,11 @@ impl From<AuthError> for DaemonError {
     }
 }
 
+/// The backend API only accepts whole sat/vB feerates, round up so we never pay less than asked.
+fn sat_per_vb(feerate: Feerate) -> u64 {
+    feerate.to_sat_per_kvb().div_ceil(1_000)
+}
+
 fn request<U: IntoUrl>(
     http: &reqwest::Client,
     method: Method,
@@ -711,18 +718,24 @@ impl Daemon for BackendWalletClient {
     async fn create_spend_tx(
         &self,
         coins_outpoints: &[OutPoint],
-        destinations: &HashMap<Address<address::NetworkUnchecked>, u64>,
-        feerate_vb: u64,
+        destinations: &HashMap<Destination, u64>,
+        feerate: Feerate,
         change_address: Option<Address<address::NetworkUnchecked>>,
     ) -> Result<CreateSpendResult, DaemonError> {
         let mut recipients: Vec<api::payload::Recipient> = destinations
             .iter()
-            .map(|(addr, amt)| api::payload::Recipient {
-                amount: Some(*amt),
-                address: addr.clone(),
-                is_max: false,
+            .map(|(destination, amt)| match destination {
+                Destination::Address(addr) => Ok(api::payload::Recipient {
+                    amount: Some(*amt),
+                    address: addr.clone(),
+                    is_max: false,
+                }),
+                _ => Err(DaemonError::Unexpected(
+                    "Outputs to a raw script or carrying data are not supported by this backend."
+                        .to_string(),
+                )),
             })
-            .collect();
+            .collect::<Result<_, _>>()?;
         if let Some(address) = change_address {
             recipients.push(api::payload::Recipient {
                 amount: None,
@@ -742,7 +755,7 @@ impl Daemon for BackendWalletClient {
             .await
             .json(&api::payload::GeneratePsbt {
                 save: false,
-                feerate: feerate_vb,
+                feerate: sat_per_vb(feerate),
                 inputs: coins_outpoints,
                 recipients,
             })
@@ -753,6 +766,7 @@ impl Daemon for BackendWalletClient {
         match res {
             api::DraftPsbtResult::Success(draft) => Ok(CreateSpendResult::Success {
                 psbt: draft.raw,
+                labels: HashMap::new(),
                 warnings: draft.warnings,
             }),
             api::DraftPsbtResult::InsufficientFunds(api::InsufficientFundsInfo { missing }) => {
@@ -768,7 +782,7 @@ impl Daemon for BackendWalletClient {
         &self,
         txid: &Txid,
         is_cancel: bool,
-        feerate_vb: Option<u64>,
+        feerate: Option<Feerate>,
     ) -> Result<CreateSpendResult, DaemonError> {
         let response: Response = self
             .inner
@@ -783,7 +797,7 @@ impl Daemon for BackendWalletClient {
             .json(&api::payload::GenerateRbfPsbt {
                 txid: *txid,
                 is_cancel,
-                feerate: feerate_vb,
+                feerate: feerate.map(sat_per_vb),
                 save: false,
             })
             .send()
@@ -793,6 +807,7 @@ impl Daemon for BackendWalletClient {
         match res {
             api::DraftPsbtResult::Success(draft) => Ok(CreateSpendResult::Success {
                 psbt: draft.raw,
+                labels: HashMap::new(),
                 warnings: draft.warnings,
             }),
             api::DraftPsbtResult::InsufficientFunds(api::InsufficientFundsInfo { missing }) => {
@@ -896,9 +911,13 @@ impl Daemon for BackendWalletClient {
     async fn create_recovery(
         &self,
         address: Address<address::NetworkUnchecked>,
-        feerate_vb: u64,
-        sequence: Option<u16>,
+        feerate: Feerate,
+        sequence: Option<u32>,
+        absolute_timelock: Option<u32>,
     ) -> Result<Psbt, DaemonError> {
+        if absolute_timelock.is_some() {
+            return Err(DaemonError::ClientNotSupported);
+        }
         let response: Response = self
             .inner
             .request(
@@ -911,9 +930,11 @@ impl Daemon for BackendWalletClient {
             .await
             .json(&api::payload::GenerateRecoveryPsbt {
                 save: false,
-                feerate: feerate_vb,
+                feerate: sat_per_vb(feerate),
                 timelock: sequence
-                    .ok_or(DaemonError::Unexpected("Missing sequence".to_string()))?,
+                    .ok_or(DaemonError::Unexpected("Missing sequence".to_string()))?
+                    .try_into()
+                    .map_err(|_| DaemonError::ClientNotSupported)?,
                 address,
             })
             .send()
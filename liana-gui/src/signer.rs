@@ -1,12 +1,14 @@
 pub use liana::signer::SignerError;
 
 use liana::{
+    bsms,
     miniscript::bitcoin::{
         bip32::{DerivationPath, Fingerprint, Xpub},
         psbt::Psbt,
         secp256k1, Network,
     },
     signer::HotSigner,
+    slip39,
 };
 
 pub struct Signer {
@@ -36,14 +38,29 @@ impl Signer {
         self.key.set_network(network)
     }
 
-    pub fn mnemonic(&self) -> [&'static str; 12] {
+    pub fn mnemonic(&self) -> Vec<&'static str> {
         self.key.words()
     }
 
+    pub fn slip39_shares(
+        &self,
+        group_threshold: u8,
+        groups: &[slip39::Group],
+    ) -> Result<Vec<Vec<String>>, SignerError> {
+        self.key.slip39_shares(group_threshold, groups)
+    }
+
     pub fn generate(network: Network) -> Result<Self, SignerError> {
         Ok(Self::new(HotSigner::generate(network)?))
     }
 
+    /// Generate a signer with a mnemonic of the given number of words (12, 18 or 24).
+    pub fn generate_with(network: Network, word_count: usize) -> Result<Self, SignerError> {
+        Ok(Self::new(HotSigner::generate_with(
+            network, word_count, "",
+        )?))
+    }
+
     pub fn fingerprint(&self) -> Fingerprint {
         self.fingerprint
     }
@@ -52,6 +69,17 @@ impl Signer {
         self.key.xpub_at(path, &self.curve)
     }
 
+    /// Get a BIP-129 key record for the extended public key at the given derivation path.
+    pub fn bsms_key_record(
+        &self,
+        token: bsms::Token,
+        path: &DerivationPath,
+        description: String,
+    ) -> bsms::KeyRecord {
+        self.key
+            .bsms_key_record(token, path, description, &self.curve)
+    }
+
     pub fn sign_psbt(&self, psbt: Psbt) -> Result<Psbt, SignerError> {
         self.key.sign_psbt(psbt, &self.curve)
     }
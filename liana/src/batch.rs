@@ -0,0 +1,402 @@
+//! Batch payments.
+//!
+//! Parse a list of payments, to be made in a single transaction, from a CSV or JSON file. Each
+//! payment is an address, an amount in BTC and an optional label. A CSV file has one payment per
+//! line, as `address,amount[,label]`. An optional header line is ignored, as well as empty lines
+//! and lines starting with `#`. A JSON file is a list of objects with an `address`, an `amount`
+//! and an optional `label` field.
+//!
+//! All the payments are validated, and an error is reported for each invalid row of the file.
+
+use crate::spend::DUST_OUTPUT_SATS;
+
+use std::{error, fmt, str::FromStr};
+
+use miniscript::bitcoin::{self, address, Amount, Denomination, Network};
+
+/// The maximum length of a payment label, as for any other label in the wallet.
+pub const MAX_LABEL_LEN: usize = 100;
+
+/// A payment to make as part of a batch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Payment {
+    pub address: bitcoin::Address,
+    pub amount: Amount,
+    pub label: Option<String>,
+}
+
+/// The reason a row of a batch could not be turned into a payment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RowErrorKind {
+    /// The row does not have an address and an amount, or has too many fields.
+    Malformed,
+    InvalidAddress(String),
+    /// The address is valid, but not for the network of the wallet.
+    WrongNetwork(String),
+    InvalidAmount(String),
+    /// The amount is below our dust threshold, or above the total supply.
+    AmountOutOfRange(Amount),
+    LabelTooLong,
+}
+
+/// An invalid row of a batch, along with its (1-indexed) line number for a CSV file or its
+/// (1-indexed) position in the list for a JSON file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RowError {
+    pub row: usize,
+    pub kind: RowErrorKind,
+}
+
+impl fmt::Display for RowError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Row {}: ", self.row)?;
+        match &self.kind {
+            RowErrorKind::Malformed => {
+                write!(f, "expected an address, an amount and an optional label.")
+            }
+            RowErrorKind::InvalidAddress(a) => write!(f, "invalid address '{}'.", a),
+            RowErrorKind::WrongNetwork(a) => {
+                write!(f, "address '{}' is not valid for this network.", a)
+            }
+            RowErrorKind::InvalidAmount(a) => write!(f, "invalid amount '{}'.", a),
+            RowErrorKind::AmountOutOfRange(a) => write!(
+                f,
+                "amount {} is out of range, must be at least {}.",
+                a,
+                Amount::from_sat(DUST_OUTPUT_SATS)
+            ),
+            RowErrorKind::LabelTooLong => {
+                write!(f, "label is longer than {} characters.", MAX_LABEL_LEN)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BatchError {
+    /// The file does not contain any payment.
+    Empty,
+    /// The file looks like JSON but could not be parsed as a list of objects.
+    InvalidJson(String),
+    /// Some of the rows are invalid.
+    InvalidRows(Vec<RowError>),
+}
+
+impl fmt::Display for BatchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "No payment in batch."),
+            Self::InvalidJson(e) => write!(f, "Invalid JSON batch: '{}'.", e),
+            Self::InvalidRows(errors) => {
+                write!(f, "Invalid payments in batch:")?;
+                for e in errors {
+                    write!(f, "\n{}", e)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl error::Error for BatchError {}
+
+fn parse_address(address: &str, network: Network) -> Result<bitcoin::Address, RowErrorKind> {
+    bitcoin::Address::<address::NetworkUnchecked>::from_str(address)
+        .map_err(|_| RowErrorKind::InvalidAddress(address.to_string()))?
+        .require_network(network)
+        .map_err(|_| RowErrorKind::WrongNetwork(address.to_string()))
+}
+
+fn check_amount(amount: Amount) -> Result<Amount, RowErrorKind> {
+    if amount.to_sat() < DUST_OUTPUT_SATS || amount > Amount::MAX_MONEY {
+        return Err(RowErrorKind::AmountOutOfRange(amount));
+    }
+    Ok(amount)
+}
+
+fn check_label(label: Option<&str>) -> Result<Option<String>, RowErrorKind> {
+    match label.map(str::trim) {
+        None | Some("") => Ok(None),
+        Some(l) if l.chars().count() > MAX_LABEL_LEN => Err(RowErrorKind::LabelTooLong),
+        Some(l) => Ok(Some(l.to_string())),
+    }
+}
+
+// Split a CSV line into its fields. Fields may be enclosed in double quotes, in which case they
+// may contain commas and escaped (doubled) double quotes. Returns `None` if a quote isn't closed.
+fn csv_fields(line: &str) -> Option<Vec<String>> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut chars = line.chars().peekable();
+    let mut in_quotes = false;
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' if in_quotes => in_quotes = false,
+            '"' if field.trim().is_empty() => {
+                field.clear();
+                in_quotes = true;
+            }
+            ',' if !in_quotes => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    if in_quotes {
+        return None;
+    }
+    fields.push(field);
+    Some(fields)
+}
+
+// Parse a row of a CSV batch. The caller must have skipped empty and comment lines.
+fn csv_payment(line: &str, network: Network) -> Result<Payment, RowErrorKind> {
+    let fields = csv_fields(line).ok_or(RowErrorKind::Malformed)?;
+    let (address, amount, label) = match fields.as_slice() {
+        [address, amount] => (address, amount, None),
+        [address, amount, label] => (address, amount, Some(label.as_str())),
+        _ => return Err(RowErrorKind::Malformed),
+    };
+    let (address, amount) = (address.trim(), amount.trim());
+    let amount = Amount::from_str_in(amount, Denomination::Bitcoin)
+        .map_err(|_| RowErrorKind::InvalidAmount(amount.to_string()))?;
+    Ok(Payment {
+        address: parse_address(address, network)?,
+        amount: check_amount(amount)?,
+        label: check_label(label)?,
+    })
+}
+
+/// Parse a batch of payments from a CSV file, as `address,amount[,label]` lines with the amount
+/// in BTC.
+pub fn parse_csv(content: &str, network: Network) -> Result<Vec<Payment>, BatchError> {
+    let mut payments = Vec::new();
+    let mut errors = Vec::new();
+    let mut is_first_row = true;
+    for (i, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        // The first row may be a header naming the columns.
+        if std::mem::take(&mut is_first_row)
+            && csv_fields(line)
+                .and_then(|fields| fields.get(1).cloned())
+                .map(|f| f.trim().eq_ignore_ascii_case("amount"))
+                .unwrap_or(false)
+        {
+            continue;
+        }
+        match csv_payment(line, network) {
+            Ok(payment) => payments.push(payment),
+            Err(kind) => errors.push(RowError { row: i + 1, kind }),
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(BatchError::InvalidRows(errors));
+    }
+    if payments.is_empty() {
+        return Err(BatchError::Empty);
+    }
+    Ok(payments)
+}
+
+// Parse an entry of a JSON batch.
+fn json_payment(entry: &serde_json::Value, network: Network) -> Result<Payment, RowErrorKind> {
+    let address = entry
+        .get("address")
+        .and_then(|a| a.as_str())
+        .ok_or(RowErrorKind::Malformed)?;
+    // The amount may be given as a number or as a string.
+    let amount = match entry.get("amount").ok_or(RowErrorKind::Malformed)? {
+        serde_json::Value::Number(n) => n
+            .as_f64()
+            .and_then(|a| Amount::from_btc(a).ok())
+            .ok_or_else(|| RowErrorKind::InvalidAmount(n.to_string()))?,
+        serde_json::Value::String(s) => Amount::from_str_in(s.trim(), Denomination::Bitcoin)
+            .map_err(|_| RowErrorKind::InvalidAmount(s.to_string()))?,
+        _ => return Err(RowErrorKind::Malformed),
+    };
+    let label = match entry.get("label") {
+        None | Some(serde_json::Value::Null) => None,
+        Some(l) => Some(l.as_str().ok_or(RowErrorKind::Malformed)?),
+    };
+    Ok(Payment {
+        address: parse_address(address.trim(), network)?,
+        amount: check_amount(amount)?,
+        label: check_label(label)?,
+    })
+}
+
+/// Parse a batch of payments from a JSON list of `{"address", "amount", "label"}` objects, with
+/// the amount in BTC.
+pub fn parse_json(content: &str, network: Network) -> Result<Vec<Payment>, BatchError> {
+    let entries: Vec<serde_json::Value> =
+        serde_json::from_str(content).map_err(|e| BatchError::InvalidJson(e.to_string()))?;
+    let mut payments = Vec::with_capacity(entries.len());
+    let mut errors = Vec::new();
+    for (i, entry) in entries.iter().enumerate() {
+        match json_payment(entry, network) {
+            Ok(payment) => payments.push(payment),
+            Err(kind) => errors.push(RowError { row: i + 1, kind }),
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(BatchError::InvalidRows(errors));
+    }
+    if payments.is_empty() {
+        return Err(BatchError::Empty);
+    }
+    Ok(payments)
+}
+
+/// Parse a batch of payments from the content of either a JSON or a CSV file. A JSON file is
+/// detected by its content starting with a list.
+pub fn parse_batch(content: &str, network: Network) -> Result<Vec<Payment>, BatchError> {
+    if content.trim_start().starts_with('[') {
+        parse_json(content, network)
+    } else {
+        parse_csv(content, network)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ADDR_A: &str = "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4";
+    const ADDR_B: &str = "bc1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3qccfmv3";
+    const TESTNET_ADDR: &str = "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx";
+
+    #[test]
+    fn batch_csv() {
+        // Payments may repeat an address, and the header line is ignored.
+        let csv = format!(
+            "address,amount,label\n{a},0.001,Invoice 1\n\n# A comment\n{a},0.002\n \
+            {b} , 1 ,\"Payroll, October\"\n",
+            a = ADDR_A,
+            b = ADDR_B
+        );
+        let payments = parse_batch(&csv, Network::Bitcoin).unwrap();
+        assert_eq!(payments.len(), 3);
+        assert_eq!(payments[0].address.to_string(), ADDR_A);
+        assert_eq!(payments[0].amount, Amount::from_sat(100_000));
+        assert_eq!(payments[0].label.as_deref(), Some("Invoice 1"));
+        assert_eq!(payments[1].address, payments[0].address);
+        assert_eq!(payments[1].amount, Amount::from_sat(200_000));
+        assert_eq!(payments[1].label, None);
+        assert_eq!(payments[2].address.to_string(), ADDR_B);
+        assert_eq!(payments[2].amount, Amount::ONE_BTC);
+        assert_eq!(payments[2].label.as_deref(), Some("Payroll, October"));
+
+        // Quotes may be escaped.
+        let csv = format!("{},0.001,\"The \"\"big\"\" one\"", ADDR_A);
+        let payments = parse_csv(&csv, Network::Bitcoin).unwrap();
+        assert_eq!(payments[0].label.as_deref(), Some("The \"big\" one"));
+
+        // An error is reported for each invalid row.
+        let csv = format!(
+            "{a},0.001\n{t},0.001\nnotanaddress,0.001\n{a},0.00001\n{a},abc\n{a}\n{a},0.001,\"label\n{a},0.001,{l}\n{a},21000001",
+            a = ADDR_A,
+            t = TESTNET_ADDR,
+            l = "l".repeat(101),
+        );
+        let errors = match parse_csv(&csv, Network::Bitcoin) {
+            Err(BatchError::InvalidRows(errors)) => errors,
+            res => panic!("Unexpected result: {:?}", res),
+        };
+        let kinds: Vec<(usize, RowErrorKind)> =
+            errors.into_iter().map(|e| (e.row, e.kind)).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (2, RowErrorKind::WrongNetwork(TESTNET_ADDR.to_string())),
+                (3, RowErrorKind::InvalidAddress("notanaddress".to_string())),
+                (4, RowErrorKind::AmountOutOfRange(Amount::from_sat(1_000))),
+                (5, RowErrorKind::InvalidAmount("abc".to_string())),
+                (6, RowErrorKind::Malformed),
+                (7, RowErrorKind::Malformed),
+                (8, RowErrorKind::LabelTooLong),
+                (
+                    9,
+                    RowErrorKind::AmountOutOfRange(Amount::from_sat(21_000_001 * 100_000_000))
+                ),
+            ]
+        );
+
+        // The address is checked against the given network.
+        let csv = format!("{},0.001", TESTNET_ADDR);
+        assert!(parse_csv(&csv, Network::Testnet).is_ok());
+        assert!(parse_csv(&csv, Network::Signet).is_ok());
+        assert_eq!(
+            parse_csv("address,amount\n# Nothing\n", Network::Bitcoin),
+            Err(BatchError::Empty)
+        );
+    }
+
+    #[test]
+    fn batch_json() {
+        let json = format!(
+            r#"[
+                {{"address": "{a}", "amount": 0.001, "label": "Invoice 1"}},
+                {{"address": "{a}", "amount": "0.002", "label": null}},
+                {{"address": "{b}", "amount": 1}}
+            ]"#,
+            a = ADDR_A,
+            b = ADDR_B
+        );
+        let payments = parse_batch(&json, Network::Bitcoin).unwrap();
+        assert_eq!(
+            payments
+                .iter()
+                .map(|p| (p.address.to_string(), p.amount.to_sat(), p.label.clone()))
+                .collect::<Vec<_>>(),
+            vec![
+                (ADDR_A.to_string(), 100_000, Some("Invoice 1".to_string())),
+                (ADDR_A.to_string(), 200_000, None),
+                (ADDR_B.to_string(), 100_000_000, None),
+            ]
+        );
+
+        let json = format!(
+            r#"[
+                {{"address": "{t}", "amount": 0.001}},
+                {{"address": "{a}", "amount": 0.000000001}},
+                {{"address": "{a}"}},
+                {{"address": "{a}", "amount": 0.001, "label": 1}},
+                {{"address": "{a}", "amount": 0.001}}
+            ]"#,
+            a = ADDR_A,
+            t = TESTNET_ADDR
+        );
+        let errors = match parse_json(&json, Network::Bitcoin) {
+            Err(BatchError::InvalidRows(errors)) => errors,
+            res => panic!("Unexpected result: {:?}", res),
+        };
+        assert_eq!(
+            errors.iter().map(|e| e.row).collect::<Vec<_>>(),
+            vec![1, 2, 3, 4]
+        );
+        assert_eq!(
+            errors[0].to_string(),
+            format!(
+                "Row 1: address '{}' is not valid for this network.",
+                TESTNET_ADDR
+            )
+        );
+        assert_eq!(
+            errors[1].kind,
+            RowErrorKind::InvalidAmount("1e-9".to_string())
+        );
+
+        assert!(matches!(
+            parse_batch("[{]", Network::Bitcoin),
+            Err(BatchError::InvalidJson(..))
+        ));
+        assert_eq!(parse_batch("[]", Network::Bitcoin), Err(BatchError::Empty));
+    }
+}
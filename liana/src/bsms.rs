@@ -0,0 +1,722 @@
+//! BIP-129 Bitcoin Secure Multisig Setup (BSMS).
+//!
+//! Helpers for a Coordinator and Signers to set up a Liana wallet in an authenticated manner.
+//! Each Signer sends a signed key record to the Coordinator, which verifies them and creates the
+//! descriptor. The Coordinator then sends an encrypted descriptor record to all Signers, which
+//! check their key is part of the descriptor and verify the first address of the wallet.
+//! See https://github.com/bitcoin/bips/blob/master/bip-0129.mediawiki.
+
+use crate::{
+    descriptors::{analysis::DescKeyChecker, LianaDescError, LianaDescriptor},
+    random,
+};
+
+use std::{convert::TryInto, error, fmt, str::FromStr};
+
+use aes::cipher::{KeyIvInit, StreamCipher};
+use miniscript::{
+    bitcoin::{
+        self, bip32,
+        hashes::{hex::FromHex, hmac, sha256, sha512, Hash, HashEngine},
+        secp256k1,
+        sign_message::{self, MessageSignature},
+    },
+    descriptor,
+};
+
+/// The version of BIP-129 implemented.
+pub const BSMS_VERSION: &str = "BSMS 1.0";
+
+// As per BIP-129.
+const PBKDF2_SALT: &[u8] = b"No SPOF";
+const PBKDF2_ITERATIONS: u32 = 2048;
+
+// The derivation steps for receive and change addresses, for keys using the standard `/<0;1>/*`.
+const PATH_RESTRICTIONS: &str = "/0/*,/1/*";
+const NO_PATH_RESTRICTIONS: &str = "No path restrictions";
+
+type Aes256Ctr = ctr::Ctr128BE<aes::Aes256>;
+
+#[derive(Debug)]
+pub enum BsmsError {
+    InvalidToken(String),
+    UnsupportedVersion(String),
+    /// The record does not contain the expected number of lines.
+    MalformedRecord,
+    InvalidKey(String),
+    InvalidSignature,
+    InvalidPathRestrictions(String),
+    /// The first address in the descriptor record does not match the one derived from the
+    /// descriptor.
+    FirstAddressMismatch(String),
+    /// The encrypted record is not valid hex, or its authentication code doesn't match.
+    Decryption,
+    Descriptor(LianaDescError),
+    Randomness(random::RandomnessError),
+}
+
+impl fmt::Display for BsmsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::InvalidToken(t) => write!(
+                f,
+                "Invalid token '{}'. Must be '00', or 16 or 32 hex characters.",
+                t
+            ),
+            Self::UnsupportedVersion(v) => write!(f, "Unsupported BSMS version '{}'.", v),
+            Self::MalformedRecord => write!(f, "Malformed BSMS record."),
+            Self::InvalidKey(k) => write!(f, "Invalid key '{}' in BSMS key record.", k),
+            Self::InvalidSignature => write!(f, "Invalid BSMS key record signature."),
+            Self::InvalidPathRestrictions(p) => write!(f, "Unsupported path restrictions '{}'.", p),
+            Self::FirstAddressMismatch(a) => write!(
+                f,
+                "First address '{}' does not match the address derived from the descriptor.",
+                a
+            ),
+            Self::Decryption => write!(
+                f,
+                "Could not decrypt the BSMS record. Is the token correct?"
+            ),
+            Self::Descriptor(e) => write!(f, "{}", e),
+            Self::Randomness(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl error::Error for BsmsError {}
+
+impl From<LianaDescError> for BsmsError {
+    fn from(e: LianaDescError) -> BsmsError {
+        BsmsError::Descriptor(e)
+    }
+}
+
+/// A token shared by the Coordinator with each Signer, used to encrypt the records. An empty token
+/// (serialized as `00`) means no encryption.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token(Vec<u8>);
+
+impl Token {
+    /// A token for a setup without encryption.
+    pub fn no_encryption() -> Self {
+        Self(Vec::new())
+    }
+
+    /// Generate a random 64-bit token, as recommended by BIP-129 for most setups.
+    pub fn generate() -> Result<Self, BsmsError> {
+        let entropy = random::random_bytes().map_err(BsmsError::Randomness)?;
+        Ok(Self(entropy[..8].to_vec()))
+    }
+
+    /// Whether the records should be encrypted with this token.
+    pub fn is_encrypted(&self) -> bool {
+        !self.0.is_empty()
+    }
+
+    fn hex(&self) -> String {
+        if self.0.is_empty() {
+            "00".to_string()
+        } else {
+            bitcoin::hex::DisplayHex::to_lower_hex_string(&self.0[..])
+        }
+    }
+
+    // The encryption key, derived from the token using PBKDF2-HMAC-SHA512.
+    fn encryption_key(&self) -> [u8; 32] {
+        // PBKDF2 with a single block, since the key is shorter than the hash output.
+        let mut engine = hmac::HmacEngine::<sha512::Hash>::new(&self.0);
+        engine.input(PBKDF2_SALT);
+        engine.input(&1u32.to_be_bytes());
+        let mut u = hmac::Hmac::<sha512::Hash>::from_engine(engine).to_byte_array();
+        let mut key = u;
+        for _ in 1..PBKDF2_ITERATIONS {
+            let mut engine = hmac::HmacEngine::<sha512::Hash>::new(&self.0);
+            engine.input(&u);
+            u = hmac::Hmac::<sha512::Hash>::from_engine(engine).to_byte_array();
+            key.iter_mut().zip(u.iter()).for_each(|(k, u)| *k ^= u);
+        }
+        key[..32].try_into().expect("32 bytes")
+    }
+
+    // The authentication code of this data, as per BIP-129.
+    fn mac(&self, encryption_key: &[u8; 32], data: &[u8]) -> [u8; 32] {
+        let hmac_key = sha256::Hash::hash(encryption_key);
+        let mut engine = hmac::HmacEngine::<sha256::Hash>::new(hmac_key.as_ref());
+        engine.input(&self.0);
+        engine.input(data);
+        hmac::Hmac::<sha256::Hash>::from_engine(engine).to_byte_array()
+    }
+
+    /// Encrypt a record with this token. Returns the hex encoded authentication code followed by
+    /// the ciphertext. If this token means no encryption, returns the record as is.
+    pub fn encrypt(&self, record: &str) -> String {
+        if !self.is_encrypted() {
+            return record.to_string();
+        }
+        let key = self.encryption_key();
+        let mac = self.mac(&key, record.as_bytes());
+        let mut data = record.as_bytes().to_vec();
+        Aes256Ctr::new(&key.into(), mac[..16].into()).apply_keystream(&mut data);
+        let mut res = mac.to_vec();
+        res.extend_from_slice(&data);
+        bitcoin::hex::DisplayHex::to_lower_hex_string(&res[..])
+    }
+
+    /// Decrypt a record encrypted with this token, checking its authentication code.
+    pub fn decrypt(&self, encrypted: &str) -> Result<String, BsmsError> {
+        if !self.is_encrypted() {
+            return Ok(encrypted.to_string());
+        }
+        let bytes = Vec::<u8>::from_hex(encrypted.trim()).map_err(|_| BsmsError::Decryption)?;
+        if bytes.len() < 32 {
+            return Err(BsmsError::Decryption);
+        }
+        let (mac, data) = bytes.split_at(32);
+        let key = self.encryption_key();
+        let mut data = data.to_vec();
+        Aes256Ctr::new(&key.into(), mac[..16].into()).apply_keystream(&mut data);
+        if self.mac(&key, &data)[..] != mac[..] {
+            return Err(BsmsError::Decryption);
+        }
+        String::from_utf8(data).map_err(|_| BsmsError::Decryption)
+    }
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.hex())
+    }
+}
+
+impl FromStr for Token {
+    type Err = BsmsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.len() {
+            2 if s == "00" => Ok(Self::no_encryption()),
+            16 | 32 => Vec::<u8>::from_hex(s)
+                .map(Self)
+                .map_err(|_| BsmsError::InvalidToken(s.to_string())),
+            _ => Err(BsmsError::InvalidToken(s.to_string())),
+        }
+    }
+}
+
+// Get the lines of a record, checking its version and number of lines.
+fn record_lines(record: &str, n_lines: usize) -> Result<Vec<&str>, BsmsError> {
+    let lines: Vec<&str> = record.trim().lines().map(|l| l.trim()).collect();
+    if lines.len() != n_lines {
+        return Err(BsmsError::MalformedRecord);
+    }
+    if lines[0] != BSMS_VERSION {
+        return Err(BsmsError::UnsupportedVersion(lines[0].to_string()));
+    }
+    Ok(lines)
+}
+
+// The key expression for an xpub with this origin.
+fn key_expression(
+    fingerprint: bip32::Fingerprint,
+    origin_path: &bip32::DerivationPath,
+    xpub: &bip32::Xpub,
+) -> String {
+    if origin_path.is_master() {
+        format!("[{}]{}", fingerprint, xpub)
+    } else {
+        format!("[{}/{}]{}", fingerprint, origin_path, xpub)
+    }
+}
+
+// The first four lines of a key record, which are signed by the Signer.
+fn signed_message(token: &Token, key: &str, description: &str) -> String {
+    format!("{}\n{}\n{}\n{}", BSMS_VERSION, token, key, description)
+}
+
+/// The key record a Signer sends to the Coordinator (round 1). It is signed by the private key
+/// corresponding to the xpub.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyRecord {
+    token: Token,
+    fingerprint: bip32::Fingerprint,
+    origin_path: bip32::DerivationPath,
+    xpub: bip32::Xpub,
+    description: String,
+    signature: MessageSignature,
+}
+
+impl KeyRecord {
+    // The key expression, that is the xpub with its origin.
+    fn key(&self) -> String {
+        key_expression(self.fingerprint, &self.origin_path, &self.xpub)
+    }
+
+    // The part of the record which is signed.
+    fn signed_message(&self) -> String {
+        signed_message(&self.token, &self.key(), &self.description)
+    }
+
+    /// Create a key record signed with the given extended private key, the origin of which is
+    /// `fingerprint` and `origin_path`. Line breaks in the description are replaced by spaces.
+    pub fn new(
+        token: Token,
+        fingerprint: bip32::Fingerprint,
+        origin_path: bip32::DerivationPath,
+        xpriv: &bip32::Xpriv,
+        description: String,
+        secp: &secp256k1::Secp256k1<impl secp256k1::Signing>,
+    ) -> KeyRecord {
+        let description = description.lines().collect::<Vec<_>>().join(" ");
+        let xpub = bip32::Xpub::from_priv(secp, xpriv);
+        let msg = signed_message(
+            &token,
+            &key_expression(fingerprint, &origin_path, &xpub),
+            &description,
+        );
+        let msg_hash = sign_message::signed_msg_hash(&msg);
+        let sig = secp.sign_ecdsa_recoverable(
+            &secp256k1::Message::from_digest(msg_hash.to_byte_array()),
+            &xpriv.private_key,
+        );
+        KeyRecord {
+            token,
+            fingerprint,
+            origin_path,
+            xpub,
+            description,
+            signature: MessageSignature::new(sig, true),
+        }
+    }
+
+    pub fn token(&self) -> &Token {
+        &self.token
+    }
+
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    /// The key of this Signer, as a Liana multipath descriptor key.
+    pub fn descriptor_key(&self) -> descriptor::DescriptorPublicKey {
+        descriptor::DescriptorPublicKey::from_str(&format!("{}/<0;1>/*", self.key()))
+            .expect("Valid key expression")
+    }
+
+    /// The key of this Signer with its origin and without derivation steps, as found in the record.
+    pub fn descriptor_xkey(&self) -> descriptor::DescriptorXKey<bip32::Xpub> {
+        descriptor::DescriptorXKey {
+            origin: Some((self.fingerprint, self.origin_path.clone())),
+            xkey: self.xpub,
+            derivation_path: bip32::DerivationPath::master(),
+            wildcard: descriptor::Wildcard::None,
+        }
+    }
+
+    /// Check the record was signed by the key it contains, and that this key is usable in a Liana
+    /// descriptor.
+    pub fn verify(
+        &self,
+        secp: &secp256k1::Secp256k1<impl secp256k1::Verification>,
+    ) -> Result<(), BsmsError> {
+        DescKeyChecker::new()
+            .check(&self.descriptor_key())
+            .map_err(|_| BsmsError::InvalidKey(self.key()))?;
+        let msg_hash = sign_message::signed_msg_hash(&self.signed_message());
+        let pubkey = self
+            .signature
+            .recover_pubkey(secp, msg_hash)
+            .map_err(|_| BsmsError::InvalidSignature)?;
+        if pubkey.inner != self.xpub.public_key {
+            return Err(BsmsError::InvalidSignature);
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for KeyRecord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}\n{}",
+            self.signed_message(),
+            self.signature.to_base64()
+        )
+    }
+}
+
+impl FromStr for KeyRecord {
+    type Err = BsmsError;
+
+    /// Parse a key record. Note this does not verify its signature, see [`KeyRecord::verify`].
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lines = record_lines(s, 5)?;
+        let token = Token::from_str(lines[1])?;
+        let invalid_key = || BsmsError::InvalidKey(lines[2].to_string());
+        let (fingerprint, origin_path, xpub) =
+            match descriptor::DescriptorPublicKey::from_str(lines[2]).map_err(|_| invalid_key())? {
+                descriptor::DescriptorPublicKey::XPub(descriptor::DescriptorXKey {
+                    origin: Some((fg, origin_path)),
+                    xkey,
+                    derivation_path,
+                    wildcard: descriptor::Wildcard::None,
+                }) if derivation_path.is_master() => (fg, origin_path, xkey),
+                _ => return Err(invalid_key()),
+            };
+        let signature =
+            MessageSignature::from_base64(lines[4]).map_err(|_| BsmsError::InvalidSignature)?;
+        Ok(KeyRecord {
+            token,
+            fingerprint,
+            origin_path,
+            xpub,
+            description: lines[3].to_string(),
+            signature,
+        })
+    }
+}
+
+/// The descriptor record the Coordinator sends to all Signers (round 2).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DescriptorRecord {
+    descriptor: LianaDescriptor,
+    first_address: bitcoin::Address,
+}
+
+impl DescriptorRecord {
+    /// Create the descriptor record for this descriptor on this network.
+    pub fn new(
+        descriptor: LianaDescriptor,
+        network: bitcoin::Network,
+        secp: &secp256k1::Secp256k1<impl secp256k1::Verification>,
+    ) -> DescriptorRecord {
+        let first_address = descriptor
+            .receive_descriptor()
+            .derive(0.into(), secp)
+            .address(network);
+        DescriptorRecord {
+            descriptor,
+            first_address,
+        }
+    }
+
+    /// Parse a descriptor record and check its first address matches the one derived from its
+    /// descriptor on this network.
+    pub fn parse(
+        record: &str,
+        network: bitcoin::Network,
+        secp: &secp256k1::Secp256k1<impl secp256k1::Verification>,
+    ) -> Result<DescriptorRecord, BsmsError> {
+        let lines = record_lines(record, 4)?;
+        if lines[2] != PATH_RESTRICTIONS && lines[2] != NO_PATH_RESTRICTIONS {
+            return Err(BsmsError::InvalidPathRestrictions(lines[2].to_string()));
+        }
+        let descriptor = LianaDescriptor::from_str(&lines[1].replace("/**", "/<0;1>/*"))?;
+        let record = DescriptorRecord::new(descriptor, network, secp);
+        if record.first_address.to_string() != lines[3] {
+            return Err(BsmsError::FirstAddressMismatch(lines[3].to_string()));
+        }
+        Ok(record)
+    }
+
+    /// Decrypt and parse a descriptor record. See [`DescriptorRecord::parse`].
+    pub fn decrypt(
+        token: &Token,
+        encrypted: &str,
+        network: bitcoin::Network,
+        secp: &secp256k1::Secp256k1<impl secp256k1::Verification>,
+    ) -> Result<DescriptorRecord, BsmsError> {
+        DescriptorRecord::parse(&token.decrypt(encrypted)?, network, secp)
+    }
+
+    /// Get the record encrypted with this token.
+    pub fn encrypt(&self, token: &Token) -> String {
+        token.encrypt(&self.to_string())
+    }
+
+    pub fn descriptor(&self) -> &LianaDescriptor {
+        &self.descriptor
+    }
+
+    pub fn first_address(&self) -> &bitcoin::Address {
+        &self.first_address
+    }
+
+    /// Whether the key from this key record is part of the descriptor.
+    pub fn contains_key(&self, key_record: &KeyRecord) -> bool {
+        self.descriptor
+            .explain()
+            .paths
+            .iter()
+            .flat_map(|path| path.keys.iter())
+            .any(|key| match key.key {
+                descriptor::DescriptorPublicKey::MultiXPub(ref xpub) => {
+                    xpub.xkey == key_record.xpub
+                        && key.fingerprint == key_record.fingerprint
+                        && key.origin_path == key_record.origin_path
+                }
+                _ => false,
+            })
+    }
+}
+
+impl fmt::Display for DescriptorRecord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // The descriptor template, without checksum, with the standard derivation steps expressed
+        // as `/**`.
+        let desc = self.descriptor.to_string();
+        let template = desc.split_once('#').map(|(d, _)| d).unwrap_or(&desc);
+        write!(
+            f,
+            "{}\n{}\n{}\n{}",
+            BSMS_VERSION,
+            template.replace("/<0;1>/*", "/**"),
+            PATH_RESTRICTIONS,
+            self.first_address
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        descriptors::{LianaPolicy, PathInfo},
+        signer::HotSigner,
+    };
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn bsms_token() {
+        assert_eq!(Token::from_str("00").unwrap(), Token::no_encryption());
+        assert!(!Token::no_encryption().is_encrypted());
+        let token = Token::from_str("a54044308ceac9b7").unwrap();
+        assert!(token.is_encrypted());
+        assert_eq!(token.to_string(), "a54044308ceac9b7");
+        Token::from_str("a54044308ceac9b7a54044308ceac9b7").unwrap();
+        Token::from_str("a54044308ceac9").unwrap_err();
+        Token::from_str("z54044308ceac9b7").unwrap_err();
+        Token::from_str("").unwrap_err();
+        assert_eq!(Token::generate().unwrap().to_string().len(), 16);
+        assert_ne!(Token::generate().unwrap(), Token::generate().unwrap());
+
+        // Encryption roundtrip, and a record can't be decrypted with another token or if it was
+        // tampered with.
+        let record = "BSMS 1.0\na54044308ceac9b7\nsome data";
+        let encrypted = token.encrypt(record);
+        assert_ne!(encrypted, record);
+        assert_eq!(token.decrypt(&encrypted).unwrap(), record);
+        let other_token = Token::from_str("b54044308ceac9b7").unwrap();
+        assert!(matches!(
+            other_token.decrypt(&encrypted),
+            Err(BsmsError::Decryption)
+        ));
+        let mut tampered = encrypted.clone().into_bytes();
+        let last = tampered.len() - 1;
+        tampered[last] = if tampered[last] == b'0' { b'1' } else { b'0' };
+        assert!(matches!(
+            token.decrypt(&String::from_utf8(tampered).unwrap()),
+            Err(BsmsError::Decryption)
+        ));
+        assert!(matches!(token.decrypt("abcd"), Err(BsmsError::Decryption)));
+        assert!(matches!(
+            token.decrypt("not hex"),
+            Err(BsmsError::Decryption)
+        ));
+
+        // Without encryption the record is passed as is.
+        assert_eq!(Token::no_encryption().encrypt(record), record);
+        assert_eq!(Token::no_encryption().decrypt(record).unwrap(), record);
+    }
+
+    // Known answers for the token of the BIP-129 examples and the key at m/48'/0'/0'/2' of the
+    // "abandon ... about" mnemonic, checked against an independent implementation of the BIP.
+    #[test]
+    fn bsms_vectors() {
+        let secp = secp256k1::Secp256k1::new();
+        let token = Token::from_str("a54044308ceac9b7").unwrap();
+        assert_eq!(
+            bitcoin::hex::DisplayHex::to_lower_hex_string(&token.encryption_key()[..]),
+            "847fba0c67fac00c7db56b106ac5040de236765fbb7e3bf7dedcbb6b3baeaa61"
+        );
+
+        // The key record and its signature.
+        let record = "BSMS 1.0\na54044308ceac9b7\n[73c5da0a/48'/0'/0'/2']xpub6DkFAXWQ2dHxq2vatrt9qyA3bXYU4ToWQwCHbf5XB2mSTexcHZCeKS1VZYcPoBd5X8yVcbXFHJR9R8UCVpt82VX1VhR28mCyxUFL4r6KFrf\nSigner 1 key\nIFj7bDQKOtzHLvuEduAA40svfLqS4aq08xZxnx9sA42xS+XkeCWG7TDAXk7SqhkvK+PbxSLsF9GfBf/l7YIfghc=";
+        let signer = HotSigner::from_str(
+            bitcoin::Network::Bitcoin,
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about",
+        )
+        .unwrap();
+        let key_record = signer.bsms_key_record(
+            token.clone(),
+            &bip32::DerivationPath::from_str("m/48'/0'/0'/2'").unwrap(),
+            "Signer 1 key".to_string(),
+            &secp,
+        );
+        assert_eq!(key_record.to_string(), record);
+        KeyRecord::from_str(record).unwrap().verify(&secp).unwrap();
+
+        // The authentication code followed by the ciphertext of the key record.
+        let mac = "7f2b20b8d384db36fc2c79dfb13deaa220cbe4b9e310f70d27f62523b1179a8a";
+        let ciphertext = "68469742e7188d1a12078be46dffa2d50cc903d7b63dd15fa380e96305e8ea32ede41a5aa1990a93c1c6a906f63ac1422dc24c6abece9609c6bc4c3abdd33e837ae15b2f2011ed47b879e5c7b87b29f444d35fc74e74a32863bea00090a04c9edfb72b5ce56e3d8535ea08ea3d85e6569db08bfd1c5984f91b95a25fe465cc3c33f4fe0e6be50b28bd0c9c7a14484842ee78255705f124058b2b9e57a20bd8f168b7caa6f80ad4b7a07d94740f9f39cf85e99c26bd5e2981b08a28d5086a6412a7163a4fc715964baeb3440a1bf361c58ffcff48ef3835c2f96d391d692ca11699dbf2aa15973c1f35cfd2bd94b82f57b66f1765231afc4e0f0c23cdacf3ae0428bdaa601c17";
+        let encrypted = token.encrypt(record);
+        assert_eq!(encrypted, format!("{}{}", mac, ciphertext));
+        assert_eq!(token.decrypt(&encrypted).unwrap(), record);
+    }
+
+    #[test]
+    fn bsms_setup() {
+        let secp = secp256k1::Secp256k1::new();
+        let network = bitcoin::Network::Testnet;
+        let token = Token::generate().unwrap();
+        let der_path = bip32::DerivationPath::from_str("m/48'/1'/0'/2'").unwrap();
+        let (signer_a, signer_b) = (
+            HotSigner::generate(network).unwrap(),
+            HotSigner::generate(network).unwrap(),
+        );
+
+        // Round 1: each Signer sends a key record to the Coordinator, which verifies it.
+        let record_a = signer_a.bsms_key_record(
+            token.clone(),
+            &der_path,
+            "Alice's\nlaptop".to_string(),
+            &secp,
+        );
+        let record_b = signer_b.bsms_key_record(
+            token.clone(),
+            &bip32::DerivationPath::master(),
+            "Bob".to_string(),
+            &secp,
+        );
+        // Line breaks in the description are removed.
+        assert_eq!(record_a.description(), "Alice's laptop");
+        let record_a_str = record_a.to_string();
+        assert_eq!(record_a_str.lines().count(), 5);
+        assert!(record_a_str.starts_with(&format!(
+            "BSMS 1.0\n{}\n[{}/48'/1'/0'/2']tpub",
+            token,
+            signer_a.fingerprint(&secp)
+        )));
+        assert!(record_b
+            .to_string()
+            .contains(&format!("\n[{}]tpub", signer_b.fingerprint(&secp))));
+        let parsed_a = KeyRecord::from_str(&record_a_str).unwrap();
+        assert_eq!(parsed_a, record_a);
+        parsed_a.verify(&secp).unwrap();
+        let parsed_b = KeyRecord::from_str(&record_b.to_string()).unwrap();
+        parsed_b.verify(&secp).unwrap();
+        assert_eq!(
+            descriptor::DescriptorPublicKey::XPub(parsed_b.descriptor_xkey()).to_string(),
+            record_b.to_string().lines().nth(2).unwrap()
+        );
+        assert_eq!(parsed_a.token(), &token);
+
+        // A record whose signed content was modified is rejected.
+        let tampered = record_a_str.replace("Alice's laptop", "Mallory's laptop");
+        assert!(matches!(
+            KeyRecord::from_str(&tampered).unwrap().verify(&secp),
+            Err(BsmsError::InvalidSignature)
+        ));
+        let sig_b = record_b.to_string().lines().last().unwrap().to_string();
+        let sig_a = record_a_str.lines().last().unwrap().to_string();
+        assert!(matches!(
+            KeyRecord::from_str(&record_a_str.replace(&sig_a, &sig_b))
+                .unwrap()
+                .verify(&secp),
+            Err(BsmsError::InvalidSignature)
+        ));
+
+        // Malformed records.
+        assert!(matches!(
+            KeyRecord::from_str(&record_a_str.replace("BSMS 1.0", "BSMS 2.0")),
+            Err(BsmsError::UnsupportedVersion(_))
+        ));
+        let four_lines = record_a_str.lines().take(4).collect::<Vec<_>>().join("\n");
+        assert!(matches!(
+            KeyRecord::from_str(&four_lines),
+            Err(BsmsError::MalformedRecord)
+        ));
+        let key_line = record_a_str.lines().nth(2).unwrap();
+        assert!(matches!(
+            KeyRecord::from_str(&record_a_str.replace(key_line, &format!("{}/<0;1>/*", key_line))),
+            Err(BsmsError::InvalidKey(_))
+        ));
+
+        // The Coordinator creates the descriptor and sends the encrypted descriptor record to the
+        // Signers.
+        let policy = LianaPolicy::new_legacy(
+            PathInfo::Single(parsed_a.descriptor_key()),
+            [(52560, PathInfo::Single(parsed_b.descriptor_key()))]
+                .iter()
+                .cloned()
+                .collect::<BTreeMap<_, _>>(),
+        )
+        .unwrap();
+        let desc = LianaDescriptor::new(policy);
+        let desc_record = DescriptorRecord::new(desc.clone(), network, &secp);
+        let desc_record_str = desc_record.to_string();
+        let mut lines = desc_record_str.lines();
+        assert_eq!(lines.next(), Some("BSMS 1.0"));
+        let template = lines.next().unwrap();
+        assert!(template.starts_with("wsh(or_d(pk(["));
+        assert!(template.contains("/**)"));
+        assert!(!template.contains('#') && !template.contains("<0;1>"));
+        assert_eq!(lines.next(), Some("/0/*,/1/*"));
+        assert_eq!(
+            lines.next(),
+            Some(
+                desc.receive_descriptor()
+                    .derive(0.into(), &secp)
+                    .address(network)
+                    .to_string()
+                    .as_str()
+            )
+        );
+        let encrypted = desc_record.encrypt(&token);
+
+        // Each Signer decrypts it, checks its key is part of it and verifies the first address.
+        let received = DescriptorRecord::decrypt(&token, &encrypted, network, &secp).unwrap();
+        assert_eq!(received, desc_record);
+        assert_eq!(received.descriptor(), &desc);
+        assert!(received.contains_key(&record_a));
+        assert!(received.contains_key(&record_b));
+        let record_c = HotSigner::generate(network).unwrap().bsms_key_record(
+            token.clone(),
+            &der_path,
+            "Carol".to_string(),
+            &secp,
+        );
+        assert!(!received.contains_key(&record_c));
+
+        // A wrong first address, or another network, is detected.
+        let other_addr = desc
+            .receive_descriptor()
+            .derive(1.into(), &secp)
+            .address(network)
+            .to_string();
+        let wrong_addr =
+            desc_record_str.replace(&desc_record.first_address().to_string(), &other_addr);
+        assert!(matches!(
+            DescriptorRecord::parse(&wrong_addr, network, &secp),
+            Err(BsmsError::FirstAddressMismatch(_))
+        ));
+        assert!(matches!(
+            DescriptorRecord::parse(&desc_record_str, bitcoin::Network::Bitcoin, &secp),
+            Err(BsmsError::FirstAddressMismatch(_))
+        ));
+        assert!(matches!(
+            DescriptorRecord::parse(
+                &desc_record_str.replace("/0/*,/1/*", "/0/*"),
+                network,
+                &secp
+            ),
+            Err(BsmsError::InvalidPathRestrictions(_))
+        ));
+        assert!(matches!(
+            DescriptorRecord::decrypt(&Token::generate().unwrap(), &encrypted, network, &secp),
+            Err(BsmsError::Decryption)
+        ));
+        // Records exchanged without encryption.
+        let plain = desc_record.encrypt(&Token::no_encryption());
+        assert_eq!(plain, desc_record_str);
+        DescriptorRecord::decrypt(&Token::no_encryption(), &plain, network, &secp).unwrap();
+    }
+}
@@ -1,16 +1,16 @@
 use miniscript::{
     bitcoin::{
         self, bip32,
-        hashes::{sha256, Hash},
+        hashes::{hash160, sha256, Hash},
         secp256k1,
     },
     descriptor,
     policy::{Concrete as ConcretePolicy, Liftable, Semantic as SemanticPolicy},
-    RelLockTime, ScriptContext, Threshold,
+    AbsLockTime, RelLockTime, ScriptContext, Threshold,
 };
 
 use std::{
-    collections::{BTreeMap, HashMap, HashSet},
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
     convert::TryFrom,
     error, fmt,
     str::FromStr,
@@ -27,6 +27,15 @@ pub enum LianaPolicyError {
     DuplicateOriginSamePath(Box<descriptor::DescriptorPublicKey>),
     InvalidMultiThresh(usize),
     InvalidMultiKeys(usize),
+    /// The threshold of a decaying multisig must strictly decrease as the timelock increases.
+    InvalidDecayingThreshold(usize),
+    /// Each step of an expanding multisig must add at least one key.
+    EmptyKeysetExpansion(u16),
+    /// The threshold of an expanding multisig must never increase as the timelock increases.
+    InvalidExpandingThreshold(usize),
+    /// The xpubs of a decaying or expanding multisig must be account-level xpubs, without
+    /// derivation path nor wildcard.
+    InvalidAccountXpub(Box<descriptor::DescriptorPublicKey>),
     IncompatibleDesc,
     PolicyAnalysis(miniscript::Error),
     /// The spending policy is not a valid Miniscript policy: it may for instance be malleable, or
@@ -50,6 +59,10 @@ impl std::fmt::Display for LianaPolicyError {
             }
             Self::InvalidMultiThresh(thresh) => write!(f, "Invalid multisig threshold value '{}'. The threshold must be > to 0 and <= to the number of keys.", thresh),
             Self::InvalidMultiKeys(n_keys) => write!(f, "Invalid number of keys '{}'. Between 2 and 20 keys must be given to use multiple keys in a specific path.", n_keys),
+            Self::InvalidDecayingThreshold(thresh) => write!(f, "Invalid decaying multisig threshold value '{}'. Each recovery path threshold must be > to 0 and < to the threshold of the path before it.", thresh),
+            Self::EmptyKeysetExpansion(timelock) => write!(f, "The recovery path with timelock '{}' does not add any key to the path before it.", timelock),
+            Self::InvalidExpandingThreshold(thresh) => write!(f, "Invalid expanding multisig threshold value '{}'. Each recovery path threshold must be > to 0 and <= to the threshold of the path before it.", thresh),
+            Self::InvalidAccountXpub(key) => write!(f, "Invalid key '{}'. An account-level xpub without derivation path nor wildcard must be given.", key),
             Self::DuplicateKey(key) => {
                 write!(f, "Duplicate key '{}'.", key)
             }
@@ -80,7 +93,7 @@ fn is_single_key_or_multisig(policy: &SemanticPolicy<descriptor::DescriptorPubli
     }
 }
 
-struct DescKeyChecker {
+pub(crate) struct DescKeyChecker {
     keys_set: HashSet<(bip32::Xpub, descriptor::DerivPaths)>,
 }
 
@@ -146,6 +159,26 @@ fn csv_check(csv_value: u32) -> Result<u16, LianaPolicyError> {
     }
 }
 
+// The flag set in a relative locktime to signal it's in units of 512 seconds (BIP68).
+const CSV_TYPE_FLAG: u32 = 1 << 22;
+
+// We require a time-based locktime to:
+//  - not be disabled
+//  - have the type flag set
+//  - be 'clean' / minimal, ie all bits without consensus meaning should be 0
+//  - be positive
+//
+// The returned value is the number of 512-second intervals.
+fn csv_time_check(csv_value: u32) -> Result<u16, LianaPolicyError> {
+    if csv_value & CSV_TYPE_FLAG == 0 {
+        return Err(LianaPolicyError::InsaneTimelock(csv_value));
+    }
+    match u16::try_from(csv_value & !CSV_TYPE_FLAG) {
+        Ok(intervals) if intervals > 0 => Ok(intervals),
+        _ => Err(LianaPolicyError::InsaneTimelock(csv_value)),
+    }
+}
+
 // Get the fingerprint and the full derivation paths (path from the master fingerprint in the
 // origin, with the xpub derivation path appended) for a multipath xpub.
 fn key_origins(
@@ -171,6 +204,133 @@ fn key_origins(
     }
 }
 
+/// The hash of a secret which must be revealed to spend through a hash-locked path.
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Ord, PartialOrd, Hash)]
+pub enum HashLock {
+    Sha256(sha256::Hash),
+    Hash160(hash160::Hash),
+}
+
+impl fmt::Display for HashLock {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Sha256(h) => write!(f, "sha256({})", h),
+            Self::Hash160(h) => write!(f, "hash160({})", h),
+        }
+    }
+}
+
+impl HashLock {
+    /// The hash lock for a SHA256 hash of this preimage.
+    pub fn sha256(preimage: &[u8; 32]) -> Self {
+        Self::Sha256(sha256::Hash::hash(preimage))
+    }
+
+    /// The hash lock for a HASH160 hash of this preimage.
+    pub fn hash160(preimage: &[u8; 32]) -> Self {
+        Self::Hash160(hash160::Hash::hash(preimage))
+    }
+
+    /// Whether the given preimage unlocks this hash lock.
+    pub fn is_unlocked_by(&self, preimage: &[u8; 32]) -> bool {
+        match self {
+            Self::Sha256(h) => sha256::Hash::hash(preimage) == *h,
+            Self::Hash160(h) => hash160::Hash::hash(preimage) == *h,
+        }
+    }
+
+    // Get the hash lock from a Miniscript policy node, if it is one.
+    fn from_policy(policy: &SemanticPolicy<descriptor::DescriptorPublicKey>) -> Option<Self> {
+        match policy {
+            SemanticPolicy::Sha256(h) => Some(Self::Sha256(*h)),
+            SemanticPolicy::Hash160(h) => Some(Self::Hash160(*h)),
+            _ => None,
+        }
+    }
+
+    fn into_ms_policy(self) -> ConcretePolicy<descriptor::DescriptorPublicKey> {
+        match self {
+            Self::Sha256(h) => ConcretePolicy::Sha256(h),
+            Self::Hash160(h) => ConcretePolicy::Hash160(h),
+        }
+    }
+}
+
+/// The condition which must be met, in addition to providing signatures for its keys, to spend
+/// through a spending path other than the primary one.
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Ord, PartialOrd, Hash)]
+pub enum PathCondition {
+    /// A relative timelock in number of blocks.
+    RelativeBlocks(u16),
+    /// A relative timelock in number of 512-second intervals.
+    RelativeTime(u16),
+    /// An absolute timelock: a block height or a UNIX timestamp, as per the `nLockTime` semantics.
+    Absolute(AbsLockTime),
+    /// The reveal of the preimage of a hash. The path is available at any time.
+    HashLock(HashLock),
+}
+
+impl PathCondition {
+    /// Whether this is a relative timelock, in blocks or in time.
+    pub fn is_relative_timelock(&self) -> bool {
+        matches!(self, Self::RelativeBlocks(_) | Self::RelativeTime(_))
+    }
+
+    /// Whether this condition is met by a transaction input with the given nSequence, in a
+    /// transaction with the given nLockTime. A hash lock is always considered met, as the
+    /// preimage is not part of the transaction.
+    pub fn is_available(
+        &self,
+        sequence: bitcoin::Sequence,
+        lock_time: bitcoin::absolute::LockTime,
+    ) -> bool {
+        match self {
+            Self::HashLock(_) => true,
+            Self::Absolute(timelock) => {
+                let timelock: bitcoin::absolute::LockTime = (*timelock).into();
+                sequence.enables_absolute_lock_time() && timelock.is_implied_by(lock_time)
+            }
+            Self::RelativeTime(timelock) => matches!(
+                sequence.to_relative_lock_time(),
+                Some(bitcoin::relative::LockTime::Time(time)) if time.value() >= *timelock
+            ),
+            Self::RelativeBlocks(timelock) => {
+                sequence.is_height_locked() && sequence.0 >= *timelock as u32
+            }
+        }
+    }
+
+    // Get the condition from a Miniscript policy node, if it is one. Returns an error if it is a
+    // timelock we don't support.
+    fn from_policy(
+        policy: &SemanticPolicy<descriptor::DescriptorPublicKey>,
+    ) -> Option<Result<Self, LianaPolicyError>> {
+        match policy {
+            SemanticPolicy::Older(val) if val.is_time_locked() => {
+                Some(csv_time_check(val.to_consensus_u32()).map(Self::RelativeTime))
+            }
+            SemanticPolicy::Older(val) => {
+                Some(csv_check(val.to_consensus_u32()).map(Self::RelativeBlocks))
+            }
+            SemanticPolicy::After(val) => Some(Ok(Self::Absolute(*val))),
+            _ => HashLock::from_policy(policy).map(|h| Ok(Self::HashLock(h))),
+        }
+    }
+
+    fn into_ms_policy(self) -> ConcretePolicy<descriptor::DescriptorPublicKey> {
+        match self {
+            Self::HashLock(h) => h.into_ms_policy(),
+            Self::Absolute(timelock) => ConcretePolicy::After(timelock),
+            Self::RelativeTime(timelock) => {
+                ConcretePolicy::Older(RelLockTime::from_512_second_intervals(timelock))
+            }
+            Self::RelativeBlocks(timelock) => {
+                ConcretePolicy::Older(RelLockTime::from_height(timelock))
+            }
+        }
+    }
+}
+
 /// Information about a single spending path in the descriptor.
 #[derive(Debug, Eq, PartialEq, Clone, Ord, PartialOrd, Hash)]
 pub enum PathInfo {
@@ -203,60 +363,69 @@ impl PathInfo {
         }
     }
 
-    /// Get the information about the recovery spending path.
-    /// Returns None if the policy does not describe the recovery spending path of a Liana
-    /// descriptor (that is, a set of keys after a timelock).
-    pub fn from_recovery_path(
+    /// Get the information about a spending path other than the primary one, along with the
+    /// condition it is behind. Returns None if the policy does not describe such a spending path
+    /// of a Liana descriptor (that is, a set of keys along with a timelock or a hash lock).
+    pub fn from_conditional_path(
+        policy: SemanticPolicy<descriptor::DescriptorPublicKey>,
+    ) -> Result<(PathCondition, PathInfo), LianaPolicyError> {
+        PathInfo::from_path_with_condition(policy, PathCondition::from_policy)
+    }
+
+    // Get the information about a spending path made of a set of keys and a single additional
+    // condition (a timelock, a hashlock, ..). `parse_cond` must return `None` for any policy node
+    // which is not the condition for this path.
+    fn from_path_with_condition<T>(
         policy: SemanticPolicy<descriptor::DescriptorPublicKey>,
-    ) -> Result<(u16, PathInfo), LianaPolicyError> {
-        // The recovery spending path must always be a policy of type `thresh(2, older(x), thresh(n, key1,
-        // key2, ..))`. In the special case n == 1, it is only `thresh(2, older(x), key)`. In the
+        parse_cond: impl Fn(
+            &SemanticPolicy<descriptor::DescriptorPublicKey>,
+        ) -> Option<Result<T, LianaPolicyError>>,
+    ) -> Result<(T, PathInfo), LianaPolicyError> {
+        // The spending path must always be a policy of type `thresh(2, cond, thresh(n, key1,
+        // key2, ..))`. In the special case n == 1, it is only `thresh(2, cond, key)`. In the
         // special case n == len(keys) (i.e. it's an N-of-N multisig), it is normalized as
-        // `thresh(n+1, older(x), key1, key2, ...)`.
+        // `thresh(n+1, cond, key1, key2, ...)`.
         let (k, subs) = match policy {
             SemanticPolicy::Thresh(thresh) => (thresh.k(), thresh.into_data()),
             _ => return Err(LianaPolicyError::IncompatibleDesc),
         };
         if k == 2 && subs.len() == 2 {
-            // The general case (as well as the n == 1 case). The sub that is not the timelock is
+            // The general case (as well as the n == 1 case). The sub that is not the condition is
             // of the same form as a primary path.
-            let tl_value = subs
+            let cond = subs
                 .iter()
-                .find_map(|s| match s.as_ref() {
-                    SemanticPolicy::Older(val) => Some(csv_check(val.to_consensus_u32())),
-                    _ => None,
-                })
+                .find_map(|s| parse_cond(s.as_ref()))
                 .ok_or(LianaPolicyError::IncompatibleDesc)??;
             let keys_sub = subs
                 .into_iter()
                 .find(|sub| is_single_key_or_multisig(sub.as_ref()))
                 .ok_or(LianaPolicyError::IncompatibleDesc)?;
-            PathInfo::from_primary_path(keys_sub.as_ref().clone()).map(|info| (tl_value, info))
+            PathInfo::from_primary_path(keys_sub.as_ref().clone()).map(|info| (cond, info))
         } else if k == subs.len() && subs.len() > 2 {
-            // The N-of-N case. All subs but the threshold must be keys (if one had been thresh()
+            // The N-of-N case. All subs but the condition must be keys (if one had been thresh()
             // of keys it would have been normalized).
-            let mut tl_value = None;
+            let mut cond = None;
             let mut keys = Vec::with_capacity(subs.len());
             for sub in subs {
-                match sub.as_ref() {
-                    SemanticPolicy::Key(key) => keys.push(key.clone()),
-                    SemanticPolicy::Older(val) => {
-                        if tl_value.is_some() {
-                            return Err(LianaPolicyError::IncompatibleDesc);
-                        }
-                        tl_value = Some(csv_check(val.to_consensus_u32())?);
+                if let SemanticPolicy::Key(key) = sub.as_ref() {
+                    keys.push(key.clone());
+                } else if let Some(parsed_cond) = parse_cond(sub.as_ref()) {
+                    if cond.is_some() {
+                        return Err(LianaPolicyError::IncompatibleDesc);
                     }
-                    _ => return Err(LianaPolicyError::IncompatibleDesc),
+                    cond = Some(parsed_cond?);
+                } else {
+                    return Err(LianaPolicyError::IncompatibleDesc);
                 }
             }
-            assert!(keys.len() > 1); // At least 3 subs, only one of which may be older().
+            assert!(keys.len() > 1); // At least 3 subs, only one of which may be the condition.
             Ok((
-                tl_value.ok_or(LianaPolicyError::IncompatibleDesc)?,
+                cond.ok_or(LianaPolicyError::IncompatibleDesc)?,
                 PathInfo::Multi(k - 1, keys),
             ))
         } else {
-            // If there is less than 2 subs, there can't be both a timelock and keys. If the
-            // threshold is not equal to the number of subs, the timelock can't be mandatory.
+            // If there is less than 2 subs, there can't be both a condition and keys. If the
+            // threshold is not equal to the number of subs, the condition can't be mandatory.
             Err(LianaPolicyError::IncompatibleDesc)
         }
     }
@@ -396,6 +565,53 @@ fn get_multi_xkey(desc_key: &descriptor::DescriptorPublicKey) -> Option<&bip32::
     }
 }
 
+// Get a multipath descriptor key for the given signer's xpub, for use as its `index`-th
+// appearance in the descriptor. Each appearance of a signer derives from different multipath
+// indexes (`<0;1>`, `<2;3>`, ..) so the same xpub may be used across spending paths.
+fn multi_xkey_from_xpub(
+    xpub: &descriptor::DescriptorXKey<bip32::Xpub>,
+    index: u32,
+) -> Result<descriptor::DescriptorPublicKey, LianaPolicyError> {
+    // We would otherwise silently drop them.
+    if !xpub.derivation_path.is_empty() || xpub.wildcard != descriptor::Wildcard::None {
+        return Err(LianaPolicyError::InvalidAccountXpub(Box::new(
+            descriptor::DescriptorPublicKey::XPub(xpub.clone()),
+        )));
+    }
+    Ok(descriptor::DescriptorPublicKey::MultiXPub(
+        descriptor::DescriptorMultiXKey {
+            origin: xpub.origin.clone(),
+            xkey: xpub.xkey,
+            derivation_paths: descriptor::DerivPaths::new(vec![
+                [bip32::ChildNumber::from_normal_idx(2 * index).expect("Small index")][..].into(),
+                [bip32::ChildNumber::from_normal_idx(2 * index + 1).expect("Small index")][..]
+                    .into(),
+            ])
+            .expect("Non empty vec"),
+            wildcard: descriptor::Wildcard::Unhardened,
+        },
+    ))
+}
+
+// Create a spending path made of the given keys and threshold.
+fn path_from_keys(
+    threshold: usize,
+    keys: Vec<descriptor::DescriptorPublicKey>,
+) -> Result<PathInfo, LianaPolicyError> {
+    if threshold == 0 || threshold > keys.len() {
+        return Err(LianaPolicyError::InvalidMultiThresh(threshold));
+    }
+    if keys.len() == 1 {
+        return Ok(PathInfo::Single(
+            keys.into_iter().next().expect("Length is 1"),
+        ));
+    }
+    if keys.len() > 20 {
+        return Err(LianaPolicyError::InvalidMultiKeys(keys.len()));
+    }
+    Ok(PathInfo::Multi(threshold, keys))
+}
+
 // Construct an unspendable xpub to be used as internal key in a Taproot descriptor, in a way which
 // could eventually be standardized into wallet policies for a signer to display to the user
 // "UNSPENDABLE" upon registration (instead of a meaningless key).
@@ -459,6 +675,14 @@ fn unspendable_internal_key(
 ///     - A directly available path with any number of keys checks; or
 ///     - One or more recovery paths with any number of keys checks, behind increasing relative
 ///     timelocks. No two recovery paths may have the same timelock.
+/// The relative timelocks are expressed either in blocks or in units of 512 seconds. There must
+/// always be at least one recovery path behind a relative timelock, of either kind.
+/// It may also contain any number of recovery paths with any number of keys checks behind an
+/// absolute timelock (a block height or a date), which unlike relative timelocks does not depend on
+/// when a coin was received. No two of those may have the same timelock.
+/// It may also contain any number of hash-locked paths, available at any time with any number of
+/// keys checks along with the reveal of a hash preimage. No two hash-locked paths may use the same
+/// hash.
 /// A Liana policy can be created from some settings (the primary and recovery keys, the
 /// timelock(s)) and be used to derive a descriptor. It can also be inferred from a descriptor and
 /// be used to retrieve the settings.
@@ -467,7 +691,7 @@ fn unspendable_internal_key(
 #[derive(Debug, Eq, PartialEq, Clone)]
 pub struct LianaPolicy {
     pub(super) primary_path: PathInfo,
-    pub(super) recovery_paths: BTreeMap<u16, PathInfo>,
+    pub(super) conditional_paths: BTreeMap<PathCondition, PathInfo>,
     is_taproot: bool,
 }
 
@@ -478,30 +702,36 @@ impl LianaPolicy {
     /// to miniscript before returning.
     fn _new(
         primary_path: PathInfo,
-        recovery_paths: BTreeMap<u16, PathInfo>,
+        conditional_paths: BTreeMap<PathCondition, PathInfo>,
         is_taproot: bool,
         compile: bool,
     ) -> Result<LianaPolicy, LianaPolicyError> {
-        if recovery_paths.is_empty() {
+        if !conditional_paths
+            .keys()
+            .any(PathCondition::is_relative_timelock)
+        {
             return Err(LianaPolicyError::MissingRecoveryPath);
         }
 
-        // We require the locktime to:
+        // We require the relative locktimes to:
         //  - not be disabled
-        //  - be in number of blocks
         //  - be 'clean' / minimal, ie all bits without consensus meaning should be 0
         //  - be positive (Miniscript requires it not to be 0)
         //
-        // All this is achieved through asking for a 16-bit integer.
-        if recovery_paths.contains_key(&0) {
+        // All this is achieved through asking for a 16-bit integer, either a number of blocks or
+        // of 512-second intervals depending on the type of the timelock.
+        if conditional_paths.contains_key(&PathCondition::RelativeBlocks(0)) {
             return Err(LianaPolicyError::InsaneTimelock(0));
         }
+        if conditional_paths.contains_key(&PathCondition::RelativeTime(0)) {
+            return Err(LianaPolicyError::InsaneTimelock(CSV_TYPE_FLAG));
+        }
 
         // Check all keys are valid according to our standard (this checks all are multipath keys).
         // Note while the Miniscript compiler does check for duplicate, it does so at the
         // "descriptor key expression" level. We don't want duplicate xpubs at all so we do it
         // ourselves here.
-        let spending_paths = recovery_paths
+        let spending_paths = conditional_paths
             .values()
             .chain(std::iter::once(&primary_path));
         let mut key_checker = DescKeyChecker::new();
@@ -533,7 +763,7 @@ impl LianaPolicy {
         // Make sure it is a valid Miniscript policy by (ab)using the compiler.
         let policy = LianaPolicy {
             primary_path,
-            recovery_paths,
+            conditional_paths,
             is_taproot,
         };
         if compile {
@@ -542,6 +772,16 @@ impl LianaPolicy {
         Ok(policy)
     }
 
+    // Key the given recovery paths, behind relative timelocks in blocks, by their condition.
+    fn relative_blocks_paths(
+        recovery_paths: BTreeMap<u16, PathInfo>,
+    ) -> BTreeMap<PathCondition, PathInfo> {
+        recovery_paths
+            .into_iter()
+            .map(|(timelock, path)| (PathCondition::RelativeBlocks(timelock), path))
+            .collect()
+    }
+
     /// Create a new Liana policy for use under a Taproot context.
     pub fn new(
         primary_path: PathInfo,
@@ -549,7 +789,7 @@ impl LianaPolicy {
     ) -> Result<LianaPolicy, LianaPolicyError> {
         Self::_new(
             primary_path,
-            recovery_paths,
+            Self::relative_blocks_paths(recovery_paths),
             /* is_taproot = */ true,
             /* compile = */ true,
         )
@@ -562,12 +802,125 @@ impl LianaPolicy {
     ) -> Result<LianaPolicy, LianaPolicyError> {
         Self::_new(
             primary_path,
-            recovery_paths,
+            Self::relative_blocks_paths(recovery_paths),
             /* is_taproot = */ false,
             /* compile = */ true,
         )
     }
 
+    /// Create a new Liana policy whose spending paths other than the primary one may be behind
+    /// any of the supported conditions: relative timelocks in blocks or in time, absolute
+    /// timelocks and hash locks.
+    pub fn from_conditional_paths(
+        primary_path: PathInfo,
+        conditional_paths: BTreeMap<PathCondition, PathInfo>,
+        is_taproot: bool,
+    ) -> Result<LianaPolicy, LianaPolicyError> {
+        Self::_new(
+            primary_path,
+            conditional_paths,
+            is_taproot,
+            /* compile = */ true,
+        )
+    }
+
+    /// Create a new Liana policy where the same set of signers can spend through every path, but
+    /// fewer of them are needed as time passes. For instance a 3-of-5 primary path, a 2-of-5 after
+    /// 6 months and a 1-of-5 after a year.
+    ///
+    /// `decaying_thresholds` maps the timelock (in blocks) of each recovery path to its threshold.
+    /// The thresholds must strictly decrease as the timelocks increase. The xpubs must be
+    /// the signers' account-level xpubs with their origin: each path derives its keys from
+    /// different multipath indexes.
+    pub fn new_decaying_multisig(
+        keys: Vec<descriptor::DescriptorXKey<bip32::Xpub>>,
+        primary_threshold: usize,
+        decaying_thresholds: BTreeMap<u16, usize>,
+        is_taproot: bool,
+    ) -> Result<LianaPolicy, LianaPolicyError> {
+        let path_keys = |index: u32| {
+            keys.iter()
+                .map(|xpub| multi_xkey_from_xpub(xpub, index))
+                .collect::<Result<Vec<_>, _>>()
+        };
+        let primary_path = path_from_keys(primary_threshold, path_keys(0)?)?;
+
+        let mut prev_threshold = primary_threshold;
+        let mut recovery_paths = BTreeMap::new();
+        for (i, (timelock, threshold)) in decaying_thresholds.into_iter().enumerate() {
+            if threshold == 0 || threshold >= prev_threshold {
+                return Err(LianaPolicyError::InvalidDecayingThreshold(threshold));
+            }
+            let index = u32::try_from(i + 1).expect("At most 2^16 recovery paths");
+            recovery_paths.insert(
+                PathCondition::RelativeBlocks(timelock),
+                path_from_keys(threshold, path_keys(index)?)?,
+            );
+            prev_threshold = threshold;
+        }
+
+        Self::_new(
+            primary_path,
+            recovery_paths,
+            is_taproot,
+            /* compile = */ true,
+        )
+    }
+
+    /// Create a new Liana policy where more signers can spend as time passes. For instance a 2-of-2
+    /// primary path, a 2-of-3 after 6 months with a third signer and a 1-of-4 after a year with a
+    /// fourth signer.
+    ///
+    /// `expansions` maps the timelock (in blocks) of each recovery path to its threshold and the
+    /// signers it adds to the path before it. Each recovery path must add at least one signer, and
+    /// its threshold may not be larger than the threshold of the path before it. The
+    /// xpubs must be the signers' account-level xpubs with their origin: each path derives its
+    /// keys from different multipath indexes.
+    pub fn new_expanding_multisig(
+        primary_keys: Vec<descriptor::DescriptorXKey<bip32::Xpub>>,
+        primary_threshold: usize,
+        expansions: BTreeMap<u16, (usize, Vec<descriptor::DescriptorXKey<bip32::Xpub>>)>,
+        is_taproot: bool,
+    ) -> Result<LianaPolicy, LianaPolicyError> {
+        // Each signer's keys derive from the next multipath indexes every time it appears in a
+        // new path, so a signer added in a recovery path starts at `<0;1>`.
+        let path_keys = |signers: &mut [(descriptor::DescriptorXKey<bip32::Xpub>, u32)]| {
+            signers
+                .iter_mut()
+                .map(|(xpub, index)| {
+                    *index += 1;
+                    multi_xkey_from_xpub(xpub, *index - 1)
+                })
+                .collect::<Result<Vec<_>, _>>()
+        };
+        let mut signers: Vec<_> = primary_keys.into_iter().map(|xpub| (xpub, 0)).collect();
+        let primary_path = path_from_keys(primary_threshold, path_keys(&mut signers)?)?;
+
+        let mut prev_threshold = primary_threshold;
+        let mut recovery_paths = BTreeMap::new();
+        for (timelock, (threshold, added_keys)) in expansions {
+            if added_keys.is_empty() {
+                return Err(LianaPolicyError::EmptyKeysetExpansion(timelock));
+            }
+            if threshold == 0 || threshold > prev_threshold {
+                return Err(LianaPolicyError::InvalidExpandingThreshold(threshold));
+            }
+            signers.extend(added_keys.into_iter().map(|xpub| (xpub, 0)));
+            recovery_paths.insert(
+                PathCondition::RelativeBlocks(timelock),
+                path_from_keys(threshold, path_keys(&mut signers)?)?,
+            );
+            prev_threshold = threshold;
+        }
+
+        Self::_new(
+            primary_path,
+            recovery_paths,
+            is_taproot,
+            /* compile = */ true,
+        )
+    }
+
     /// Create a Liana policy from a descriptor. This will check the descriptor is correctly formed
     /// (P2WSH, multipath, ..) and has a valid Liana semantic.
     pub fn from_multipath_descriptor(
@@ -622,8 +975,8 @@ impl LianaPolicy {
         };
 
         // Fetch all spending paths' semantic policies. The primary path is identified as the only
-        // one that isn't timelocked.
-        let (mut primary_path, mut recovery_paths) = (None::<PathInfo>, BTreeMap::new());
+        // one that isn't timelocked nor hash-locked.
+        let (mut primary_path, mut conditional_paths) = (None::<PathInfo>, BTreeMap::new());
         for sub in subs {
             // Rust-Miniscript now forces the policy in thresholds to be wrapped into an Arc. Since
             // we lift the policy from the descriptor right above, there is necessarily a single
@@ -648,25 +1001,25 @@ impl LianaPolicy {
                     primary_path = Some(PathInfo::from_primary_path(sub)?);
                 }
             } else {
-                // If it's not a simple (multi)key check, it must be (one of) the timelocked
-                // recovery path(s).
-                let (timelock, path_info) = PathInfo::from_recovery_path(sub)?;
-                if recovery_paths.contains_key(&timelock) {
+                // If it's not a simple (multi)key check, it must be (one of) the timelocked or
+                // hash-locked path(s).
+                let (condition, path_info) = PathInfo::from_conditional_path(sub)?;
+                if conditional_paths.contains_key(&condition) {
                     return Err(LianaPolicyError::IncompatibleDesc);
                 }
-                recovery_paths.insert(timelock, path_info);
+                conditional_paths.insert(condition, path_info);
             }
         }
 
         // Use the constructor for sanity checking the keys and the Miniscript policy. Note this
-        // makes sure the recovery paths mapping isn't empty, too.
+        // makes sure there is at least one recovery path, too.
         let prim_path = primary_path.ok_or(LianaPolicyError::IncompatibleDesc)?;
         // We don't compile the policy as we assume it compiles given we started with a descriptor.
         // This will still perform all other checks to make sure the descriptor conforms to
         // a Liana policy.
         LianaPolicy::_new(
             prim_path,
-            recovery_paths,
+            conditional_paths,
             is_taproot,
             /* compile = */ false,
         )
@@ -676,11 +1029,23 @@ impl LianaPolicy {
         &self.primary_path
     }
 
-    /// Timelocks and path info of the recovery paths. Note we guarantee this mapping is never
-    /// empty, as there is always at least one recovery path.
-    pub fn recovery_paths(&self) -> &BTreeMap<u16, PathInfo> {
-        assert!(!self.recovery_paths.is_empty());
-        &self.recovery_paths
+    /// Timelocks and path info of the recovery paths behind a relative timelock expressed in
+    /// blocks. Note this mapping may be empty if all the recovery paths behind a relative timelock
+    /// are time-based.
+    pub fn recovery_paths(&self) -> BTreeMap<u16, PathInfo> {
+        self.conditional_paths
+            .iter()
+            .filter_map(|(cond, path)| match cond {
+                PathCondition::RelativeBlocks(timelock) => Some((*timelock, path.clone())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Conditions and path info of all the spending paths but the primary one: the recovery paths
+    /// behind a relative or an absolute timelock and the hash-locked paths.
+    pub fn conditional_paths(&self) -> &BTreeMap<PathCondition, PathInfo> {
+        &self.conditional_paths
     }
 
     fn into_policy(
@@ -689,25 +1054,32 @@ impl LianaPolicy {
     {
         let LianaPolicy {
             primary_path,
-            recovery_paths,
+            conditional_paths,
             ..
         } = self;
 
-        // Start with the primary spending path. We'll then or() all the recovery paths to it.
+        // Incrementally create the top-level policy by or()ing all the other spending paths to the
+        // primary one. We assume the hash-locked paths are the most likely to be used, then the
+        // absolute-timelocked, the time-based and the block-based relative-timelocked recovery
+        // paths. Within each kind, the larger the timelock the less likely a path would be used.
+        assert!(!conditional_paths.is_empty());
+        let mut conditional_paths: Vec<_> = conditional_paths.into_iter().collect();
+        conditional_paths.sort_by_key(|(condition, _)| match condition {
+            PathCondition::HashLock(_) => 0,
+            PathCondition::Absolute(_) => 1,
+            PathCondition::RelativeTime(_) => 2,
+            PathCondition::RelativeBlocks(_) => 3,
+        });
         let primary_keys = primary_path.into_ms_policy()?;
-
-        // Incrementally create the top-level policy using all recovery paths.
-        assert!(!recovery_paths.is_empty());
-        recovery_paths
+        conditional_paths
             .into_iter()
-            .try_fold(primary_keys, |tl_policy, (timelock, path_info)| {
-                let timelock = ConcretePolicy::Older(RelLockTime::from_height(timelock));
+            .try_fold(primary_keys, |tl_policy, (condition, path_info)| {
                 let keys = path_info.into_ms_policy()?;
-                let recovery_branch = ConcretePolicy::And(vec![keys.into(), timelock.into()]);
-                // We assume the larger the timelock the less likely a branch would be used.
+                let branch =
+                    ConcretePolicy::And(vec![keys.into(), condition.into_ms_policy().into()]);
                 Ok(ConcretePolicy::Or(vec![
                     (99, tl_policy.into()),
-                    (1, recovery_branch.into()),
+                    (1, branch.into()),
                 ]))
             })
     }
@@ -802,9 +1174,11 @@ pub struct PathSpendInfo {
 pub struct PartialSpendInfo {
     /// Number of signatures present for the primary path
     pub(super) primary_path: PathSpendInfo,
-    /// Number of signatures present for the recovery path, only present for the recovery paths
-    /// that are available.
-    pub(super) recovery_paths: BTreeMap<u16, PathSpendInfo>,
+    /// Number of signatures present for the other spending paths, only present for the paths
+    /// that are available. Hash-locked paths are always available.
+    pub(super) conditional_paths: BTreeMap<PathCondition, PathSpendInfo>,
+    /// The hash locks of the hash-locked paths for which the preimage was provided.
+    pub(super) preimages: BTreeSet<HashLock>,
 }
 
 impl PartialSpendInfo {
@@ -813,10 +1187,44 @@ impl PartialSpendInfo {
         &self.primary_path
     }
 
-    /// Get the number of signatures present for each recovery path. Only present for available
-    /// paths.
-    pub fn recovery_paths(&self) -> &BTreeMap<u16, PathSpendInfo> {
-        &self.recovery_paths
+    /// Get the number of signatures present for each recovery path behind a relative timelock
+    /// expressed in blocks. Only present for available paths.
+    pub fn recovery_paths(&self) -> BTreeMap<u16, PathSpendInfo> {
+        self.conditional_paths
+            .iter()
+            .filter_map(|(cond, info)| match cond {
+                PathCondition::RelativeBlocks(timelock) => Some((*timelock, info.clone())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Get the number of signatures present for each spending path but the primary one. Only
+    /// present for available paths.
+    pub fn conditional_paths(&self) -> &BTreeMap<PathCondition, PathSpendInfo> {
+        &self.conditional_paths
+    }
+
+    /// Get the condition and the number of signatures present for the latest available recovery
+    /// path, that is behind a relative or an absolute timelock. None if the spend does not unlock
+    /// any recovery path. Relative-timelocked recovery paths are preferred over
+    /// absolute-timelocked ones, and those expressed in blocks over time-based ones.
+    pub fn last_available_recovery_path(&self) -> Option<(&PathCondition, &PathSpendInfo)> {
+        self.conditional_paths
+            .iter()
+            .filter_map(|(condition, info)| match condition {
+                PathCondition::RelativeBlocks(_) => Some((2, condition, info)),
+                PathCondition::RelativeTime(_) => Some((1, condition, info)),
+                PathCondition::Absolute(_) => Some((0, condition, info)),
+                PathCondition::HashLock(_) => None,
+            })
+            .max_by_key(|(rank, condition, _)| (*rank, *condition))
+            .map(|(_, condition, info)| (condition, info))
+    }
+
+    /// Whether the preimage for this hash lock was provided.
+    pub fn has_preimage(&self, hashlock: &HashLock) -> bool {
+        self.preimages.contains(hashlock)
     }
 }
 
@@ -0,0 +1,308 @@
+//! A structured, human-readable summary of a Liana descriptor's spending policy.
+//!
+//! This lets a reader audit the spending conditions of a wallet without having to parse the
+//! Miniscript in the descriptor string.
+
+use crate::descriptors::{HashLock, LianaDescriptor, PathCondition, PathInfo};
+
+use miniscript::{bitcoin::bip32, descriptor};
+
+use std::fmt;
+
+use serde::{Serialize, Serializer};
+
+// Serialize a value as its string representation.
+fn ser_to_string<T: fmt::Display, S: Serializer>(field: T, s: S) -> Result<S::Ok, S::Error> {
+    s.serialize_str(&field.to_string())
+}
+
+// The expected interval between two blocks, in seconds.
+const BLOCK_INTERVAL_SECS: u64 = 10 * 60;
+
+// The granularity of time-based relative timelocks, in seconds.
+const SEQUENCE_TIME_UNIT_SECS: u64 = 512;
+
+/// When a spending path becomes available.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ConditionExplanation {
+    /// The path is always available.
+    None,
+    /// The path is available once the coin has been confirmed for this number of blocks.
+    RelativeBlocks {
+        blocks: u16,
+        /// Approximate duration, assuming a block every 10 minutes.
+        approx_seconds: u64,
+    },
+    /// The path is available once the coin has been confirmed for this number of 512-second
+    /// intervals.
+    RelativeTime { intervals: u16, seconds: u64 },
+    /// The path is available from this block height onward.
+    AbsoluteHeight {
+        height: u32,
+        /// Approximate UNIX timestamp at which this height is reached, if it was estimated. See
+        /// [`PolicyExplanation::estimate_dates`].
+        approx_timestamp: Option<u64>,
+    },
+    /// The path is available once the median time past of the chain reached this UNIX timestamp.
+    AbsoluteTime { timestamp: u32 },
+    /// The path is available at any time by revealing the preimage of this hash.
+    Hashlock {
+        #[serde(serialize_with = "ser_to_string")]
+        hash: HashLock,
+    },
+}
+
+impl From<PathCondition> for ConditionExplanation {
+    fn from(condition: PathCondition) -> Self {
+        match condition {
+            PathCondition::RelativeBlocks(blocks) => Self::RelativeBlocks {
+                blocks,
+                approx_seconds: blocks as u64 * BLOCK_INTERVAL_SECS,
+            },
+            PathCondition::RelativeTime(intervals) => Self::RelativeTime {
+                intervals,
+                seconds: intervals as u64 * SEQUENCE_TIME_UNIT_SECS,
+            },
+            PathCondition::Absolute(timelock) if timelock.is_block_height() => {
+                Self::AbsoluteHeight {
+                    height: timelock.to_consensus_u32(),
+                    approx_timestamp: None,
+                }
+            }
+            PathCondition::Absolute(timelock) => Self::AbsoluteTime {
+                timestamp: timelock.to_consensus_u32(),
+            },
+            PathCondition::HashLock(hash) => Self::Hashlock { hash },
+        }
+    }
+}
+
+// Format a duration in seconds as an approximate number of days, hours or minutes.
+fn approx_duration(secs: u64) -> String {
+    let (n, unit) = if secs >= 24 * 3600 {
+        (secs / (24 * 3600), "day")
+    } else if secs >= 3600 {
+        (secs / 3600, "hour")
+    } else {
+        (secs / 60, "minute")
+    };
+    format!("{} {}{}", n, unit, if n > 1 { "s" } else { "" })
+}
+
+// Format a UNIX timestamp as a UTC date (YYYY-MM-DD).
+fn date(timestamp: u64) -> String {
+    // See http://howardhinnant.github.io/date_algorithms.html#civil_from_days.
+    let z = (timestamp / (24 * 3600)) as i64 + 719_468;
+    let era = z / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+impl fmt::Display for ConditionExplanation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::None => write!(f, "always available"),
+            Self::RelativeBlocks {
+                blocks,
+                approx_seconds,
+            } => write!(
+                f,
+                "available {} blocks (about {}) after the coin is confirmed",
+                blocks,
+                approx_duration(*approx_seconds)
+            ),
+            Self::RelativeTime { seconds, .. } => write!(
+                f,
+                "available {} seconds (about {}) after the coin is confirmed",
+                seconds,
+                approx_duration(*seconds)
+            ),
+            Self::AbsoluteHeight {
+                height,
+                approx_timestamp,
+            } => {
+                write!(f, "available from block height {}", height)?;
+                if let Some(timestamp) = approx_timestamp {
+                    write!(f, " (around {})", date(*timestamp))?;
+                }
+                Ok(())
+            }
+            Self::AbsoluteTime { timestamp } => write!(
+                f,
+                "available once the median time past reached UNIX timestamp {}",
+                timestamp
+            ),
+            Self::Hashlock { hash } => write!(f, "available by revealing the preimage of {}", hash),
+        }
+    }
+}
+
+/// A key which can sign for a spending path.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PolicyKey {
+    /// Fingerprint of the signer's master extended key.
+    pub fingerprint: bip32::Fingerprint,
+    /// Derivation path of the xpub from the signer's master extended key.
+    #[serde(serialize_with = "ser_to_string")]
+    pub origin_path: bip32::DerivationPath,
+    /// The key as it appears in the descriptor.
+    #[serde(serialize_with = "ser_to_string")]
+    pub key: descriptor::DescriptorPublicKey,
+}
+
+impl fmt::Display for PolicyKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // Displayed as a descriptor key origin.
+        if self.origin_path.is_master() {
+            write!(f, "[{}]", self.fingerprint)
+        } else {
+            write!(f, "[{}/{}]", self.fingerprint, self.origin_path)
+        }
+    }
+}
+
+/// A summary of a spending path.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PathExplanation {
+    /// The condition, besides the signatures, for spending through this path.
+    pub condition: ConditionExplanation,
+    /// The number of signatures required.
+    pub threshold: usize,
+    /// The keys which may provide the signatures.
+    pub keys: Vec<PolicyKey>,
+    /// Maximum weight of the satisfaction of an input spent through this path, in weight units.
+    pub max_sat_weight: usize,
+}
+
+impl PathExplanation {
+    fn new(condition: ConditionExplanation, path: &PathInfo, max_sat_weight: usize) -> Self {
+        let (threshold, keys) = match path {
+            PathInfo::Single(key) => (1, vec![key]),
+            PathInfo::Multi(thresh, keys) => (*thresh, keys.iter().collect()),
+        };
+        let keys = keys
+            .into_iter()
+            .map(|key| {
+                // All keys in a Liana descriptor are xpubs with an origin.
+                let (fingerprint, origin_path) = match key {
+                    descriptor::DescriptorPublicKey::MultiXPub(xpub) => xpub.origin.clone(),
+                    _ => None,
+                }
+                .unwrap_or_else(|| (key.master_fingerprint(), bip32::DerivationPath::master()));
+                PolicyKey {
+                    fingerprint,
+                    origin_path,
+                    key: key.clone(),
+                }
+            })
+            .collect();
+        PathExplanation {
+            condition,
+            threshold,
+            keys,
+            max_sat_weight,
+        }
+    }
+}
+
+impl fmt::Display for PathExplanation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let keys = self
+            .keys
+            .iter()
+            .map(|k| k.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        write!(
+            f,
+            "{} of {} key{} ({}), {}. Worst-case satisfaction weight: {} WU.",
+            self.threshold,
+            self.keys.len(),
+            if self.keys.len() > 1 { "s" } else { "" },
+            keys,
+            self.condition,
+            self.max_sat_weight
+        )
+    }
+}
+
+/// A summary of the spending policy of a Liana descriptor.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PolicyExplanation {
+    pub is_taproot: bool,
+    /// All the spending paths, the primary path first.
+    pub paths: Vec<PathExplanation>,
+}
+
+impl PolicyExplanation {
+    /// Estimate when the paths behind an absolute block height become available, from the current
+    /// chain tip and assuming a block every 10 minutes.
+    pub fn estimate_dates(&mut self, tip_height: u32, tip_time: u64) {
+        for path in &mut self.paths {
+            if let ConditionExplanation::AbsoluteHeight {
+                height,
+                approx_timestamp,
+            } = &mut path.condition
+            {
+                let blocks = *height as i64 - tip_height as i64;
+                *approx_timestamp =
+                    Some((tip_time as i64 + blocks * BLOCK_INTERVAL_SECS as i64).max(0) as u64);
+            }
+        }
+    }
+}
+
+impl fmt::Display for PolicyExplanation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} descriptor with {} spending paths:",
+            if self.is_taproot { "Taproot" } else { "P2WSH" },
+            self.paths.len()
+        )?;
+        for path in &self.paths {
+            write!(
+                f,
+                "\n- {} path: {}",
+                match path.condition {
+                    ConditionExplanation::None => "Primary",
+                    ConditionExplanation::Hashlock { .. } => "Hash-locked",
+                    _ => "Recovery",
+                },
+                path
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl LianaDescriptor {
+    /// Get a structured summary of the spending policy of this descriptor. It can be displayed as
+    /// plain text or serialized as JSON.
+    pub fn explain(&self) -> PolicyExplanation {
+        let policy = self.policy();
+        let mut paths = vec![PathExplanation::new(
+            ConditionExplanation::None,
+            policy.primary_path(),
+            self.max_sat_weight(true),
+        )];
+        for (condition, path) in policy.conditional_paths() {
+            paths.push(PathExplanation::new(
+                (*condition).into(),
+                path,
+                self.path_max_sat_weight(path, Some(condition)),
+            ));
+        }
+        PolicyExplanation {
+            is_taproot: self.is_taproot(),
+            paths,
+        }
+    }
+}
@@ -27,6 +27,12 @@ pub use keys::*;
 pub mod analysis;
 pub use analysis::*;
 
+pub mod explanation;
+pub use explanation::*;
+
+pub mod wallet_policy;
+pub use wallet_policy::*;
+
 #[derive(Debug)]
 pub enum LianaDescError {
     Miniscript(miniscript::Error),
@@ -36,6 +42,8 @@ pub enum LianaDescError {
     InsanePsbt,
     /// Not all inputs' sequence the same, not all inputs signed with the same key, ..
     InconsistentPsbt,
+    /// The given preimage doesn't unlock any of the hash-locked paths.
+    UnknownPreimage,
 }
 
 impl std::fmt::Display for LianaDescError {
@@ -46,6 +54,10 @@ impl std::fmt::Display for LianaDescError {
             Self::Policy(e) => write!(f, "{}", e),
             Self::InsanePsbt => write!(f, "Analyzed PSBT is empty or malformed."),
             Self::InconsistentPsbt => write!(f, "Analyzed PSBT is inconsistent across inputs."),
+            Self::UnknownPreimage => write!(
+                f,
+                "The preimage doesn't correspond to any of the hash-locked spending paths."
+            ),
         }
     }
 }
@@ -226,72 +238,124 @@ impl LianaDescriptor {
             .expect("We never create a Liana descriptor with an invalid Liana policy.")
     }
 
-    /// Get the value (in blocks) of the smallest relative timelock of the recovery paths.
-    pub fn first_timelock_value(&self) -> u16 {
-        *self
+    /// Get the smallest relative timelock of the recovery paths. Timelocks expressed in blocks take
+    /// precedence: a time-based one is only returned if no recovery path is behind a relative
+    /// timelock in blocks. This doesn't take into account the recovery paths behind an absolute
+    /// timelock.
+    pub fn first_relative_timelock(&self) -> bitcoin::relative::LockTime {
+        // Conditions are ordered by kind first, and those in blocks come first.
+        match self
             .policy()
-            .recovery_paths
-            .iter()
-            .next()
-            .expect("There is always at least one recovery path")
+            .conditional_paths()
+            .keys()
+            .find(|cond| cond.is_relative_timelock())
+            .expect("There is always at least one recovery path behind a relative timelock")
+        {
+            PathCondition::RelativeBlocks(tl) => bitcoin::relative::LockTime::from_height(*tl),
+            PathCondition::RelativeTime(tl) => {
+                bitcoin::relative::LockTime::from_512_second_intervals(*tl)
+            }
+            PathCondition::Absolute(_) | PathCondition::HashLock(_) => {
+                unreachable!("Only relative timelocks were searched for.")
+            }
+        }
+    }
+
+    /// Get the value (in blocks) of the smallest relative timelock of the recovery paths, as
+    /// returned by [`Self::first_relative_timelock`]. A time-based timelock is converted to an
+    /// approximate number of blocks, assuming a block every 10 minutes.
+    pub fn first_timelock_value(&self) -> u16 {
+        match self.first_relative_timelock() {
+            bitcoin::relative::LockTime::Blocks(height) => height.value(),
+            bitcoin::relative::LockTime::Time(time) => {
+                // Can't overflow: at most 65535 * 512 / 600 = 55924.
+                ((time.value() as u32 * 512 + 599) / 600) as u16
+            }
+        }
+    }
+
+    // Get the maximum size difference of a transaction input spent through this path, which is
+    // available under this condition (None for the primary path), before and after satisfaction.
+    // If another spending path is available with the same keys and condition and is cheaper to
+    // satisfy, its satisfaction is considered instead as it would be preferred.
+    fn path_max_sat_weight(&self, path: &PathInfo, condition: Option<&PathCondition>) -> usize {
+        // Get the keys from the path, to get a satisfaction size estimation only considering
+        // those.
+        let keys = path.thresh_origins().1.into_iter().fold(
+            BTreeSet::new(),
+            |mut keys, (fg, der_paths)| {
+                for der_path in der_paths {
+                    keys.insert(((fg, der_path), CanSign::default()));
+                }
+                keys
+            },
+        );
+        let mut assets = Assets {
+            keys,
+            ..Default::default()
+        };
+        match condition {
+            None => {}
+            Some(PathCondition::RelativeBlocks(blocks)) => {
+                assets.relative_timelock = Some(bitcoin::relative::LockTime::from_height(*blocks));
+            }
+            Some(PathCondition::RelativeTime(intervals)) => {
+                assets.relative_timelock = Some(
+                    bitcoin::relative::LockTime::from_512_second_intervals(*intervals),
+                );
+            }
+            Some(PathCondition::Absolute(timelock)) => {
+                assets.absolute_timelock = Some((*timelock).into());
+            }
+            Some(PathCondition::HashLock(HashLock::Sha256(hash))) => {
+                assets.sha256_preimages.insert(*hash);
+            }
+            Some(PathCondition::HashLock(HashLock::Hash160(hash))) => {
+                assets.hash160_preimages.insert(*hash);
+            }
+        }
+
+        // Unfortunately rust-miniscript satisfaction size estimation is inconsistent. For
+        // Taproot it considers the whole witness (except the control block size + the
+        // script size), while under P2WSH it does not consider the witscript! Therefore we
+        // manually add the size of the witscript under P2WSH by means of the
+        // `explicit_script()` helper, which gives an error for Taproot, and for Taproot
+        // we add the sizes of the control block and script.
+        let der_desc = self
+            .receive_desc
             .0
+            .at_derivation_index(0)
+            .expect("unhardened index");
+        let witscript_size = der_desc
+            .explicit_script()
+            .map(|s| varint_len(s.len()) + s.len());
+
+        // Finally, compute the satisfaction template for the path and get its size.
+        let plan = der_desc.plan(&assets).expect("Always satisfiable");
+        plan.witness_size()
+            + witscript_size.unwrap_or_else(|_| {
+                plan.witness_template()
+                    .iter()
+                    .map(|elem| match elem {
+                        // We need to calculate the size manually before calculating the varint length.
+                        // See https://docs.rs/miniscript/11.0.0/src/miniscript/util.rs.html#35-36.
+                        Placeholder::TapScript(s) => varint_len(s.len()),
+                        Placeholder::TapControlBlock(cb) => varint_len(cb.serialize().len()),
+                        _ => 0,
+                    })
+                    .sum()
+            })
     }
 
     /// Get the maximum size difference of a transaction input spending a Script derived from this
     /// descriptor before and after satisfaction. The returned value is in weight units.
     /// Callers are expected to account for the Segwit marker (2 WU). This takes into account the
     /// size of the witness stack length varint.
+    /// When not using the primary path, this considers all the other spending paths, including the
+    /// hash-locked ones (and the size of their preimage).
     pub fn max_sat_weight(&self, use_primary_path: bool) -> usize {
         if use_primary_path {
-            // Get the keys from the primary path, to get a satisfaction size estimation only
-            // considering those.
-            let keys = self
-                .policy()
-                .primary_path
-                .thresh_origins()
-                .1
-                .into_iter()
-                .fold(BTreeSet::new(), |mut keys, (fg, der_paths)| {
-                    for der_path in der_paths {
-                        keys.insert(((fg, der_path), CanSign::default()));
-                    }
-                    keys
-                });
-            let assets = Assets {
-                keys,
-                ..Default::default()
-            };
-
-            // Unfortunately rust-miniscript satisfaction size estimation is inconsistent. For
-            // Taproot it considers the whole witness (except the control block size + the
-            // script size), while under P2WSH it does not consider the witscript! Therefore we
-            // manually add the size of the witscript under P2WSH by means of the
-            // `explicit_script()` helper, which gives an error for Taproot, and for Taproot
-            // we add the sizes of the control block and script.
-            let der_desc = self
-                .receive_desc
-                .0
-                .at_derivation_index(0)
-                .expect("unhardened index");
-            let witscript_size = der_desc
-                .explicit_script()
-                .map(|s| varint_len(s.len()) + s.len());
-
-            // Finally, compute the satisfaction template for the primary path and get its size.
-            let plan = der_desc.plan(&assets).expect("Always satisfiable");
-            plan.witness_size()
-                + witscript_size.unwrap_or_else(|_| {
-                    plan.witness_template()
-                        .iter()
-                        .map(|elem| match elem {
-                            // We need to calculate the size manually before calculating the varint length.
-                            // See https://docs.rs/miniscript/11.0.0/src/miniscript/util.rs.html#35-36.
-                            Placeholder::TapScript(s) => varint_len(s.len()),
-                            Placeholder::TapControlBlock(cb) => varint_len(cb.serialize().len()),
-                            _ => 0,
-                        })
-                        .sum()
-                })
+            self.path_max_sat_weight(&self.policy().primary_path, None)
         } else {
             // We add one to account for the witness stack size, as the values above give the
             // difference in size for a satisfied input that was *already* in a transaction
@@ -339,10 +403,13 @@ impl LianaDescriptor {
     /// This analysis assumes that:
     /// - The PSBT input actually spend a Liana coin for this descriptor. Otherwise the analysis will be off.
     /// - The signatures contained in the PSBT input are valid for this script.
+    ///
+    /// The `lock_time` is the nLockTime of the transaction this input is part of.
     pub fn partial_spend_info_txin(
         &self,
         psbt_in: &PsbtIn,
         txin: &bitcoin::TxIn,
+        lock_time: bitcoin::absolute::LockTime,
     ) -> PartialSpendInfo {
         let is_taproot = self.is_taproot();
         // Get the origin ECDSA or Schnorr signatures, depending on the descriptor type.
@@ -382,21 +449,36 @@ impl LianaDescriptor {
         // (ie if the nSequence is >= to the chosen CSV value).
         let desc_info = self.policy();
         let primary_path = desc_info.primary_path.spend_info(pubkeys_signed.clone());
-        let recovery_paths = desc_info
-            .recovery_paths
+        let conditional_paths = desc_info
+            .conditional_paths
             .iter()
-            .filter_map(|(timelock, path_info)| {
-                if txin.sequence.is_height_locked() && txin.sequence.0 >= *timelock as u32 {
-                    Some((*timelock, path_info.spend_info(pubkeys_signed.clone())))
+            .filter_map(|(condition, path_info)| {
+                if condition.is_available(txin.sequence, lock_time) {
+                    Some((*condition, path_info.spend_info(pubkeys_signed.clone())))
                 } else {
                     None
                 }
             })
             .collect();
+        // The hash-locked paths are always available. Also record whether the preimage was
+        // provided for each of them.
+        let preimages = desc_info
+            .conditional_paths
+            .keys()
+            .filter_map(|condition| match condition {
+                PathCondition::HashLock(hashlock) => Some(*hashlock),
+                _ => None,
+            })
+            .filter(|hashlock| match hashlock {
+                HashLock::Sha256(h) => psbt_in.sha256_preimages.contains_key(h),
+                HashLock::Hash160(h) => psbt_in.hash160_preimages.contains_key(h),
+            })
+            .collect();
 
         PartialSpendInfo {
             primary_path,
-            recovery_paths,
+            conditional_paths,
+            preimages,
         }
     }
 
@@ -431,12 +513,13 @@ impl LianaDescriptor {
                 .expect("We checked at least one is present."),
             txins.next().expect("We checked at least one is present."),
         );
-        let spend_info = self.partial_spend_info_txin(first_psbt_in, first_txin);
+        let lock_time = psbt.unsigned_tx.lock_time;
+        let spend_info = self.partial_spend_info_txin(first_psbt_in, first_txin, lock_time);
         for (psbt_in, txin) in psbt_ins.zip(txins) {
             // TODO: maybe it's better to not error if one of the input has more, or different
             // signatures? Instead of erroring we could ignore the superfluous data?
             if txin.sequence != first_txin.sequence
-                || spend_info != self.partial_spend_info_txin(psbt_in, txin)
+                || spend_info != self.partial_spend_info_txin(psbt_in, txin, lock_time)
             {
                 return Err(LianaDescError::InconsistentPsbt);
             }
@@ -445,6 +528,37 @@ impl LianaDescriptor {
         Ok(spend_info)
     }
 
+    /// Provide the preimage for one of the hash-locked spending paths in all the inputs of this
+    /// PSBT, so it can be used when finalizing it. Returns the hash lock it unlocks.
+    pub fn add_hashlock_preimage(
+        &self,
+        psbt: &mut Psbt,
+        preimage: [u8; 32],
+    ) -> Result<HashLock, LianaDescError> {
+        let hashlock = self
+            .policy()
+            .conditional_paths
+            .into_keys()
+            .find_map(|condition| match condition {
+                PathCondition::HashLock(hashlock) if hashlock.is_unlocked_by(&preimage) => {
+                    Some(hashlock)
+                }
+                _ => None,
+            })
+            .ok_or(LianaDescError::UnknownPreimage)?;
+        for psbt_in in psbt.inputs.iter_mut() {
+            match hashlock {
+                HashLock::Sha256(h) => {
+                    psbt_in.sha256_preimages.insert(h, preimage.to_vec());
+                }
+                HashLock::Hash160(h) => {
+                    psbt_in.hash160_preimages.insert(h, preimage.to_vec());
+                }
+            }
+        }
+        Ok(hashlock)
+    }
+
     /// List the indexes of the change outputs in this PSBT. It relies on the PSBT to be
     /// well-formed: sane BIP32 derivations must be set for every change output, the inner
     /// transaction must have the same number of outputs as the PSBT.
@@ -535,18 +649,19 @@ impl LianaDescriptor {
     /// - If there is two recovery paths, and the PSBT's first input nSequence is set to unlock the
     ///     first one, prune all but the first recovery path's bip32 derivations.
     /// - Etc..
+    ///
+    /// Relative-timelocked recovery paths are preferred over absolute-timelocked ones, and those
+    /// expressed in blocks over time-based ones.
     pub fn prune_bip32_derivs_last_avail(&self, psbt: Psbt) -> Result<Psbt, LianaDescError> {
         let spend_info = self.partial_spend_info(&psbt)?;
         let policy = self.policy();
         let path_info = spend_info
-            .recovery_paths
-            .iter()
-            .last()
-            .map(|(tl, _)| {
+            .last_available_recovery_path()
+            .map(|(condition, _)| {
                 policy
-                    .recovery_paths
-                    .get(tl)
-                    .expect("Same timelocks must be keys in both mappings.")
+                    .conditional_paths
+                    .get(condition)
+                    .expect("Same conditions must be keys in both mappings.")
             })
             .unwrap_or(&policy.primary_path);
         Ok(self.prune_bip32_derivs(psbt, path_info))
@@ -745,6 +860,7 @@ mod tests {
     use super::*;
 
     use bitcoin::{hashes::Hash, Sequence};
+    use miniscript::AbsLockTime;
 
     use crate::signer::HotSigner;
 
@@ -1401,24 +1517,24 @@ mod tests {
         assert_eq!(info.primary_path.threshold, 1);
         assert_eq!(info.primary_path.sigs_count, 0);
         assert!(info.primary_path.signed_pubkeys.is_empty());
-        assert!(info.recovery_paths.is_empty());
+        assert!(info.recovery_paths().is_empty());
 
         // If we set the sequence too low we still won't have the recovery path info.
         unsigned_single_psbt.unsigned_tx.input[0].sequence =
-            Sequence::from_height(desc_info.recovery_paths.keys().next().unwrap() - 1);
+            Sequence::from_height(desc_info.recovery_paths().keys().next().unwrap() - 1);
         let info = desc.partial_spend_info(&unsigned_single_psbt).unwrap();
-        assert!(info.recovery_paths.is_empty());
+        assert!(info.recovery_paths().is_empty());
 
         // Now if we set the sequence at the right value we'll have it.
-        let timelock = *desc_info.recovery_paths.keys().next().unwrap();
+        let timelock = *desc_info.recovery_paths().keys().next().unwrap();
         unsigned_single_psbt.unsigned_tx.input[0].sequence = Sequence::from_height(timelock);
         let info = desc.partial_spend_info(&unsigned_single_psbt).unwrap();
-        assert!(info.recovery_paths.contains_key(&timelock));
+        assert!(info.recovery_paths().contains_key(&timelock));
 
         // Even if it's a bit too high (as long as it's still a block height and activated)
         unsigned_single_psbt.unsigned_tx.input[0].sequence = Sequence::from_height(timelock + 42);
         let info = desc.partial_spend_info(&unsigned_single_psbt).unwrap();
-        let recov_info = info.recovery_paths.get(&timelock).unwrap();
+        let recov_info = info.recovery_paths().get(&timelock).cloned().unwrap();
         assert_eq!(recov_info.threshold, 1);
         assert_eq!(recov_info.sigs_count, 0);
         assert!(recov_info.signed_pubkeys.is_empty());
@@ -1433,7 +1549,7 @@ mod tests {
             info.primary_path.signed_pubkeys.len() == 1
                 && info.primary_path.signed_pubkeys.contains_key(&prim_key_fg)
         );
-        assert!(info.recovery_paths.is_empty());
+        assert!(info.recovery_paths().is_empty());
 
         // Now enable the recovery path and add a signature for the recovery key.
         signed_single_psbt.unsigned_tx.input[0].sequence = Sequence::from_height(timelock);
@@ -1467,7 +1583,7 @@ mod tests {
         assert_eq!(info.primary_path.threshold, 1);
         assert_eq!(info.primary_path.sigs_count, 0);
         assert!(info.primary_path.signed_pubkeys.is_empty());
-        let recov_info = info.recovery_paths.get(&timelock).unwrap();
+        let recov_info = info.recovery_paths().get(&timelock).cloned().unwrap();
         assert_eq!(recov_info.threshold, 1);
         assert_eq!(recov_info.sigs_count, 1);
         assert!(
@@ -1488,7 +1604,7 @@ mod tests {
             info.primary_path.signed_pubkeys.len() == 1
                 && info.primary_path.signed_pubkeys.contains_key(&prim_key_fg)
         );
-        assert!(info.recovery_paths.is_empty());
+        assert!(info.recovery_paths().is_empty());
 
         // Enable the recovery path, it should show no recovery sig.
         let mut rec_psbt = psbt.clone();
@@ -1506,7 +1622,7 @@ mod tests {
             info.primary_path.signed_pubkeys.len() == 1
                 && info.primary_path.signed_pubkeys.contains_key(&prim_key_fg)
         );
-        let recov_info = info.recovery_paths.get(&timelock).unwrap();
+        let recov_info = info.recovery_paths().get(&timelock).cloned().unwrap();
         assert_eq!(recov_info.threshold, 1);
         assert_eq!(recov_info.sigs_count, 0);
         assert!(recov_info.signed_pubkeys.is_empty());
@@ -1543,7 +1659,7 @@ mod tests {
             info.primary_path.signed_pubkeys.len() == 1
                 && info.primary_path.signed_pubkeys.contains_key(&prim_key_fg)
         );
-        assert!(info.recovery_paths.is_empty());
+        assert!(info.recovery_paths().is_empty());
 
         let desc = LianaDescriptor::from_str("wsh(or_d(multi(2,[636adf3f/48'/1'/0'/2']tpubDEE9FvWbG4kg4gxDNrALgrWLiHwNMXNs8hk6nXNPw4VHKot16xd2251vwi2M6nsyQTkak5FJNHVHkCcuzmvpSbWHdumX3DxpDm89iTfSBaL/<0;1>/*,[ffd63c8d/48'/1'/0'/2']tpubDExA3EC3iAsPxPhFn4j6gMiVup6V2eH3qKyk69RcTc9TTNRfFYVPad8bJD5FCHVQxyBT4izKsvr7Btd2R4xmQ1hZkvsqGBaeE82J71uTK4N/<0;1>/*),and_v(v:multi(2,[636adf3f/48'/1'/1'/2']tpubDDvF2khuoBBj8vcSjQfa7iKaxsQZE7YjJ7cJL8A8eaneadMPKbHSpoSr4JD1F5LUvWD82HCxdtSppGfrMUmiNbFxrA2EHEVLnrdCFNFe75D/<0;1>/*,[ffd63c8d/48'/1'/1'/2']tpubDFMs44FD4kFt3M7Z317cFh5tdKEGN8tyQRY6Q5gcSha4NtxZfGmTVRMbsD1bWN469LstXU4aVSARDxrvxFCUjHeegfEY2cLSazMBkNCmDPD/<0;1>/*),older(2))))#xcf6jr2r").unwrap();
         let info = desc.policy();
@@ -1554,7 +1670,7 @@ mod tests {
                 descriptor::DescriptorPublicKey::from_str("[ffd63c8d/48'/1'/0'/2']tpubDExA3EC3iAsPxPhFn4j6gMiVup6V2eH3qKyk69RcTc9TTNRfFYVPad8bJD5FCHVQxyBT4izKsvr7Btd2R4xmQ1hZkvsqGBaeE82J71uTK4N/<0;1>/*").unwrap(),
             ],
         ));
-        assert_eq!(info.recovery_paths, [(2, PathInfo::Multi(
+        assert_eq!(info.recovery_paths(), [(2, PathInfo::Multi(
             2,
             vec![
                 descriptor::DescriptorPublicKey::from_str("[636adf3f/48'/1'/1'/2']tpubDDvF2khuoBBj8vcSjQfa7iKaxsQZE7YjJ7cJL8A8eaneadMPKbHSpoSr4JD1F5LUvWD82HCxdtSppGfrMUmiNbFxrA2EHEVLnrdCFNFe75D/<0;1>/*").unwrap(),
@@ -1566,7 +1682,7 @@ mod tests {
         assert_eq!(partial_info.primary_path.threshold, 2);
         assert_eq!(partial_info.primary_path.sigs_count, 1);
         assert_eq!(partial_info.primary_path.signed_pubkeys.len(), 1);
-        assert!(partial_info.recovery_paths.is_empty());
+        assert!(partial_info.recovery_paths().is_empty());
 
         // A not very well thought-out decaying multisig.
         let prim_path = PathInfo::Multi(3, (0..3).map(|_| random_desc_key(&secp)).collect());
@@ -1595,7 +1711,7 @@ mod tests {
             empty_partial_info.primary_path.sigs_count,
             empty_partial_info.primary_path.signed_pubkeys.len()
         );
-        assert!(empty_partial_info.recovery_paths.is_empty());
+        assert!(empty_partial_info.recovery_paths().is_empty());
 
         // Now set a signature for the primary path. All recovery paths still empty, a signature is
         // present for the primary path.
@@ -1617,7 +1733,7 @@ mod tests {
             partial_info.primary_path.sigs_count,
             partial_info.primary_path.signed_pubkeys.len()
         );
-        assert!(partial_info.recovery_paths.is_empty());
+        assert!(partial_info.recovery_paths().is_empty());
 
         // Now enable the first recovery path and make the signature be for this path.
         let fingerprint = first_reco_path
@@ -1639,15 +1755,15 @@ mod tests {
             partial_info.primary_path.sigs_count,
             partial_info.primary_path.signed_pubkeys.len()
         );
-        assert!(partial_info.recovery_paths.is_empty());
+        assert!(partial_info.recovery_paths().is_empty());
         psbt.unsigned_tx.input[0].sequence = bitcoin::Sequence::from_height(26784);
         let partial_info = desc.partial_spend_info(&psbt).unwrap();
-        assert_eq!(partial_info.recovery_paths.len(), 1);
-        assert_eq!(partial_info.recovery_paths[&26784].threshold, 3);
-        assert_eq!(partial_info.recovery_paths[&26784].sigs_count, 1);
+        assert_eq!(partial_info.recovery_paths().len(), 1);
+        assert_eq!(partial_info.recovery_paths()[&26784].threshold, 3);
+        assert_eq!(partial_info.recovery_paths()[&26784].sigs_count, 1);
         assert_eq!(
-            partial_info.recovery_paths[&26784].signed_pubkeys.len(),
-            partial_info.recovery_paths[&26784].sigs_count
+            partial_info.recovery_paths()[&26784].signed_pubkeys.len(),
+            partial_info.recovery_paths()[&26784].sigs_count
         );
 
         // Now enable the second recovery path and make the signature be for this path.
@@ -1671,12 +1787,12 @@ mod tests {
             partial_info.primary_path.sigs_count,
             partial_info.primary_path.signed_pubkeys.len()
         );
-        assert_eq!(partial_info.recovery_paths.len(), 2);
-        assert_eq!(partial_info.recovery_paths[&26784].threshold, 3);
-        assert_eq!(partial_info.recovery_paths[&26784].sigs_count, 0);
-        assert_eq!(partial_info.recovery_paths[&53568].threshold, 2);
-        assert_eq!(partial_info.recovery_paths[&53568].sigs_count, 1);
-        for rec_path in partial_info.recovery_paths.values() {
+        assert_eq!(partial_info.recovery_paths().len(), 2);
+        assert_eq!(partial_info.recovery_paths()[&26784].threshold, 3);
+        assert_eq!(partial_info.recovery_paths()[&26784].sigs_count, 0);
+        assert_eq!(partial_info.recovery_paths()[&53568].threshold, 2);
+        assert_eq!(partial_info.recovery_paths()[&53568].sigs_count, 1);
+        for rec_path in partial_info.recovery_paths().values() {
             assert_eq!(rec_path.sigs_count, rec_path.signed_pubkeys.len());
         }
 
@@ -1701,14 +1817,14 @@ mod tests {
             partial_info.primary_path.sigs_count,
             partial_info.primary_path.signed_pubkeys.len()
         );
-        assert_eq!(partial_info.recovery_paths.len(), 3);
-        assert_eq!(partial_info.recovery_paths[&26784].threshold, 3);
-        assert_eq!(partial_info.recovery_paths[&26784].sigs_count, 0);
-        assert_eq!(partial_info.recovery_paths[&53568].threshold, 2);
-        assert_eq!(partial_info.recovery_paths[&53568].sigs_count, 0);
-        assert_eq!(partial_info.recovery_paths[&62496].threshold, 1);
-        assert_eq!(partial_info.recovery_paths[&62496].sigs_count, 1);
-        for rec_path in partial_info.recovery_paths.values() {
+        assert_eq!(partial_info.recovery_paths().len(), 3);
+        assert_eq!(partial_info.recovery_paths()[&26784].threshold, 3);
+        assert_eq!(partial_info.recovery_paths()[&26784].sigs_count, 0);
+        assert_eq!(partial_info.recovery_paths()[&53568].threshold, 2);
+        assert_eq!(partial_info.recovery_paths()[&53568].sigs_count, 0);
+        assert_eq!(partial_info.recovery_paths()[&62496].threshold, 1);
+        assert_eq!(partial_info.recovery_paths()[&62496].sigs_count, 1);
+        for rec_path in partial_info.recovery_paths().values() {
             assert_eq!(rec_path.sigs_count, rec_path.signed_pubkeys.len());
         }
     }
@@ -1748,24 +1864,24 @@ mod tests {
         assert_eq!(info.primary_path.threshold, 1);
         assert_eq!(info.primary_path.sigs_count, 0);
         assert!(info.primary_path.signed_pubkeys.is_empty());
-        assert!(info.recovery_paths.is_empty());
+        assert!(info.recovery_paths().is_empty());
 
         // If we set the sequence too low we still won't have the recovery path info.
         unsigned_single_psbt.unsigned_tx.input[0].sequence =
-            Sequence::from_height(desc_info.recovery_paths.keys().next().unwrap() - 1);
+            Sequence::from_height(desc_info.recovery_paths().keys().next().unwrap() - 1);
         let info = desc.partial_spend_info(&unsigned_single_psbt).unwrap();
-        assert!(info.recovery_paths.is_empty());
+        assert!(info.recovery_paths().is_empty());
 
         // Now if we set the sequence at the right value we'll have it.
-        let timelock = *desc_info.recovery_paths.keys().next().unwrap();
+        let timelock = *desc_info.recovery_paths().keys().next().unwrap();
         unsigned_single_psbt.unsigned_tx.input[0].sequence = Sequence::from_height(timelock);
         let info = desc.partial_spend_info(&unsigned_single_psbt).unwrap();
-        assert!(info.recovery_paths.contains_key(&timelock));
+        assert!(info.recovery_paths().contains_key(&timelock));
 
         // Even if it's a bit too high (as long as it's still a block height and activated)
         unsigned_single_psbt.unsigned_tx.input[0].sequence = Sequence::from_height(timelock + 42);
         let info = desc.partial_spend_info(&unsigned_single_psbt).unwrap();
-        let recov_info = info.recovery_paths.get(&timelock).unwrap();
+        let recov_info = info.recovery_paths().get(&timelock).cloned().unwrap();
         assert_eq!(recov_info.threshold, 1);
         assert_eq!(recov_info.sigs_count, 0);
         assert!(recov_info.signed_pubkeys.is_empty());
@@ -1788,7 +1904,7 @@ mod tests {
             info.primary_path.signed_pubkeys.len() == 1
                 && info.primary_path.signed_pubkeys.contains_key(&prim_key_fg)
         );
-        assert!(info.recovery_paths.is_empty());
+        assert!(info.recovery_paths().is_empty());
 
         // Now enable the recovery path and add a signature for the recovery key.
         let mut signed_recov_psbt = dummy_psbt.clone();
@@ -1806,7 +1922,7 @@ mod tests {
         assert_eq!(info.primary_path.threshold, 1);
         assert_eq!(info.primary_path.sigs_count, 0);
         assert!(info.primary_path.signed_pubkeys.is_empty());
-        let recov_info = info.recovery_paths.get(&timelock).unwrap();
+        let recov_info = info.recovery_paths().get(&timelock).cloned().unwrap();
         assert_eq!(recov_info.threshold, 1);
         assert_eq!(recov_info.sigs_count, 1);
         assert!(
@@ -1825,7 +1941,7 @@ mod tests {
         assert_eq!(info.primary_path.threshold, 1);
         assert_eq!(info.primary_path.sigs_count, 0);
         assert!(info.primary_path.signed_pubkeys.is_empty());
-        assert!(info.recovery_paths.is_empty());
+        assert!(info.recovery_paths().is_empty());
 
         // If we analyze a descriptor with a multisig we'll get the right threshold.
         let desc = LianaDescriptor::new(
@@ -1868,7 +1984,7 @@ mod tests {
             info.primary_path.signed_pubkeys.len() == 1
                 && info.primary_path.signed_pubkeys.contains_key(&prim_key_fg)
         );
-        assert!(info.recovery_paths.is_empty());
+        assert!(info.recovery_paths().is_empty());
 
         // A not very well thought-out decaying multisig.
         let prim_path = PathInfo::Multi(3, vec![
@@ -1902,7 +2018,7 @@ mod tests {
             empty_partial_info.primary_path.sigs_count,
             empty_partial_info.primary_path.signed_pubkeys.len()
         );
-        assert!(empty_partial_info.recovery_paths.is_empty());
+        assert!(empty_partial_info.recovery_paths().is_empty());
 
         // Now set a signature for the primary path. All recovery paths still empty, a signature is
         // present for the primary path.
@@ -1922,7 +2038,7 @@ mod tests {
             partial_info.primary_path.sigs_count,
             partial_info.primary_path.signed_pubkeys.len()
         );
-        assert!(partial_info.recovery_paths.is_empty());
+        assert!(partial_info.recovery_paths().is_empty());
 
         // Now enable the first recovery path and make the signature be for this path.
         let fingerprint = first_reco_path
@@ -1945,15 +2061,15 @@ mod tests {
             partial_info.primary_path.sigs_count,
             partial_info.primary_path.signed_pubkeys.len()
         );
-        assert!(partial_info.recovery_paths.is_empty());
+        assert!(partial_info.recovery_paths().is_empty());
         psbt.unsigned_tx.input[0].sequence = bitcoin::Sequence::from_height(26784);
         let partial_info = desc.partial_spend_info(&psbt).unwrap();
-        assert_eq!(partial_info.recovery_paths.len(), 1);
-        assert_eq!(partial_info.recovery_paths[&26784].threshold, 3);
-        assert_eq!(partial_info.recovery_paths[&26784].sigs_count, 1);
+        assert_eq!(partial_info.recovery_paths().len(), 1);
+        assert_eq!(partial_info.recovery_paths()[&26784].threshold, 3);
+        assert_eq!(partial_info.recovery_paths()[&26784].sigs_count, 1);
         assert_eq!(
-            partial_info.recovery_paths[&26784].signed_pubkeys.len(),
-            partial_info.recovery_paths[&26784].sigs_count
+            partial_info.recovery_paths()[&26784].signed_pubkeys.len(),
+            partial_info.recovery_paths()[&26784].sigs_count
         );
 
         // Now enable the second recovery path and make the signature be for this path.
@@ -1978,12 +2094,12 @@ mod tests {
             partial_info.primary_path.sigs_count,
             partial_info.primary_path.signed_pubkeys.len()
         );
-        assert_eq!(partial_info.recovery_paths.len(), 2);
-        assert_eq!(partial_info.recovery_paths[&26784].threshold, 3);
-        assert_eq!(partial_info.recovery_paths[&26784].sigs_count, 0);
-        assert_eq!(partial_info.recovery_paths[&53568].threshold, 2);
-        assert_eq!(partial_info.recovery_paths[&53568].sigs_count, 1);
-        for rec_path in partial_info.recovery_paths.values() {
+        assert_eq!(partial_info.recovery_paths().len(), 2);
+        assert_eq!(partial_info.recovery_paths()[&26784].threshold, 3);
+        assert_eq!(partial_info.recovery_paths()[&26784].sigs_count, 0);
+        assert_eq!(partial_info.recovery_paths()[&53568].threshold, 2);
+        assert_eq!(partial_info.recovery_paths()[&53568].sigs_count, 1);
+        for rec_path in partial_info.recovery_paths().values() {
             assert_eq!(rec_path.sigs_count, rec_path.signed_pubkeys.len());
         }
 
@@ -2010,14 +2126,14 @@ mod tests {
             partial_info.primary_path.sigs_count,
             partial_info.primary_path.signed_pubkeys.len()
         );
-        assert_eq!(partial_info.recovery_paths.len(), 3);
-        assert_eq!(partial_info.recovery_paths[&26784].threshold, 3);
-        assert_eq!(partial_info.recovery_paths[&26784].sigs_count, 0);
-        assert_eq!(partial_info.recovery_paths[&53568].threshold, 2);
-        assert_eq!(partial_info.recovery_paths[&53568].sigs_count, 0);
-        assert_eq!(partial_info.recovery_paths[&62496].threshold, 1);
-        assert_eq!(partial_info.recovery_paths[&62496].sigs_count, 1);
-        for rec_path in partial_info.recovery_paths.values() {
+        assert_eq!(partial_info.recovery_paths().len(), 3);
+        assert_eq!(partial_info.recovery_paths()[&26784].threshold, 3);
+        assert_eq!(partial_info.recovery_paths()[&26784].sigs_count, 0);
+        assert_eq!(partial_info.recovery_paths()[&53568].threshold, 2);
+        assert_eq!(partial_info.recovery_paths()[&53568].sigs_count, 0);
+        assert_eq!(partial_info.recovery_paths()[&62496].threshold, 1);
+        assert_eq!(partial_info.recovery_paths()[&62496].sigs_count, 1);
+        for rec_path in partial_info.recovery_paths().values() {
             assert_eq!(rec_path.sigs_count, rec_path.signed_pubkeys.len());
         }
     }
@@ -2085,7 +2201,7 @@ mod tests {
             .unwrap(),
         );
         let prim_path_info = tap_desc.policy().primary_path;
-        let rec_path_info = &tap_desc.policy().recovery_paths[&14];
+        let rec_path_info = &tap_desc.policy().recovery_paths()[&14];
         let mut tap_psbt = psbt;
         let dummy_xonly_pubkey = bitcoin::XOnlyPublicKey::from_str(
             "85899827df71b16f2f0eed47cb920e3963f5204e171f5ef7ea4eeec3d5ecc607",
@@ -2197,6 +2313,900 @@ mod tests {
         run_change_detection(desc, &secp);
     }
 
+    #[test]
+    fn hashlock_paths() {
+        let secp = secp256k1::Secp256k1::new();
+        let prim_path = PathInfo::Single(random_desc_key(&secp));
+        let rec_paths: BTreeMap<_, _> = [(
+            PathCondition::RelativeBlocks(52560),
+            PathInfo::Single(random_desc_key(&secp)),
+        )]
+        .iter()
+        .cloned()
+        .collect();
+        let preimage = [42; 32];
+        let other_preimage = [43; 32];
+        let hashlock_paths: BTreeMap<_, _> = [
+            (
+                PathCondition::HashLock(HashLock::sha256(&preimage)),
+                PathInfo::Single(random_desc_key(&secp)),
+            ),
+            (
+                PathCondition::HashLock(HashLock::hash160(&other_preimage)),
+                PathInfo::Multi(2, vec![random_desc_key(&secp), random_desc_key(&secp)]),
+            ),
+        ]
+        .iter()
+        .cloned()
+        .collect();
+        let all_paths: BTreeMap<_, _> = rec_paths
+            .clone()
+            .into_iter()
+            .chain(hashlock_paths.clone())
+            .collect();
+
+        for is_taproot in [false, true] {
+            let desc_wo_hashlocks = LianaDescriptor::new(
+                LianaPolicy::from_conditional_paths(
+                    prim_path.clone(),
+                    rec_paths.clone(),
+                    is_taproot,
+                )
+                .unwrap(),
+            );
+            let policy = LianaPolicy::from_conditional_paths(
+                prim_path.clone(),
+                all_paths.clone(),
+                is_taproot,
+            )
+            .unwrap();
+            let desc = LianaDescriptor::new(policy.clone());
+
+            // The hash-locked paths are correctly inferred from the descriptor.
+            let desc = LianaDescriptor::from_str(&desc.to_string()).unwrap();
+            assert_eq!(desc.policy(), policy);
+            assert_eq!(desc.policy().conditional_paths(), &all_paths);
+            assert_eq!(desc.first_timelock_value(), 52560);
+
+            // The 2-of-2 hash-locked path is the most expensive one to satisfy: it needs two
+            // signatures and a preimage. It is only accounted for when not using the primary path.
+            assert!(desc.max_sat_weight(false) > desc_wo_hashlocks.max_sat_weight(false));
+            assert!(desc.max_sat_weight(false) > desc.max_sat_weight(true) + 32);
+            // It is also the heaviest when explaining the policy.
+            let weights: Vec<_> = desc
+                .explain()
+                .paths
+                .iter()
+                .map(|path| (path.condition.clone(), path.max_sat_weight))
+                .collect();
+            let heaviest = weights.iter().max_by_key(|(_, weight)| *weight).unwrap();
+            assert!(matches!(heaviest.0, ConditionExplanation::Hashlock { .. }));
+            assert!(heaviest.1 <= desc.max_sat_weight(false));
+
+            // A PSBT spending a coin from this descriptor. The hash-locked paths are always
+            // reported, with no signature and no preimage.
+            let der_desc = desc.receive_descriptor().derive(0.into(), &secp);
+            let mut psbt = Psbt {
+                unsigned_tx: bitcoin::Transaction {
+                    version: bitcoin::transaction::Version::TWO,
+                    lock_time: bitcoin::absolute::LockTime::ZERO,
+                    input: vec![bitcoin::TxIn::default()],
+                    output: vec![bitcoin::TxOut {
+                        value: bitcoin::Amount::from_sat(10_000),
+                        script_pubkey: der_desc.script_pubkey(),
+                    }],
+                },
+                version: 0,
+                xpub: BTreeMap::new(),
+                proprietary: BTreeMap::new(),
+                unknown: BTreeMap::new(),
+                inputs: vec![PsbtIn::default()],
+                outputs: vec![PsbtOut::default()],
+            };
+            der_desc.update_psbt_in(&mut psbt.inputs[0]);
+            let spend_info = desc.partial_spend_info(&psbt).unwrap();
+            assert_eq!(spend_info.conditional_paths().len(), 2);
+            assert!(hashlock_paths.keys().all(|cond| match cond {
+                PathCondition::HashLock(h) => {
+                    !spend_info.has_preimage(h)
+                        && spend_info.conditional_paths()[cond].sigs_count == 0
+                }
+                _ => unreachable!(),
+            }));
+
+            // Only a preimage for one of the hash-locked paths can be added to the PSBT.
+            desc.add_hashlock_preimage(&mut psbt, [0; 32]).unwrap_err();
+            let hashlock = desc.add_hashlock_preimage(&mut psbt, preimage).unwrap();
+            assert_eq!(hashlock, HashLock::sha256(&preimage));
+            let hashlock = desc
+                .add_hashlock_preimage(&mut psbt, other_preimage)
+                .unwrap();
+            assert_eq!(hashlock, HashLock::hash160(&other_preimage));
+            let spend_info = desc.partial_spend_info(&psbt).unwrap();
+            assert!([
+                HashLock::sha256(&preimage),
+                HashLock::hash160(&other_preimage)
+            ]
+            .iter()
+            .all(|h| spend_info.has_preimage(h)));
+        }
+
+        // Hash-locked paths are checked like any other spending path.
+        let mut paths = rec_paths.clone();
+        paths.insert(
+            PathCondition::HashLock(HashLock::sha256(&preimage)),
+            prim_path.clone(),
+        );
+        LianaPolicy::from_conditional_paths(prim_path.clone(), paths, true).unwrap_err();
+        let mut paths = rec_paths;
+        paths.insert(
+            PathCondition::HashLock(HashLock::sha256(&preimage)),
+            PathInfo::Multi(3, vec![random_desc_key(&secp), random_desc_key(&secp)]),
+        );
+        LianaPolicy::from_conditional_paths(prim_path, paths, true).unwrap_err();
+
+        // A hash-locked path must not also be timelocked.
+        LianaDescriptor::from_str("wsh(or_d(pk([abcdef01]tpubDEN9WSToTyy9ZQfaYqSKfmVqmq1VVLNtYfj3Vkqh67et57eJ5sTKZQBkHqSwPUsoSskJeaYnPttHe2VrkCsKA27kUaN9SDc5zhqeLzKa1rr/<0;1>/*),and_v(v:and_v(v:pkh([abcdef02]tpubD8LYfn6njiA2inCoxwM7EuN3cuLVcaHAwLYeups13dpevd3nHLRdK9NdQksWXrhLQVxcUZRpnp5CkJ1FhE61WRAsHxDNAkvGkoQkAeWDYjV/<0;1>/*),sha256(d1ec675902ef1633427ca360b290b0b3045a0d9058ddb5e648b4c3c3224c5c68)),older(10))))").unwrap_err();
+    }
+
+    #[test]
+    fn absolute_recovery_paths() {
+        let secp = secp256k1::Secp256k1::new();
+        let prim_path = PathInfo::Single(random_desc_key(&secp));
+        let rec_path = (
+            PathCondition::RelativeBlocks(52560),
+            PathInfo::Single(random_desc_key(&secp)),
+        );
+        let height_lock = AbsLockTime::from_consensus(900_000).unwrap();
+        let time_lock = AbsLockTime::from_consensus(1_900_000_000).unwrap();
+        let all_paths: BTreeMap<_, _> = [
+            rec_path.clone(),
+            (
+                PathCondition::Absolute(height_lock),
+                PathInfo::Single(random_desc_key(&secp)),
+            ),
+            (
+                PathCondition::Absolute(time_lock),
+                PathInfo::Multi(2, vec![random_desc_key(&secp), random_desc_key(&secp)]),
+            ),
+        ]
+        .iter()
+        .cloned()
+        .collect();
+        // The absolute-timelocked paths available to this spend.
+        let abs_paths = |spend_info: &PartialSpendInfo| {
+            spend_info
+                .conditional_paths()
+                .keys()
+                .filter_map(|cond| match cond {
+                    PathCondition::Absolute(tl) => Some(*tl),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        };
+
+        for is_taproot in [false, true] {
+            let policy = LianaPolicy::from_conditional_paths(
+                prim_path.clone(),
+                all_paths.clone(),
+                is_taproot,
+            )
+            .unwrap();
+            let desc = LianaDescriptor::new(policy.clone());
+
+            // The absolute-timelocked paths are correctly inferred from the descriptor, and don't
+            // interfere with the relative-timelocked ones.
+            let desc = LianaDescriptor::from_str(&desc.to_string()).unwrap();
+            assert_eq!(desc.policy(), policy);
+            assert_eq!(desc.policy().conditional_paths(), &all_paths);
+            assert_eq!(desc.first_timelock_value(), 52560);
+
+            // A PSBT spending a coin from this descriptor. The absolute-timelocked paths are only
+            // available once the nLockTime is set to (at least) their timelock, of the same unit.
+            let der_desc = desc.receive_descriptor().derive(0.into(), &secp);
+            let mut psbt = Psbt {
+                unsigned_tx: bitcoin::Transaction {
+                    version: bitcoin::transaction::Version::TWO,
+                    lock_time: bitcoin::absolute::LockTime::ZERO,
+                    input: vec![bitcoin::TxIn {
+                        sequence: bitcoin::Sequence::ENABLE_LOCKTIME_NO_RBF,
+                        ..bitcoin::TxIn::default()
+                    }],
+                    output: vec![bitcoin::TxOut {
+                        value: bitcoin::Amount::from_sat(10_000),
+                        script_pubkey: der_desc.script_pubkey(),
+                    }],
+                },
+                version: 0,
+                xpub: BTreeMap::new(),
+                proprietary: BTreeMap::new(),
+                unknown: BTreeMap::new(),
+                inputs: vec![PsbtIn::default()],
+                outputs: vec![PsbtOut::default()],
+            };
+            der_desc.update_psbt_in(&mut psbt.inputs[0]);
+            let spend_info = desc.partial_spend_info(&psbt).unwrap();
+            assert!(abs_paths(&spend_info).is_empty());
+
+            psbt.unsigned_tx.lock_time = bitcoin::absolute::LockTime::from_consensus(899_999);
+            let spend_info = desc.partial_spend_info(&psbt).unwrap();
+            assert!(abs_paths(&spend_info).is_empty());
+
+            psbt.unsigned_tx.lock_time = bitcoin::absolute::LockTime::from_consensus(900_001);
+            let spend_info = desc.partial_spend_info(&psbt).unwrap();
+            assert_eq!(abs_paths(&spend_info), vec![height_lock]);
+
+            psbt.unsigned_tx.lock_time = bitcoin::absolute::LockTime::from_consensus(1_900_000_000);
+            let spend_info = desc.partial_spend_info(&psbt).unwrap();
+            assert_eq!(abs_paths(&spend_info), vec![time_lock]);
+
+            // A final nSequence disables the nLockTime, and therefore the paths.
+            psbt.unsigned_tx.input[0].sequence = bitcoin::Sequence::MAX;
+            let spend_info = desc.partial_spend_info(&psbt).unwrap();
+            assert!(abs_paths(&spend_info).is_empty());
+        }
+
+        // Absolute-timelocked paths are checked like any other spending path.
+        let paths = [
+            rec_path,
+            (PathCondition::Absolute(height_lock), prim_path.clone()),
+        ]
+        .iter()
+        .cloned()
+        .collect();
+        LianaPolicy::from_conditional_paths(prim_path, paths, true).unwrap_err();
+    }
+
+    #[test]
+    fn time_recovery_paths() {
+        let secp = secp256k1::Secp256k1::new();
+        let prim_path = PathInfo::Single(random_desc_key(&secp));
+        let rec_path = (
+            PathCondition::RelativeBlocks(52560),
+            PathInfo::Single(random_desc_key(&secp)),
+        );
+        // About 30 and 90 days.
+        let all_paths: BTreeMap<_, _> = [
+            rec_path.clone(),
+            (
+                PathCondition::RelativeTime(5063),
+                PathInfo::Single(random_desc_key(&secp)),
+            ),
+            (
+                PathCondition::RelativeTime(15188),
+                PathInfo::Multi(2, vec![random_desc_key(&secp), random_desc_key(&secp)]),
+            ),
+        ]
+        .iter()
+        .cloned()
+        .collect();
+        // The time-based paths available to this spend.
+        let time_paths = |spend_info: &PartialSpendInfo| {
+            spend_info
+                .conditional_paths()
+                .keys()
+                .filter_map(|cond| match cond {
+                    PathCondition::RelativeTime(tl) => Some(*tl),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        };
+
+        for is_taproot in [false, true] {
+            let policy = LianaPolicy::from_conditional_paths(
+                prim_path.clone(),
+                all_paths.clone(),
+                is_taproot,
+            )
+            .unwrap();
+            let desc = LianaDescriptor::new(policy.clone());
+            assert!(desc.to_string().contains("older(4199367)"));
+
+            // The time-based paths are correctly inferred from the descriptor, and don't interfere
+            // with the ones expressed in blocks.
+            let desc = LianaDescriptor::from_str(&desc.to_string()).unwrap();
+            assert_eq!(desc.policy(), policy);
+            assert_eq!(desc.policy().conditional_paths(), &all_paths);
+            assert_eq!(desc.first_timelock_value(), 52560);
+
+            // A PSBT spending a coin from this descriptor. The time-based paths are only available
+            // if the nSequence is a time-based relative locktime of at least their value.
+            let der_desc = desc.receive_descriptor().derive(0.into(), &secp);
+            let mut psbt = Psbt {
+                unsigned_tx: bitcoin::Transaction {
+                    version: bitcoin::transaction::Version::TWO,
+                    lock_time: bitcoin::absolute::LockTime::ZERO,
+                    input: vec![bitcoin::TxIn {
+                        sequence: Sequence::from_height(52560),
+                        ..bitcoin::TxIn::default()
+                    }],
+                    output: vec![bitcoin::TxOut {
+                        value: bitcoin::Amount::from_sat(10_000),
+                        script_pubkey: der_desc.script_pubkey(),
+                    }],
+                },
+                version: 0,
+                xpub: BTreeMap::new(),
+                proprietary: BTreeMap::new(),
+                unknown: BTreeMap::new(),
+                inputs: vec![PsbtIn::default()],
+                outputs: vec![PsbtOut::default()],
+            };
+            der_desc.update_psbt_in(&mut psbt.inputs[0]);
+            let spend_info = desc.partial_spend_info(&psbt).unwrap();
+            assert_eq!(spend_info.recovery_paths().len(), 1);
+            assert!(time_paths(&spend_info).is_empty());
+
+            psbt.unsigned_tx.input[0].sequence = Sequence::from_512_second_intervals(5062);
+            let spend_info = desc.partial_spend_info(&psbt).unwrap();
+            assert!(spend_info.recovery_paths().is_empty());
+            assert!(time_paths(&spend_info).is_empty());
+
+            psbt.unsigned_tx.input[0].sequence = Sequence::from_512_second_intervals(5063);
+            let spend_info = desc.partial_spend_info(&psbt).unwrap();
+            assert!(spend_info.recovery_paths().is_empty());
+            assert_eq!(time_paths(&spend_info), vec![5063]);
+
+            psbt.unsigned_tx.input[0].sequence = Sequence::from_512_second_intervals(u16::MAX);
+            let spend_info = desc.partial_spend_info(&psbt).unwrap();
+            assert_eq!(time_paths(&spend_info).len(), 2);
+        }
+
+        // You can't use a null timelock in Miniscript.
+        let paths = [
+            rec_path.clone(),
+            (
+                PathCondition::RelativeTime(0),
+                PathInfo::Single(random_desc_key(&secp)),
+            ),
+        ]
+        .iter()
+        .cloned()
+        .collect();
+        LianaPolicy::from_conditional_paths(prim_path.clone(), paths, true).unwrap_err();
+
+        // Time-based paths are checked like any other spending path.
+        let paths = [
+            rec_path,
+            (PathCondition::RelativeTime(5063), prim_path.clone()),
+        ]
+        .iter()
+        .cloned()
+        .collect();
+        LianaPolicy::from_conditional_paths(prim_path, paths, true).unwrap_err();
+    }
+
+    fn random_xpub(
+        secp: &secp256k1::Secp256k1<impl secp256k1::Signing>,
+    ) -> descriptor::DescriptorXKey<bip32::Xpub> {
+        let signer = HotSigner::generate(bitcoin::Network::Bitcoin).unwrap();
+        let der_path = bip32::DerivationPath::from_str("m/48'/0'/0'/2'").unwrap();
+        descriptor::DescriptorXKey {
+            origin: Some((signer.fingerprint(secp), der_path.clone())),
+            xkey: signer.xpub_at(&der_path, secp),
+            derivation_path: bip32::DerivationPath::master(),
+            wildcard: descriptor::Wildcard::None,
+        }
+    }
+
+    #[test]
+    fn decaying_multisig() {
+        let secp = secp256k1::Secp256k1::new();
+        let keys: Vec<_> = (0..5).map(|_| random_xpub(&secp)).collect();
+        // 3-of-5 now, 2-of-5 after about 6 months, 1-of-5 after about a year.
+        let thresholds: BTreeMap<_, _> = [(26280, 2), (52560, 1)].iter().cloned().collect();
+
+        for is_taproot in [false, true] {
+            let policy =
+                LianaPolicy::new_decaying_multisig(keys.clone(), 3, thresholds.clone(), is_taproot)
+                    .unwrap();
+            assert_eq!(policy.primary_path().thresh_origins().0, 3);
+            let rec_threshs: Vec<_> = policy
+                .recovery_paths()
+                .iter()
+                .map(|(tl, path)| (*tl, path.thresh_origins().0))
+                .collect();
+            assert_eq!(rec_threshs, vec![(26280, 2), (52560, 1)]);
+
+            // All signers are present in all paths, with different derivation paths.
+            let prim_origins = policy.primary_path().thresh_origins().1;
+            for path in policy.recovery_paths().values() {
+                let origins = path.thresh_origins().1;
+                assert_eq!(origins.len(), 5);
+                for (fg, der_paths) in origins {
+                    assert!(prim_origins[&fg].is_disjoint(&der_paths));
+                }
+            }
+
+            let desc = LianaDescriptor::new(policy.clone());
+            let desc = LianaDescriptor::from_str(&desc.to_string()).unwrap();
+            assert_eq!(desc.policy(), policy);
+            assert_eq!(desc.first_timelock_value(), 26280);
+            assert!(desc.max_sat_weight(false) >= desc.max_sat_weight(true));
+        }
+
+        // Under P2WSH the primary path is a 3-of-5 CHECKMULTISIG: the witness is the dummy
+        // element, 3 signatures, the empty element to select its or_i() branch and the witness
+        // script.
+        let desc = LianaDescriptor::new(
+            LianaPolicy::new_decaying_multisig(keys.clone(), 3, thresholds.clone(), false).unwrap(),
+        );
+        let witscript_size = desc
+            .receive_descriptor()
+            .0
+            .at_derivation_index(0)
+            .unwrap()
+            .explicit_script()
+            .unwrap()
+            .len();
+        assert_eq!(
+            desc.max_sat_weight(true),
+            1 + 1 + 3 * (1 + 72) + 1 + varint_len(witscript_size) + witscript_size
+        );
+
+        // The thresholds must strictly decrease, and never reach 0.
+        for invalid in [
+            vec![(26280, 3)],
+            vec![(26280, 2), (52560, 2)],
+            vec![(26280, 1), (52560, 2)],
+            vec![(26280, 0)],
+        ] {
+            let err = LianaPolicy::new_decaying_multisig(
+                keys.clone(),
+                3,
+                invalid.into_iter().collect(),
+                true,
+            )
+            .unwrap_err();
+            assert!(matches!(err, LianaPolicyError::InvalidDecayingThreshold(_)));
+        }
+        // The primary threshold must be valid.
+        LianaPolicy::new_decaying_multisig(keys.clone(), 6, thresholds.clone(), true).unwrap_err();
+        // There must be at least one recovery path.
+        LianaPolicy::new_decaying_multisig(keys.clone(), 3, BTreeMap::new(), true).unwrap_err();
+        // The same signer can't be used twice.
+        let mut dup_keys = keys;
+        dup_keys.push(dup_keys[0].clone());
+        LianaPolicy::new_decaying_multisig(dup_keys, 3, thresholds, true).unwrap_err();
+    }
+
+    #[test]
+    fn expanding_multisig() {
+        let secp = secp256k1::Secp256k1::new();
+        let prim_keys: Vec<_> = (0..2).map(|_| random_xpub(&secp)).collect();
+        let (lawyer, heir) = (random_xpub(&secp), random_xpub(&secp));
+        // 2-of-2 now, 2-of-3 with a lawyer after about 6 months, 1-of-4 with an heir after about
+        // a year.
+        let expansions: BTreeMap<_, _> = [
+            (26280, (2, vec![lawyer.clone()])),
+            (52560, (1, vec![heir.clone()])),
+        ]
+        .iter()
+        .cloned()
+        .collect();
+
+        for is_taproot in [false, true] {
+            let policy = LianaPolicy::new_expanding_multisig(
+                prim_keys.clone(),
+                2,
+                expansions.clone(),
+                is_taproot,
+            )
+            .unwrap();
+            let (prim_thresh, prim_origins) = policy.primary_path().thresh_origins();
+            assert_eq!((prim_thresh, prim_origins.len()), (2, 2));
+            let rec_paths: Vec<_> = policy
+                .recovery_paths()
+                .iter()
+                .map(|(tl, path)| {
+                    let (thresh, origins) = path.thresh_origins();
+                    (*tl, thresh, origins.len())
+                })
+                .collect();
+            assert_eq!(rec_paths, vec![(26280, 2, 3), (52560, 1, 4)]);
+
+            // Each signer derives its keys starting from the first multipath indexes, regardless
+            // of the path in which it was added.
+            let lawyer_fg = lawyer.origin.as_ref().unwrap().0;
+            let heir_fg = heir.origin.as_ref().unwrap().0;
+            let first_origins = policy.recovery_paths()[&26280].thresh_origins().1;
+            let last_origins = policy.recovery_paths()[&52560].thresh_origins().1;
+            let first_der_path = bip32::DerivationPath::from_str("m/48'/0'/0'/2'/0").unwrap();
+            assert!(first_origins[&lawyer_fg].contains(&first_der_path));
+            assert!(!last_origins[&lawyer_fg].contains(&first_der_path));
+            assert!(last_origins[&heir_fg].contains(&first_der_path));
+
+            let desc = LianaDescriptor::new(policy.clone());
+            let desc = LianaDescriptor::from_str(&desc.to_string()).unwrap();
+            assert_eq!(desc.policy(), policy);
+            assert!(desc.max_sat_weight(false) >= desc.max_sat_weight(true));
+        }
+
+        // Under Taproot the primary path is a 2-of-2 multi_a(): the witness is the 2 signatures,
+        // the script and the control block.
+        let desc = LianaDescriptor::new(
+            LianaPolicy::new_expanding_multisig(prim_keys.clone(), 2, expansions.clone(), true)
+                .unwrap(),
+        );
+        let leaf_sizes: Vec<_> = match &desc.receive_descriptor().0 {
+            descriptor::Descriptor::Tr(tr) => tr
+                .iter_scripts()
+                .map(|(depth, ms)| (depth, ms.script_size()))
+                .collect(),
+            _ => unreachable!(),
+        };
+        let (depth, script_size) = leaf_sizes
+            .into_iter()
+            .find(|(_, size)| *size == 2 * 33 + 2)
+            .expect("The primary path leaf");
+        assert_eq!(
+            desc.max_sat_weight(true),
+            1 + 2 * (1 + 64) + (1 + script_size) + (1 + 33 + 32 * depth as usize)
+        );
+
+        // Each expansion must add a key.
+        let err = LianaPolicy::new_expanding_multisig(
+            prim_keys.clone(),
+            2,
+            [(26280, (2, vec![]))].iter().cloned().collect(),
+            true,
+        )
+        .unwrap_err();
+        assert!(matches!(err, LianaPolicyError::EmptyKeysetExpansion(26280)));
+        // The thresholds must never increase, and never reach 0.
+        for invalid in [
+            vec![(26280, (3, vec![lawyer.clone()]))],
+            vec![
+                (26280, (1, vec![lawyer.clone()])),
+                (52560, (2, vec![heir.clone()])),
+            ],
+            vec![(26280, (0, vec![lawyer.clone()]))],
+        ] {
+            let err = LianaPolicy::new_expanding_multisig(
+                prim_keys.clone(),
+                2,
+                invalid.into_iter().collect(),
+                true,
+            )
+            .unwrap_err();
+            assert!(matches!(
+                err,
+                LianaPolicyError::InvalidExpandingThreshold(_)
+            ));
+        }
+        // The primary threshold must be valid for its number of keys.
+        LianaPolicy::new_expanding_multisig(prim_keys.clone(), 3, expansions.clone(), true)
+            .unwrap_err();
+        // The xpubs must not have a derivation path nor a wildcard.
+        for (der_path, wildcard) in [
+            ("m/0", descriptor::Wildcard::None),
+            ("m", descriptor::Wildcard::Unhardened),
+        ] {
+            let mut xpub = lawyer.clone();
+            xpub.derivation_path = bip32::DerivationPath::from_str(der_path).unwrap();
+            xpub.wildcard = wildcard;
+            let err = LianaPolicy::new_expanding_multisig(
+                prim_keys.clone(),
+                2,
+                [(26280, (2, vec![xpub.clone()]))].iter().cloned().collect(),
+                true,
+            )
+            .unwrap_err();
+            assert!(matches!(err, LianaPolicyError::InvalidAccountXpub(_)));
+            let mut keys = prim_keys.clone();
+            keys.push(xpub);
+            let err = LianaPolicy::new_decaying_multisig(
+                keys,
+                2,
+                [(26280, 1)].iter().cloned().collect(),
+                true,
+            )
+            .unwrap_err();
+            assert!(matches!(err, LianaPolicyError::InvalidAccountXpub(_)));
+        }
+        // A signer can't be added twice.
+        LianaPolicy::new_expanding_multisig(
+            prim_keys.clone(),
+            2,
+            [(26280, (2, vec![prim_keys[0].clone()]))]
+                .iter()
+                .cloned()
+                .collect(),
+            true,
+        )
+        .unwrap_err();
+    }
+
+    #[test]
+    fn policy_explanation() {
+        let owner_key = PathInfo::Single(descriptor::DescriptorPublicKey::from_str("[abcdef01/48'/0'/0'/2']xpub6Eze7yAT3Y1wGrnzedCNVYDXUqa9NmHVWck5emBaTbXtURbe1NWZbK9bsz1TiVE7Cz341PMTfYgFw1KdLWdzcM1UMFTcdQfCYhhXZ2HJvTW/<0;1>/*").unwrap());
+        let heir_key = PathInfo::Single(descriptor::DescriptorPublicKey::from_str("[01234567]xpub688Hn4wScQAAiYJLPg9yH27hUpfZAUnmJejRQBCiwfP5PEDzjWMNW1wChcninxr5gyavFqbbDjdV1aK5USJz8NDVjUy7FRQaaqqXHh5SbXe/<0;1>/*").unwrap());
+        let owner_time_key = PathInfo::Single(descriptor::DescriptorPublicKey::from_str("[abcdef01/48'/0'/0'/2']xpub6Eze7yAT3Y1wGrnzedCNVYDXUqa9NmHVWck5emBaTbXtURbe1NWZbK9bsz1TiVE7Cz341PMTfYgFw1KdLWdzcM1UMFTcdQfCYhhXZ2HJvTW/<2;3>/*").unwrap());
+        let heir_abs_key = PathInfo::Single(descriptor::DescriptorPublicKey::from_str("[01234567]xpub688Hn4wScQAAiYJLPg9yH27hUpfZAUnmJejRQBCiwfP5PEDzjWMNW1wChcninxr5gyavFqbbDjdV1aK5USJz8NDVjUy7FRQaaqqXHh5SbXe/<2;3>/*").unwrap());
+        let abs_timelock = AbsLockTime::from_consensus(900_000).unwrap();
+        let desc = LianaDescriptor::new(
+            LianaPolicy::from_conditional_paths(
+                owner_key,
+                [
+                    (PathCondition::RelativeBlocks(52560), heir_key),
+                    (PathCondition::RelativeTime(5063), owner_time_key),
+                    (PathCondition::Absolute(abs_timelock), heir_abs_key),
+                ]
+                .iter()
+                .cloned()
+                .collect(),
+                false,
+            )
+            .unwrap(),
+        );
+
+        let explanation = desc.explain();
+        assert!(!explanation.is_taproot);
+        let conditions: Vec<_> = explanation
+            .paths
+            .iter()
+            .map(|path| path.condition.clone())
+            .collect();
+        assert_eq!(
+            conditions,
+            vec![
+                ConditionExplanation::None,
+                ConditionExplanation::RelativeBlocks {
+                    blocks: 52560,
+                    approx_seconds: 52560 * 600
+                },
+                ConditionExplanation::RelativeTime {
+                    intervals: 5063,
+                    seconds: 5063 * 512
+                },
+                ConditionExplanation::AbsoluteHeight {
+                    height: 900_000,
+                    approx_timestamp: None
+                },
+            ]
+        );
+        let primary = &explanation.paths[0];
+        assert_eq!(primary.threshold, 1);
+        assert_eq!(
+            primary.keys[0].fingerprint,
+            bip32::Fingerprint::from_str("abcdef01").unwrap()
+        );
+        assert_eq!(
+            primary.keys[0].origin_path,
+            bip32::DerivationPath::from_str("m/48'/0'/0'/2'").unwrap()
+        );
+        assert_eq!(primary.max_sat_weight, desc.max_sat_weight(true));
+        // Each path has its own satisfaction weight, bounded by the worst case of all the
+        // non-primary paths.
+        assert!(explanation.paths[1..]
+            .iter()
+            .all(|path| path.max_sat_weight <= desc.max_sat_weight(false)));
+
+        let text = explanation.to_string();
+        assert!(text.starts_with("P2WSH descriptor with 4 spending paths:\n- Primary path: 1 of 1 key ([abcdef01/48'/0'/0'/2']), always available."));
+        assert!(text.contains("- Recovery path: 1 of 1 key ([01234567]), available 52560 blocks (about 365 days) after the coin is confirmed."));
+        assert!(
+            text.contains("available 2592256 seconds (about 30 days) after the coin is confirmed.")
+        );
+        assert!(text.contains("available from block height 900000."));
+
+        // The date of an absolute height is estimated from the chain tip, 144 blocks before.
+        let mut explanation = explanation;
+        explanation.estimate_dates(899_856, 1_700_000_000);
+        assert_eq!(
+            explanation.paths[3].condition,
+            ConditionExplanation::AbsoluteHeight {
+                height: 900_000,
+                approx_timestamp: Some(1_700_086_400)
+            }
+        );
+        assert!(explanation
+            .to_string()
+            .contains("available from block height 900000 (around 2023-11-15)."));
+
+        // A path requiring more signatures has a larger satisfaction weight.
+        let secp = secp256k1::Secp256k1::new();
+        let desc = LianaDescriptor::new(
+            LianaPolicy::new_legacy(
+                PathInfo::Single(random_desc_key(&secp)),
+                [
+                    (26280, PathInfo::Single(random_desc_key(&secp))),
+                    (
+                        52560,
+                        PathInfo::Multi(
+                            2,
+                            vec![
+                                random_desc_key(&secp),
+                                random_desc_key(&secp),
+                                random_desc_key(&secp),
+                            ],
+                        ),
+                    ),
+                ]
+                .iter()
+                .cloned()
+                .collect(),
+            )
+            .unwrap(),
+        );
+        let weights: Vec<_> = desc
+            .explain()
+            .paths
+            .iter()
+            .map(|path| path.max_sat_weight)
+            .collect();
+        assert_eq!(weights[0], desc.max_sat_weight(true));
+        assert!(weights[1] < weights[2]);
+        assert!(weights[2] <= desc.max_sat_weight(false));
+    }
+
+    #[test]
+    fn wallet_policy_roundtrip() {
+        fn roundtrip(desc: &LianaDescriptor) -> WalletPolicy {
+            let policy = desc.to_wallet_policy().unwrap();
+            assert!(!policy.template.contains("xpub") && !policy.template.contains('#'));
+            assert_eq!(&LianaDescriptor::from_wallet_policy(&policy).unwrap(), desc);
+            policy
+        }
+
+        // A known vector.
+        let desc = LianaDescriptor::from_str("wsh(or_d(pk([abcdef01/48'/0'/0'/2']xpub6Eze7yAT3Y1wGrnzedCNVYDXUqa9NmHVWck5emBaTbXtURbe1NWZbK9bsz1TiVE7Cz341PMTfYgFw1KdLWdzcM1UMFTcdQfCYhhXZ2HJvTW/<0;1>/*),and_v(v:pkh([01234567]xpub688Hn4wScQAAiYJLPg9yH27hUpfZAUnmJejRQBCiwfP5PEDzjWMNW1wChcninxr5gyavFqbbDjdV1aK5USJz8NDVjUy7FRQaaqqXHh5SbXe/<2;3>/*),older(52560))))").unwrap();
+        let policy = roundtrip(&desc);
+        assert_eq!(
+            policy.template,
+            "wsh(or_d(pk(@0/**),and_v(v:pkh(@1/<2;3>/*),older(52560))))"
+        );
+        assert_eq!(policy.keys_info, vec!["[abcdef01/48'/0'/0'/2']xpub6Eze7yAT3Y1wGrnzedCNVYDXUqa9NmHVWck5emBaTbXtURbe1NWZbK9bsz1TiVE7Cz341PMTfYgFw1KdLWdzcM1UMFTcdQfCYhhXZ2HJvTW".to_string(), "[01234567]xpub688Hn4wScQAAiYJLPg9yH27hUpfZAUnmJejRQBCiwfP5PEDzjWMNW1wChcninxr5gyavFqbbDjdV1aK5USJz8NDVjUy7FRQaaqqXHh5SbXe".to_string()]);
+
+        // All the wallet shapes supported by the crate, under both P2WSH and Taproot.
+        let secp = secp256k1::Secp256k1::new();
+        let xpubs: Vec<_> = (0..5).map(|_| random_xpub(&secp)).collect();
+        let prim_path = PathInfo::Single(random_desc_key(&secp));
+        let multi_path = PathInfo::Multi(2, vec![random_desc_key(&secp), random_desc_key(&secp)]);
+        let rec_paths: BTreeMap<_, _> = [
+            (26280, PathInfo::Single(random_desc_key(&secp))),
+            (
+                52560,
+                PathInfo::Multi(1, vec![random_desc_key(&secp), random_desc_key(&secp)]),
+            ),
+        ]
+        .iter()
+        .cloned()
+        .collect();
+        for is_taproot in [false, true] {
+            let new_policy = |prim: PathInfo, rec: BTreeMap<u16, PathInfo>| {
+                if is_taproot {
+                    LianaPolicy::new(prim, rec).unwrap()
+                } else {
+                    LianaPolicy::new_legacy(prim, rec).unwrap()
+                }
+            };
+            let rec_conds: BTreeMap<_, _> = rec_paths
+                .iter()
+                .map(|(tl, path)| (PathCondition::RelativeBlocks(*tl), path.clone()))
+                .collect();
+            let policies = vec![
+                new_policy(
+                    prim_path.clone(),
+                    rec_paths
+                        .iter()
+                        .take(1)
+                        .map(|(k, v)| (*k, v.clone()))
+                        .collect(),
+                ),
+                new_policy(multi_path.clone(), rec_paths.clone()),
+                LianaPolicy::from_conditional_paths(
+                    prim_path.clone(),
+                    rec_conds
+                        .clone()
+                        .into_iter()
+                        .chain([(
+                            PathCondition::HashLock(HashLock::sha256(&[1; 32])),
+                            PathInfo::Single(random_desc_key(&secp)),
+                        )])
+                        .collect(),
+                    is_taproot,
+                )
+                .unwrap(),
+                LianaPolicy::from_conditional_paths(
+                    prim_path.clone(),
+                    rec_conds
+                        .clone()
+                        .into_iter()
+                        .chain([
+                            (
+                                PathCondition::RelativeTime(5063),
+                                PathInfo::Single(random_desc_key(&secp)),
+                            ),
+                            (
+                                PathCondition::Absolute(
+                                    AbsLockTime::from_consensus(1_800_000_000).unwrap(),
+                                ),
+                                PathInfo::Single(random_desc_key(&secp)),
+                            ),
+                        ])
+                        .collect(),
+                    is_taproot,
+                )
+                .unwrap(),
+                LianaPolicy::new_decaying_multisig(
+                    xpubs.clone(),
+                    3,
+                    [(26280, 2), (52560, 1)].iter().cloned().collect(),
+                    is_taproot,
+                )
+                .unwrap(),
+                LianaPolicy::new_expanding_multisig(
+                    xpubs[..2].to_vec(),
+                    2,
+                    [(26280, (2, xpubs[2..].to_vec()))]
+                        .iter()
+                        .cloned()
+                        .collect(),
+                    is_taproot,
+                )
+                .unwrap(),
+            ];
+            for policy in policies {
+                let desc = LianaDescriptor::new(policy);
+                let wallet_policy = roundtrip(&desc);
+                assert_eq!(wallet_policy.template.starts_with("tr("), is_taproot);
+            }
+
+            // The same xpub used in several spending paths has a single key information.
+            let desc = LianaDescriptor::new(
+                LianaPolicy::new_decaying_multisig(
+                    xpubs.clone(),
+                    3,
+                    [(26280, 2)].iter().cloned().collect(),
+                    is_taproot,
+                )
+                .unwrap(),
+            );
+            let wallet_policy = roundtrip(&desc);
+            // Under Taproot the unspendable internal key is also part of the key information.
+            assert_eq!(
+                wallet_policy.keys_info.len(),
+                if is_taproot { 6 } else { 5 }
+            );
+            assert!(wallet_policy.template.contains("@0/**"));
+            assert!(wallet_policy.template.contains("@0/<2;3>/*"));
+        }
+
+        // Invalid wallet policies.
+        let mut invalid = policy.clone();
+        invalid.keys_info.pop();
+        assert!(matches!(
+            LianaDescriptor::from_wallet_policy(&invalid),
+            Err(WalletPolicyError::MissingKeyInfo(1))
+        ));
+        let mut invalid = policy.clone();
+        invalid.keys_info.push(invalid.keys_info[0].clone());
+        assert!(matches!(
+            LianaDescriptor::from_wallet_policy(&invalid),
+            Err(WalletPolicyError::UnusedKeyInfo(2))
+        ));
+        let mut invalid = policy.clone();
+        invalid.template = invalid.template.replace("@0/**", "@0/0/*");
+        assert!(matches!(
+            LianaDescriptor::from_wallet_policy(&invalid),
+            Err(WalletPolicyError::InvalidPlaceholder(_))
+        ));
+        let mut invalid = policy.clone();
+        invalid.keys_info[0].push_str("/<0;1>/*");
+        assert!(matches!(
+            LianaDescriptor::from_wallet_policy(&invalid),
+            Err(WalletPolicyError::InvalidKeyInfo(_))
+        ));
+        // A valid wallet policy, but not a Liana one.
+        let mut invalid = policy;
+        invalid.template = "wsh(multi(2,@0/**,@1/**))".to_string();
+        assert!(matches!(
+            LianaDescriptor::from_wallet_policy(&invalid),
+            Err(WalletPolicyError::Descriptor(_))
+        ));
+    }
+
     #[test]
     fn unliftable_miniscript() {
         LianaDescriptor::from_str("wsh(0)").unwrap_err();
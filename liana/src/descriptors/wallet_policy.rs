@@ -0,0 +1,258 @@
+//! Conversion of a Liana descriptor to and from a BIP-388 wallet policy.
+//!
+//! A wallet policy is a descriptor template, where each key expression is replaced by a `@i`
+//! placeholder followed by its derivation steps, along with the vector of the key information (the
+//! xpubs along with their origin) the placeholders refer to. This is the format in which hardware
+//! signers register descriptors.
+//! See https://github.com/bitcoin/bips/blob/master/bip-0388.mediawiki.
+
+use crate::descriptors::{LianaDescError, LianaDescriptor};
+
+use miniscript::{
+    bitcoin::{bip32, hashes},
+    descriptor, hash256, TranslatePk, Translator,
+};
+
+use std::{collections::HashSet, error, fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug)]
+pub enum WalletPolicyError {
+    /// The descriptor contains a key which can't be expressed in a wallet policy.
+    UnsupportedKey(Box<descriptor::DescriptorPublicKey>),
+    /// The descriptor template could not be parsed.
+    InvalidTemplate(miniscript::Error),
+    InvalidPlaceholder(String),
+    /// A placeholder refers to a key information which is not in the vector.
+    MissingKeyInfo(usize),
+    /// A key information in the vector is never referred to in the template.
+    UnusedKeyInfo(usize),
+    InvalidKeyInfo(String),
+    Descriptor(LianaDescError),
+}
+
+impl fmt::Display for WalletPolicyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::UnsupportedKey(key) => write!(
+                f,
+                "Key '{}' can't be expressed in a wallet policy. Keys must be xpubs with a single derivation step before the wildcard.",
+                key
+            ),
+            Self::InvalidTemplate(e) => write!(f, "Invalid descriptor template: '{}'.", e),
+            Self::InvalidPlaceholder(s) => write!(
+                f,
+                "Invalid key placeholder '{}'. Must be of the form '@i/**' or '@i/<m;n>/*'.",
+                s
+            ),
+            Self::MissingKeyInfo(i) => write!(f, "No key information at index {}.", i),
+            Self::UnusedKeyInfo(i) => write!(
+                f,
+                "Key information at index {} is not used in the descriptor template.",
+                i
+            ),
+            Self::InvalidKeyInfo(s) => write!(f, "Invalid key information '{}'.", s),
+            Self::Descriptor(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl error::Error for WalletPolicyError {}
+
+impl From<LianaDescError> for WalletPolicyError {
+    fn from(e: LianaDescError) -> WalletPolicyError {
+        WalletPolicyError::Descriptor(e)
+    }
+}
+
+/// A BIP-388 wallet policy: a descriptor template along with the key information vector.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WalletPolicy {
+    /// The descriptor template, for instance `wsh(or_d(pk(@0/**),and_v(v:pkh(@1/**),older(52560))))`.
+    pub template: String,
+    /// The key information (xpub with its origin, if any) each placeholder refers to.
+    pub keys_info: Vec<String>,
+}
+
+// Get the key information and the multipath derivation steps for a key in a Liana descriptor.
+fn key_info(
+    key: &descriptor::DescriptorPublicKey,
+) -> Result<(String, [u32; 2]), WalletPolicyError> {
+    if let descriptor::DescriptorPublicKey::MultiXPub(xpub) = key {
+        let paths = xpub.derivation_paths.paths();
+        if let [recv, change] = &paths[..] {
+            if let (
+                [bip32::ChildNumber::Normal { index: recv }],
+                [bip32::ChildNumber::Normal { index: change }],
+            ) = (recv.as_ref(), change.as_ref())
+            {
+                if xpub.wildcard == descriptor::Wildcard::Unhardened {
+                    let origin = match xpub.origin {
+                        Some((fg, ref path)) if path.is_master() => format!("[{}]", fg),
+                        Some((fg, ref path)) => format!("[{}/{}]", fg, path),
+                        None => String::new(),
+                    };
+                    return Ok((format!("{}{}", origin, xpub.xkey), [*recv, *change]));
+                }
+            }
+        }
+    }
+    Err(WalletPolicyError::UnsupportedKey(key.clone().into()))
+}
+
+// Replaces the keys in a descriptor with placeholders, recording the key information vector.
+struct ToTemplate {
+    keys_info: Vec<String>,
+}
+
+impl Translator<descriptor::DescriptorPublicKey, String, WalletPolicyError> for ToTemplate {
+    fn pk(&mut self, pk: &descriptor::DescriptorPublicKey) -> Result<String, WalletPolicyError> {
+        let (info, [recv, change]) = key_info(pk)?;
+        // The same xpub may appear with different derivation steps. It is the same key information.
+        let index = self
+            .keys_info
+            .iter()
+            .position(|i| *i == info)
+            .unwrap_or_else(|| {
+                self.keys_info.push(info);
+                self.keys_info.len() - 1
+            });
+        Ok(if (recv, change) == (0, 1) {
+            format!("@{}/**", index)
+        } else {
+            format!("@{}/<{};{}>/*", index, recv, change)
+        })
+    }
+
+    fn sha256(&mut self, sha256: &hashes::sha256::Hash) -> Result<String, WalletPolicyError> {
+        Ok(sha256.to_string())
+    }
+
+    fn hash256(&mut self, hash256: &hash256::Hash) -> Result<String, WalletPolicyError> {
+        Ok(hash256.to_string())
+    }
+
+    fn ripemd160(
+        &mut self,
+        ripemd160: &hashes::ripemd160::Hash,
+    ) -> Result<String, WalletPolicyError> {
+        Ok(ripemd160.to_string())
+    }
+
+    fn hash160(&mut self, hash160: &hashes::hash160::Hash) -> Result<String, WalletPolicyError> {
+        Ok(hash160.to_string())
+    }
+}
+
+// Replaces the placeholders in a descriptor template with the keys they refer to.
+struct FromTemplate<'a> {
+    keys_info: &'a [String],
+    used: HashSet<usize>,
+}
+
+impl<'a> Translator<String, descriptor::DescriptorPublicKey, WalletPolicyError>
+    for FromTemplate<'a>
+{
+    fn pk(&mut self, pk: &String) -> Result<descriptor::DescriptorPublicKey, WalletPolicyError> {
+        let invalid = || WalletPolicyError::InvalidPlaceholder(pk.clone());
+        let (index, steps) = pk
+            .strip_prefix('@')
+            .and_then(|s| s.split_once('/'))
+            .ok_or_else(invalid)?;
+        let index: usize = index.parse().map_err(|_| invalid())?;
+        let steps = match steps {
+            "**" => "<0;1>/*",
+            s if s.starts_with('<') && s.ends_with(">/*") => s,
+            _ => return Err(invalid()),
+        };
+        let info = self
+            .keys_info
+            .get(index)
+            .ok_or(WalletPolicyError::MissingKeyInfo(index))?;
+        // The key information must be an xpub, not a full key expression.
+        if info.contains("/<") || info.ends_with('*') {
+            return Err(WalletPolicyError::InvalidKeyInfo(info.clone()));
+        }
+        self.used.insert(index);
+        descriptor::DescriptorPublicKey::from_str(&format!("{}/{}", info, steps))
+            .map_err(|_| WalletPolicyError::InvalidKeyInfo(info.clone()))
+    }
+
+    fn sha256(&mut self, sha256: &String) -> Result<hashes::sha256::Hash, WalletPolicyError> {
+        hashes::sha256::Hash::from_str(sha256).map_err(|_| {
+            WalletPolicyError::InvalidTemplate(miniscript::Error::Unexpected(sha256.clone()))
+        })
+    }
+
+    fn hash256(&mut self, hash256: &String) -> Result<hash256::Hash, WalletPolicyError> {
+        hash256::Hash::from_str(hash256).map_err(|_| {
+            WalletPolicyError::InvalidTemplate(miniscript::Error::Unexpected(hash256.clone()))
+        })
+    }
+
+    fn ripemd160(
+        &mut self,
+        ripemd160: &String,
+    ) -> Result<hashes::ripemd160::Hash, WalletPolicyError> {
+        hashes::ripemd160::Hash::from_str(ripemd160).map_err(|_| {
+            WalletPolicyError::InvalidTemplate(miniscript::Error::Unexpected(ripemd160.clone()))
+        })
+    }
+
+    fn hash160(&mut self, hash160: &String) -> Result<hashes::hash160::Hash, WalletPolicyError> {
+        hashes::hash160::Hash::from_str(hash160).map_err(|_| {
+            WalletPolicyError::InvalidTemplate(miniscript::Error::Unexpected(hash160.clone()))
+        })
+    }
+}
+
+// Map a translation error to our error type.
+fn translate_err(e: miniscript::TranslateErr<WalletPolicyError>) -> WalletPolicyError {
+    match e {
+        miniscript::TranslateErr::TranslatorErr(e) => e,
+        miniscript::TranslateErr::OuterError(e) => WalletPolicyError::InvalidTemplate(e),
+    }
+}
+
+impl LianaDescriptor {
+    /// Get the BIP-388 wallet policy for this descriptor. The key information vector is ordered
+    /// by first appearance of the keys in the descriptor.
+    pub fn to_wallet_policy(&self) -> Result<WalletPolicy, WalletPolicyError> {
+        let mut translator = ToTemplate {
+            keys_info: Vec::new(),
+        };
+        let template = self
+            .multi_desc
+            .translate_pk(&mut translator)
+            .map_err(translate_err)?
+            .to_string();
+        // Wallet policies don't have a checksum.
+        let template = template
+            .split_once('#')
+            .map(|(t, _)| t.to_string())
+            .unwrap_or(template);
+        Ok(WalletPolicy {
+            template,
+            keys_info: translator.keys_info,
+        })
+    }
+
+    /// Parse a Liana descriptor from a BIP-388 wallet policy. This performs the same checks as
+    /// when parsing a descriptor string.
+    pub fn from_wallet_policy(policy: &WalletPolicy) -> Result<LianaDescriptor, WalletPolicyError> {
+        let template = descriptor::Descriptor::<String>::from_str(&policy.template)
+            .map_err(WalletPolicyError::InvalidTemplate)?;
+        let mut translator = FromTemplate {
+            keys_info: &policy.keys_info,
+            used: HashSet::with_capacity(policy.keys_info.len()),
+        };
+        let desc = template
+            .translate_pk(&mut translator)
+            .map_err(translate_err)?;
+        if let Some(unused) = (0..policy.keys_info.len()).find(|i| !translator.used.contains(i)) {
+            return Err(WalletPolicyError::UnusedKeyInfo(unused));
+        }
+        Ok(LianaDescriptor::from_str(&desc.to_string())?)
+    }
+}
@@ -1,6 +1,11 @@
+pub mod batch;
+pub mod bsms;
 pub mod descriptors;
+pub mod psbt_analysis;
+pub mod psbt_v2;
 pub mod random;
 pub mod signer;
+pub mod slip39;
 pub mod spend;
 
 pub use bip39;
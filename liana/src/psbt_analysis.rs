@@ -0,0 +1,882 @@
+//! PSBT analysis.
+//!
+//! Inspect a PSBT, possibly created by another coordinator, from the point of view of a Liana
+//! wallet: which inputs are ours and through which spending paths they can be spent, which outputs
+//! pay back to us, how much fees are paid and which signatures are still missing.
+
+use crate::{
+    descriptors::{ChangeOutput, LianaDescriptor, PathCondition, PathInfo, PathSpendInfo},
+    spend::{Feerate, DUST_OUTPUT_SATS, MAX_FEE, MAX_FEERATE, MIN_FEERATE},
+};
+
+use std::{collections::HashMap, convert::TryInto, fmt};
+
+use miniscript::bitcoin::{
+    self, bip32,
+    constants::WITNESS_SCALE_FACTOR,
+    psbt::{Input as PsbtIn, Psbt},
+    secp256k1,
+};
+use serde::Serialize;
+
+/// Information about a coin of our wallet, as known by the caller.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KnownCoin {
+    pub amount: bitcoin::Amount,
+    pub derivation_index: bip32::ChildNumber,
+    pub is_change: bool,
+    /// The height of the block this coin was confirmed in, if it is confirmed.
+    pub block_height: Option<u32>,
+    /// The median time past of the block preceding the one this coin was confirmed in, against
+    /// which its time-based relative timelocks are measured.
+    pub block_prev_mtp: Option<u32>,
+}
+
+/// The information available to the caller to analyze a PSBT. Anything which is not known may
+/// be left empty, in which case the report will contain less information.
+#[derive(Debug, Clone)]
+pub struct AnalysisContext {
+    /// The network, used to display the output addresses.
+    pub network: bitcoin::Network,
+    /// The height of the current chain tip.
+    pub tip_height: Option<u32>,
+    /// The median time past of the current chain tip.
+    pub tip_mtp: Option<u32>,
+    /// The coins of our wallet we know about, which may be spent by the PSBT.
+    pub coins: HashMap<bitcoin::OutPoint, KnownCoin>,
+    /// Human-readable names for the keys of the descriptor, by master key fingerprint.
+    pub key_aliases: HashMap<bip32::Fingerprint, String>,
+}
+
+impl AnalysisContext {
+    /// A context without any information about the chain or our coins.
+    pub fn new(network: bitcoin::Network) -> Self {
+        Self {
+            network,
+            tip_height: None,
+            tip_mtp: None,
+            coins: HashMap::new(),
+            key_aliases: HashMap::new(),
+        }
+    }
+}
+
+/// A spending path of the descriptor.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SpendingPath {
+    Primary,
+    /// A recovery path behind a relative timelock of this many blocks.
+    Recovery {
+        blocks: u16,
+    },
+    /// A recovery path behind a relative timelock of this many 512-seconds units.
+    TimeRecovery {
+        time_units: u16,
+    },
+    /// A recovery path behind this absolute timelock (a height or a UNIX timestamp).
+    AbsoluteRecovery {
+        locktime: u32,
+    },
+    /// A path requiring to reveal the preimage of this hash.
+    Hashlock {
+        hash: String,
+    },
+}
+
+/// Whether the timelock of a spending path is matured, that is whether the transaction would be
+/// valid in the next block as far as this timelock is concerned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum TimelockStatus {
+    /// This path isn't timelocked.
+    NoTimelock,
+    Mature,
+    /// The transaction can be included in a block at the earliest in this many blocks.
+    ImmatureBlocks {
+        remaining: u32,
+    },
+    /// The transaction can be included in a block at the earliest in this many seconds.
+    ImmatureTime {
+        remaining_seconds: u32,
+    },
+    /// Not enough information to determine whether the timelock is matured.
+    Unknown,
+}
+
+/// A key which is part of a spending path.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct KeyReport {
+    pub fingerprint: bip32::Fingerprint,
+    pub alias: Option<String>,
+}
+
+/// The state of a spending path usable by an input of the transaction.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SpendingPathReport {
+    pub path: SpendingPath,
+    /// The number of signatures required to spend through this path.
+    pub threshold: usize,
+    /// The number of signatures provided for this path.
+    pub sigs_count: usize,
+    /// The number of additional signatures needed to reach the threshold.
+    pub missing_signatures: usize,
+    /// The keys of this path which did not sign yet.
+    pub unsigned_keys: Vec<KeyReport>,
+    pub timelock: TimelockStatus,
+    /// For hash-locked paths, whether the preimage was provided.
+    pub has_preimage: Option<bool>,
+}
+
+/// Information about an input spending one of our coins.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct OwnedInputReport {
+    pub derivation_index: u32,
+    pub is_change: bool,
+    /// The spending paths the transaction can use for this input given its nSequence and
+    /// nLockTime.
+    pub spending_paths: Vec<SpendingPathReport>,
+}
+
+/// Information about an input of the transaction.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct InputReport {
+    pub outpoint: bitcoin::OutPoint,
+    /// The value of the coin spent, if known.
+    #[serde(with = "bitcoin::amount::serde::as_sat::opt")]
+    pub value: Option<bitcoin::Amount>,
+    pub sequence: u32,
+    /// Set if this input spends one of our coins.
+    pub ours: Option<OwnedInputReport>,
+}
+
+/// To whom an output of the transaction pays.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputKind {
+    /// An address of the change keychain of our wallet.
+    Change,
+    /// A receive address of our wallet.
+    Receive,
+    External,
+}
+
+/// Information about an output of the transaction.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct OutputReport {
+    #[serde(with = "bitcoin::amount::serde::as_sat")]
+    pub value: bitcoin::Amount,
+    /// The address this output pays to, if it has one.
+    pub address: Option<String>,
+    pub kind: OutputKind,
+}
+
+/// A potential issue with the PSBT.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PsbtWarning {
+    /// The input at this index does not spend a coin from our wallet.
+    ForeignInput(usize),
+    /// The value of the coin spent by the input at this index is unknown.
+    MissingInputValue(usize),
+    /// The coin value the PSBT input at this index commits to differs from our records.
+    InputValueMismatch(usize),
+    /// The previous transaction set in the PSBT input at this index is not the one it spends.
+    InvalidPreviousTransaction(usize),
+    /// The outputs are worth more than the inputs.
+    NegativeFee,
+    /// The fee is larger than the maximum we would use ourselves.
+    InsaneFee,
+    /// The feerate is lower than the minimum relay feerate.
+    FeerateTooLow,
+    /// The output at this index is below the dust threshold.
+    DustOutput(usize),
+}
+
+impl fmt::Display for PsbtWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::ForeignInput(i) => write!(f, "Input #{} is not from this wallet.", i),
+            Self::MissingInputValue(i) => write!(
+                f,
+                "The value of the coin spent by input #{} is unknown. The fee can't be computed.",
+                i
+            ),
+            Self::InputValueMismatch(i) => write!(
+                f,
+                "The value of the coin spent by input #{} differs from the wallet's records.",
+                i
+            ),
+            Self::InvalidPreviousTransaction(i) => write!(
+                f,
+                "The previous transaction of input #{} is not the one it spends from.",
+                i
+            ),
+            Self::NegativeFee => write!(f, "The outputs are worth more than the inputs."),
+            Self::InsaneFee => write!(
+                f,
+                "The transaction pays more than {} or more than {} sat/vb in fees.",
+                MAX_FEE, MAX_FEERATE
+            ),
+            Self::FeerateTooLow => write!(
+                f,
+                "The feerate is below the minimum of {} sat/vb. The transaction won't be relayed.",
+                MIN_FEERATE
+            ),
+            Self::DustOutput(i) => write!(
+                f,
+                "Output #{} is worth less than {} sats.",
+                i, DUST_OUTPUT_SATS
+            ),
+        }
+    }
+}
+
+/// A report about a PSBT from the point of view of our wallet.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PsbtReport {
+    pub txid: bitcoin::Txid,
+    pub inputs: Vec<InputReport>,
+    pub outputs: Vec<OutputReport>,
+    /// The absolute fee, if the value of all the inputs is known.
+    #[serde(with = "bitcoin::amount::serde::as_sat::opt")]
+    pub fee: Option<bitcoin::Amount>,
+    /// The maximum size of the transaction once satisfied, if the satisfaction size of all the
+    /// inputs is known.
+    pub max_vbytes: Option<u64>,
+    /// The feerate for the maximum size of the transaction. The actual feerate may be higher.
+    pub feerate: Option<Feerate>,
+    /// Whether the transaction signals for replaceability (BIP125).
+    pub signals_rbf: bool,
+    /// Serialized as human-readable messages.
+    #[serde(serialize_with = "ser_to_strings")]
+    pub warnings: Vec<PsbtWarning>,
+}
+
+fn ser_to_strings<T: fmt::Display, S: serde::Serializer>(
+    items: &[T],
+    s: S,
+) -> Result<S::Ok, S::Error> {
+    s.collect_seq(items.iter().map(|item| item.to_string()))
+}
+
+// The value of the coin spent by this input according to the PSBT.
+fn psbt_input_value(psbt_in: &PsbtIn, txin: &bitcoin::TxIn) -> Option<bitcoin::Amount> {
+    psbt_in
+        .witness_utxo
+        .as_ref()
+        .map(|txo| txo.value)
+        .or_else(|| {
+            psbt_in
+                .non_witness_utxo
+                .as_ref()
+                .and_then(|tx| tx.output.get(txin.previous_output.vout as usize))
+                .map(|txo| txo.value)
+        })
+}
+
+// The script of the coin spent by this input according to the PSBT.
+fn psbt_input_spk(psbt_in: &PsbtIn, txin: &bitcoin::TxIn) -> Option<bitcoin::ScriptBuf> {
+    psbt_in
+        .witness_utxo
+        .as_ref()
+        .map(|txo| txo.script_pubkey.clone())
+        .or_else(|| {
+            psbt_in
+                .non_witness_utxo
+                .as_ref()
+                .and_then(|tx| tx.output.get(txin.previous_output.vout as usize))
+                .map(|txo| txo.script_pubkey.clone())
+        })
+}
+
+// Find out whether this input spends a coin from our descriptor using the BIP32 derivations in the
+// PSBT input. Returns the derivation index and whether it's for the change keychain.
+fn owned_by_derivation(
+    desc: &LianaDescriptor,
+    psbt_in: &PsbtIn,
+    txin: &bitcoin::TxIn,
+    secp: &secp256k1::Secp256k1<impl secp256k1::Verification>,
+) -> Option<(bip32::ChildNumber, bool)> {
+    let spk = psbt_input_spk(psbt_in, txin)?;
+    // Like for change detection, all the keys use the same last derivation index. Just try the
+    // first one.
+    let der_index = psbt_in
+        .bip32_derivation
+        .values()
+        .map(|(_, der_path)| der_path)
+        .chain(
+            psbt_in
+                .tap_key_origins
+                .values()
+                .map(|(_, (_, der_path))| der_path),
+        )
+        .next()
+        .and_then(|der_path| der_path.into_iter().last())
+        .filter(|index| index.is_normal())?;
+    if desc
+        .receive_descriptor()
+        .derive(*der_index, secp)
+        .script_pubkey()
+        == spk
+    {
+        Some((*der_index, false))
+    } else if desc
+        .change_descriptor()
+        .derive(*der_index, secp)
+        .script_pubkey()
+        == spk
+    {
+        Some((*der_index, true))
+    } else {
+        None
+    }
+}
+
+// The keys of this path which haven't signed.
+fn unsigned_keys(
+    path_info: &PathInfo,
+    spend_info: &PathSpendInfo,
+    key_aliases: &HashMap<bip32::Fingerprint, String>,
+) -> Vec<KeyReport> {
+    let (_, origins) = path_info.thresh_origins();
+    let mut keys: Vec<_> = origins
+        .iter()
+        .filter(|(fg, der_paths)| {
+            spend_info.signed_pubkeys.get(*fg).copied().unwrap_or(0) < der_paths.len()
+        })
+        .map(|(fg, _)| KeyReport {
+            fingerprint: *fg,
+            alias: key_aliases.get(fg).cloned(),
+        })
+        .collect();
+    keys.sort_by_key(|k| k.fingerprint);
+    keys
+}
+
+fn path_report(
+    path: SpendingPath,
+    path_info: &PathInfo,
+    spend_info: &PathSpendInfo,
+    timelock: TimelockStatus,
+    has_preimage: Option<bool>,
+    key_aliases: &HashMap<bip32::Fingerprint, String>,
+) -> SpendingPathReport {
+    SpendingPathReport {
+        path,
+        threshold: spend_info.threshold,
+        sigs_count: spend_info.sigs_count,
+        missing_signatures: spend_info.threshold.saturating_sub(spend_info.sigs_count),
+        unsigned_keys: unsigned_keys(path_info, spend_info, key_aliases),
+        timelock,
+        has_preimage,
+    }
+}
+
+// The status of a relative timelock of this many blocks for this coin.
+fn rel_height_status(
+    blocks: u16,
+    coin: Option<&KnownCoin>,
+    tip_height: Option<u32>,
+) -> TimelockStatus {
+    let coin = match coin {
+        Some(coin) => coin,
+        None => return TimelockStatus::Unknown,
+    };
+    match (coin.block_height, tip_height) {
+        // The coin isn't confirmed yet, it will take at least this many blocks once it is.
+        (None, _) => TimelockStatus::ImmatureBlocks {
+            remaining: blocks.into(),
+        },
+        (Some(coin_height), Some(tip_height)) => {
+            // We are interested in whether it can be included in the *next* block.
+            let mature_height = coin_height + u32::from(blocks);
+            if tip_height + 1 >= mature_height {
+                TimelockStatus::Mature
+            } else {
+                TimelockStatus::ImmatureBlocks {
+                    remaining: mature_height - tip_height - 1,
+                }
+            }
+        }
+        (Some(_), None) => TimelockStatus::Unknown,
+    }
+}
+
+// The status of a relative timelock of this many 512-seconds units for this coin.
+fn rel_time_status(
+    time_units: u16,
+    coin: Option<&KnownCoin>,
+    tip_mtp: Option<u32>,
+) -> TimelockStatus {
+    // As per BIP68, the coin's age is measured from the median time past of the block preceding
+    // the one it was confirmed in.
+    match (coin.and_then(|c| c.block_prev_mtp), tip_mtp) {
+        (Some(coin_mtp), Some(tip_mtp)) => {
+            let mature_time = coin_mtp + u32::from(time_units) * 512;
+            if mature_time <= tip_mtp {
+                TimelockStatus::Mature
+            } else {
+                TimelockStatus::ImmatureTime {
+                    remaining_seconds: mature_time - tip_mtp,
+                }
+            }
+        }
+        _ => TimelockStatus::Unknown,
+    }
+}
+
+// The status of an absolute timelock, given the current chain state.
+fn abs_status(
+    locktime: bitcoin::absolute::LockTime,
+    tip_height: Option<u32>,
+    tip_mtp: Option<u32>,
+) -> TimelockStatus {
+    let value = locktime.to_consensus_u32();
+    // For the transaction to be valid in the next block, a height must be at most the current
+    // height and a time must be strictly lower than the median time past of the current tip.
+    match locktime {
+        bitcoin::absolute::LockTime::Blocks(_) => match tip_height {
+            Some(tip) if value <= tip => TimelockStatus::Mature,
+            Some(tip) => TimelockStatus::ImmatureBlocks {
+                remaining: value - tip,
+            },
+            None => TimelockStatus::Unknown,
+        },
+        bitcoin::absolute::LockTime::Seconds(_) => match tip_mtp {
+            Some(mtp) if value < mtp => TimelockStatus::Mature,
+            Some(mtp) => TimelockStatus::ImmatureTime {
+                remaining_seconds: value - mtp + 1,
+            },
+            None => TimelockStatus::Unknown,
+        },
+    }
+}
+
+// The spending paths usable by this input along with their state.
+fn spending_paths(
+    desc: &LianaDescriptor,
+    psbt_in: &PsbtIn,
+    txin: &bitcoin::TxIn,
+    lock_time: bitcoin::absolute::LockTime,
+    coin: Option<&KnownCoin>,
+    ctx: &AnalysisContext,
+) -> Vec<SpendingPathReport> {
+    let policy = desc.policy();
+    let spend_info = desc.partial_spend_info_txin(psbt_in, txin, lock_time);
+    let aliases = &ctx.key_aliases;
+
+    let mut paths = vec![path_report(
+        SpendingPath::Primary,
+        policy.primary_path(),
+        spend_info.primary_path(),
+        TimelockStatus::NoTimelock,
+        None,
+        aliases,
+    )];
+    for (condition, info) in spend_info.conditional_paths() {
+        let (path, timelock, has_preimage) = match condition {
+            PathCondition::RelativeBlocks(blocks) => (
+                SpendingPath::Recovery { blocks: *blocks },
+                rel_height_status(*blocks, coin, ctx.tip_height),
+                None,
+            ),
+            PathCondition::RelativeTime(time_units) => (
+                SpendingPath::TimeRecovery {
+                    time_units: *time_units,
+                },
+                rel_time_status(*time_units, coin, ctx.tip_mtp),
+                None,
+            ),
+            PathCondition::Absolute(abs_tl) => (
+                SpendingPath::AbsoluteRecovery {
+                    locktime: abs_tl.to_consensus_u32(),
+                },
+                // What matters is the transaction's nLockTime, which implies the path's timelock.
+                abs_status(lock_time, ctx.tip_height, ctx.tip_mtp),
+                None,
+            ),
+            PathCondition::HashLock(hashlock) => (
+                SpendingPath::Hashlock {
+                    hash: hashlock.to_string(),
+                },
+                TimelockStatus::NoTimelock,
+                Some(spend_info.has_preimage(hashlock)),
+            ),
+        };
+        paths.push(path_report(
+            path,
+            &policy.conditional_paths()[condition],
+            info,
+            timelock,
+            has_preimage,
+            aliases,
+        ));
+    }
+
+    paths
+}
+
+// The maximum weight the satisfaction of this input adds to the unsigned transaction, if known.
+fn input_sat_weight(
+    desc: &LianaDescriptor,
+    psbt_in: &PsbtIn,
+    ours: Option<&OwnedInputReport>,
+) -> Option<u64> {
+    if let Some(ref witness) = psbt_in.final_script_witness {
+        let script_sig_len = psbt_in
+            .final_script_sig
+            .as_ref()
+            .map(|s| s.len())
+            .unwrap_or(0);
+        return Some((witness.size() + script_sig_len * WITNESS_SCALE_FACTOR) as u64);
+    }
+    ours.map(|ours| {
+        // If no recovery path is available, the input can only be satisfied using the primary
+        // path. This is the size estimation we use when creating transactions.
+        let use_primary_path = ours.spending_paths.len() == 1;
+        desc.max_sat_weight(use_primary_path)
+            .try_into()
+            .expect("Sat weight must fit in u64.")
+    })
+}
+
+/// Analyze a PSBT, which may not have been created by this wallet, from the point of view of the
+/// wallet using the `desc` descriptor.
+///
+/// Inputs are detected as ours if they spend one of the coins in the context or if their BIP32
+/// derivations derive to the script of the coin they spend. Likewise outputs are detected as ours
+/// through their BIP32 derivations. Signatures are only accounted for keys with BIP32 derivations
+/// in the PSBT inputs, and are not checked.
+pub fn analyze_psbt(
+    desc: &LianaDescriptor,
+    psbt: &Psbt,
+    ctx: &AnalysisContext,
+    secp: &secp256k1::Secp256k1<impl secp256k1::Verification>,
+) -> PsbtReport {
+    let tx = &psbt.unsigned_tx;
+    let mut warnings = Vec::new();
+
+    let mut inputs = Vec::with_capacity(tx.input.len());
+    let mut input_sat_weights = Vec::with_capacity(tx.input.len());
+    let default_psbt_in = PsbtIn::default();
+    for (i, txin) in tx.input.iter().enumerate() {
+        let psbt_in = psbt.inputs.get(i).unwrap_or(&default_psbt_in);
+        if let Some(ref prev_tx) = psbt_in.non_witness_utxo {
+            if prev_tx.compute_txid() != txin.previous_output.txid {
+                warnings.push(PsbtWarning::InvalidPreviousTransaction(i));
+            }
+        }
+
+        let coin = ctx.coins.get(&txin.previous_output);
+        let psbt_value = psbt_input_value(psbt_in, txin);
+        let value = if let Some(coin) = coin {
+            if psbt_value.map(|v| v != coin.amount) == Some(true) {
+                warnings.push(PsbtWarning::InputValueMismatch(i));
+            }
+            Some(coin.amount)
+        } else {
+            psbt_value
+        };
+        if value.is_none() {
+            warnings.push(PsbtWarning::MissingInputValue(i));
+        }
+
+        let ownership = coin
+            .map(|c| (c.derivation_index, c.is_change))
+            .or_else(|| owned_by_derivation(desc, psbt_in, txin, secp));
+        let ours = ownership.map(|(der_index, is_change)| OwnedInputReport {
+            derivation_index: der_index.into(),
+            is_change,
+            spending_paths: spending_paths(desc, psbt_in, txin, tx.lock_time, coin, ctx),
+        });
+        if ours.is_none() {
+            warnings.push(PsbtWarning::ForeignInput(i));
+        }
+        input_sat_weights.push(input_sat_weight(desc, psbt_in, ours.as_ref()));
+
+        inputs.push(InputReport {
+            outpoint: txin.previous_output,
+            value,
+            sequence: txin.sequence.to_consensus_u32(),
+            ours,
+        });
+    }
+
+    let change_indexes = desc.change_indexes(psbt, secp);
+    let outputs = tx
+        .output
+        .iter()
+        .enumerate()
+        .map(|(i, txo)| {
+            let kind = match change_indexes.iter().find(|c| c.index() == i) {
+                Some(ChangeOutput::ChangeAddress { .. }) => OutputKind::Change,
+                Some(ChangeOutput::DepositAddress { .. }) => OutputKind::Receive,
+                None => OutputKind::External,
+            };
+            if !txo.script_pubkey.is_op_return() && txo.value.to_sat() < DUST_OUTPUT_SATS {
+                warnings.push(PsbtWarning::DustOutput(i));
+            }
+            OutputReport {
+                value: txo.value,
+                address: bitcoin::Address::from_script(&txo.script_pubkey, ctx.network)
+                    .ok()
+                    .map(|addr| addr.to_string()),
+                kind,
+            }
+        })
+        .collect();
+
+    let inputs_value = inputs
+        .iter()
+        .map(|input| input.value)
+        .sum::<Option<bitcoin::Amount>>();
+    let outputs_value: bitcoin::Amount = tx.output.iter().map(|txo| txo.value).sum();
+    let fee = inputs_value.and_then(|inputs_value| {
+        let fee = inputs_value.checked_sub(outputs_value);
+        if fee.is_none() {
+            warnings.push(PsbtWarning::NegativeFee);
+        }
+        fee
+    });
+
+    // The maximum size of the transaction once all inputs are satisfied. We need to account for
+    // the Segwit marker and flag, as the unsigned transaction doesn't have any witness.
+    let max_vbytes = input_sat_weights
+        .into_iter()
+        .sum::<Option<u64>>()
+        .map(|sat_weight| {
+            let marker_weight = if tx.input.is_empty() { 0 } else { 2 };
+            (tx.weight().to_wu() + sat_weight + marker_weight).div_ceil(WITNESS_SCALE_FACTOR as u64)
+        });
+    let feerate = fee
+        .zip(max_vbytes)
+        .and_then(|(fee, vbytes)| Feerate::from_fee(fee.to_sat(), vbytes));
+    if fee.map(|fee| fee > MAX_FEE) == Some(true) || feerate.map(|f| f > MAX_FEERATE) == Some(true)
+    {
+        warnings.push(PsbtWarning::InsaneFee);
+    }
+    if feerate.map(|f| f < MIN_FEERATE) == Some(true) {
+        warnings.push(PsbtWarning::FeerateTooLow);
+    }
+
+    PsbtReport {
+        txid: tx.compute_txid(),
+        inputs,
+        outputs,
+        fee,
+        max_vbytes,
+        feerate,
+        signals_rbf: tx.is_explicitly_rbf(),
+        warnings,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    use miniscript::bitcoin::{consensus, Sequence};
+
+    fn psbt_from_str(psbt_str: &str) -> Psbt {
+        Psbt::from_str(psbt_str).unwrap()
+    }
+
+    #[test]
+    fn analyze_psbt_p2wsh() {
+        let secp = secp256k1::Secp256k1::verification_only();
+        let desc = LianaDescriptor::from_str("wsh(or_d(pk([f5acc2fd]tpubD6NzVbkrYhZ4YgUx2ZLNt2rLYAMTdYysCRzKoLu2BeSHKvzqPaBDvf17GeBPnExUVPkuBpx4kniP964e2MxyzzazcXLptxLXModSVCVEV1T/<0;1>/*),and_v(v:pkh([8a64f2a9]tpubD6NzVbkrYhZ4WmzFjvQrp7sDa4ECUxTi9oby8K4FZkd3XCBtEdKwUiQyYJaxiJo5y42gyDWEczrFpozEjeLxMPxjf2WtkfcbpUdfvNnozWF/<0;1>/*),older(10))))#d72le4dr").unwrap();
+        let prim_fg = bip32::Fingerprint::from_str("f5acc2fd").unwrap();
+        let recov_fg = bip32::Fingerprint::from_str("8a64f2a9").unwrap();
+        let mut psbt = psbt_from_str("cHNidP8BAHECAAAAAUSHuliRtuCX1S6JxRuDRqDCKkWfKmWL5sV9ukZ/wzvfAAAAAAD9////AogTAAAAAAAAFgAUIxe7UY6LJ6y5mFBoWTOoVispDmdwFwAAAAAAABYAFKqO83TK+t/KdpAt21z2HGC7/Z2FAAAAAAABASsQJwAAAAAAACIAIIIySQjGCTeyx/rKUQx8qobjhJeNCiVCliBJPdyRX6XKAQVBIQI2cqWpc9UAW2gZt2WkKjvi8KoMCui00pRlL6wG32uKDKxzZHapFNYASzIYkEdH9bJz6nnqUG3uBB8kiK1asmgiBgI2cqWpc9UAW2gZt2WkKjvi8KoMCui00pRlL6wG32uKDAz1rML9AAAAAG8AAAAiBgMLcbOxsfLe6+3r1UcjQo77HY0As8OKE4l37yj0/qhIyQyKZPKpAAAAAG8AAAAAAAA=");
+        let mut ctx = AnalysisContext::new(bitcoin::Network::Testnet);
+        ctx.key_aliases.insert(prim_fg, "Alice".to_string());
+
+        // Without any knowledge of our coins, the input is detected as ours through its BIP32
+        // derivations. It can only be spent through the primary path, which misses Alice's
+        // signature. The outputs are worth more than the input.
+        let report = analyze_psbt(&desc, &psbt, &ctx, &secp);
+        assert_eq!(report.txid, psbt.unsigned_tx.compute_txid());
+        assert_eq!(report.inputs.len(), 1);
+        let ours = report.inputs[0].ours.as_ref().unwrap();
+        assert_eq!((ours.derivation_index, ours.is_change), (111, false));
+        assert_eq!(ours.spending_paths.len(), 1);
+        let primary = &ours.spending_paths[0];
+        assert_eq!(primary.path, SpendingPath::Primary);
+        assert_eq!((primary.threshold, primary.sigs_count), (1, 0));
+        assert_eq!(primary.missing_signatures, 1);
+        assert_eq!(
+            primary.unsigned_keys,
+            vec![KeyReport {
+                fingerprint: prim_fg,
+                alias: Some("Alice".to_string())
+            }]
+        );
+        assert_eq!(primary.timelock, TimelockStatus::NoTimelock);
+        assert_eq!(
+            report.inputs[0].value,
+            Some(bitcoin::Amount::from_sat(10_000))
+        );
+        assert!(report
+            .outputs
+            .iter()
+            .all(|o| o.kind == OutputKind::External && o.address.is_some()));
+        assert!(report.signals_rbf);
+        assert_eq!(report.fee, None);
+        assert!(report.max_vbytes.is_some());
+        assert_eq!(report.feerate, None);
+        assert_eq!(report.warnings, vec![PsbtWarning::NegativeFee]);
+
+        // Reduce the outputs so the transaction pays a sane fee. The fee is computed against the
+        // maximum satisfaction size.
+        psbt.unsigned_tx.output[1].value = bitcoin::Amount::from_sat(4_000);
+        let report = analyze_psbt(&desc, &psbt, &ctx, &secp);
+        assert_eq!(report.fee, Some(bitcoin::Amount::from_sat(1_000)));
+        let max_vbytes = report.max_vbytes.unwrap();
+        assert_eq!(report.feerate, Feerate::from_fee(1_000, max_vbytes));
+        assert_eq!(report.warnings, vec![PsbtWarning::DustOutput(1)]);
+        psbt.unsigned_tx.output[1].value = bitcoin::Amount::from_sat(6_000);
+
+        // Enable the recovery path. Without knowledge of the coin, we can't tell whether the
+        // timelock is matured.
+        psbt.unsigned_tx.input[0].sequence = Sequence::from_height(10);
+        let report = analyze_psbt(&desc, &psbt, &ctx, &secp);
+        let ours = report.inputs[0].ours.as_ref().unwrap();
+        assert_eq!(ours.spending_paths.len(), 2);
+        let recovery = &ours.spending_paths[1];
+        assert_eq!(recovery.path, SpendingPath::Recovery { blocks: 10 });
+        assert_eq!(recovery.timelock, TimelockStatus::Unknown);
+        assert_eq!(
+            recovery.unsigned_keys,
+            vec![KeyReport {
+                fingerprint: recov_fg,
+                alias: None
+            }]
+        );
+        // A relative timelock implies replaceability.
+        assert!(report.signals_rbf);
+
+        // Now tell it about the coin. It was confirmed at height 100. At height 105 the
+        // transaction may be included in a block in 4 blocks, and at height 109 in the next one.
+        // The value recorded for the coin takes precedence over the PSBT's.
+        let outpoint = psbt.unsigned_tx.input[0].previous_output;
+        ctx.coins.insert(
+            outpoint,
+            KnownCoin {
+                amount: bitcoin::Amount::from_sat(12_000),
+                derivation_index: 111.into(),
+                is_change: false,
+                block_height: Some(100),
+                block_prev_mtp: None,
+            },
+        );
+        ctx.tip_height = Some(105);
+        let report = analyze_psbt(&desc, &psbt, &ctx, &secp);
+        let ours = report.inputs[0].ours.as_ref().unwrap();
+        assert_eq!(
+            ours.spending_paths[1].timelock,
+            TimelockStatus::ImmatureBlocks { remaining: 4 }
+        );
+        assert_eq!(report.fee, Some(bitcoin::Amount::from_sat(1_000)));
+        assert_eq!(report.warnings, vec![PsbtWarning::InputValueMismatch(0)]);
+        ctx.tip_height = Some(109);
+        let report = analyze_psbt(&desc, &psbt, &ctx, &secp);
+        let ours = report.inputs[0].ours.as_ref().unwrap();
+        assert_eq!(ours.spending_paths[1].timelock, TimelockStatus::Mature);
+
+        // Unconfirmed coins are never mature.
+        ctx.coins.get_mut(&outpoint).unwrap().block_height = None;
+        let report = analyze_psbt(&desc, &psbt, &ctx, &secp);
+        let ours = report.inputs[0].ours.as_ref().unwrap();
+        assert_eq!(
+            ours.spending_paths[1].timelock,
+            TimelockStatus::ImmatureBlocks { remaining: 10 }
+        );
+
+        // An input which doesn't derive from our descriptor and which we don't know about is
+        // foreign. Its satisfaction size, and therefore the feerate, can't be known.
+        let mut foreign_psbt = psbt.clone();
+        foreign_psbt.unsigned_tx.input[0].previous_output.vout = 1;
+        foreign_psbt.inputs[0]
+            .witness_utxo
+            .as_mut()
+            .unwrap()
+            .script_pubkey = foreign_psbt.unsigned_tx.output[0].script_pubkey.clone();
+        let report = analyze_psbt(&desc, &foreign_psbt, &ctx, &secp);
+        assert!(report.inputs[0].ours.is_none());
+        assert_eq!(report.max_vbytes, None);
+        assert_eq!(report.feerate, None);
+        assert_eq!(
+            report.warnings,
+            vec![PsbtWarning::ForeignInput(0), PsbtWarning::NegativeFee]
+        );
+
+        // An invalid previous transaction is reported.
+        let prev_tx: bitcoin::Transaction =
+            consensus::deserialize(&consensus::serialize(&foreign_psbt.unsigned_tx)).unwrap();
+        psbt.inputs[0].non_witness_utxo = Some(prev_tx);
+        let report = analyze_psbt(&desc, &psbt, &ctx, &secp);
+        assert!(report
+            .warnings
+            .contains(&PsbtWarning::InvalidPreviousTransaction(0)));
+    }
+
+    #[test]
+    fn timelock_status() {
+        let coin = KnownCoin {
+            amount: bitcoin::Amount::from_sat(10_000),
+            derivation_index: 0.into(),
+            is_change: false,
+            block_height: Some(100),
+            block_prev_mtp: Some(1_000_000),
+        };
+        assert_eq!(
+            rel_height_status(10, None, Some(200)),
+            TimelockStatus::Unknown
+        );
+        assert_eq!(
+            rel_height_status(10, Some(&coin), None),
+            TimelockStatus::Unknown
+        );
+        assert_eq!(
+            rel_time_status(2, Some(&coin), Some(1_000_000)),
+            TimelockStatus::ImmatureTime {
+                remaining_seconds: 1024
+            }
+        );
+        assert_eq!(
+            rel_time_status(2, Some(&coin), Some(1_001_024)),
+            TimelockStatus::Mature
+        );
+        assert_eq!(
+            rel_time_status(2, None, Some(1_001_024)),
+            TimelockStatus::Unknown
+        );
+
+        let height_lock = bitcoin::absolute::LockTime::from_height(150).unwrap();
+        assert_eq!(
+            abs_status(height_lock, Some(140), None),
+            TimelockStatus::ImmatureBlocks { remaining: 10 }
+        );
+        assert_eq!(
+            abs_status(height_lock, Some(150), None),
+            TimelockStatus::Mature
+        );
+        assert_eq!(
+            abs_status(height_lock, None, Some(1)),
+            TimelockStatus::Unknown
+        );
+        let time_lock = bitcoin::absolute::LockTime::from_time(1_700_000_000).unwrap();
+        assert_eq!(
+            abs_status(time_lock, Some(150), Some(1_700_000_000)),
+            TimelockStatus::ImmatureTime {
+                remaining_seconds: 1
+            }
+        );
+        assert_eq!(
+            abs_status(time_lock, None, Some(1_700_000_001)),
+            TimelockStatus::Mature
+        );
+    }
+}
@@ -0,0 +1,920 @@
+//! PSBT version 2 (BIP-370) support.
+//!
+//! We work with version 0 PSBTs internally. This module converts PSBTs between the two versions
+//! when (de)serializing them, such as to accept and export version 2 PSBTs from and to signing
+//! devices and coordinators which only support this version.
+//!
+//! The conversion only rewrites the fields which differ between the two versions: the global
+//! unsigned transaction of version 0 PSBTs is replaced by per-input and per-output fields (and a
+//! couple global fields) in version 2 PSBTs. All the other fields are left untouched. Note the
+//! `PSBT_GLOBAL_TX_MODIFIABLE` flags of a version 2 PSBT are not preserved.
+
+use std::{
+    collections::BTreeMap,
+    convert::{TryFrom, TryInto},
+    error, fmt,
+};
+
+use miniscript::bitcoin::{
+    self,
+    base64::{engine::general_purpose::STANDARD as BASE64_STANDARD, Engine},
+    consensus,
+    hashes::Hash,
+    psbt::{raw, Psbt},
+};
+
+const PSBT_MAGIC: &[u8; 5] = b"psbt\xff";
+
+const PSBT_GLOBAL_UNSIGNED_TX: u8 = 0x00;
+const PSBT_GLOBAL_TX_VERSION: u8 = 0x02;
+const PSBT_GLOBAL_FALLBACK_LOCKTIME: u8 = 0x03;
+const PSBT_GLOBAL_INPUT_COUNT: u8 = 0x04;
+const PSBT_GLOBAL_OUTPUT_COUNT: u8 = 0x05;
+const PSBT_GLOBAL_TX_MODIFIABLE: u8 = 0x06;
+const PSBT_GLOBAL_VERSION: u8 = 0xfb;
+
+const PSBT_IN_PREVIOUS_TXID: u8 = 0x0e;
+const PSBT_IN_OUTPUT_INDEX: u8 = 0x0f;
+const PSBT_IN_SEQUENCE: u8 = 0x10;
+const PSBT_IN_REQUIRED_TIME_LOCKTIME: u8 = 0x11;
+const PSBT_IN_REQUIRED_HEIGHT_LOCKTIME: u8 = 0x12;
+
+const PSBT_OUT_AMOUNT: u8 = 0x03;
+const PSBT_OUT_SCRIPT: u8 = 0x04;
+
+const PSBT_GLOBAL_V2_FIELDS: &[(u8, &str)] = &[
+    (PSBT_GLOBAL_TX_VERSION, "PSBT_GLOBAL_TX_VERSION"),
+    (
+        PSBT_GLOBAL_FALLBACK_LOCKTIME,
+        "PSBT_GLOBAL_FALLBACK_LOCKTIME",
+    ),
+    (PSBT_GLOBAL_INPUT_COUNT, "PSBT_GLOBAL_INPUT_COUNT"),
+    (PSBT_GLOBAL_OUTPUT_COUNT, "PSBT_GLOBAL_OUTPUT_COUNT"),
+    (PSBT_GLOBAL_TX_MODIFIABLE, "PSBT_GLOBAL_TX_MODIFIABLE"),
+];
+const PSBT_IN_V2_FIELDS: &[(u8, &str)] = &[
+    (PSBT_IN_PREVIOUS_TXID, "PSBT_IN_PREVIOUS_TXID"),
+    (PSBT_IN_OUTPUT_INDEX, "PSBT_IN_OUTPUT_INDEX"),
+    (PSBT_IN_SEQUENCE, "PSBT_IN_SEQUENCE"),
+    (
+        PSBT_IN_REQUIRED_TIME_LOCKTIME,
+        "PSBT_IN_REQUIRED_TIME_LOCKTIME",
+    ),
+    (
+        PSBT_IN_REQUIRED_HEIGHT_LOCKTIME,
+        "PSBT_IN_REQUIRED_HEIGHT_LOCKTIME",
+    ),
+];
+const PSBT_OUT_V2_FIELDS: &[(u8, &str)] = &[
+    (PSBT_OUT_AMOUNT, "PSBT_OUT_AMOUNT"),
+    (PSBT_OUT_SCRIPT, "PSBT_OUT_SCRIPT"),
+];
+
+/// The version of a PSBT.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PsbtVersion {
+    /// BIP-174 PSBTs, with a global unsigned transaction.
+    #[default]
+    V0,
+    /// BIP-370 PSBTs, without a global unsigned transaction.
+    V2,
+}
+
+impl PsbtVersion {
+    /// Get the PSBT version from its number. Only versions 0 and 2 are supported.
+    pub fn from_number(version: u64) -> Option<Self> {
+        match version {
+            0 => Some(Self::V0),
+            2 => Some(Self::V2),
+            _ => None,
+        }
+    }
+
+    /// The number of this PSBT version.
+    pub fn number(&self) -> u32 {
+        match self {
+            Self::V0 => 0,
+            Self::V2 => 2,
+        }
+    }
+}
+
+impl fmt::Display for PsbtVersion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.number())
+    }
+}
+
+/// An error when converting a PSBT from or to version 2.
+#[derive(Debug)]
+pub enum PsbtV2Error {
+    Base64(String),
+    /// The PSBT isn't a valid serialization of key-value maps.
+    InvalidEncoding(&'static str),
+    UnsupportedVersion(u32),
+    MissingField(&'static str),
+    InvalidField(&'static str),
+    /// A field which must be excluded from PSBTs of this version is present.
+    ExcludedField(&'static str),
+    /// The input locktime requirements can't all be satisfied.
+    IncompatibleLocktimes,
+    Psbt(bitcoin::psbt::Error),
+}
+
+impl fmt::Display for PsbtV2Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Base64(e) => write!(f, "Invalid base64 encoding: {}", e),
+            Self::InvalidEncoding(s) => write!(f, "Invalid PSBT encoding: {}", s),
+            Self::UnsupportedVersion(v) => write!(f, "Unsupported PSBT version: {}", v),
+            Self::MissingField(s) => write!(f, "Missing required PSBT field: {}", s),
+            Self::InvalidField(s) => write!(f, "Invalid value for PSBT field: {}", s),
+            Self::ExcludedField(s) => {
+                write!(f, "PSBT field not allowed for this PSBT version: {}", s)
+            }
+            Self::IncompatibleLocktimes => {
+                write!(
+                    f,
+                    "The locktime requirements of the PSBT inputs are incompatible."
+                )
+            }
+            Self::Psbt(e) => write!(f, "Invalid PSBT: {}", e),
+        }
+    }
+}
+
+impl error::Error for PsbtV2Error {}
+
+// The key-value pairs of a PSBT map, in order.
+type KeyValueMap = Vec<(Vec<u8>, Vec<u8>)>;
+
+// A PSBT as its raw key-value maps.
+struct RawPsbt {
+    global: KeyValueMap,
+    inputs: Vec<KeyValueMap>,
+    outputs: Vec<KeyValueMap>,
+}
+
+fn read_compact_size(data: &[u8], pos: &mut usize) -> Result<u64, PsbtV2Error> {
+    let first = *data
+        .get(*pos)
+        .ok_or(PsbtV2Error::InvalidEncoding("unexpected end of data"))?;
+    *pos += 1;
+    let len = match first {
+        0xfd => 2,
+        0xfe => 4,
+        0xff => 8,
+        n => return Ok(n.into()),
+    };
+    let bytes = read_bytes(data, pos, len)?;
+    let mut buf = [0u8; 8];
+    buf[..len].copy_from_slice(bytes);
+    Ok(u64::from_le_bytes(buf))
+}
+
+fn read_bytes<'a>(data: &'a [u8], pos: &mut usize, len: usize) -> Result<&'a [u8], PsbtV2Error> {
+    let end = pos
+        .checked_add(len)
+        .filter(|end| *end <= data.len())
+        .ok_or(PsbtV2Error::InvalidEncoding("unexpected end of data"))?;
+    let bytes = &data[*pos..end];
+    *pos = end;
+    Ok(bytes)
+}
+
+fn read_map(data: &[u8], pos: &mut usize) -> Result<KeyValueMap, PsbtV2Error> {
+    let mut map = Vec::new();
+    loop {
+        let key_len = read_compact_size(data, pos)?;
+        if key_len == 0 {
+            return Ok(map);
+        }
+        let key = read_bytes(data, pos, key_len as usize)?.to_vec();
+        if map.iter().any(|(k, _)| *k == key) {
+            return Err(PsbtV2Error::InvalidEncoding("duplicate key"));
+        }
+        let value_len = read_compact_size(data, pos)?;
+        let value = read_bytes(data, pos, value_len as usize)?.to_vec();
+        map.push((key, value));
+    }
+}
+
+fn write_compact_size(buf: &mut Vec<u8>, n: u64) {
+    match n {
+        0..=0xfc => buf.push(n as u8),
+        0xfd..=0xffff => {
+            buf.push(0xfd);
+            buf.extend_from_slice(&(n as u16).to_le_bytes());
+        }
+        0x10000..=0xffff_ffff => {
+            buf.push(0xfe);
+            buf.extend_from_slice(&(n as u32).to_le_bytes());
+        }
+        _ => {
+            buf.push(0xff);
+            buf.extend_from_slice(&n.to_le_bytes());
+        }
+    }
+}
+
+fn write_map(buf: &mut Vec<u8>, map: &KeyValueMap) {
+    for (key, value) in map {
+        write_compact_size(buf, key.len() as u64);
+        buf.extend_from_slice(key);
+        write_compact_size(buf, value.len() as u64);
+        buf.extend_from_slice(value);
+    }
+    buf.push(0x00);
+}
+
+// Get the value of the field with this type and no key data.
+fn get_field(map: &KeyValueMap, key_type: u8) -> Option<&[u8]> {
+    map.iter()
+        .find(|(key, _)| key[..] == [key_type])
+        .map(|(_, value)| &value[..])
+}
+
+// Remove the fields with any of these types and no key data.
+fn remove_fields(map: &mut KeyValueMap, key_types: &[u8]) {
+    map.retain(|(key, _)| !(key.len() == 1 && key_types.contains(&key[0])));
+}
+
+fn get_u32(
+    map: &KeyValueMap,
+    key_type: u8,
+    name: &'static str,
+) -> Result<Option<u32>, PsbtV2Error> {
+    get_field(map, key_type)
+        .map(|value| {
+            value
+                .try_into()
+                .map(u32::from_le_bytes)
+                .map_err(|_| PsbtV2Error::InvalidField(name))
+        })
+        .transpose()
+}
+
+fn get_count(map: &KeyValueMap, key_type: u8, name: &'static str) -> Result<usize, PsbtV2Error> {
+    let value = get_field(map, key_type).ok_or(PsbtV2Error::MissingField(name))?;
+    let mut pos = 0;
+    let count = read_compact_size(value, &mut pos).map_err(|_| PsbtV2Error::InvalidField(name))?;
+    if pos != value.len() {
+        return Err(PsbtV2Error::InvalidField(name));
+    }
+    count
+        .try_into()
+        .map_err(|_| PsbtV2Error::InvalidField(name))
+}
+
+impl RawPsbt {
+    // Parse the key-value maps of a PSBT. The number of input and output maps is read from the
+    // global map using the given function.
+    fn parse(
+        data: &[u8],
+        counts: impl Fn(&KeyValueMap) -> Result<(usize, usize), PsbtV2Error>,
+    ) -> Result<Self, PsbtV2Error> {
+        if !data.starts_with(PSBT_MAGIC) {
+            return Err(PsbtV2Error::InvalidEncoding("invalid magic"));
+        }
+        let mut pos = PSBT_MAGIC.len();
+        let global = read_map(data, &mut pos)?;
+        let (input_count, output_count) = counts(&global)?;
+        let inputs = (0..input_count)
+            .map(|_| read_map(data, &mut pos))
+            .collect::<Result<_, _>>()?;
+        let outputs = (0..output_count)
+            .map(|_| read_map(data, &mut pos))
+            .collect::<Result<_, _>>()?;
+        if pos != data.len() {
+            return Err(PsbtV2Error::InvalidEncoding("trailing data"));
+        }
+        Ok(Self {
+            global,
+            inputs,
+            outputs,
+        })
+    }
+
+    fn serialize(&self) -> Vec<u8> {
+        let mut buf = PSBT_MAGIC.to_vec();
+        write_map(&mut buf, &self.global);
+        for map in self.inputs.iter().chain(self.outputs.iter()) {
+            write_map(&mut buf, map);
+        }
+        buf
+    }
+}
+
+// Get the version of a serialized PSBT.
+fn psbt_version(data: &[u8]) -> Result<u32, PsbtV2Error> {
+    if !data.starts_with(PSBT_MAGIC) {
+        return Err(PsbtV2Error::InvalidEncoding("invalid magic"));
+    }
+    let mut pos = PSBT_MAGIC.len();
+    let global = read_map(data, &mut pos)?;
+    Ok(get_u32(&global, PSBT_GLOBAL_VERSION, "PSBT_GLOBAL_VERSION")?.unwrap_or(0))
+}
+
+// Get the name of the first field without key data in this map which is part of the excluded ones.
+fn find_excluded(
+    unknown: &BTreeMap<raw::Key, Vec<u8>>,
+    excluded: &[(u8, &'static str)],
+) -> Option<&'static str> {
+    unknown
+        .keys()
+        .filter(|key| key.key.is_empty())
+        .find_map(|key| {
+            excluded
+                .iter()
+                .find(|(key_type, _)| *key_type == key.type_value)
+                .map(|(_, name)| *name)
+        })
+}
+
+// Make sure a version 0 PSBT doesn't contain any field which BIP-370 excludes from this version.
+// rust-bitcoin doesn't know about those and parses them as unknown fields.
+fn check_no_v2_fields(psbt: &Psbt) -> Result<(), PsbtV2Error> {
+    let excluded = find_excluded(&psbt.unknown, PSBT_GLOBAL_V2_FIELDS)
+        .or_else(|| {
+            psbt.inputs
+                .iter()
+                .find_map(|psbtin| find_excluded(&psbtin.unknown, PSBT_IN_V2_FIELDS))
+        })
+        .or_else(|| {
+            psbt.outputs
+                .iter()
+                .find_map(|psbtout| find_excluded(&psbtout.unknown, PSBT_OUT_V2_FIELDS))
+        });
+    match excluded {
+        Some(name) => Err(PsbtV2Error::ExcludedField(name)),
+        None => Ok(()),
+    }
+}
+
+// Determine the locktime of the transaction from the inputs' requirements as per BIP-370.
+fn compute_locktime(
+    fallback: u32,
+    requirements: &[(Option<u32>, Option<u32>)],
+) -> Result<u32, PsbtV2Error> {
+    let constrained: Vec<_> = requirements
+        .iter()
+        .filter(|(time, height)| time.is_some() || height.is_some())
+        .collect();
+    if constrained.is_empty() {
+        return Ok(fallback);
+    }
+    // A height-based locktime is preferred if all constrained inputs support it.
+    if constrained.iter().all(|(_, height)| height.is_some()) {
+        return Ok(constrained
+            .iter()
+            .filter_map(|(_, height)| *height)
+            .max()
+            .expect("Not empty"));
+    }
+    if constrained.iter().all(|(time, _)| time.is_some()) {
+        return Ok(constrained
+            .iter()
+            .filter_map(|(time, _)| *time)
+            .max()
+            .expect("Not empty"));
+    }
+    Err(PsbtV2Error::IncompatibleLocktimes)
+}
+
+// Convert a version 2 PSBT to version 0.
+fn v2_to_v0(data: &[u8]) -> Result<Psbt, PsbtV2Error> {
+    let mut raw = RawPsbt::parse(data, |global| {
+        Ok((
+            get_count(global, PSBT_GLOBAL_INPUT_COUNT, "PSBT_GLOBAL_INPUT_COUNT")?,
+            get_count(global, PSBT_GLOBAL_OUTPUT_COUNT, "PSBT_GLOBAL_OUTPUT_COUNT")?,
+        ))
+    })?;
+    if get_field(&raw.global, PSBT_GLOBAL_UNSIGNED_TX).is_some() {
+        return Err(PsbtV2Error::ExcludedField("PSBT_GLOBAL_UNSIGNED_TX"));
+    }
+
+    let version = get_u32(
+        &raw.global,
+        PSBT_GLOBAL_TX_VERSION,
+        "PSBT_GLOBAL_TX_VERSION",
+    )?
+    .ok_or(PsbtV2Error::MissingField("PSBT_GLOBAL_TX_VERSION"))?;
+    let fallback_locktime = get_u32(
+        &raw.global,
+        PSBT_GLOBAL_FALLBACK_LOCKTIME,
+        "PSBT_GLOBAL_FALLBACK_LOCKTIME",
+    )?
+    .unwrap_or(0);
+
+    let mut txins = Vec::with_capacity(raw.inputs.len());
+    let mut locktime_reqs = Vec::with_capacity(raw.inputs.len());
+    for map in &raw.inputs {
+        let txid = get_field(map, PSBT_IN_PREVIOUS_TXID)
+            .ok_or(PsbtV2Error::MissingField("PSBT_IN_PREVIOUS_TXID"))?
+            .try_into()
+            .map(bitcoin::Txid::from_byte_array)
+            .map_err(|_| PsbtV2Error::InvalidField("PSBT_IN_PREVIOUS_TXID"))?;
+        let vout = get_u32(map, PSBT_IN_OUTPUT_INDEX, "PSBT_IN_OUTPUT_INDEX")?
+            .ok_or(PsbtV2Error::MissingField("PSBT_IN_OUTPUT_INDEX"))?;
+        let sequence = get_u32(map, PSBT_IN_SEQUENCE, "PSBT_IN_SEQUENCE")?
+            .map(bitcoin::Sequence)
+            .unwrap_or(bitcoin::Sequence::MAX);
+        let time_req = get_u32(
+            map,
+            PSBT_IN_REQUIRED_TIME_LOCKTIME,
+            "PSBT_IN_REQUIRED_TIME_LOCKTIME",
+        )?;
+        if time_req.map(|t| t < bitcoin::absolute::LOCK_TIME_THRESHOLD) == Some(true) {
+            return Err(PsbtV2Error::InvalidField("PSBT_IN_REQUIRED_TIME_LOCKTIME"));
+        }
+        let height_req = get_u32(
+            map,
+            PSBT_IN_REQUIRED_HEIGHT_LOCKTIME,
+            "PSBT_IN_REQUIRED_HEIGHT_LOCKTIME",
+        )?;
+        if height_req.map(|h| h >= bitcoin::absolute::LOCK_TIME_THRESHOLD) == Some(true) {
+            return Err(PsbtV2Error::InvalidField(
+                "PSBT_IN_REQUIRED_HEIGHT_LOCKTIME",
+            ));
+        }
+        locktime_reqs.push((time_req, height_req));
+        txins.push(bitcoin::TxIn {
+            previous_output: bitcoin::OutPoint { txid, vout },
+            sequence,
+            ..bitcoin::TxIn::default()
+        });
+    }
+
+    let mut txouts = Vec::with_capacity(raw.outputs.len());
+    for map in &raw.outputs {
+        let amount = get_field(map, PSBT_OUT_AMOUNT)
+            .ok_or(PsbtV2Error::MissingField("PSBT_OUT_AMOUNT"))?
+            .try_into()
+            .map(i64::from_le_bytes)
+            .ok()
+            .and_then(|a| u64::try_from(a).ok())
+            .ok_or(PsbtV2Error::InvalidField("PSBT_OUT_AMOUNT"))?;
+        let script =
+            get_field(map, PSBT_OUT_SCRIPT).ok_or(PsbtV2Error::MissingField("PSBT_OUT_SCRIPT"))?;
+        txouts.push(bitcoin::TxOut {
+            value: bitcoin::Amount::from_sat(amount),
+            script_pubkey: bitcoin::ScriptBuf::from_bytes(script.to_vec()),
+        });
+    }
+
+    let unsigned_tx = bitcoin::Transaction {
+        version: bitcoin::transaction::Version(version as i32),
+        lock_time: bitcoin::absolute::LockTime::from_consensus(compute_locktime(
+            fallback_locktime,
+            &locktime_reqs,
+        )?),
+        input: txins,
+        output: txouts,
+    };
+
+    remove_fields(
+        &mut raw.global,
+        &[
+            PSBT_GLOBAL_TX_VERSION,
+            PSBT_GLOBAL_FALLBACK_LOCKTIME,
+            PSBT_GLOBAL_INPUT_COUNT,
+            PSBT_GLOBAL_OUTPUT_COUNT,
+            PSBT_GLOBAL_TX_MODIFIABLE,
+            PSBT_GLOBAL_VERSION,
+        ],
+    );
+    raw.global.insert(
+        0,
+        (
+            vec![PSBT_GLOBAL_UNSIGNED_TX],
+            consensus::serialize(&unsigned_tx),
+        ),
+    );
+    for map in raw.inputs.iter_mut() {
+        remove_fields(
+            map,
+            &[
+                PSBT_IN_PREVIOUS_TXID,
+                PSBT_IN_OUTPUT_INDEX,
+                PSBT_IN_SEQUENCE,
+                PSBT_IN_REQUIRED_TIME_LOCKTIME,
+                PSBT_IN_REQUIRED_HEIGHT_LOCKTIME,
+            ],
+        );
+    }
+    for map in raw.outputs.iter_mut() {
+        remove_fields(map, &[PSBT_OUT_AMOUNT, PSBT_OUT_SCRIPT]);
+    }
+
+    Psbt::deserialize(&raw.serialize()).map_err(PsbtV2Error::Psbt)
+}
+
+// Convert a version 0 PSBT to version 2.
+fn v0_to_v2(psbt: &Psbt) -> Vec<u8> {
+    let tx = &psbt.unsigned_tx;
+    let mut raw = RawPsbt::parse(&psbt.serialize(), |_| Ok((tx.input.len(), tx.output.len())))
+        .expect("Serialized by rust-bitcoin, always valid.");
+
+    let mut input_count = Vec::new();
+    write_compact_size(&mut input_count, tx.input.len() as u64);
+    let mut output_count = Vec::new();
+    write_compact_size(&mut output_count, tx.output.len() as u64);
+    remove_fields(
+        &mut raw.global,
+        &[PSBT_GLOBAL_UNSIGNED_TX, PSBT_GLOBAL_VERSION],
+    );
+    let v2_fields = vec![
+        (
+            PSBT_GLOBAL_TX_VERSION,
+            (tx.version.0 as u32).to_le_bytes().to_vec(),
+        ),
+        (
+            PSBT_GLOBAL_FALLBACK_LOCKTIME,
+            tx.lock_time.to_consensus_u32().to_le_bytes().to_vec(),
+        ),
+        (PSBT_GLOBAL_INPUT_COUNT, input_count),
+        (PSBT_GLOBAL_OUTPUT_COUNT, output_count),
+    ];
+    raw.global.splice(
+        0..0,
+        v2_fields
+            .into_iter()
+            .map(|(key_type, value)| (vec![key_type], value)),
+    );
+    raw.global.push((
+        vec![PSBT_GLOBAL_VERSION],
+        PsbtVersion::V2.number().to_le_bytes().to_vec(),
+    ));
+
+    for (map, txin) in raw.inputs.iter_mut().zip(tx.input.iter()) {
+        let v2_fields = vec![
+            (
+                PSBT_IN_PREVIOUS_TXID,
+                txin.previous_output.txid.to_byte_array().to_vec(),
+            ),
+            (
+                PSBT_IN_OUTPUT_INDEX,
+                txin.previous_output.vout.to_le_bytes().to_vec(),
+            ),
+            (
+                PSBT_IN_SEQUENCE,
+                txin.sequence.to_consensus_u32().to_le_bytes().to_vec(),
+            ),
+        ];
+        map.splice(
+            0..0,
+            v2_fields
+                .into_iter()
+                .map(|(key_type, value)| (vec![key_type], value)),
+        );
+    }
+    for (map, txout) in raw.outputs.iter_mut().zip(tx.output.iter()) {
+        let v2_fields = vec![
+            (
+                PSBT_OUT_AMOUNT,
+                (txout.value.to_sat() as i64).to_le_bytes().to_vec(),
+            ),
+            (PSBT_OUT_SCRIPT, txout.script_pubkey.to_bytes()),
+        ];
+        map.splice(
+            0..0,
+            v2_fields
+                .into_iter()
+                .map(|(key_type, value)| (vec![key_type], value)),
+        );
+    }
+
+    raw.serialize()
+}
+
+/// Deserialize a PSBT of either version 0 or version 2.
+pub fn deserialize(data: &[u8]) -> Result<Psbt, PsbtV2Error> {
+    match psbt_version(data)? {
+        0 => {
+            let psbt = Psbt::deserialize(data).map_err(PsbtV2Error::Psbt)?;
+            check_no_v2_fields(&psbt)?;
+            Ok(psbt)
+        }
+        2 => v2_to_v0(data),
+        v => Err(PsbtV2Error::UnsupportedVersion(v)),
+    }
+}
+
+/// Parse a base64-encoded PSBT of either version 0 or version 2.
+pub fn from_base64(s: &str) -> Result<Psbt, PsbtV2Error> {
+    let data = BASE64_STANDARD
+        .decode(s.trim())
+        .map_err(|e| PsbtV2Error::Base64(e.to_string()))?;
+    deserialize(&data)
+}
+
+/// Serialize a PSBT as the given version.
+pub fn serialize(psbt: &Psbt, version: PsbtVersion) -> Vec<u8> {
+    match version {
+        PsbtVersion::V0 => psbt.serialize(),
+        PsbtVersion::V2 => v0_to_v2(psbt),
+    }
+}
+
+/// Encode a PSBT as base64, as the given version.
+pub fn to_base64(psbt: &Psbt, version: PsbtVersion) -> String {
+    BASE64_STANDARD.encode(serialize(psbt, version))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    // A PSBT with a single P2WSH input, with BIP32 derivations, and a single output.
+    const PSBT_V0: &str = "cHNidP8BAFICAAAAAc+3IQFejOVro5Hlwy18au5Jr5mJX+tNMGk0ZE1hydIbAQAAAAD9////ARhzAQAAAAAAFgAUqJZUU7Fqu+bIvxjNw+TAtTwP9HQAAAAAAAEAzQIAAAAAAQEIoAeUdfZj04Ds8EspEK222TJdDNy1WZb/Mg1PJbQekwAAAAAA/f///wKQCQQAAAAAACJRIPJojBgnDc9oUS5lDNx/YJznYR2NPQue7h/d+o5Z+2FQoIYBAAAAAAAiACDZrCBvscZpg+S+IaoZBJjyKDdrNS3oXPaF17DNaB+4mAFAe9yuRS3Vn8A5NUglhwiX7vN0wpQ0Q43ClWtJRnC2HJ66h5HYJ/p8xHgHOhRDUWRzcXLLGl+brc5dW+k0OvIZEyuLAgABASughgEAAAAAACIAINmsIG+xxmmD5L4hqhkEmPIoN2s1Lehc9oXXsM1oH7iYAQX9GQFjdqkU2zK+b9oTL/KfnOSYtq3wmtf4qP6IrGt2qRTSNOD0U7fuHdAnKchIf8GmUO904YisbJNrdqkUE5TQk5mdyYtviaGAsIiOgc4y6wGIrGyTU4hWsmdTIQOirPI1KXBtP2Tg2FQxSo4BjFBTf+dCKtZwDQt056slgCEDDHE7Hpxq++JsjZdbfwsPiA6pmq0dV00tR3hc2sus8KkhA2nPUthIMe1SeFegiZEKZF69yJerP1RFVlyu66C5lOVVU65zZHapFEUmCTccyLJXczvUfPUOCXr7CN0uiKxrdqkUeJmVqUt1Q4aFREOUWKX9U/SuZZ2IrGyTa3apFBDmKn40ceTWVbwxRI21c2qji1tOiKxsk1KIU7JoaCIGAwxxOx6cavvibI2XW38LD4gOqZqtHVdNLUd4XNrLrPCpHBcrobwwAACAAQAAgAAAAIACAACAAAAAAAgAAAAiBgNpz1LYSDHtUnhXoImRCmRevciXqz9URVZcruuguZTlVRyQMRXvMAAAgAEAAIAAAACAAgAAgAAAAAAIAAAAIgYDoqzyNSlwbT9k4NhUMUqOAYxQU3/nQirWcA0LdOerJYAcY2rfPzAAAIABAACAAAAAgAIAAIAAAAAACAAAAAAA";
+
+    #[test]
+    fn psbt_v2_roundtrip() {
+        let psbt = Psbt::from_str(PSBT_V0).unwrap();
+
+        // Version 0 serialization is left untouched.
+        assert_eq!(to_base64(&psbt, PsbtVersion::V0), PSBT_V0);
+        assert_eq!(from_base64(PSBT_V0).unwrap(), psbt);
+
+        // The version 2 serialization isn't readable as a version 0 PSBT, but converts back to
+        // the same PSBT.
+        let v2 = serialize(&psbt, PsbtVersion::V2);
+        assert_eq!(psbt_version(&v2).unwrap(), 2);
+        assert!(Psbt::deserialize(&v2).is_err());
+        let raw = RawPsbt::parse(&v2, |_| Ok((1, 1))).unwrap();
+        assert!(get_field(&raw.global, PSBT_GLOBAL_UNSIGNED_TX).is_none());
+        assert_eq!(
+            get_field(&raw.inputs[0], PSBT_IN_PREVIOUS_TXID).unwrap(),
+            psbt.unsigned_tx.input[0]
+                .previous_output
+                .txid
+                .to_byte_array()
+        );
+        assert_eq!(
+            get_field(&raw.outputs[0], PSBT_OUT_SCRIPT).unwrap(),
+            psbt.unsigned_tx.output[0].script_pubkey.as_bytes()
+        );
+        assert_eq!(deserialize(&v2).unwrap(), psbt);
+        let v2_base64 = to_base64(&psbt, PsbtVersion::V2);
+        assert_eq!(from_base64(&v2_base64).unwrap(), psbt);
+
+        // Unsupported versions and missing fields are rejected.
+        let mut raw = RawPsbt::parse(&v2, |_| Ok((1, 1))).unwrap();
+        remove_fields(&mut raw.global, &[PSBT_GLOBAL_VERSION]);
+        raw.global
+            .push((vec![PSBT_GLOBAL_VERSION], 1u32.to_le_bytes().to_vec()));
+        assert!(matches!(
+            deserialize(&raw.serialize()),
+            Err(PsbtV2Error::UnsupportedVersion(1))
+        ));
+        let mut raw = RawPsbt::parse(&v2, |_| Ok((1, 1))).unwrap();
+        remove_fields(&mut raw.inputs[0], &[PSBT_IN_PREVIOUS_TXID]);
+        assert!(matches!(
+            deserialize(&raw.serialize()),
+            Err(PsbtV2Error::MissingField("PSBT_IN_PREVIOUS_TXID"))
+        ));
+        assert!(from_base64("cHNidP8=").is_err());
+        assert!(from_base64("not base64").is_err());
+    }
+
+    #[test]
+    fn psbt_v2_invalid() {
+        let psbt = Psbt::from_str(PSBT_V0).unwrap();
+        let v0 = serialize(&psbt, PsbtVersion::V0);
+        let v2 = serialize(&psbt, PsbtVersion::V2);
+        // Apply a modification to the maps of one of the PSBTs above and deserialize the result.
+        let mutated = |data: &[u8], modify: &dyn Fn(&mut RawPsbt)| {
+            let mut raw = RawPsbt::parse(data, |_| Ok((1, 1))).unwrap();
+            modify(&mut raw);
+            deserialize(&raw.serialize())
+        };
+
+        // Version 0 PSBTs must not contain any of the fields introduced by version 2.
+        for key_type in [
+            PSBT_GLOBAL_TX_VERSION,
+            PSBT_GLOBAL_FALLBACK_LOCKTIME,
+            PSBT_GLOBAL_INPUT_COUNT,
+            PSBT_GLOBAL_OUTPUT_COUNT,
+            PSBT_GLOBAL_TX_MODIFIABLE,
+        ]
+        .iter()
+        {
+            assert!(matches!(
+                mutated(&v0, &|raw| raw.global.push((vec![*key_type], vec![0x01]))),
+                Err(PsbtV2Error::ExcludedField(_))
+            ));
+        }
+        for key_type in [
+            PSBT_IN_PREVIOUS_TXID,
+            PSBT_IN_OUTPUT_INDEX,
+            PSBT_IN_SEQUENCE,
+            PSBT_IN_REQUIRED_TIME_LOCKTIME,
+            PSBT_IN_REQUIRED_HEIGHT_LOCKTIME,
+        ]
+        .iter()
+        {
+            assert!(matches!(
+                mutated(&v0, &|raw| raw.inputs[0]
+                    .push((vec![*key_type], vec![0x01]))),
+                Err(PsbtV2Error::ExcludedField(_))
+            ));
+        }
+        assert!(matches!(
+            mutated(&v0, &|raw| raw.outputs[0]
+                .push((vec![PSBT_OUT_AMOUNT], 0i64.to_le_bytes().to_vec()))),
+            Err(PsbtV2Error::ExcludedField("PSBT_OUT_AMOUNT"))
+        ));
+        assert!(matches!(
+            mutated(&v0, &|raw| raw.outputs[0]
+                .push((vec![PSBT_OUT_SCRIPT], vec![]))),
+            Err(PsbtV2Error::ExcludedField("PSBT_OUT_SCRIPT"))
+        ));
+        // Fields with the same type but some key data aren't those from version 2.
+        assert!(mutated(&v0, &|raw| raw.inputs[0]
+            .push((vec![PSBT_IN_PREVIOUS_TXID, 0x01], vec![0x01])))
+        .is_ok());
+
+        // Version 2 PSBTs must not contain the global unsigned transaction.
+        assert!(matches!(
+            mutated(&v2, &|raw| raw.global.insert(
+                0,
+                (
+                    vec![PSBT_GLOBAL_UNSIGNED_TX],
+                    consensus::serialize(&psbt.unsigned_tx)
+                )
+            )),
+            Err(PsbtV2Error::ExcludedField("PSBT_GLOBAL_UNSIGNED_TX"))
+        ));
+
+        // Version 2 PSBTs must contain the transaction version and the input and output counts.
+        assert!(matches!(
+            mutated(&v2, &|raw| remove_fields(
+                &mut raw.global,
+                &[PSBT_GLOBAL_TX_VERSION]
+            )),
+            Err(PsbtV2Error::MissingField("PSBT_GLOBAL_TX_VERSION"))
+        ));
+        assert!(matches!(
+            mutated(&v2, &|raw| remove_fields(
+                &mut raw.global,
+                &[PSBT_GLOBAL_INPUT_COUNT]
+            )),
+            Err(PsbtV2Error::MissingField("PSBT_GLOBAL_INPUT_COUNT"))
+        ));
+        assert!(matches!(
+            mutated(&v2, &|raw| remove_fields(
+                &mut raw.global,
+                &[PSBT_GLOBAL_OUTPUT_COUNT]
+            )),
+            Err(PsbtV2Error::MissingField("PSBT_GLOBAL_OUTPUT_COUNT"))
+        ));
+
+        // The counts must be valid compact sizes and match the number of maps.
+        let set_count = |raw: &mut RawPsbt, key_type: u8, value: Vec<u8>| {
+            remove_fields(&mut raw.global, &[key_type]);
+            raw.global.push((vec![key_type], value));
+        };
+        assert!(matches!(
+            mutated(&v2, &|raw| set_count(
+                raw,
+                PSBT_GLOBAL_INPUT_COUNT,
+                vec![0xfd, 0x01]
+            )),
+            Err(PsbtV2Error::InvalidField("PSBT_GLOBAL_INPUT_COUNT"))
+        ));
+        assert!(matches!(
+            mutated(&v2, &|raw| set_count(
+                raw,
+                PSBT_GLOBAL_OUTPUT_COUNT,
+                vec![0x01, 0x00]
+            )),
+            Err(PsbtV2Error::InvalidField("PSBT_GLOBAL_OUTPUT_COUNT"))
+        ));
+        assert!(matches!(
+            mutated(&v2, &|raw| set_count(
+                raw,
+                PSBT_GLOBAL_INPUT_COUNT,
+                vec![0x02]
+            )),
+            Err(PsbtV2Error::InvalidEncoding(_))
+        ));
+        assert!(matches!(
+            mutated(&v2, &|raw| set_count(
+                raw,
+                PSBT_GLOBAL_OUTPUT_COUNT,
+                vec![0x00]
+            )),
+            Err(PsbtV2Error::InvalidEncoding("trailing data"))
+        ));
+        assert!(matches!(
+            mutated(&v2, &|raw| set_count(
+                raw,
+                PSBT_GLOBAL_OUTPUT_COUNT,
+                vec![0x02]
+            )),
+            Err(PsbtV2Error::InvalidEncoding(_))
+        ));
+
+        // The inputs and outputs must contain the required fields, with valid values.
+        assert!(matches!(
+            mutated(&v2, &|raw| remove_fields(
+                &mut raw.inputs[0],
+                &[PSBT_IN_OUTPUT_INDEX]
+            )),
+            Err(PsbtV2Error::MissingField("PSBT_IN_OUTPUT_INDEX"))
+        ));
+        assert!(matches!(
+            mutated(&v2, &|raw| {
+                remove_fields(&mut raw.inputs[0], &[PSBT_IN_PREVIOUS_TXID]);
+                raw.inputs[0].push((vec![PSBT_IN_PREVIOUS_TXID], vec![0x01; 31]));
+            }),
+            Err(PsbtV2Error::InvalidField("PSBT_IN_PREVIOUS_TXID"))
+        ));
+        assert!(matches!(
+            mutated(&v2, &|raw| raw.inputs[0].push((
+                vec![PSBT_IN_REQUIRED_TIME_LOCKTIME],
+                800_000u32.to_le_bytes().to_vec()
+            ))),
+            Err(PsbtV2Error::InvalidField("PSBT_IN_REQUIRED_TIME_LOCKTIME"))
+        ));
+        assert!(matches!(
+            mutated(&v2, &|raw| remove_fields(
+                &mut raw.outputs[0],
+                &[PSBT_OUT_AMOUNT]
+            )),
+            Err(PsbtV2Error::MissingField("PSBT_OUT_AMOUNT"))
+        ));
+        assert!(matches!(
+            mutated(&v2, &|raw| remove_fields(
+                &mut raw.outputs[0],
+                &[PSBT_OUT_SCRIPT]
+            )),
+            Err(PsbtV2Error::MissingField("PSBT_OUT_SCRIPT"))
+        ));
+        assert!(matches!(
+            mutated(&v2, &|raw| {
+                remove_fields(&mut raw.outputs[0], &[PSBT_OUT_AMOUNT]);
+                raw.outputs[0].push((vec![PSBT_OUT_AMOUNT], (-1i64).to_le_bytes().to_vec()));
+            }),
+            Err(PsbtV2Error::InvalidField("PSBT_OUT_AMOUNT"))
+        ));
+    }
+
+    #[test]
+    fn psbt_v2_locktime() {
+        let psbt = Psbt::from_str(PSBT_V0).unwrap();
+        let v2 = serialize(&psbt, PsbtVersion::V2);
+
+        // Without requirement, the fallback locktime is used.
+        assert_eq!(
+            compute_locktime(42, &[(None, None), (None, None)]).unwrap(),
+            42
+        );
+        // Height is preferred if supported by all inputs, and the maximum is used.
+        assert_eq!(
+            compute_locktime(
+                42,
+                &[(Some(1_700_000_000), Some(800_000)), (None, Some(800_010))]
+            )
+            .unwrap(),
+            800_010
+        );
+        assert_eq!(
+            compute_locktime(
+                42,
+                &[
+                    (Some(1_700_000_000), Some(800_000)),
+                    (Some(1_700_000_010), None)
+                ]
+            )
+            .unwrap(),
+            1_700_000_010
+        );
+        assert!(matches!(
+            compute_locktime(42, &[(Some(1_700_000_000), None), (None, Some(800_000))]),
+            Err(PsbtV2Error::IncompatibleLocktimes)
+        ));
+
+        // The input requirements are applied when converting to version 0.
+        let mut raw = RawPsbt::parse(&v2, |_| Ok((1, 1))).unwrap();
+        raw.inputs[0].push((
+            vec![PSBT_IN_REQUIRED_HEIGHT_LOCKTIME],
+            800_000u32.to_le_bytes().to_vec(),
+        ));
+        let converted = deserialize(&raw.serialize()).unwrap();
+        assert_eq!(converted.unsigned_tx.lock_time.to_consensus_u32(), 800_000);
+        assert!(converted.inputs[0].unknown.is_empty());
+        let mut raw = RawPsbt::parse(&v2, |_| Ok((1, 1))).unwrap();
+        raw.inputs[0].push((
+            vec![PSBT_IN_REQUIRED_HEIGHT_LOCKTIME],
+            1_700_000_000u32.to_le_bytes().to_vec(),
+        ));
+        assert!(matches!(
+            deserialize(&raw.serialize()),
+            Err(PsbtV2Error::InvalidField(
+                "PSBT_IN_REQUIRED_HEIGHT_LOCKTIME"
+            ))
+        ));
+    }
+}
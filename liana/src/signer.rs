@@ -1,9 +1,9 @@
 //! Signer module
 //!
-//! Some helpers to facilitate the usage of a signer in client of the Liana daemon. For now
-//! only contains a hot signer.
+//! Some helpers to facilitate the usage of a signer in client of the Liana daemon. Contains a hot
+//! signer backed by a BIP39 mnemonic and a signer backed by extended private keys.
 
-use crate::random;
+use crate::{bsms, descriptors, random, slip39};
 
 use std::{
     convert::TryInto,
@@ -13,14 +13,17 @@ use std::{
     str::FromStr,
 };
 
-use miniscript::bitcoin::{
-    self,
-    bip32::{self, Error as Bip32Error},
-    ecdsa,
-    hashes::Hash,
-    key::TapTweak,
-    psbt::{Input as PsbtIn, Psbt},
-    secp256k1, sighash,
+use miniscript::{
+    bitcoin::{
+        self,
+        bip32::{self, Error as Bip32Error},
+        ecdsa,
+        hashes::Hash,
+        key::TapTweak,
+        psbt::{Input as PsbtIn, Psbt},
+        secp256k1, sighash,
+    },
+    descriptor, ForEachKey,
 };
 
 /// An error related to using a signer.
@@ -30,6 +33,22 @@ pub enum SignerError {
     Mnemonic(bip39::Error),
     Bip32(Bip32Error),
     MnemonicStorage(io::Error),
+    /// An encrypted mnemonic was found but no password was provided to decrypt it.
+    MissingPassword(path::PathBuf),
+    /// A stored mnemonic requires a BIP39 passphrase but none was provided.
+    MissingPassphrase(path::PathBuf),
+    /// The encrypted mnemonic could not be authenticated. Either the password is wrong or the
+    /// file was tampered with.
+    MnemonicDecryption(path::PathBuf),
+    /// The encrypted mnemonic file is malformed or uses an unknown version.
+    InvalidEncryptedMnemonic(String),
+    MnemonicEncryption(String),
+    Slip39(slip39::Slip39Error),
+    /// The extended private key or descriptor could not be parsed or isn't supported.
+    InvalidPrivateKey(String),
+    /// An extended private key of the signer (identified by its origin) isn't part of the
+    /// descriptor.
+    KeyNotInDescriptor(bip32::Fingerprint, bip32::DerivationPath),
     InsanePsbt,
     IncompletePsbt,
 }
@@ -41,6 +60,30 @@ impl fmt::Display for SignerError {
             Self::Mnemonic(s) => write!(f, "Error when working with mnemonics: {}", s),
             Self::Bip32(e) => write!(f, "BIP32 error: {}", e),
             Self::MnemonicStorage(e) => write!(f, "BIP39 mnemonic storage error: {}", e),
+            Self::MissingPassword(p) => write!(
+                f,
+                "A password is required to decrypt the mnemonic at '{}'.",
+                p.display()
+            ),
+            Self::MissingPassphrase(p) => write!(
+                f,
+                "A BIP39 passphrase is required for the mnemonic at '{}'.",
+                p.display()
+            ),
+            Self::MnemonicDecryption(p) => write!(
+                f,
+                "Failed to decrypt the mnemonic at '{}': wrong password or corrupted file.",
+                p.display()
+            ),
+            Self::InvalidEncryptedMnemonic(s) => write!(f, "Invalid encrypted mnemonic: {}", s),
+            Self::MnemonicEncryption(s) => write!(f, "Error when encrypting mnemonic: {}", s),
+            Self::Slip39(e) => write!(f, "SLIP-39 error: {}", e),
+            Self::InvalidPrivateKey(s) => write!(f, "Invalid extended private key: {}", s),
+            Self::KeyNotInDescriptor(fg, path) => write!(
+                f,
+                "The extended private key at '[{}/{}]' is not part of the descriptor.",
+                fg, path
+            ),
             Self::InsanePsbt => write!(f, "Information contained in the PSBT is wrong."),
             Self::IncompletePsbt => write!(
                 f,
@@ -54,11 +97,181 @@ impl error::Error for SignerError {}
 
 pub const MNEMONICS_FOLDER_NAME: &str = "mnemonics";
 
-// TODO: zeroize, mlock, etc.. For now we don't even encrypt the seed on disk so that'd be
-// overkill.
+/// Magic bytes at the start of an encrypted mnemonic file.
+pub const ENCRYPTED_MNEMONIC_MAGIC: &[u8; 8] = b"LIANAMNE";
+/// Current version of the encrypted mnemonic file format. Version 1 derives a key from the
+/// password using Argon2id and encrypts the mnemonic using ChaCha20-Poly1305.
+pub const ENCRYPTED_MNEMONIC_VERSION: u8 = 1;
+const ENCRYPTED_MNEMONIC_SALT_LEN: usize = 16;
+const ENCRYPTED_MNEMONIC_NONCE_LEN: usize = 12;
+// Magic, version, the three Argon2 parameters, salt and nonce.
+const ENCRYPTED_MNEMONIC_HEADER_LEN: usize =
+    8 + 1 + 3 * 4 + ENCRYPTED_MNEMONIC_SALT_LEN + ENCRYPTED_MNEMONIC_NONCE_LEN;
+// The maximum Argon2 parameters we accept when decrypting a mnemonic. They are read from the
+// header before it can be authenticated, so a tampered file could otherwise make us use all the
+// memory or spin forever. These are well above the defaults we encrypt with.
+const ENCRYPTED_MNEMONIC_MAX_M_COST: u32 = 1024 * 1024; // 1GiB
+const ENCRYPTED_MNEMONIC_MAX_T_COST: u32 = 64;
+const ENCRYPTED_MNEMONIC_MAX_P_COST: u32 = 16;
+
+// Derive the encryption key from the password using Argon2id with the given parameters.
+fn mnemonic_encryption_key(
+    password: &str,
+    salt: &[u8],
+    params: argon2::Params,
+) -> Result<chacha20poly1305::Key, String> {
+    let mut key = chacha20poly1305::Key::default();
+    argon2::Argon2::new(argon2::Algorithm::Argon2id, argon2::Version::V0x13, params)
+        .hash_password_into(password.as_bytes(), salt, &mut key)
+        .map_err(|e| e.to_string())?;
+    Ok(key)
+}
+
+/// Encrypt a mnemonic with the given password.
+///
+/// The format is the magic bytes, a version byte, the Argon2id memory cost, number of
+/// iterations and degree of parallelism (as little-endian 32-bit integers), a 16-byte salt, a
+/// 12-byte nonce and finally the ChaCha20-Poly1305 ciphertext. The header is authenticated as
+/// associated data.
+fn encrypt_mnemonic(mnemonic_str: &str, password: &str) -> Result<Vec<u8>, SignerError> {
+    use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+
+    let params = argon2::Params::default();
+    let random_32bytes = random::random_bytes().map_err(SignerError::Randomness)?;
+    let (salt, rest) = random_32bytes.split_at(ENCRYPTED_MNEMONIC_SALT_LEN);
+    let nonce = &rest[..ENCRYPTED_MNEMONIC_NONCE_LEN];
+
+    let mut data = Vec::with_capacity(ENCRYPTED_MNEMONIC_HEADER_LEN + mnemonic_str.len() + 16);
+    data.extend_from_slice(ENCRYPTED_MNEMONIC_MAGIC);
+    data.push(ENCRYPTED_MNEMONIC_VERSION);
+    data.extend_from_slice(&params.m_cost().to_le_bytes());
+    data.extend_from_slice(&params.t_cost().to_le_bytes());
+    data.extend_from_slice(&params.p_cost().to_le_bytes());
+    data.extend_from_slice(salt);
+    data.extend_from_slice(nonce);
+
+    let key =
+        mnemonic_encryption_key(password, salt, params).map_err(SignerError::MnemonicEncryption)?;
+    let ciphertext = chacha20poly1305::ChaCha20Poly1305::new(&key)
+        .encrypt(
+            nonce.into(),
+            Payload {
+                msg: mnemonic_str.as_bytes(),
+                aad: &data,
+            },
+        )
+        .map_err(|e| SignerError::MnemonicEncryption(e.to_string()))?;
+    data.extend_from_slice(&ciphertext);
+
+    Ok(data)
+}
+
+/// Decrypt a mnemonic encrypted with [`encrypt_mnemonic`]. The path is only used for error
+/// reporting.
+fn decrypt_mnemonic(
+    data: &[u8],
+    password: &str,
+    file_path: &path::Path,
+) -> Result<String, SignerError> {
+    use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+
+    if data.len() < ENCRYPTED_MNEMONIC_HEADER_LEN {
+        return Err(SignerError::InvalidEncryptedMnemonic(
+            "file is too short".to_string(),
+        ));
+    }
+    let (header, ciphertext) = data.split_at(ENCRYPTED_MNEMONIC_HEADER_LEN);
+    let version = header[ENCRYPTED_MNEMONIC_MAGIC.len()];
+    if version != ENCRYPTED_MNEMONIC_VERSION {
+        return Err(SignerError::InvalidEncryptedMnemonic(format!(
+            "unknown version {}",
+            version
+        )));
+    }
+    let read_u32 = |offset: usize| {
+        u32::from_le_bytes(
+            header[offset..offset + 4]
+                .try_into()
+                .expect("Slice of 4 bytes"),
+        )
+    };
+    let (m_cost, t_cost, p_cost) = (read_u32(9), read_u32(13), read_u32(17));
+    if m_cost > ENCRYPTED_MNEMONIC_MAX_M_COST
+        || t_cost > ENCRYPTED_MNEMONIC_MAX_T_COST
+        || p_cost > ENCRYPTED_MNEMONIC_MAX_P_COST
+    {
+        return Err(SignerError::InvalidEncryptedMnemonic(format!(
+            "key derivation parameters are too large (m={}, t={}, p={})",
+            m_cost, t_cost, p_cost
+        )));
+    }
+    let params = argon2::Params::new(m_cost, t_cost, p_cost, None)
+        .map_err(|e| SignerError::InvalidEncryptedMnemonic(e.to_string()))?;
+    let salt = &header[21..21 + ENCRYPTED_MNEMONIC_SALT_LEN];
+    let nonce = &header[21 + ENCRYPTED_MNEMONIC_SALT_LEN..];
+
+    let key = mnemonic_encryption_key(password, salt, params)
+        .map_err(SignerError::InvalidEncryptedMnemonic)?;
+    let plaintext = chacha20poly1305::ChaCha20Poly1305::new(&key)
+        .decrypt(
+            nonce.into(),
+            Payload {
+                msg: ciphertext,
+                aad: header,
+            },
+        )
+        .map_err(|_| SignerError::MnemonicDecryption(file_path.to_path_buf()))?;
+    String::from_utf8(plaintext).map_err(|_| {
+        SignerError::InvalidEncryptedMnemonic("mnemonic is not valid UTF-8".to_string())
+    })
+}
+
+// Whether the content of this mnemonic file is encrypted.
+fn is_encrypted_mnemonic(data: &[u8]) -> bool {
+    data.starts_with(ENCRYPTED_MNEMONIC_MAGIC)
+}
+
+/// Line appended to a stored mnemonic when it must be used along with a BIP39 passphrase. The
+/// passphrase itself is never stored.
+pub const STORED_PASSPHRASE_MARKER: &str = "passphrase";
+
+// Parse the (decrypted) content of a mnemonic file. Returns the mnemonic and whether it must be
+// used along with a passphrase.
+fn parse_stored_mnemonic(content: &str) -> Result<(bip39::Mnemonic, bool), SignerError> {
+    let mut lines = content.lines().map(str::trim).filter(|l| !l.is_empty());
+    let mnemonic =
+        bip39::Mnemonic::from_str(lines.next().unwrap_or("")).map_err(SignerError::Mnemonic)?;
+    let mut has_passphrase = false;
+    for line in lines {
+        if line == STORED_PASSPHRASE_MARKER {
+            has_passphrase = true;
+        } else {
+            return Err(SignerError::MnemonicStorage(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Unexpected line in mnemonic file: '{}'", line),
+            )));
+        }
+    }
+    Ok((mnemonic, has_passphrase))
+}
+
+// The iteration exponent used when creating SLIP-39 shares, as recommended by the specs.
+const SLIP39_ITERATION_EXPONENT: u8 = 1;
+
+/// A secret which may be needed when reading the mnemonics stored in the datadir.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SecretRequest<'a> {
+    /// The password the mnemonic file at this path was encrypted with.
+    Password(&'a path::Path),
+    /// The BIP39 passphrase to use along with the mnemonic stored at this path.
+    Passphrase(&'a path::Path),
+}
+
+// TODO: zeroize, mlock, etc..
 /// A signer that keeps the key on the laptop. Based on BIP39.
 pub struct HotSigner {
     mnemonic: bip39::Mnemonic,
+    has_passphrase: bool,
     master_xpriv: bip32::Xpriv,
 }
 
@@ -103,27 +316,69 @@ impl HotSigner {
     fn from_mnemonic(
         network: bitcoin::Network,
         mnemonic: bip39::Mnemonic,
+        passphrase: &str,
     ) -> Result<Self, SignerError> {
-        let master_xpriv =
-            bip32::Xpriv::new_master(network, &mnemonic.to_seed("")).map_err(SignerError::Bip32)?;
+        let master_xpriv = bip32::Xpriv::new_master(network, &mnemonic.to_seed(passphrase))
+            .map_err(SignerError::Bip32)?;
         Ok(Self {
             mnemonic,
+            has_passphrase: !passphrase.is_empty(),
             master_xpriv,
         })
     }
 
     /// Create a new hot signer from random bytes. Uses a 12-words mnemonics without a passphrase.
     pub fn generate(network: bitcoin::Network) -> Result<Self, SignerError> {
-        // We want a 12-words mnemonic so we only use 16 of the 32 bytes.
+        Self::generate_with(network, 12, "")
+    }
+
+    /// Create a new hot signer from random bytes, with a mnemonic of the given number of words
+    /// (12, 18 or 24) and an optional BIP39 passphrase (empty for none).
+    pub fn generate_with(
+        network: bitcoin::Network,
+        word_count: usize,
+        passphrase: &str,
+    ) -> Result<Self, SignerError> {
+        if ![12, 18, 24].contains(&word_count) {
+            return Err(SignerError::Mnemonic(bip39::Error::BadWordCount(
+                word_count,
+            )));
+        }
+        // Each word encodes 11 bits, of which one out of 33 is checksum.
         let random_32bytes = random::random_bytes().map_err(SignerError::Randomness)?;
-        let mnemonic =
-            bip39::Mnemonic::from_entropy(&random_32bytes[..16]).map_err(SignerError::Mnemonic)?;
-        Self::from_mnemonic(network, mnemonic)
+        let mnemonic = bip39::Mnemonic::from_entropy(&random_32bytes[..word_count * 4 / 3])
+            .map_err(SignerError::Mnemonic)?;
+        Self::from_mnemonic(network, mnemonic, passphrase)
     }
 
     pub fn from_str(network: bitcoin::Network, s: &str) -> Result<Self, SignerError> {
+        Self::from_str_with_passphrase(network, s, "")
+    }
+
+    /// Create a hot signer from a mnemonic of any standard length and a BIP39 passphrase (empty
+    /// for none).
+    pub fn from_str_with_passphrase(
+        network: bitcoin::Network,
+        s: &str,
+        passphrase: &str,
+    ) -> Result<Self, SignerError> {
         let mnemonic = bip39::Mnemonic::from_str(s).map_err(SignerError::Mnemonic)?;
-        Self::from_mnemonic(network, mnemonic)
+        Self::from_mnemonic(network, mnemonic, passphrase)
+    }
+
+    /// Recover a hot signer from SLIP-39 shares created with [`HotSigner::slip39_shares`], and
+    /// a BIP39 passphrase (empty for none).
+    ///
+    /// The shares are interpreted as encoding the BIP39 entropy: the shares of a BIP32 master
+    /// secret created by another SLIP-39 wallet would be recovered as a different key.
+    pub fn from_slip39_shares(
+        network: bitcoin::Network,
+        shares: &[impl AsRef<str>],
+        passphrase: &str,
+    ) -> Result<Self, SignerError> {
+        let entropy = slip39::combine_mnemonics(shares, "").map_err(SignerError::Slip39)?;
+        let mnemonic = bip39::Mnemonic::from_entropy(&entropy).map_err(SignerError::Mnemonic)?;
+        Self::from_mnemonic(network, mnemonic, passphrase)
     }
 
     fn mnemonics_folder(datadir_root: &path::Path, network: bitcoin::Network) -> path::PathBuf {
@@ -137,42 +392,159 @@ impl HotSigner {
     }
 
     /// Read all the mnemonics from the datadir for the given network.
+    ///
+    /// This will fail with [`SignerError::MissingPassword`] if any of the mnemonics is
+    /// encrypted, or [`SignerError::MissingPassphrase`] if any requires a BIP39 passphrase. Use
+    /// [`HotSigner::from_datadir_with_secrets`] to read those.
     pub fn from_datadir(
         datadir_root: &path::Path,
         network: bitcoin::Network,
+    ) -> Result<Vec<Self>, SignerError> {
+        let mut missing = None;
+        let signers = Self::from_datadir_with_secrets(datadir_root, network, |req| {
+            missing.get_or_insert_with(|| match req {
+                SecretRequest::Password(p) => SignerError::MissingPassword(p.to_path_buf()),
+                SecretRequest::Passphrase(p) => SignerError::MissingPassphrase(p.to_path_buf()),
+            });
+            None
+        })?;
+        match missing {
+            Some(e) => Err(e),
+            None => Ok(signers),
+        }
+    }
+
+    /// Read all the mnemonics from the datadir for the given network, decrypting the encrypted
+    /// ones and applying the BIP39 passphrase to those which require one.
+    ///
+    /// The `secret` callback is called for each encrypted mnemonic file and for each mnemonic
+    /// requiring a passphrase. It may return a secret known beforehand or prompt the user for
+    /// one. Returning `None` skips this mnemonic, the others are still read.
+    pub fn from_datadir_with_secrets(
+        datadir_root: &path::Path,
+        network: bitcoin::Network,
+        mut secret: impl FnMut(SecretRequest) -> Option<String>,
     ) -> Result<Vec<Self>, SignerError> {
         let mut signers = Vec::new();
 
         let mnemonic_paths = fs::read_dir(Self::mnemonics_folder(datadir_root, network))
             .map_err(SignerError::MnemonicStorage)?;
         for entry in mnemonic_paths {
-            let mnemonic = fs::read_to_string(entry.map_err(SignerError::MnemonicStorage)?.path())
-                .map_err(SignerError::MnemonicStorage)?;
-            signers.push(Self::from_str(network, &mnemonic)?);
+            let mnemonic_path = entry.map_err(SignerError::MnemonicStorage)?.path();
+            let data = fs::read(&mnemonic_path).map_err(SignerError::MnemonicStorage)?;
+            let content = if is_encrypted_mnemonic(&data) {
+                let password = match secret(SecretRequest::Password(&mnemonic_path)) {
+                    Some(password) => password,
+                    None => continue,
+                };
+                decrypt_mnemonic(&data, &password, &mnemonic_path)?
+            } else {
+                String::from_utf8(data).map_err(|e| {
+                    SignerError::MnemonicStorage(io::Error::new(io::ErrorKind::InvalidData, e))
+                })?
+            };
+            let (mnemonic, has_passphrase) = parse_stored_mnemonic(&content)?;
+            let passphrase = if has_passphrase {
+                match secret(SecretRequest::Passphrase(&mnemonic_path)) {
+                    Some(passphrase) => passphrase,
+                    None => continue,
+                }
+            } else {
+                String::new()
+            };
+            signers.push(Self::from_mnemonic(network, mnemonic, &passphrase)?);
         }
 
         Ok(signers)
     }
 
+    /// Encrypt all the plaintext mnemonics stored in the datadir for the given network with the
+    /// given password. Each plaintext file is removed once its encrypted counterpart was
+    /// written. Mnemonics which are already encrypted are left untouched.
+    ///
+    /// Returns the number of mnemonics which were encrypted.
+    pub fn encrypt_datadir(
+        datadir_root: &path::Path,
+        network: bitcoin::Network,
+        password: &str,
+    ) -> Result<usize, SignerError> {
+        let mut count = 0;
+
+        let mnemonic_paths = fs::read_dir(Self::mnemonics_folder(datadir_root, network))
+            .map_err(SignerError::MnemonicStorage)?;
+        for entry in mnemonic_paths {
+            let mnemonic_path = entry.map_err(SignerError::MnemonicStorage)?.path();
+            let data = fs::read(&mnemonic_path).map_err(SignerError::MnemonicStorage)?;
+            if is_encrypted_mnemonic(&data) {
+                continue;
+            }
+            let content = String::from_utf8(data).map_err(|e| {
+                SignerError::MnemonicStorage(io::Error::new(io::ErrorKind::InvalidData, e))
+            })?;
+            // Make sure we are not about to encrypt garbage. We keep the content as is since
+            // we may not know the passphrase to compute the fingerprint.
+            parse_stored_mnemonic(&content)?;
+            let encrypted_path = mnemonic_path.with_extension("enc");
+            let file_name = encrypted_path
+                .file_name()
+                .expect("Read from a directory")
+                .to_string_lossy();
+            let encrypted = encrypt_mnemonic(&content, password)?;
+            Self::write_mnemonic_file(datadir_root, network, &file_name, &encrypted)?;
+            fs::remove_file(&mnemonic_path).map_err(SignerError::MnemonicStorage)?;
+            count += 1;
+        }
+
+        Ok(count)
+    }
+
     /// The BIP39 mnemonics from which the master key of this signer is derived.
-    pub fn words(&self) -> [&'static str; 12] {
-        let words: Vec<&'static str> = self.mnemonic.words().collect();
-        words.try_into().expect("Always 12 words")
+    pub fn words(&self) -> Vec<&'static str> {
+        self.mnemonic.words().collect()
     }
 
     /// The BIP39 mnemonic words as a string.
     pub fn mnemonic_str(&self) -> String {
-        let mut mnemonic_str = String::with_capacity(12 * 7);
-        let words = self.words();
+        self.words().join(" ")
+    }
 
-        for (i, word) in words.iter().enumerate() {
-            mnemonic_str += word;
-            if i < words.len() - 1 {
-                mnemonic_str += " ";
-            }
-        }
+    /// Split this signer's secret into SLIP-39 shares, for each group. See
+    /// [`slip39::generate_mnemonics`] for the meaning of the parameters.
+    ///
+    /// The secret shared is the BIP39 entropy, not the BIP32 master secret as specified by
+    /// SLIP-39, so the shares can be converted back to the BIP39 mnemonic using
+    /// [`HotSigner::from_slip39_shares`]. This makes them specific to Liana: restoring them in
+    /// another SLIP-39 wallet would result in a different key. The BIP39 passphrase, if any, is
+    /// not part of the shares and must be backed up separately.
+    pub fn slip39_shares(
+        &self,
+        group_threshold: u8,
+        groups: &[slip39::Group],
+    ) -> Result<Vec<Vec<String>>, SignerError> {
+        slip39::generate_mnemonics(
+            group_threshold,
+            groups,
+            &self.mnemonic.to_entropy(),
+            "",
+            true,
+            SLIP39_ITERATION_EXPONENT,
+        )
+        .map_err(SignerError::Slip39)
+    }
+
+    /// Whether the master key of this signer is derived using a BIP39 passphrase.
+    pub fn has_passphrase(&self) -> bool {
+        self.has_passphrase
+    }
 
-        mnemonic_str
+    // The content of the file this mnemonic is stored in, before any encryption.
+    fn stored_content(&self) -> String {
+        let mut content = self.mnemonic_str();
+        if self.has_passphrase {
+            content.push('\n');
+            content += STORED_PASSPHRASE_MARKER;
+        }
+        content
     }
 
     /// Get the fingerprint of the master xpub for this signer.
@@ -185,12 +557,43 @@ impl HotSigner {
 
     /// Store the mnemonic in a file within the given "data directory".
     /// The file is stored within a "mnemonics" folder, with the filename set to the fingerprint of
-    /// the master xpub corresponding to this mnemonic.
+    /// the master xpub corresponding to this mnemonic. If a BIP39 passphrase is used, only the
+    /// fact that one is required is recorded.
     pub fn store(
         &self,
         datadir_root: &path::Path,
         network: bitcoin::Network,
         secp: &secp256k1::Secp256k1<impl secp256k1::Signing>,
+    ) -> Result<(), SignerError> {
+        let file_name = format!("mnemonic-{:x}.txt", self.fingerprint(secp));
+        Self::write_mnemonic_file(
+            datadir_root,
+            network,
+            &file_name,
+            self.stored_content().as_bytes(),
+        )
+    }
+
+    /// Store the mnemonic encrypted with the given password in a file within the given "data
+    /// directory". Like for [`HotSigner::store`] the file is stored within a "mnemonics" folder
+    /// and named after the fingerprint of the master xpub, but with a `.enc` extension.
+    pub fn store_encrypted(
+        &self,
+        datadir_root: &path::Path,
+        network: bitcoin::Network,
+        secp: &secp256k1::Secp256k1<impl secp256k1::Signing>,
+        password: &str,
+    ) -> Result<(), SignerError> {
+        let file_name = format!("mnemonic-{:x}.enc", self.fingerprint(secp));
+        let data = encrypt_mnemonic(&self.stored_content(), password)?;
+        Self::write_mnemonic_file(datadir_root, network, &file_name, &data)
+    }
+
+    fn write_mnemonic_file(
+        datadir_root: &path::Path,
+        network: bitcoin::Network,
+        file_name: &str,
+        data: &[u8],
     ) -> Result<(), SignerError> {
         let mut mnemonics_folder = Self::mnemonics_folder(datadir_root, network);
         if !mnemonics_folder.exists() {
@@ -198,12 +601,15 @@ impl HotSigner {
         }
 
         // This will fail if a file with this fingerprint exists already.
-        mnemonics_folder.push(format!("mnemonic-{:x}.txt", self.fingerprint(secp)));
+        mnemonics_folder.push(file_name);
         let mnemonic_path = mnemonics_folder;
         let mut mnemonic_file =
             create_file(&mnemonic_path).map_err(SignerError::MnemonicStorage)?;
         mnemonic_file
-            .write_all(self.mnemonic_str().as_bytes())
+            .write_all(data)
+            .map_err(SignerError::MnemonicStorage)?;
+        mnemonic_file
+            .sync_all()
             .map_err(SignerError::MnemonicStorage)?;
 
         Ok(())
@@ -229,113 +635,127 @@ impl HotSigner {
         bip32::Xpub::from_priv(secp, &xpriv)
     }
 
-    // Provide an ECDSA signature for this transaction input from the PSBT input information.
-    fn sign_p2wsh(
+    /// Get a BIP-129 key record for the extended public key at the given derivation path, signed
+    /// with the corresponding private key.
+    pub fn bsms_key_record(
         &self,
+        token: bsms::Token,
+        der_path: &bip32::DerivationPath,
+        description: String,
         secp: &secp256k1::Secp256k1<impl secp256k1::Signing>,
-        sighash_cache: &mut sighash::SighashCache<&bitcoin::Transaction>,
-        master_fingerprint: bip32::Fingerprint,
-        psbt_in: &mut PsbtIn,
-        input_index: usize,
-    ) -> Result<(), SignerError> {
-        // First of all compute the sighash for this input. We assume P2WSH spend: the sighash
-        // script code is always the witness script.
-        let witscript = psbt_in
-            .witness_script
-            .as_ref()
-            .ok_or(SignerError::IncompletePsbt)?;
-        let value = psbt_in
-            .witness_utxo
-            .as_ref()
-            .ok_or(SignerError::IncompletePsbt)?
-            .value;
-        let sighash_type = sighash::EcdsaSighashType::All;
-        let sighash = sighash_cache
-            .p2wsh_signature_hash(input_index, witscript, value, sighash_type)
-            .map_err(|_| SignerError::InsanePsbt)?;
-        let sighash = secp256k1::Message::from_digest_slice(sighash.as_byte_array())
-            .expect("Sighash is always 32 bytes.");
-
-        // Then provide a signature for all the keys they asked for.
-        for (curr_pubkey, (fingerprint, der_path)) in psbt_in.bip32_derivation.iter() {
-            if *fingerprint != master_fingerprint {
-                continue;
-            }
-            let privkey = self.xpriv_at(der_path, secp).to_priv();
-            let pubkey = privkey.public_key(secp);
-            if pubkey.inner != *curr_pubkey {
-                return Err(SignerError::InsanePsbt);
-            }
-            let signature = secp.sign_ecdsa_low_r(&sighash, &privkey.inner);
-            psbt_in.partial_sigs.insert(
-                pubkey,
-                ecdsa::Signature {
-                    signature,
-                    sighash_type,
-                },
-            );
-        }
-
-        Ok(())
+    ) -> bsms::KeyRecord {
+        let xpriv = self.xpriv_at(der_path, secp);
+        bsms::KeyRecord::new(
+            token,
+            self.fingerprint(secp),
+            der_path.clone(),
+            &xpriv,
+            description,
+            secp,
+        )
     }
 
-    // Provide a BIP340 signature for this transaction input from the PSBT input information.
-    fn sign_taproot(
+    /// Sign all inputs of the given PSBT.
+    ///
+    /// **This does not perform any check. It will blindly sign anything that's passed.**
+    pub fn sign_psbt(
         &self,
+        psbt: Psbt,
         secp: &secp256k1::Secp256k1<secp256k1::All>,
-        sighash_cache: &mut sighash::SighashCache<&bitcoin::Transaction>,
-        master_fingerprint: bip32::Fingerprint,
-        prevouts: &[bitcoin::TxOut],
-        psbt_in: &mut PsbtIn,
-        input_index: usize,
-    ) -> Result<(), SignerError> {
-        let sighash_type = sighash::TapSighashType::Default;
-        let prevouts = sighash::Prevouts::All(prevouts);
-
-        // If the details of the internal key are filled, provide a keypath signature.
-        if let Some(ref int_key) = psbt_in.tap_internal_key {
-            // NB: we don't check for empty leaf hashes on purpose, in case the internal key also
-            // appears in a leaf.
-            if let Some((_, (fg, der_path))) = psbt_in.tap_key_origins.get(int_key) {
-                if *fg == master_fingerprint {
-                    let privkey = self.xpriv_at(der_path, secp).to_priv();
-                    let keypair = secp256k1::Keypair::from_secret_key(secp, &privkey.inner);
-                    if keypair.x_only_public_key().0 != *int_key {
-                        return Err(SignerError::InsanePsbt);
-                    }
-                    let keypair = keypair.tap_tweak(secp, psbt_in.tap_merkle_root).to_inner();
-                    let sighash = sighash_cache
-                        .taproot_key_spend_signature_hash(input_index, &prevouts, sighash_type)
-                        .map_err(|_| SignerError::InsanePsbt)?;
-                    let sighash = secp256k1::Message::from_digest_slice(sighash.as_byte_array())
-                        .expect("Sighash is always 32 bytes.");
-                    let signature = secp.sign_schnorr_no_aux_rand(&sighash, &keypair);
-                    let sig = bitcoin::taproot::Signature {
-                        signature,
-                        sighash_type,
-                    };
-                    psbt_in.tap_key_sig = Some(sig);
-                }
+    ) -> Result<Psbt, SignerError> {
+        let master_fingerprint = self.fingerprint(secp);
+        sign_psbt_inputs(psbt, secp, &|fingerprint, der_path| {
+            if *fingerprint == master_fingerprint {
+                Some(self.xpriv_at(der_path, secp).to_priv())
+            } else {
+                None
             }
+        })
+    }
+
+    /// Change the network of generated extended keys. Note this value only has to do with the
+    /// BIP32 encoding of those keys (xpubs, tpubs, ..) but does not affect any data (whether it is
+    /// the keys or the mnemonics).
+    pub fn set_network(&mut self, network: bitcoin::Network) {
+        self.master_xpriv.network = network.into();
+    }
+}
+
+// Provide an ECDSA signature for this transaction input from the PSBT input information, for all
+// the keys `privkey_at` returns a private key for.
+fn sign_p2wsh(
+    secp: &secp256k1::Secp256k1<impl secp256k1::Signing>,
+    sighash_cache: &mut sighash::SighashCache<&bitcoin::Transaction>,
+    privkey_at: &impl Fn(&bip32::Fingerprint, &bip32::DerivationPath) -> Option<bitcoin::PrivateKey>,
+    psbt_in: &mut PsbtIn,
+    input_index: usize,
+) -> Result<(), SignerError> {
+    // First of all compute the sighash for this input. We assume P2WSH spend: the sighash
+    // script code is always the witness script.
+    let witscript = psbt_in
+        .witness_script
+        .as_ref()
+        .ok_or(SignerError::IncompletePsbt)?;
+    let value = psbt_in
+        .witness_utxo
+        .as_ref()
+        .ok_or(SignerError::IncompletePsbt)?
+        .value;
+    let sighash_type = sighash::EcdsaSighashType::All;
+    let sighash = sighash_cache
+        .p2wsh_signature_hash(input_index, witscript, value, sighash_type)
+        .map_err(|_| SignerError::InsanePsbt)?;
+    let sighash = secp256k1::Message::from_digest_slice(sighash.as_byte_array())
+        .expect("Sighash is always 32 bytes.");
+
+    // Then provide a signature for all the keys they asked for.
+    for (curr_pubkey, (fingerprint, der_path)) in psbt_in.bip32_derivation.iter() {
+        let privkey = match privkey_at(fingerprint, der_path) {
+            Some(privkey) => privkey,
+            None => continue,
+        };
+        let pubkey = privkey.public_key(secp);
+        if pubkey.inner != *curr_pubkey {
+            return Err(SignerError::InsanePsbt);
         }
+        let signature = secp.sign_ecdsa_low_r(&sighash, &privkey.inner);
+        psbt_in.partial_sigs.insert(
+            pubkey,
+            ecdsa::Signature {
+                signature,
+                sighash_type,
+            },
+        );
+    }
 
-        // Now sign for all the public keys derived from our master secret, in all the leaves where
-        // they are present.
-        for (pubkey, (leaf_hashes, (fg, der_path))) in &psbt_in.tap_key_origins {
-            if *fg != master_fingerprint {
-                continue;
-            }
+    Ok(())
+}
 
-            for leaf_hash in leaf_hashes {
-                let privkey = self.xpriv_at(der_path, secp).to_priv();
+// Provide a BIP340 signature for this transaction input from the PSBT input information.
+fn sign_taproot(
+    secp: &secp256k1::Secp256k1<secp256k1::All>,
+    sighash_cache: &mut sighash::SighashCache<&bitcoin::Transaction>,
+    privkey_at: &impl Fn(&bip32::Fingerprint, &bip32::DerivationPath) -> Option<bitcoin::PrivateKey>,
+    prevouts: &[bitcoin::TxOut],
+    psbt_in: &mut PsbtIn,
+    input_index: usize,
+) -> Result<(), SignerError> {
+    let sighash_type = sighash::TapSighashType::Default;
+    let prevouts = sighash::Prevouts::All(prevouts);
+
+    // If the details of the internal key are filled, provide a keypath signature.
+    if let Some(ref int_key) = psbt_in.tap_internal_key {
+        // NB: we don't check for empty leaf hashes on purpose, in case the internal key also
+        // appears in a leaf.
+        if let Some((_, (fg, der_path))) = psbt_in.tap_key_origins.get(int_key) {
+            if let Some(privkey) = privkey_at(fg, der_path) {
                 let keypair = secp256k1::Keypair::from_secret_key(secp, &privkey.inner);
+                if keypair.x_only_public_key().0 != *int_key {
+                    return Err(SignerError::InsanePsbt);
+                }
+                let keypair = keypair.tap_tweak(secp, psbt_in.tap_merkle_root).to_inner();
                 let sighash = sighash_cache
-                    .taproot_script_spend_signature_hash(
-                        input_index,
-                        &prevouts,
-                        *leaf_hash,
-                        sighash_type,
-                    )
+                    .taproot_key_spend_signature_hash(input_index, &prevouts, sighash_type)
                     .map_err(|_| SignerError::InsanePsbt)?;
                 let sighash = secp256k1::Message::from_digest_slice(sighash.as_byte_array())
                     .expect("Sighash is always 32 bytes.");
@@ -344,63 +764,316 @@ impl HotSigner {
                     signature,
                     sighash_type,
                 };
-                psbt_in.tap_script_sigs.insert((*pubkey, *leaf_hash), sig);
+                psbt_in.tap_key_sig = Some(sig);
             }
         }
+    }
+
+    // Now sign for all the public keys we hold the private key for, in all the leaves where
+    // they are present.
+    for (pubkey, (leaf_hashes, (fg, der_path))) in &psbt_in.tap_key_origins {
+        let privkey = match privkey_at(fg, der_path) {
+            Some(privkey) => privkey,
+            None => continue,
+        };
+
+        let keypair = secp256k1::Keypair::from_secret_key(secp, &privkey.inner);
+        if keypair.x_only_public_key().0 != *pubkey {
+            return Err(SignerError::InsanePsbt);
+        }
 
-        Ok(())
+        for leaf_hash in leaf_hashes {
+            let sighash = sighash_cache
+                .taproot_script_spend_signature_hash(
+                    input_index,
+                    &prevouts,
+                    *leaf_hash,
+                    sighash_type,
+                )
+                .map_err(|_| SignerError::InsanePsbt)?;
+            let sighash = secp256k1::Message::from_digest_slice(sighash.as_byte_array())
+                .expect("Sighash is always 32 bytes.");
+            let signature = secp.sign_schnorr_no_aux_rand(&sighash, &keypair);
+            let sig = bitcoin::taproot::Signature {
+                signature,
+                sighash_type,
+            };
+            psbt_in.tap_script_sigs.insert((*pubkey, *leaf_hash), sig);
+        }
     }
 
-    /// Sign all inputs of the given PSBT.
-    ///
-    /// **This does not perform any check. It will blindly sign anything that's passed.**
-    pub fn sign_psbt(
+    Ok(())
+}
+
+// Sign all inputs of the given PSBT with the private keys returned by `privkey_at` for the key
+// origins found in the PSBT inputs.
+fn sign_psbt_inputs(
+    mut psbt: Psbt,
+    secp: &secp256k1::Secp256k1<secp256k1::All>,
+    privkey_at: &impl Fn(&bip32::Fingerprint, &bip32::DerivationPath) -> Option<bitcoin::PrivateKey>,
+) -> Result<Psbt, SignerError> {
+    let mut sighash_cache = sighash::SighashCache::new(&psbt.unsigned_tx);
+
+    let prevouts: Vec<_> = psbt
+        .inputs
+        .iter()
+        .filter_map(|psbt_in| psbt_in.witness_utxo.clone())
+        .collect();
+    if prevouts.len() != psbt.inputs.len() {
+        return Err(SignerError::IncompletePsbt);
+    }
+
+    // Sign each input in the PSBT.
+    for i in 0..psbt.inputs.len() {
+        if psbt.inputs[i].witness_script.is_some() {
+            sign_p2wsh(secp, &mut sighash_cache, privkey_at, &mut psbt.inputs[i], i)?;
+        } else {
+            sign_taproot(
+                secp,
+                &mut sighash_cache,
+                privkey_at,
+                &prevouts,
+                &mut psbt.inputs[i],
+                i,
+            )?;
+        }
+    }
+
+    Ok(psbt)
+}
+
+// Miniscript can't parse descriptors containing multipath extended private keys. We only care
+// about the origin of the keys and not the derivation steps after them, so replace all multipath
+// steps (`<0;1>`) with their first derivation index.
+fn first_multipath_step(desc_str: &str) -> String {
+    let mut res = String::with_capacity(desc_str.len());
+    let mut rest = desc_str;
+    while let Some(start) = rest.find('<') {
+        let end = match rest[start..].find('>') {
+            Some(end) => start + end,
+            None => break,
+        };
+        res.push_str(&rest[..start]);
+        let steps = &rest[start + 1..end];
+        res.push_str(steps.split(';').next().unwrap_or(steps));
+        rest = &rest[end + 1..];
+    }
+    res.push_str(rest);
+    res
+}
+
+// Check the checksum of a descriptor string, if it has one, and return the descriptor without it.
+fn strip_desc_checksum(s: &str) -> Result<&str, SignerError> {
+    let mut parts = s.splitn(2, '#');
+    let desc_str = parts.next().expect("Always at least one part.");
+    if let Some(checksum) = parts.next() {
+        let expected = descriptor::checksum::desc_checksum(desc_str)
+            .map_err(|e| SignerError::InvalidPrivateKey(e.to_string()))?;
+        if checksum != expected {
+            return Err(SignerError::InvalidPrivateKey(format!(
+                "invalid descriptor checksum '{}', expected '{}'",
+                checksum, expected
+            )));
+        }
+    }
+    Ok(desc_str)
+}
+
+/// A key held by an [`XprvSigner`], along with its origin.
+#[derive(Debug, Clone)]
+struct OriginXpriv {
+    master_fingerprint: bip32::Fingerprint,
+    origin_path: bip32::DerivationPath,
+    xpriv: bip32::Xpriv,
+}
+
+impl OriginXpriv {
+    // A key without origin must be a master key, otherwise we couldn't tell where it's derived
+    // from.
+    fn new(
+        xpriv: bip32::Xpriv,
+        origin: Option<(bip32::Fingerprint, bip32::DerivationPath)>,
+        secp: &secp256k1::Secp256k1<impl secp256k1::Signing>,
+    ) -> Result<Self, SignerError> {
+        let (master_fingerprint, origin_path) = match origin {
+            Some(origin) => origin,
+            None if xpriv.depth == 0 => (xpriv.fingerprint(secp), bip32::DerivationPath::master()),
+            None => {
+                return Err(SignerError::InvalidPrivateKey(
+                    "the origin of a non-master extended private key must be given".to_string(),
+                ))
+            }
+        };
+        Ok(Self {
+            master_fingerprint,
+            origin_path,
+            xpriv,
+        })
+    }
+
+    // Get the private key at this full derivation path from the master key, if it's derived
+    // from this extended key.
+    fn privkey_at(
         &self,
-        mut psbt: Psbt,
-        secp: &secp256k1::Secp256k1<secp256k1::All>,
-    ) -> Result<Psbt, SignerError> {
-        let master_fingerprint = self.fingerprint(secp);
-        let mut sighash_cache = sighash::SighashCache::new(&psbt.unsigned_tx);
+        fingerprint: &bip32::Fingerprint,
+        der_path: &bip32::DerivationPath,
+        secp: &secp256k1::Secp256k1<impl secp256k1::Signing>,
+    ) -> Option<bitcoin::PrivateKey> {
+        let rem_path = self.remaining_path(fingerprint, der_path)?;
+        self.xpriv
+            .derive_priv(secp, &rem_path)
+            .ok()
+            .map(|xpriv| xpriv.to_priv())
+    }
 
-        let prevouts: Vec<_> = psbt
-            .inputs
-            .iter()
-            .filter_map(|psbt_in| psbt_in.witness_utxo.clone())
-            .collect();
-        if prevouts.len() != psbt.inputs.len() {
-            return Err(SignerError::IncompletePsbt);
+    // The derivation path from this extended key to the key at this full derivation path from the
+    // master key, if it's derived from this extended key.
+    fn remaining_path(
+        &self,
+        fingerprint: &bip32::Fingerprint,
+        der_path: &bip32::DerivationPath,
+    ) -> Option<bip32::DerivationPath> {
+        if *fingerprint != self.master_fingerprint {
+            return None;
         }
+        let origin_len = self.origin_path.len();
+        if der_path.len() < origin_len || der_path[..origin_len] != self.origin_path[..] {
+            return None;
+        }
+        Some(der_path[origin_len..].into())
+    }
+}
 
-        // Sign each input in the PSBT.
-        for i in 0..psbt.inputs.len() {
-            if psbt.inputs[i].witness_script.is_some() {
-                self.sign_p2wsh(
-                    secp,
-                    &mut sighash_cache,
-                    master_fingerprint,
-                    &mut psbt.inputs[i],
-                    i,
-                )?;
-            } else {
-                self.sign_taproot(
-                    secp,
-                    &mut sighash_cache,
-                    master_fingerprint,
-                    &prevouts,
-                    &mut psbt.inputs[i],
-                    i,
-                )?;
+/// A signer using extended private keys directly, instead of a BIP39 mnemonic.
+///
+/// This is useful for signers whose secrets were never backed up as a mnemonic, such as extended
+/// private keys exported from a HSM. Each extended private key is stored along with its origin
+/// (master key fingerprint and derivation path from the master key), which is used to find the
+/// keys to sign with in a PSBT.
+#[derive(Debug, Clone)]
+pub struct XprvSigner {
+    keys: Vec<OriginXpriv>,
+}
+
+impl XprvSigner {
+    /// Create a signer from an extended private key. The origin may only be omitted for a master
+    /// key.
+    pub fn from_xpriv(
+        xpriv: bip32::Xpriv,
+        origin: Option<(bip32::Fingerprint, bip32::DerivationPath)>,
+        secp: &secp256k1::Secp256k1<impl secp256k1::Signing>,
+    ) -> Result<Self, SignerError> {
+        Ok(Self {
+            keys: vec![OriginXpriv::new(xpriv, origin, secp)?],
+        })
+    }
+
+    /// Create a signer from either a descriptor key containing an extended private key (such as
+    /// `[aabbccdd/48'/1'/0'/2']tprv.../<0;1>/*`), or from a descriptor containing any number of
+    /// such keys. The origin may only be omitted for master keys.
+    pub fn from_str(
+        s: &str,
+        secp: &secp256k1::Secp256k1<impl secp256k1::Signing>,
+    ) -> Result<Self, SignerError> {
+        let secret_keys = match descriptor::DescriptorSecretKey::from_str(s) {
+            Ok(key) => vec![key],
+            Err(_) => {
+                // The checksum is computed over the original descriptor string, so it must be
+                // checked (and removed) before the multipath steps are rewritten.
+                let desc_str = strip_desc_checksum(s)?;
+                let (_, key_map) =
+                    descriptor::Descriptor::<descriptor::DescriptorPublicKey>::parse_descriptor(
+                        secp,
+                        &first_multipath_step(desc_str),
+                    )
+                    .map_err(|e| SignerError::InvalidPrivateKey(e.to_string()))?;
+                key_map.into_values().collect()
             }
+        };
+        if secret_keys.is_empty() {
+            return Err(SignerError::InvalidPrivateKey(
+                "no private key in descriptor".to_string(),
+            ));
         }
 
-        Ok(psbt)
+        let keys = secret_keys
+            .into_iter()
+            .map(|key| {
+                let (origin, xpriv) = match key {
+                    descriptor::DescriptorSecretKey::XPrv(xkey) => (xkey.origin, xkey.xkey),
+                    descriptor::DescriptorSecretKey::MultiXPrv(xkey) => (xkey.origin, xkey.xkey),
+                    descriptor::DescriptorSecretKey::Single(_) => {
+                        return Err(SignerError::InvalidPrivateKey(
+                            "only extended private keys are supported".to_string(),
+                        ))
+                    }
+                };
+                OriginXpriv::new(xpriv, origin, secp)
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { keys })
     }
 
-    /// Change the network of generated extended keys. Note this value only has to do with the
-    /// BIP32 encoding of those keys (xpubs, tpubs, ..) but does not affect any data (whether it is
-    /// the keys or the mnemonics).
-    pub fn set_network(&mut self, network: bitcoin::Network) {
-        self.master_xpriv.network = network.into();
+    /// The fingerprints of the master keys this signer holds extended private keys for.
+    pub fn fingerprints(&self) -> Vec<bip32::Fingerprint> {
+        let mut fingerprints: Vec<_> = self.keys.iter().map(|k| k.master_fingerprint).collect();
+        fingerprints.sort();
+        fingerprints.dedup();
+        fingerprints
+    }
+
+    /// Check that each of the extended private keys of this signer corresponds to a key of
+    /// this descriptor, with the same origin.
+    pub fn validate_against(
+        &self,
+        desc: &descriptors::LianaDescriptor,
+        secp: &secp256k1::Secp256k1<impl secp256k1::Signing>,
+    ) -> Result<(), SignerError> {
+        let mut desc_keys = Vec::new();
+        desc.receive_descriptor()
+            .as_descriptor_public_key()
+            .for_each_key(|key| {
+                if let descriptor::DescriptorPublicKey::XPub(xkey) = key {
+                    desc_keys.push(xkey.clone());
+                }
+                true
+            });
+
+        for key in &self.keys {
+            let is_in_desc = desc_keys.iter().any(|desc_key| {
+                let (desc_fg, desc_origin_path) = match desc_key.origin {
+                    Some((fg, ref path)) => (fg, path.clone()),
+                    None => (desc_key.xkey.fingerprint(), bip32::DerivationPath::master()),
+                };
+                key.remaining_path(&desc_fg, &desc_origin_path)
+                    .and_then(|rem_path| key.xpriv.derive_priv(secp, &rem_path).ok())
+                    .map(|xpriv| bip32::Xpub::from_priv(secp, &xpriv) == desc_key.xkey)
+                    .unwrap_or(false)
+            });
+            if !is_in_desc {
+                return Err(SignerError::KeyNotInDescriptor(
+                    key.master_fingerprint,
+                    key.origin_path.clone(),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Sign all inputs of the given PSBT.
+    ///
+    /// **This does not perform any check. It will blindly sign anything that's passed.**
+    pub fn sign_psbt(
+        &self,
+        psbt: Psbt,
+        secp: &secp256k1::Secp256k1<secp256k1::All>,
+    ) -> Result<Psbt, SignerError> {
+        sign_psbt_inputs(psbt, secp, &|fingerprint, der_path| {
+            self.keys
+                .iter()
+                .find_map(|key| key.privkey_at(fingerprint, der_path, secp))
+        })
     }
 }
 
@@ -411,6 +1084,7 @@ mod tests {
     use miniscript::{
         bitcoin::{locktime::absolute, psbt::Input as PsbtIn, Amount},
         descriptor::{DerivPaths, DescriptorMultiXKey, DescriptorPublicKey, Wildcard},
+        psbt::PsbtExt,
     };
     use std::collections::{BTreeMap, HashSet};
 
@@ -485,6 +1159,204 @@ mod tests {
         fs::remove_dir_all(tmp_dir).unwrap();
     }
 
+    #[test]
+    fn hot_signer_passphrase() {
+        let secp = secp256k1::Secp256k1::signing_only();
+        let tmp_dir = tmp_dir();
+        fs::create_dir_all(&tmp_dir).unwrap();
+        let network = bitcoin::Network::Signet;
+
+        // We can generate mnemonics of all standard lengths, but not others.
+        for word_count in [12, 18, 24] {
+            let signer = HotSigner::generate_with(network, word_count, "").unwrap();
+            assert_eq!(signer.words().len(), word_count);
+            assert!(!signer.has_passphrase());
+        }
+        assert!(matches!(
+            HotSigner::generate_with(network, 15, ""),
+            Err(SignerError::Mnemonic(bip39::Error::BadWordCount(15)))
+        ));
+
+        // The passphrase changes the master key.
+        let signer = HotSigner::generate_with(network, 24, "hunter2").unwrap();
+        assert!(signer.has_passphrase());
+        let mnemonic_str = signer.mnemonic_str();
+        let no_passphrase = HotSigner::from_str(network, &mnemonic_str).unwrap();
+        assert_ne!(signer.fingerprint(&secp), no_passphrase.fingerprint(&secp));
+        let with_passphrase =
+            HotSigner::from_str_with_passphrase(network, &mnemonic_str, "hunter2").unwrap();
+        assert_eq!(
+            signer.fingerprint(&secp),
+            with_passphrase.fingerprint(&secp)
+        );
+
+        // Only the need for a passphrase is stored, not the passphrase itself.
+        signer.store(&tmp_dir, network, &secp).unwrap();
+        let mnemonic_path = HotSigner::mnemonics_folder(&tmp_dir, network)
+            .join(format!("mnemonic-{:x}.txt", signer.fingerprint(&secp)));
+        let content = fs::read_to_string(&mnemonic_path).unwrap();
+        assert!(!content.contains("hunter2"));
+        assert!(matches!(
+            HotSigner::from_datadir(&tmp_dir, network),
+            Err(SignerError::MissingPassphrase(p)) if p == mnemonic_path
+        ));
+        let signers = HotSigner::from_datadir_with_secrets(&tmp_dir, network, |req| {
+            assert_eq!(req, SecretRequest::Passphrase(&mnemonic_path));
+            Some("hunter2".to_string())
+        })
+        .unwrap();
+        assert_eq!(signers.len(), 1);
+        assert_eq!(signers[0].words(), signer.words());
+        assert_eq!(signers[0].fingerprint(&secp), signer.fingerprint(&secp));
+
+        // Declining to give the passphrase only skips this mnemonic.
+        let other = HotSigner::generate(network).unwrap();
+        other.store(&tmp_dir, network, &secp).unwrap();
+        let signers = HotSigner::from_datadir_with_secrets(&tmp_dir, network, |_| None).unwrap();
+        assert_eq!(signers.len(), 1);
+        assert_eq!(signers[0].fingerprint(&secp), other.fingerprint(&secp));
+        assert!(matches!(
+            HotSigner::from_datadir(&tmp_dir, network),
+            Err(SignerError::MissingPassphrase(p)) if p == mnemonic_path
+        ));
+        fs::remove_file(
+            HotSigner::mnemonics_folder(&tmp_dir, network)
+                .join(format!("mnemonic-{:x}.txt", other.fingerprint(&secp))),
+        )
+        .unwrap();
+
+        // It's preserved when encrypting the mnemonic, too.
+        assert_eq!(
+            HotSigner::encrypt_datadir(&tmp_dir, network, "password").unwrap(),
+            1
+        );
+        let signers = HotSigner::from_datadir_with_secrets(&tmp_dir, network, |req| match req {
+            SecretRequest::Password(_) => Some("password".to_string()),
+            SecretRequest::Passphrase(_) => Some("hunter2".to_string()),
+        })
+        .unwrap();
+        assert_eq!(signers[0].fingerprint(&secp), signer.fingerprint(&secp));
+
+        fs::remove_dir_all(tmp_dir).unwrap();
+    }
+
+    #[test]
+    fn hot_signer_slip39() {
+        let secp = secp256k1::Secp256k1::signing_only();
+        let network = bitcoin::Network::Signet;
+        let signer = HotSigner::generate_with(network, 24, "hunter2").unwrap();
+
+        // Split among the children, 2 of which are needed, or the notary alone.
+        let groups = [
+            slip39::Group {
+                member_threshold: 2,
+                member_count: 3,
+            },
+            slip39::Group {
+                member_threshold: 1,
+                member_count: 1,
+            },
+        ];
+        let shares = signer.slip39_shares(1, &groups).unwrap();
+        let recovered = HotSigner::from_slip39_shares(network, &shares[0][1..], "hunter2").unwrap();
+        assert_eq!(recovered.words(), signer.words());
+        assert_eq!(recovered.fingerprint(&secp), signer.fingerprint(&secp));
+        let recovered = HotSigner::from_slip39_shares(network, &shares[1], "hunter2").unwrap();
+        assert_eq!(recovered.fingerprint(&secp), signer.fingerprint(&secp));
+        assert!(matches!(
+            HotSigner::from_slip39_shares(network, &shares[0][..1], "hunter2"),
+            Err(SignerError::Slip39(slip39::Slip39Error::InvalidShareSet(_)))
+        ));
+    }
+
+    #[test]
+    fn hot_signer_encrypted_storage() {
+        let secp = secp256k1::Secp256k1::signing_only();
+        let tmp_dir = tmp_dir();
+        fs::create_dir_all(&tmp_dir).unwrap();
+        let network = bitcoin::Network::Signet;
+        let password = "correct horse battery staple";
+
+        // An encrypted mnemonic can't be read without the password, nor with a wrong one.
+        let signer = HotSigner::generate(network).unwrap();
+        signer
+            .store_encrypted(&tmp_dir, network, &secp, password)
+            .unwrap();
+        let mnemonic_path = HotSigner::mnemonics_folder(&tmp_dir, network)
+            .join(format!("mnemonic-{:x}.enc", signer.fingerprint(&secp)));
+        let data = fs::read(&mnemonic_path).unwrap();
+        assert!(data.starts_with(ENCRYPTED_MNEMONIC_MAGIC));
+        assert!(!String::from_utf8_lossy(&data).contains(signer.words()[0]));
+        assert!(matches!(
+            HotSigner::from_datadir(&tmp_dir, network),
+            Err(SignerError::MissingPassword(p)) if p == mnemonic_path
+        ));
+        assert!(matches!(
+            HotSigner::from_datadir_with_secrets(&tmp_dir, network, |_| Some("wrong".into())),
+            Err(SignerError::MnemonicDecryption(p)) if p == mnemonic_path
+        ));
+
+        // A tampered header is detected.
+        let mut tampered = data.clone();
+        tampered[ENCRYPTED_MNEMONIC_HEADER_LEN - 1] ^= 1;
+        assert!(matches!(
+            decrypt_mnemonic(&tampered, password, &mnemonic_path),
+            Err(SignerError::MnemonicDecryption(_))
+        ));
+        let mut unknown_version = data.clone();
+        unknown_version[ENCRYPTED_MNEMONIC_MAGIC.len()] = 2;
+        assert!(matches!(
+            decrypt_mnemonic(&unknown_version, password, &mnemonic_path),
+            Err(SignerError::InvalidEncryptedMnemonic(_))
+        ));
+        // Excessive key derivation parameters are rejected before deriving the key.
+        for offset in [9, 13, 17] {
+            let mut expensive = data.clone();
+            expensive[offset..offset + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+            assert!(matches!(
+                decrypt_mnemonic(&expensive, password, &mnemonic_path),
+                Err(SignerError::InvalidEncryptedMnemonic(_))
+            ));
+        }
+
+        // Store a couple plaintext mnemonics and migrate them. They can all be read with the
+        // password afterward, and no plaintext file is left.
+        let mut words_set: HashSet<_> = (0..2)
+            .map(|_| {
+                let signer = HotSigner::generate(network).unwrap();
+                signer.store(&tmp_dir, network, &secp).unwrap();
+                signer.words()
+            })
+            .collect();
+        words_set.insert(signer.words());
+        assert_eq!(
+            HotSigner::encrypt_datadir(&tmp_dir, network, password).unwrap(),
+            2
+        );
+        assert_eq!(
+            HotSigner::encrypt_datadir(&tmp_dir, network, password).unwrap(),
+            0
+        );
+        let mut prompted = 0;
+        let words_read: HashSet<_> =
+            HotSigner::from_datadir_with_secrets(&tmp_dir, network, |_| {
+                prompted += 1;
+                Some(password.to_string())
+            })
+            .unwrap()
+            .into_iter()
+            .map(|signer| signer.words())
+            .collect();
+        assert_eq!(words_set, words_read);
+        assert_eq!(prompted, 3);
+        for entry in fs::read_dir(HotSigner::mnemonics_folder(&tmp_dir, network)).unwrap() {
+            let path = entry.unwrap().path();
+            assert_eq!(path.extension().unwrap(), "enc");
+        }
+
+        fs::remove_dir_all(tmp_dir).unwrap();
+    }
+
     #[test]
     fn hot_signer_sign_p2wsh() {
         let secp = secp256k1::Secp256k1::new();
@@ -1062,6 +1934,441 @@ mod tests {
             .all(|psbt_in| psbt_in.partial_sigs.is_empty()));
     }
 
+    #[test]
+    fn hot_signer_sign_hashlock() {
+        let secp = secp256k1::Secp256k1::new();
+        let network = bitcoin::Network::Bitcoin;
+
+        // A Liana descriptor with a single key for each of the primary, recovery and hash-locked
+        // spending paths.
+        let (prim_signer, recov_signer, hashlock_signer) = (
+            HotSigner::generate(network).unwrap(),
+            HotSigner::generate(network).unwrap(),
+            HotSigner::generate(network).unwrap(),
+        );
+        let desc_key = |signer: &HotSigner| {
+            let origin_der = bip32::DerivationPath::from_str("m/48'/0'/0'/2'").unwrap();
+            let xkey = signer.xpub_at(&origin_der, &secp);
+            DescriptorPublicKey::MultiXPub(DescriptorMultiXKey {
+                origin: Some((signer.fingerprint(&secp), origin_der)),
+                xkey,
+                derivation_paths: DerivPaths::new(vec![
+                    bip32::DerivationPath::from_str("m/0").unwrap(),
+                    bip32::DerivationPath::from_str("m/1").unwrap(),
+                ])
+                .unwrap(),
+                wildcard: Wildcard::Unhardened,
+            })
+        };
+        let preimage = [0xaa; 32];
+        let prim_keys = descriptors::PathInfo::Single(desc_key(&prim_signer));
+        let recov_keys = descriptors::PathInfo::Single(desc_key(&recov_signer));
+        let hashlock_keys = descriptors::PathInfo::Single(desc_key(&hashlock_signer));
+
+        let hashlock = descriptors::HashLock::sha256(&preimage);
+        let hashlock_cond = descriptors::PathCondition::HashLock(hashlock);
+        for is_taproot in [false, true] {
+            let policy = descriptors::LianaPolicy::from_conditional_paths(
+                prim_keys.clone(),
+                [
+                    (
+                        descriptors::PathCondition::RelativeBlocks(46),
+                        recov_keys.clone(),
+                    ),
+                    (hashlock_cond, hashlock_keys.clone()),
+                ]
+                .iter()
+                .cloned()
+                .collect(),
+                is_taproot,
+            )
+            .unwrap();
+            let desc = descriptors::LianaDescriptor::new(policy);
+
+            // A dummy PSBT spending a coin from this descriptor.
+            let spent_coin_desc = desc.receive_descriptor().derive(42.into(), &secp);
+            let mut psbt_in = PsbtIn::default();
+            spent_coin_desc.update_psbt_in(&mut psbt_in);
+            psbt_in.witness_utxo = Some(bitcoin::TxOut {
+                value: Amount::from_sat(19_000),
+                script_pubkey: spent_coin_desc.script_pubkey(),
+            });
+            let psbt = Psbt {
+                unsigned_tx: bitcoin::Transaction {
+                    version: bitcoin::transaction::Version::TWO,
+                    lock_time: absolute::LockTime::Blocks(absolute::Height::ZERO),
+                    input: vec![bitcoin::TxIn {
+                        sequence: bitcoin::Sequence::ENABLE_RBF_NO_LOCKTIME,
+                        previous_output: bitcoin::OutPoint::from_str(
+                            "4613e078e4cdbb0fce1bc6e44b028f0e11621a134a1605efdc456c32d155c922:19",
+                        )
+                        .unwrap(),
+                        ..bitcoin::TxIn::default()
+                    }],
+                    output: vec![bitcoin::TxOut {
+                        value: Amount::from_sat(18_420),
+                        script_pubkey: bitcoin::Address::from_str(
+                            "bc1qvklensptw5lk7d470ds60pcpsr0psdpgyvwepv",
+                        )
+                        .unwrap()
+                        .assume_checked()
+                        .script_pubkey(),
+                    }],
+                },
+                version: 0,
+                xpub: BTreeMap::new(),
+                proprietary: BTreeMap::new(),
+                unknown: BTreeMap::new(),
+                inputs: vec![psbt_in],
+                outputs: vec![bitcoin::psbt::Output::default()],
+            };
+
+            // The hash-locked path signer provides a signature, but it's not enough to finalize
+            // the PSBT without the preimage.
+            let mut psbt = hashlock_signer.sign_psbt(psbt, &secp).unwrap();
+            let spend_info = desc.partial_spend_info(&psbt).unwrap();
+            assert_eq!(spend_info.conditional_paths()[&hashlock_cond].sigs_count, 1);
+            assert!(!spend_info.has_preimage(&hashlock));
+            assert_eq!(spend_info.primary_path().sigs_count, 0);
+            psbt.clone().finalize_mut(&secp).unwrap_err();
+
+            // Once the preimage is provided it can be finalized.
+            desc.add_hashlock_preimage(&mut psbt, preimage).unwrap();
+            let spend_info = desc.partial_spend_info(&psbt).unwrap();
+            assert!(spend_info.has_preimage(&hashlock));
+            psbt.finalize_mut(&secp).unwrap();
+            let witness = &psbt.inputs[0].final_script_witness.as_ref().unwrap();
+            assert!(witness.iter().any(|elem| elem == preimage));
+        }
+    }
+
+    #[test]
+    fn hot_signer_sign_time_only() {
+        let secp = secp256k1::Secp256k1::new();
+        let network = bitcoin::Network::Bitcoin;
+
+        // A Liana descriptor whose only recovery path is behind a time-based relative timelock.
+        let (prim_signer, recov_signer) = (
+            HotSigner::generate(network).unwrap(),
+            HotSigner::generate(network).unwrap(),
+        );
+        let desc_key = |signer: &HotSigner| {
+            let origin_der = bip32::DerivationPath::from_str("m/48'/0'/0'/2'").unwrap();
+            let xkey = signer.xpub_at(&origin_der, &secp);
+            DescriptorPublicKey::MultiXPub(DescriptorMultiXKey {
+                origin: Some((signer.fingerprint(&secp), origin_der)),
+                xkey,
+                derivation_paths: DerivPaths::new(vec![
+                    bip32::DerivationPath::from_str("m/0").unwrap(),
+                    bip32::DerivationPath::from_str("m/1").unwrap(),
+                ])
+                .unwrap(),
+                wildcard: Wildcard::Unhardened,
+            })
+        };
+        let prim_keys = descriptors::PathInfo::Single(desc_key(&prim_signer));
+        let recov_keys = descriptors::PathInfo::Single(desc_key(&recov_signer));
+
+        // About 30 days.
+        let time_cond = descriptors::PathCondition::RelativeTime(5063);
+        for is_taproot in [false, true] {
+            // A recovery path behind an absolute timelock isn't enough.
+            descriptors::LianaPolicy::from_conditional_paths(
+                prim_keys.clone(),
+                [(
+                    descriptors::PathCondition::Absolute(
+                        miniscript::AbsLockTime::from_consensus(840_000).unwrap(),
+                    ),
+                    recov_keys.clone(),
+                )]
+                .iter()
+                .cloned()
+                .collect(),
+                is_taproot,
+            )
+            .unwrap_err();
+
+            let policy = descriptors::LianaPolicy::from_conditional_paths(
+                prim_keys.clone(),
+                [(time_cond, recov_keys.clone())].iter().cloned().collect(),
+                is_taproot,
+            )
+            .unwrap();
+            assert!(policy.recovery_paths().is_empty());
+            let desc = descriptors::LianaDescriptor::new(policy.clone());
+
+            // It can be parsed back.
+            let desc = descriptors::LianaDescriptor::from_str(&desc.to_string()).unwrap();
+            assert_eq!(desc.policy(), policy);
+            assert_eq!(
+                desc.first_relative_timelock(),
+                bitcoin::relative::LockTime::from_512_second_intervals(5063)
+            );
+            assert_eq!(desc.first_timelock_value(), 4321);
+
+            // A PSBT spending a coin from this descriptor through the recovery path.
+            let spent_coin_desc = desc.receive_descriptor().derive(42.into(), &secp);
+            let mut psbt_in = PsbtIn::default();
+            spent_coin_desc.update_psbt_in(&mut psbt_in);
+            psbt_in.witness_utxo = Some(bitcoin::TxOut {
+                value: Amount::from_sat(19_000),
+                script_pubkey: spent_coin_desc.script_pubkey(),
+            });
+            let mut psbt = Psbt {
+                unsigned_tx: bitcoin::Transaction {
+                    version: bitcoin::transaction::Version::TWO,
+                    lock_time: absolute::LockTime::Blocks(absolute::Height::ZERO),
+                    input: vec![bitcoin::TxIn {
+                        sequence: bitcoin::Sequence::from_512_second_intervals(5062),
+                        previous_output: bitcoin::OutPoint::from_str(
+                            "4613e078e4cdbb0fce1bc6e44b028f0e11621a134a1605efdc456c32d155c922:19",
+                        )
+                        .unwrap(),
+                        ..bitcoin::TxIn::default()
+                    }],
+                    output: vec![bitcoin::TxOut {
+                        value: Amount::from_sat(18_420),
+                        script_pubkey: bitcoin::Address::from_str(
+                            "bc1qvklensptw5lk7d470ds60pcpsr0psdpgyvwepv",
+                        )
+                        .unwrap()
+                        .assume_checked()
+                        .script_pubkey(),
+                    }],
+                },
+                version: 0,
+                xpub: BTreeMap::new(),
+                proprietary: BTreeMap::new(),
+                unknown: BTreeMap::new(),
+                inputs: vec![psbt_in],
+                outputs: vec![bitcoin::psbt::Output::default()],
+            };
+
+            // Before the timelock the recovery signature isn't enough to finalize the PSBT.
+            let signed_psbt = recov_signer.sign_psbt(psbt.clone(), &secp).unwrap();
+            let spend_info = desc.partial_spend_info(&signed_psbt).unwrap();
+            assert!(spend_info.last_available_recovery_path().is_none());
+            signed_psbt.clone().finalize_mut(&secp).unwrap_err();
+
+            // Once the nSequence unlocks the recovery path, it can be finalized.
+            psbt.unsigned_tx.input[0].sequence = bitcoin::Sequence::from_512_second_intervals(5063);
+            let psbt = desc.prune_bip32_derivs_last_avail(psbt).unwrap();
+            let mut psbt = recov_signer.sign_psbt(psbt, &secp).unwrap();
+            let spend_info = desc.partial_spend_info(&psbt).unwrap();
+            let (cond, path_info) = spend_info.last_available_recovery_path().unwrap();
+            assert_eq!((*cond, path_info.sigs_count), (time_cond, 1));
+            psbt.finalize_mut(&secp).unwrap();
+        }
+    }
+
+    #[test]
+    fn xprv_signer() {
+        let secp = secp256k1::Secp256k1::new();
+        let network = bitcoin::Network::Testnet;
+
+        // A 2-of-2 primary path between two signers, the second one also being used in the
+        // recovery path.
+        let (signer_a, signer_b, stranger) = (
+            HotSigner::generate(network).unwrap(),
+            HotSigner::generate(network).unwrap(),
+            HotSigner::generate(network).unwrap(),
+        );
+        let multi_key = |signer: &HotSigner, origin: &str| {
+            let origin_der = bip32::DerivationPath::from_str(origin).unwrap();
+            DescriptorPublicKey::MultiXPub(DescriptorMultiXKey {
+                origin: Some((signer.fingerprint(&secp), origin_der.clone())),
+                xkey: signer.xpub_at(&origin_der, &secp),
+                derivation_paths: DerivPaths::new(vec![
+                    bip32::DerivationPath::from_str("m/0").unwrap(),
+                    bip32::DerivationPath::from_str("m/1").unwrap(),
+                ])
+                .unwrap(),
+                wildcard: Wildcard::Unhardened,
+            })
+        };
+        let prim_keys = descriptors::PathInfo::Multi(
+            2,
+            vec![
+                multi_key(&signer_a, "m/48'/1'/0'/2'"),
+                multi_key(&signer_b, "m/48'/1'/0'/2'"),
+            ],
+        );
+        let recov_keys = descriptors::PathInfo::Single(multi_key(&signer_b, "m/48'/1'/1'/2'"));
+        let recovery_paths: BTreeMap<_, _> = [(52, recov_keys)].iter().cloned().collect();
+
+        for is_taproot in [false, true] {
+            let policy = if is_taproot {
+                descriptors::LianaPolicy::new(prim_keys.clone(), recovery_paths.clone())
+            } else {
+                descriptors::LianaPolicy::new_legacy(prim_keys.clone(), recovery_paths.clone())
+            }
+            .unwrap();
+            let desc = descriptors::LianaDescriptor::new(policy);
+
+            // The first signer is loaded from a descriptor key with its origin, the second one from
+            // its master xprv.
+            let origin_der = bip32::DerivationPath::from_str("m/48'/1'/0'/2'").unwrap();
+            let xprv_key = format!(
+                "[{}/48'/1'/0'/2']{}/<0;1>/*",
+                signer_a.fingerprint(&secp),
+                signer_a.xpriv_at(&origin_der, &secp)
+            );
+            let xprv_signer_a = XprvSigner::from_str(&xprv_key, &secp).unwrap();
+            let xprv_signer_b = XprvSigner::from_xpriv(signer_b.master_xpriv, None, &secp).unwrap();
+            assert_eq!(
+                xprv_signer_a.fingerprints(),
+                vec![signer_a.fingerprint(&secp)]
+            );
+            assert_eq!(
+                xprv_signer_b.fingerprints(),
+                vec![signer_b.fingerprint(&secp)]
+            );
+            xprv_signer_a.validate_against(&desc, &secp).unwrap();
+            xprv_signer_b.validate_against(&desc, &secp).unwrap();
+
+            // The same key can be loaded from a descriptor containing its xprv.
+            let desc_str = desc.to_string();
+            let desc_str = desc_str.split('#').next().unwrap();
+            let xpub_key = format!(
+                "[{}/48'/1'/0'/2']{}/<0;1>/*",
+                signer_a.fingerprint(&secp),
+                signer_a.xpub_at(&origin_der, &secp)
+            );
+            assert!(desc_str.contains(&xpub_key));
+            let xprv_desc = desc_str.replace(&xpub_key, &xprv_key);
+            let xprv_desc_signer = XprvSigner::from_str(&xprv_desc, &secp).unwrap();
+            assert_eq!(
+                xprv_desc_signer.fingerprints(),
+                vec![signer_a.fingerprint(&secp)]
+            );
+            xprv_desc_signer.validate_against(&desc, &secp).unwrap();
+            // Including when it has a checksum, as exported by other wallets. A wrong checksum is
+            // rejected.
+            let checksum = descriptor::checksum::desc_checksum(&xprv_desc).unwrap();
+            let checksummed_desc = format!("{}#{}", xprv_desc, checksum);
+            assert!(checksummed_desc.contains("/<0;1>/*"));
+            let xprv_desc_signer = XprvSigner::from_str(&checksummed_desc, &secp).unwrap();
+            xprv_desc_signer.validate_against(&desc, &secp).unwrap();
+            let wrong_checksum = if checksum == "qqqqqqqq" {
+                "pppppppp"
+            } else {
+                "qqqqqqqq"
+            };
+            assert!(matches!(
+                XprvSigner::from_str(&format!("{}#{}", xprv_desc, wrong_checksum), &secp),
+                Err(SignerError::InvalidPrivateKey(..))
+            ));
+
+            // Keys which aren't part of the descriptor, or with a wrong origin, are rejected.
+            let stranger_signer =
+                XprvSigner::from_xpriv(stranger.master_xpriv, None, &secp).unwrap();
+            assert!(matches!(
+                stranger_signer.validate_against(&desc, &secp),
+                Err(SignerError::KeyNotInDescriptor(..))
+            ));
+            let wrong_origin = format!(
+                "[{}/48'/1'/0'/3']{}/<0;1>/*",
+                signer_a.fingerprint(&secp),
+                signer_a.xpriv_at(&origin_der, &secp)
+            );
+            let wrong_origin_signer = XprvSigner::from_str(&wrong_origin, &secp).unwrap();
+            assert!(matches!(
+                wrong_origin_signer.validate_against(&desc, &secp),
+                Err(SignerError::KeyNotInDescriptor(..))
+            ));
+            // Non-master keys without origin are rejected, since we couldn't tell where they are
+            // derived from.
+            let no_origin_xpriv = signer_a.xpriv_at(&origin_der, &secp);
+            assert!(matches!(
+                XprvSigner::from_xpriv(no_origin_xpriv, None, &secp),
+                Err(SignerError::InvalidPrivateKey(..))
+            ));
+            assert!(matches!(
+                XprvSigner::from_str(&format!("{}/<0;1>/*", no_origin_xpriv), &secp),
+                Err(SignerError::InvalidPrivateKey(..))
+            ));
+            XprvSigner::from_xpriv(
+                no_origin_xpriv,
+                Some((signer_a.fingerprint(&secp), origin_der.clone())),
+                &secp,
+            )
+            .unwrap()
+            .validate_against(&desc, &secp)
+            .unwrap();
+
+            // Descriptors without private keys and single private keys are rejected.
+            assert!(matches!(
+                XprvSigner::from_str(desc_str, &secp),
+                Err(SignerError::InvalidPrivateKey(..))
+            ));
+            assert!(matches!(
+                XprvSigner::from_str(
+                    "cVt4o7BGAig1UXywgGSmARhxMdzP5qvQsxKkSsc1XEkw3tDTQFpy",
+                    &secp
+                ),
+                Err(SignerError::InvalidPrivateKey(..))
+            ));
+
+            // Create a dummy PSBT spending a coin from this descriptor.
+            let spent_coin_desc = desc.receive_descriptor().derive(7.into(), &secp);
+            let mut psbt_in = PsbtIn::default();
+            spent_coin_desc.update_psbt_in(&mut psbt_in);
+            psbt_in.witness_utxo = Some(bitcoin::TxOut {
+                value: Amount::from_sat(19_000),
+                script_pubkey: spent_coin_desc.script_pubkey(),
+            });
+            let dummy_psbt = Psbt {
+                unsigned_tx: bitcoin::Transaction {
+                    version: bitcoin::transaction::Version::TWO,
+                    lock_time: absolute::LockTime::Blocks(absolute::Height::ZERO),
+                    input: vec![bitcoin::TxIn {
+                        sequence: bitcoin::Sequence::ENABLE_RBF_NO_LOCKTIME,
+                        previous_output: bitcoin::OutPoint::from_str(
+                            "4613e078e4cdbb0fce1bc6e44b028f0e11621a134a1605efdc456c32d155c922:19",
+                        )
+                        .unwrap(),
+                        ..bitcoin::TxIn::default()
+                    }],
+                    output: vec![bitcoin::TxOut {
+                        value: Amount::from_sat(18_420),
+                        script_pubkey: spent_coin_desc.script_pubkey(),
+                    }],
+                },
+                version: 0,
+                xpub: BTreeMap::new(),
+                proprietary: BTreeMap::new(),
+                unknown: BTreeMap::new(),
+                inputs: vec![psbt_in],
+                outputs: Vec::new(),
+            };
+
+            // The xprv signers sign for the same keys as the hot signers they were derived from,
+            // with the same (deterministic) signatures.
+            let hot_psbt = signer_a.sign_psbt(dummy_psbt.clone(), &secp).unwrap();
+            let hot_psbt = signer_b.sign_psbt(hot_psbt, &secp).unwrap();
+            let xprv_psbt = xprv_signer_a.sign_psbt(dummy_psbt.clone(), &secp).unwrap();
+            let xprv_psbt = xprv_signer_b.sign_psbt(xprv_psbt, &secp).unwrap();
+            if is_taproot {
+                assert_eq!(xprv_psbt.inputs[0].tap_script_sigs.len(), 3);
+                assert!(xprv_psbt.inputs[0].partial_sigs.is_empty());
+            } else {
+                assert_eq!(xprv_psbt.inputs[0].partial_sigs.len(), 3);
+                assert!(xprv_psbt.inputs[0].tap_script_sigs.is_empty());
+            }
+            assert_eq!(hot_psbt, xprv_psbt);
+            let xprv_desc_psbt = xprv_desc_signer.sign_psbt(dummy_psbt, &secp).unwrap();
+            assert_eq!(
+                xprv_desc_psbt.inputs[0].partial_sigs.len()
+                    + xprv_desc_psbt.inputs[0].tap_script_sigs.len(),
+                1
+            );
+
+            // The signatures are valid: the primary path can be satisfied.
+            let mut final_psbt = xprv_psbt;
+            final_psbt.finalize_mut(&secp).unwrap();
+        }
+    }
+
     #[test]
     fn signer_set_net() {
         let secp = secp256k1::Secp256k1::signing_only();
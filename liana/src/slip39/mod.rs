@@ -0,0 +1,942 @@
+//! SLIP-39 Shamir's Secret-Sharing for Mnemonic Codes.
+//!
+//! Split a master secret into mnemonic shares organized in groups, such as a threshold of the
+//! groups each with a threshold of their members' shares is needed to recover the secret. The
+//! master secret is encrypted with an optional passphrase before being split.
+//! See https://github.com/satoshilabs/slips/blob/master/slip-0039.md.
+
+mod wordlist;
+
+pub use wordlist::WORDLIST;
+
+use crate::random;
+
+use std::{collections::BTreeMap, error, fmt, str::FromStr};
+
+use miniscript::bitcoin::hashes::{hmac, sha256, Hash, HashEngine};
+
+// The number of bits encoded by a single word.
+const RADIX_BITS: usize = 10;
+// The number of words used for the identifier, extendable flag and iteration exponent.
+const ID_EXP_WORDS: usize = 2;
+// The number of words used for the group and member parameters.
+const SHARE_PARAMS_WORDS: usize = 2;
+const CHECKSUM_WORDS: usize = 3;
+const METADATA_WORDS: usize = ID_EXP_WORDS + SHARE_PARAMS_WORDS + CHECKSUM_WORDS;
+/// The minimum length of a master secret, in bytes.
+pub const MIN_SECRET_LEN: usize = 16;
+const MIN_MNEMONIC_WORDS: usize =
+    METADATA_WORDS + (MIN_SECRET_LEN * 8 + RADIX_BITS - 1) / RADIX_BITS;
+/// The maximum number of groups, and of members within a group.
+pub const MAX_SHARE_COUNT: u8 = 16;
+const DIGEST_LEN: usize = 4;
+const DIGEST_INDEX: u8 = 254;
+const SECRET_INDEX: u8 = 255;
+const ROUND_COUNT: u8 = 4;
+const BASE_ITERATION_COUNT: u32 = 10_000;
+const CUSTOMIZATION_STRING: &[u8] = b"shamir";
+const CUSTOMIZATION_STRING_EXTENDABLE: &[u8] = b"shamir_extendable";
+
+// Points of the polynomial used to share a secret, as (index, value) pairs.
+type SharePoints = Vec<(u8, Vec<u8>)>;
+
+#[derive(Debug)]
+pub enum Slip39Error {
+    InvalidWord(String),
+    InvalidMnemonicLength(usize),
+    InvalidChecksum,
+    InvalidPadding,
+    /// The parameters encoded in a share are inconsistent.
+    InvalidShare(String),
+    /// The set of shares is insufficient or inconsistent to recover the secret.
+    InvalidShareSet(String),
+    /// The recovered secret does not match its digest.
+    DigestMismatch,
+    InvalidParameters(String),
+    /// The passphrase must only contain printable ASCII characters.
+    InvalidPassphrase,
+    Randomness(random::RandomnessError),
+}
+
+impl fmt::Display for Slip39Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::InvalidWord(w) => write!(f, "'{}' is not a SLIP-39 word.", w),
+            Self::InvalidMnemonicLength(l) => {
+                write!(f, "Invalid number of words for a SLIP-39 share: {}.", l)
+            }
+            Self::InvalidChecksum => write!(f, "Invalid share checksum."),
+            Self::InvalidPadding => write!(f, "Invalid share padding."),
+            Self::InvalidShare(s) => write!(f, "Invalid share: {}", s),
+            Self::InvalidShareSet(s) => write!(f, "Invalid set of shares: {}", s),
+            Self::DigestMismatch => write!(
+                f,
+                "The digest of the recovered secret does not match. One of the shares is wrong."
+            ),
+            Self::InvalidParameters(s) => write!(f, "Invalid sharing parameters: {}", s),
+            Self::InvalidPassphrase => write!(
+                f,
+                "The passphrase must only contain printable ASCII characters."
+            ),
+            Self::Randomness(e) => write!(f, "Error related to getting randomness: {}", e),
+        }
+    }
+}
+
+impl error::Error for Slip39Error {}
+
+// Log and exponent tables for GF(256) with the Rijndael polynomial x^8 + x^4 + x^3 + x + 1,
+// using 3 as generator.
+const fn gf_tables() -> ([u8; 255], [u8; 256]) {
+    let (mut exp, mut log) = ([0; 255], [0; 256]);
+    let mut poly: u16 = 1;
+    let mut i = 0;
+    while i < 255 {
+        exp[i] = poly as u8;
+        log[poly as usize] = i as u8;
+        // Multiply by 3 (x + 1).
+        poly ^= poly << 1;
+        if poly & 0x100 != 0 {
+            poly ^= 0x11b;
+        }
+        i += 1;
+    }
+    (exp, log)
+}
+const GF_TABLES: ([u8; 255], [u8; 256]) = gf_tables();
+
+// Evaluate at x the polynomial going through all the given points, for each byte of the values.
+fn interpolate(shares: &[(u8, Vec<u8>)], x: u8) -> Vec<u8> {
+    if let Some((_, value)) = shares.iter().find(|(index, _)| *index == x) {
+        return value.clone();
+    }
+    let (exp, log) = (&GF_TABLES.0, &GF_TABLES.1);
+
+    // The logarithm of the product of (x - x_i) for all x_i.
+    let log_prod: i64 = shares
+        .iter()
+        .map(|(index, _)| log[(index ^ x) as usize] as i64)
+        .sum();
+    let mut result = vec![0; shares[0].1.len()];
+    for (index, value) in shares {
+        // The Lagrange basis polynomial, prod((x - x_j) / (x_i - x_j)) for j != i.
+        let log_denom: i64 = shares
+            .iter()
+            .filter(|(other, _)| other != index)
+            .map(|(other, _)| log[(index ^ other) as usize] as i64)
+            .sum();
+        let log_basis = (log_prod - log[(index ^ x) as usize] as i64 - log_denom).rem_euclid(255);
+        for (res, byte) in result.iter_mut().zip(value.iter()) {
+            if *byte != 0 {
+                *res ^= exp[((log[*byte as usize] as i64 + log_basis) % 255) as usize];
+            }
+        }
+    }
+
+    result
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> [u8; 32] {
+    let mut engine = hmac::HmacEngine::<sha256::Hash>::new(key);
+    engine.input(data);
+    hmac::Hmac::<sha256::Hash>::from_engine(engine).to_byte_array()
+}
+
+fn pbkdf2_sha256(password: &[u8], salt: &[u8], iterations: u32, len: usize) -> Vec<u8> {
+    let mut output = Vec::with_capacity(len);
+    let mut block_index: u32 = 1;
+    while output.len() < len {
+        let mut engine = hmac::HmacEngine::<sha256::Hash>::new(password);
+        engine.input(salt);
+        engine.input(&block_index.to_be_bytes());
+        let mut u = hmac::Hmac::<sha256::Hash>::from_engine(engine).to_byte_array();
+        let mut block = u;
+        for _ in 1..iterations {
+            u = hmac_sha256(password, &u);
+            block.iter_mut().zip(u.iter()).for_each(|(b, u)| *b ^= u);
+        }
+        let remaining = len - output.len();
+        output.extend_from_slice(&block[..remaining.min(block.len())]);
+        block_index += 1;
+    }
+    output
+}
+
+fn random_vec(len: usize) -> Result<Vec<u8>, Slip39Error> {
+    let mut bytes = Vec::with_capacity(len + 32);
+    while bytes.len() < len {
+        bytes.extend_from_slice(&random::random_bytes().map_err(Slip39Error::Randomness)?);
+    }
+    bytes.truncate(len);
+    Ok(bytes)
+}
+
+// Run the 4-round Feistel network used to encrypt (or, with the rounds in reverse order,
+// decrypt) the master secret.
+fn feistel(
+    secret: &[u8],
+    passphrase: &[u8],
+    iteration_exponent: u8,
+    identifier: u16,
+    extendable: bool,
+    rounds: impl Iterator<Item = u8>,
+) -> Vec<u8> {
+    let (left, right) = secret.split_at(secret.len() / 2);
+    let (mut left, mut right) = (left.to_vec(), right.to_vec());
+    let salt_prefix = if extendable {
+        Vec::new()
+    } else {
+        [CUSTOMIZATION_STRING, &identifier.to_be_bytes()].concat()
+    };
+    let iterations = (BASE_ITERATION_COUNT << iteration_exponent) / ROUND_COUNT as u32;
+
+    for i in rounds {
+        let password = [&[i], passphrase].concat();
+        let salt = [&salt_prefix[..], &right].concat();
+        let round_key = pbkdf2_sha256(&password, &salt, iterations, right.len());
+        let new_right: Vec<u8> = left
+            .iter()
+            .zip(round_key.iter())
+            .map(|(l, k)| l ^ k)
+            .collect();
+        left = std::mem::replace(&mut right, new_right);
+    }
+
+    [right, left].concat()
+}
+
+fn check_passphrase(passphrase: &str) -> Result<(), Slip39Error> {
+    if passphrase.bytes().all(|b| (32..=126).contains(&b)) {
+        Ok(())
+    } else {
+        Err(Slip39Error::InvalidPassphrase)
+    }
+}
+
+// Split a secret into `count` shares, `threshold` of which are needed to recover it.
+fn split_secret(threshold: u8, count: u8, secret: &[u8]) -> Result<SharePoints, Slip39Error> {
+    if threshold == 1 {
+        return Ok((0..count).map(|i| (i, secret.to_vec())).collect());
+    }
+
+    // The shares at the first indexes are random. The digest of the secret and the secret itself
+    // are placed at fixed indexes. The rest of the shares are interpolated from those.
+    let mut shares = (0..threshold - 2)
+        .map(|i| Ok((i, random_vec(secret.len())?)))
+        .collect::<Result<Vec<_>, Slip39Error>>()?;
+    let random_part = random_vec(secret.len() - DIGEST_LEN)?;
+    let digest = [
+        &hmac_sha256(&random_part, secret)[..DIGEST_LEN],
+        &random_part,
+    ]
+    .concat();
+    let mut base_shares = shares.clone();
+    base_shares.push((DIGEST_INDEX, digest));
+    base_shares.push((SECRET_INDEX, secret.to_vec()));
+    for i in threshold - 2..count {
+        shares.push((i, interpolate(&base_shares, i)));
+    }
+
+    Ok(shares)
+}
+
+// Recover the secret from exactly `threshold` shares, and check it against its digest.
+fn recover_secret(threshold: u8, shares: &[(u8, Vec<u8>)]) -> Result<Vec<u8>, Slip39Error> {
+    if threshold == 1 {
+        return Ok(shares[0].1.clone());
+    }
+
+    let secret = interpolate(shares, SECRET_INDEX);
+    let digest = interpolate(shares, DIGEST_INDEX);
+    if hmac_sha256(&digest[DIGEST_LEN..], &secret)[..DIGEST_LEN] != digest[..DIGEST_LEN] {
+        return Err(Slip39Error::DigestMismatch);
+    }
+
+    Ok(secret)
+}
+
+fn rs1024_polymod(values: impl Iterator<Item = u32>) -> u32 {
+    const GEN: [u32; 10] = [
+        0xE0E040, 0x1C1C080, 0x3838100, 0x7070200, 0xE0E0009, 0x1C0C2412, 0x38086C24, 0x3090FC48,
+        0x21B1F890, 0x3F3F120,
+    ];
+    let mut chk = 1;
+    for v in values {
+        let b = chk >> 20;
+        chk = ((chk & 0xFFFFF) << 10) ^ v;
+        for (i, gen) in GEN.iter().enumerate() {
+            if (b >> i) & 1 == 1 {
+                chk ^= gen;
+            }
+        }
+    }
+    chk
+}
+
+fn customization_string(extendable: bool) -> &'static [u8] {
+    if extendable {
+        CUSTOMIZATION_STRING_EXTENDABLE
+    } else {
+        CUSTOMIZATION_STRING
+    }
+}
+
+// Pack bytes into 10-bit words, padding with zero bits on the left.
+fn bytes_to_words(bytes: &[u8]) -> Vec<u16> {
+    let word_count = (bytes.len() * 8 + RADIX_BITS - 1) / RADIX_BITS;
+    let mut words = Vec::with_capacity(word_count);
+    let (mut acc, mut acc_bits) = (0u32, word_count * RADIX_BITS - bytes.len() * 8);
+    for byte in bytes {
+        acc = (acc << 8) | *byte as u32;
+        acc_bits += 8;
+        if acc_bits >= RADIX_BITS {
+            acc_bits -= RADIX_BITS;
+            words.push((acc >> acc_bits) as u16);
+            acc &= (1 << acc_bits) - 1;
+        }
+    }
+    words
+}
+
+// Unpack 10-bit words into bytes, checking the padding.
+fn words_to_bytes(words: &[u16]) -> Result<Vec<u8>, Slip39Error> {
+    let mut padding = (words.len() * RADIX_BITS) % 16;
+    if padding > 8 {
+        return Err(Slip39Error::InvalidPadding);
+    }
+    let mut bytes = Vec::with_capacity(words.len() * RADIX_BITS / 8);
+    let (mut acc, mut acc_bits) = (0u32, 0);
+    for word in words {
+        acc = (acc << RADIX_BITS) | *word as u32;
+        acc_bits += RADIX_BITS;
+        if padding > 0 {
+            acc_bits -= padding;
+            if acc >> acc_bits != 0 {
+                return Err(Slip39Error::InvalidPadding);
+            }
+            padding = 0;
+        }
+        while acc_bits >= 8 {
+            acc_bits -= 8;
+            bytes.push((acc >> acc_bits) as u8);
+        }
+        acc &= (1 << acc_bits) - 1;
+    }
+    Ok(bytes)
+}
+
+/// A single SLIP-39 share.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Share {
+    /// Random identifier common to all the shares of a master secret. 15 bits.
+    pub identifier: u16,
+    /// Whether the identifier is not used as salt for the encryption of the master secret.
+    pub extendable: bool,
+    /// The total number of PBKDF2 iterations used to encrypt the master secret is
+    /// 10000 * 2^e.
+    pub iteration_exponent: u8,
+    pub group_index: u8,
+    pub group_threshold: u8,
+    pub group_count: u8,
+    pub member_index: u8,
+    pub member_threshold: u8,
+    pub value: Vec<u8>,
+}
+
+impl Share {
+    fn words(&self) -> Vec<u16> {
+        let mut words = vec![
+            self.identifier >> 5,
+            ((self.identifier & 0x1f) << 5)
+                | ((self.extendable as u16) << 4)
+                | self.iteration_exponent as u16,
+            ((self.group_index as u16) << 6)
+                | (((self.group_threshold - 1) as u16) << 2)
+                | ((self.group_count - 1) as u16 >> 2),
+            ((((self.group_count - 1) & 3) as u16) << 8)
+                | ((self.member_index as u16) << 4)
+                | (self.member_threshold - 1) as u16,
+        ];
+        words.extend(bytes_to_words(&self.value));
+        let polymod = rs1024_polymod(
+            customization_string(self.extendable)
+                .iter()
+                .map(|b| *b as u32)
+                .chain(words.iter().map(|w| *w as u32))
+                .chain([0; CHECKSUM_WORDS]),
+        ) ^ 1;
+        words.extend(
+            (0..CHECKSUM_WORDS)
+                .rev()
+                .map(|i| ((polymod >> (i * RADIX_BITS)) & ((1 << RADIX_BITS) - 1)) as u16),
+        );
+        words
+    }
+}
+
+impl fmt::Display for Share {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let words = self.words();
+        for (i, w) in words.iter().enumerate() {
+            write!(f, "{}", WORDLIST[*w as usize])?;
+            if i < words.len() - 1 {
+                write!(f, " ")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Share {
+    type Err = Slip39Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let words = s
+            .split_whitespace()
+            .map(|w| {
+                let w = w.to_lowercase();
+                WORDLIST
+                    .binary_search(&w.as_str())
+                    .map(|i| i as u16)
+                    .map_err(|_| Slip39Error::InvalidWord(w))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if words.len() < MIN_MNEMONIC_WORDS {
+            return Err(Slip39Error::InvalidMnemonicLength(words.len()));
+        }
+
+        let extendable = (words[1] >> 4) & 1 == 1;
+        let polymod = rs1024_polymod(
+            customization_string(extendable)
+                .iter()
+                .map(|b| *b as u32)
+                .chain(words.iter().map(|w| *w as u32)),
+        );
+        if polymod != 1 {
+            return Err(Slip39Error::InvalidChecksum);
+        }
+
+        let value = words_to_bytes(
+            &words[ID_EXP_WORDS + SHARE_PARAMS_WORDS..words.len() - CHECKSUM_WORDS],
+        )?;
+        if value.len() < MIN_SECRET_LEN {
+            return Err(Slip39Error::InvalidMnemonicLength(words.len()));
+        }
+        let share = Share {
+            identifier: (words[0] << 5) | (words[1] >> 5),
+            extendable,
+            iteration_exponent: (words[1] & 0xf) as u8,
+            group_index: (words[2] >> 6) as u8,
+            group_threshold: ((words[2] >> 2) & 0xf) as u8 + 1,
+            group_count: ((((words[2] & 3) << 2) | (words[3] >> 8)) & 0xf) as u8 + 1,
+            member_index: ((words[3] >> 4) & 0xf) as u8,
+            member_threshold: (words[3] & 0xf) as u8 + 1,
+            value,
+        };
+        if share.group_threshold > share.group_count {
+            return Err(Slip39Error::InvalidShare(format!(
+                "group threshold {} is greater than the group count {}",
+                share.group_threshold, share.group_count
+            )));
+        }
+        if share.group_index >= share.group_count {
+            return Err(Slip39Error::InvalidShare(format!(
+                "group index {} is out of range",
+                share.group_index
+            )));
+        }
+
+        Ok(share)
+    }
+}
+
+/// The sharing parameters of a group.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Group {
+    /// The number of member shares needed to recover the group share.
+    pub member_threshold: u8,
+    /// The number of member shares to create for this group.
+    pub member_count: u8,
+}
+
+/// Encrypt the master secret with the passphrase and split it into shares. Returns the mnemonics
+/// of the shares for each group.
+///
+/// `group_threshold` of the groups are needed to recover the master secret, and for each group
+/// `member_threshold` of its members' shares are needed. The master secret must be at least 16
+/// bytes long and of even length.
+pub fn generate_mnemonics(
+    group_threshold: u8,
+    groups: &[Group],
+    master_secret: &[u8],
+    passphrase: &str,
+    extendable: bool,
+    iteration_exponent: u8,
+) -> Result<Vec<Vec<String>>, Slip39Error> {
+    if master_secret.len() < MIN_SECRET_LEN || master_secret.len() % 2 != 0 {
+        return Err(Slip39Error::InvalidParameters(format!(
+            "the master secret must be at least {} bytes long and of even length",
+            MIN_SECRET_LEN
+        )));
+    }
+    if groups.is_empty() || groups.len() > MAX_SHARE_COUNT as usize {
+        return Err(Slip39Error::InvalidParameters(format!(
+            "there must be between 1 and {} groups",
+            MAX_SHARE_COUNT
+        )));
+    }
+    if group_threshold == 0 || group_threshold as usize > groups.len() {
+        return Err(Slip39Error::InvalidParameters(
+            "the group threshold must be between 1 and the number of groups".to_string(),
+        ));
+    }
+    for group in groups {
+        if group.member_threshold == 0
+            || group.member_threshold > group.member_count
+            || group.member_count > MAX_SHARE_COUNT
+        {
+            return Err(Slip39Error::InvalidParameters(format!(
+                "invalid {}-of-{} member sharing",
+                group.member_threshold, group.member_count
+            )));
+        }
+        if group.member_threshold == 1 && group.member_count > 1 {
+            return Err(Slip39Error::InvalidParameters(
+                "creating multiple member shares with a threshold of 1 is not allowed, use a \
+                 1-of-1 member sharing instead"
+                    .to_string(),
+            ));
+        }
+    }
+    if iteration_exponent > 0xf {
+        return Err(Slip39Error::InvalidParameters(
+            "the iteration exponent must be at most 15".to_string(),
+        ));
+    }
+    check_passphrase(passphrase)?;
+
+    let id_bytes = random_vec(2)?;
+    let identifier = u16::from_be_bytes([id_bytes[0], id_bytes[1]]) & 0x7fff;
+    let encrypted_secret = feistel(
+        master_secret,
+        passphrase.as_bytes(),
+        iteration_exponent,
+        identifier,
+        extendable,
+        0..ROUND_COUNT,
+    );
+
+    let group_shares = split_secret(group_threshold, groups.len() as u8, &encrypted_secret)?;
+    groups
+        .iter()
+        .zip(group_shares)
+        .map(|(group, (group_index, group_secret))| {
+            Ok(
+                split_secret(group.member_threshold, group.member_count, &group_secret)?
+                    .into_iter()
+                    .map(|(member_index, value)| {
+                        Share {
+                            identifier,
+                            extendable,
+                            iteration_exponent,
+                            group_index,
+                            group_threshold,
+                            group_count: groups.len() as u8,
+                            member_index,
+                            member_threshold: group.member_threshold,
+                            value,
+                        }
+                        .to_string()
+                    })
+                    .collect(),
+            )
+        })
+        .collect()
+}
+
+/// Recover the master secret from the given share mnemonics and decrypt it with the passphrase.
+///
+/// Exactly the threshold number of groups must be provided, each with exactly its threshold of
+/// member shares.
+pub fn combine_mnemonics(
+    mnemonics: &[impl AsRef<str>],
+    passphrase: &str,
+) -> Result<Vec<u8>, Slip39Error> {
+    check_passphrase(passphrase)?;
+    let shares = mnemonics
+        .iter()
+        .map(|m| Share::from_str(m.as_ref()))
+        .collect::<Result<Vec<_>, _>>()?;
+    let first = shares
+        .first()
+        .ok_or_else(|| Slip39Error::InvalidShareSet("no share provided".to_string()))?;
+
+    // The member threshold and member shares for each group index.
+    let mut groups: BTreeMap<u8, (u8, SharePoints)> = BTreeMap::new();
+    for share in &shares {
+        if share.identifier != first.identifier
+            || share.extendable != first.extendable
+            || share.iteration_exponent != first.iteration_exponent
+        {
+            return Err(Slip39Error::InvalidShareSet(
+                "the shares do not belong to the same master secret".to_string(),
+            ));
+        }
+        if share.group_threshold != first.group_threshold
+            || share.group_count != first.group_count
+            || share.value.len() != first.value.len()
+        {
+            return Err(Slip39Error::InvalidShareSet(
+                "the shares have inconsistent group parameters".to_string(),
+            ));
+        }
+        let (member_threshold, members) = groups
+            .entry(share.group_index)
+            .or_insert_with(|| (share.member_threshold, Vec::new()));
+        if *member_threshold != share.member_threshold {
+            return Err(Slip39Error::InvalidShareSet(format!(
+                "inconsistent member thresholds in group {}",
+                share.group_index + 1
+            )));
+        }
+        if members
+            .iter()
+            .any(|(index, _)| *index == share.member_index)
+        {
+            return Err(Slip39Error::InvalidShareSet(format!(
+                "duplicate member index {} in group {}",
+                share.member_index + 1,
+                share.group_index + 1
+            )));
+        }
+        members.push((share.member_index, share.value.clone()));
+    }
+
+    if groups.len() != first.group_threshold as usize {
+        return Err(Slip39Error::InvalidShareSet(format!(
+            "expected {} groups but {} were provided",
+            first.group_threshold,
+            groups.len()
+        )));
+    }
+    let group_shares = groups
+        .into_iter()
+        .map(|(group_index, (member_threshold, members))| {
+            if members.len() != member_threshold as usize {
+                return Err(Slip39Error::InvalidShareSet(format!(
+                    "expected {} shares for group {} but {} were provided",
+                    member_threshold,
+                    group_index + 1,
+                    members.len()
+                )));
+            }
+            Ok((group_index, recover_secret(member_threshold, &members)?))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let encrypted_secret = recover_secret(first.group_threshold, &group_shares)?;
+
+    Ok(feistel(
+        &encrypted_secret,
+        passphrase.as_bytes(),
+        first.iteration_exponent,
+        first.identifier,
+        first.extendable,
+        (0..ROUND_COUNT).rev(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use miniscript::bitcoin::hashes::hex::FromHex;
+
+    #[test]
+    fn wordlist() {
+        assert_eq!(WORDLIST.len(), 1024);
+        // Sorted and with unique 4-letter prefixes, so we can binary search it.
+        for pair in WORDLIST.windows(2) {
+            assert!(pair[0] < pair[1]);
+            assert_ne!(pair[0][..4], pair[1][..4]);
+        }
+    }
+
+    // A selection of the official test vectors, all using the "TREZOR" passphrase.
+    // See https://github.com/trezor/python-shamir-mnemonic/blob/master/vectors.json.
+    #[test]
+    fn test_vectors() {
+        let valid: &[(&[&str], &str)] = &[
+            // Valid mnemonic without sharing (128 bits)
+            (
+                &["duckling enlarge academic academic agency result length solution fridge kidney coal piece deal husband erode duke ajar critical decision keyboard"],
+                "bb54aac4b89dc868ba37d9cc21b2cece",
+            ),
+            // Basic sharing 2-of-3 (128 bits)
+            (
+                &[
+                    "shadow pistol academic always adequate wildlife fancy gross oasis cylinder mustang wrist rescue view short owner flip making coding armed",
+                    "shadow pistol academic acid actress prayer class unknown daughter sweater depict flip twice unkind craft early superior advocate guest smoking",
+                ],
+                "b43ceb7e57a0ea8766221624d01b0864",
+            ),
+            // Mnemonics with group sharing: 2 of 4 groups (128 bits)
+            (
+                &[
+                    "eraser senior decision roster beard treat identify grumpy salt index fake aviation theater cubic bike cause research dragon emphasis counter",
+                    "eraser senior ceramic snake clay various huge numb argue hesitate auction category timber browser greatest hanger petition script leaf pickup",
+                    "eraser senior ceramic shaft dynamic become junior wrist silver peasant force math alto coal amazing segment yelp velvet image paces",
+                    "eraser senior ceramic round column hawk trust auction smug shame alive greatest sheriff living perfect corner chest sled fumes adequate",
+                    "eraser senior decision smug corner ruin rescue cubic angel tackle skin skunk program roster trash rumor slush angel flea amazing",
+                ],
+                "7c3397a292a5941682d7a4ae2d898d11",
+            ),
+            // Valid mnemonic without sharing (256 bits)
+            (
+                &["theory painting academic academic armed sweater year military elder discuss acne wildlife boring employer fused large satoshi bundle carbon diagnose anatomy hamster leaves tracks paces beyond phantom capital marvel lips brave detect luck"],
+                "989baf9dcaad5b10ca33dfd8cc75e42477025dce88ae83e75a230086a0e00e92",
+            ),
+            // Basic sharing 2-of-3 (256 bits)
+            (
+                &[
+                    "humidity disease academic always aluminum jewelry energy woman receiver strategy amuse duckling lying evidence network walnut tactics forget hairy rebound impulse brother survive clothes stadium mailman rival ocean reward venture always armed unwrap",
+                    "humidity disease academic agency actress jacket gross physics cylinder solution fake mortgage benefit public busy prepare sharp friar change work slow purchase ruler again tricycle involve viral wireless mixture anatomy desert cargo upgrade",
+                ],
+                "c938b319067687e990e05e0da0ecce1278f75ff58d9853f19dcaeed5de104aae",
+            ),
+            // Valid extendable mnemonic without sharing (128 bits)
+            (
+                &["testify swimming academic academic column loyalty smear include exotic bedroom exotic wrist lobe cover grief golden smart junior estimate learn"],
+                "1679b4516e0ee5954351d288a838f45e",
+            ),
+            // Extendable basic sharing 2-of-3 (128 bits)
+            (
+                &[
+                    "enemy favorite academic acid cowboy phrase havoc level response walnut budget painting inside trash adjust froth kitchen learn tidy punish",
+                    "enemy favorite academic always academic sniff script carpet romp kind promise scatter center unfair training emphasis evening belong fake enforce",
+                ],
+                "48b1a4b80b8c209ad42c33672bdaa428",
+            ),
+            // Valid extendable mnemonic without sharing (256 bits)
+            (
+                &["impulse calcium academic academic alcohol sugar lyrics pajamas column facility finance tension extend space birthday rainbow swimming purple syndrome facility trial warn duration snapshot shadow hormone rhyme public spine counter easy hawk album"],
+                "8340611602fe91af634a5f4608377b5235fa2d757c51d720c0c7656249a3035f",
+            ),
+            // Extendable basic sharing 2-of-3 (256 bits)
+            (
+                &[
+                    "western apart academic always artist resident briefing sugar woman oven coding club ajar merit pecan answer prisoner artist fraction amount desktop mild false necklace muscle photo wealthy alpha category unwrap spew losing making",
+                    "western apart academic acid answer ancient auction flip image penalty oasis beaver multiple thunder problem switch alive heat inherit superior teaspoon explain blanket pencil numb lend punish endless aunt garlic humidity kidney observe",
+                ],
+                "8dc652d6d6cd370d8c963141f6d79ba440300f25c467302c1d966bff8f62300d",
+            ),
+        ];
+        for (mnemonics, secret) in valid {
+            assert_eq!(
+                combine_mnemonics(mnemonics, "TREZOR").unwrap(),
+                Vec::<u8>::from_hex(secret).unwrap()
+            );
+        }
+
+        // Mnemonic with invalid checksum (128 bits)
+        assert!(matches!(
+            combine_mnemonics(&["duckling enlarge academic academic agency result length solution fridge kidney coal piece deal husband erode duke ajar critical decision kidney"], "TREZOR"),
+            Err(Slip39Error::InvalidChecksum)
+        ));
+        // Mnemonic with invalid padding (128 bits)
+        assert!(matches!(
+            combine_mnemonics(&["duckling enlarge academic academic email result length solution fridge kidney coal piece deal husband erode duke ajar music cargo fitness"], "TREZOR"),
+            Err(Slip39Error::InvalidPadding)
+        ));
+        // Mnemonics with different identifiers (128 bits)
+        assert!(matches!(
+            combine_mnemonics(
+                &[
+                    "adequate smoking academic acid debut wine petition glen cluster slow rhyme slow simple epidemic rumor junk tracks treat olympic tolerate",
+                    "adequate stay academic agency agency formal party ting frequent learn upstairs remember smear leaf damage anatomy ladle market hush corner",
+                ],
+                "TREZOR"
+            ),
+            Err(Slip39Error::InvalidShareSet(_))
+        ));
+        // Mnemonics with different iteration exponents (128 bits)
+        assert!(matches!(
+            combine_mnemonics(
+                &[
+                    "peasant leaves academic acid desert exact olympic math alive axle trial tackle drug deny decent smear dominant desert bucket remind",
+                    "peasant leader academic agency cultural blessing percent network envelope medal junk primary human pumps jacket fragment payroll ticket evoke voice",
+                ],
+                "TREZOR"
+            ),
+            Err(Slip39Error::InvalidShareSet(_))
+        ));
+        // Basic sharing 2-of-3 with a single share (128 bits)
+        assert!(matches!(
+            combine_mnemonics(&["shadow pistol academic always adequate wildlife fancy gross oasis cylinder mustang wrist rescue view short owner flip making coding armed"], "TREZOR"),
+            Err(Slip39Error::InvalidShareSet(_))
+        ));
+        // Group sharing with only one of the two required groups (128 bits)
+        assert!(matches!(
+            combine_mnemonics(
+                &[
+                    "eraser senior ceramic snake clay various huge numb argue hesitate auction category timber browser greatest hanger petition script leaf pickup",
+                    "eraser senior ceramic shaft dynamic become junior wrist silver peasant force math alto coal amazing segment yelp velvet image paces",
+                    "eraser senior ceramic round column hawk trust auction smug shame alive greatest sheriff living perfect corner chest sled fumes adequate",
+                ],
+                "TREZOR"
+            ),
+            Err(Slip39Error::InvalidShareSet(_))
+        ));
+        // Shares of different master secrets (128 bits)
+        assert!(matches!(
+            combine_mnemonics(
+                &[
+                    "shadow pistol academic always adequate wildlife fancy gross oasis cylinder mustang wrist rescue view short owner flip making coding armed",
+                    "eraser senior ceramic snake clay various huge numb argue hesitate auction category timber browser greatest hanger petition script leaf pickup",
+                ],
+                "TREZOR"
+            ),
+            Err(Slip39Error::InvalidShareSet(_))
+        ));
+        // Duplicate share.
+        assert!(matches!(
+            combine_mnemonics(
+                &[
+                    "shadow pistol academic always adequate wildlife fancy gross oasis cylinder mustang wrist rescue view short owner flip making coding armed",
+                    "shadow pistol academic always adequate wildlife fancy gross oasis cylinder mustang wrist rescue view short owner flip making coding armed",
+                ],
+                "TREZOR"
+            ),
+            Err(Slip39Error::InvalidShareSet(_))
+        ));
+        // Unknown word and too short mnemonic.
+        assert!(matches!(
+            combine_mnemonics(&["duckling enlarge academic academic agency result length solution fridge kidney coal piece deal husband erode duke ajar critical decision keyboards"], "TREZOR"),
+            Err(Slip39Error::InvalidWord(_))
+        ));
+        assert!(matches!(
+            combine_mnemonics(&["duckling enlarge academic academic agency result length solution fridge kidney coal piece deal husband erode duke ajar critical decision"], "TREZOR"),
+            Err(Slip39Error::InvalidMnemonicLength(19))
+        ));
+    }
+
+    #[test]
+    fn share_roundtrip() {
+        let mnemonic = "eraser senior decision roster beard treat identify grumpy salt index fake aviation theater cubic bike cause research dragon emphasis counter";
+        let share = Share::from_str(mnemonic).unwrap();
+        assert_eq!(share.identifier, 9497);
+        assert!(!share.extendable);
+        assert_eq!(share.iteration_exponent, 0);
+        assert_eq!(
+            (share.group_index, share.group_threshold, share.group_count),
+            (3, 2, 4)
+        );
+        assert_eq!((share.member_index, share.member_threshold), (0, 2));
+        assert_eq!(share.to_string(), mnemonic);
+
+        // Tampering with the value is caught by the digest check.
+        let shares: Vec<String> = [
+            "shadow pistol academic always adequate wildlife fancy gross oasis cylinder mustang wrist rescue view short owner flip making coding armed",
+            "shadow pistol academic acid actress prayer class unknown daughter sweater depict flip twice unkind craft early superior advocate guest smoking",
+        ]
+        .iter()
+        .enumerate()
+        .map(|(i, m)| {
+            let mut share = Share::from_str(m).unwrap();
+            if i == 0 {
+                share.value[0] ^= 1;
+            }
+            share.to_string()
+        })
+        .collect();
+        assert!(matches!(
+            combine_mnemonics(&shares, "TREZOR"),
+            Err(Slip39Error::DigestMismatch)
+        ));
+    }
+
+    #[test]
+    fn generate_and_combine() {
+        let secret = random_vec(32).unwrap();
+        let groups = [
+            Group {
+                member_threshold: 1,
+                member_count: 1,
+            },
+            Group {
+                member_threshold: 2,
+                member_count: 3,
+            },
+            Group {
+                member_threshold: 3,
+                member_count: 5,
+            },
+        ];
+        for extendable in [false, true] {
+            let mnemonics =
+                generate_mnemonics(2, &groups, &secret, "passphrase", extendable, 0).unwrap();
+            assert_eq!(mnemonics.len(), 3);
+            assert_eq!(
+                mnemonics.iter().map(|g| g.len()).collect::<Vec<_>>(),
+                vec![1, 3, 5]
+            );
+            assert!(mnemonics
+                .iter()
+                .flatten()
+                .all(|m| m.split(' ').count() == 33));
+
+            // Any two groups with any threshold of their members work.
+            let first_and_second: Vec<&String> =
+                [&mnemonics[0][0], &mnemonics[1][2], &mnemonics[1][0]].to_vec();
+            assert_eq!(
+                combine_mnemonics(&first_and_second, "passphrase").unwrap(),
+                secret
+            );
+            let second_and_third: Vec<&String> = mnemonics[1][1..]
+                .iter()
+                .chain(mnemonics[2][1..4].iter())
+                .collect();
+            assert_eq!(
+                combine_mnemonics(&second_and_third, "passphrase").unwrap(),
+                secret
+            );
+
+            // A wrong passphrase decrypts to another secret.
+            assert_ne!(
+                combine_mnemonics(&first_and_second, "wrong").unwrap(),
+                secret
+            );
+            // Not enough members of a group.
+            assert!(matches!(
+                combine_mnemonics(&mnemonics[2][..2], "passphrase"),
+                Err(Slip39Error::InvalidShareSet(_))
+            ));
+        }
+
+        // Invalid parameters.
+        assert!(matches!(
+            generate_mnemonics(1, &groups[..1], &secret[..15], "", true, 0),
+            Err(Slip39Error::InvalidParameters(_))
+        ));
+        assert!(matches!(
+            generate_mnemonics(4, &groups, &secret, "", true, 0),
+            Err(Slip39Error::InvalidParameters(_))
+        ));
+        assert!(matches!(
+            generate_mnemonics(
+                1,
+                &[Group {
+                    member_threshold: 1,
+                    member_count: 2
+                }],
+                &secret,
+                "",
+                true,
+                0
+            ),
+            Err(Slip39Error::InvalidParameters(_))
+        ));
+        assert!(matches!(
+            generate_mnemonics(1, &groups[..1], &secret, "pässphrase", true, 0),
+            Err(Slip39Error::InvalidPassphrase)
+        ));
+    }
+}
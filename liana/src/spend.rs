@@ -1,16 +1,18 @@
 use crate::descriptors;
 
 use std::{
-    collections::{BTreeMap, HashMap},
-    convert::TryInto,
-    fmt,
+    cmp,
+    collections::{btree_map, hash_map, BTreeMap, BTreeSet, HashMap},
+    convert::{TryFrom, TryInto},
+    fmt, str,
     time::Duration,
 };
 
 pub use bdk_coin_select::InsufficientFunds;
 use bdk_coin_select::{
-    metrics::LowestFee, Candidate, ChangePolicy, CoinSelector, DrainWeights, FeeRate, Replace,
-    Target, TargetFee, TargetOutputs, TXIN_BASE_WEIGHT,
+    metrics::{Changeless, LowestFee},
+    Candidate, ChangePolicy, CoinSelector, DrainWeights, FeeRate, Replace, Target, TargetFee,
+    TargetOutputs, TXIN_BASE_WEIGHT,
 };
 use miniscript::bitcoin::{
     self,
@@ -26,14 +28,185 @@ use serde::{Deserialize, Serialize};
 /// That's 1$ at 20_000$ per BTC.
 pub const DUST_OUTPUT_SATS: u64 = 5_000;
 
-/// Long-term feerate (sats/vb) used for coin selection considerations.
-pub const LONG_TERM_FEERATE_VB: f32 = 10.0;
+/// Maximum size of the scriptPubKey of a data-carrier (OP_RETURN) output relayed by Bitcoin Core
+/// nodes with default settings. This is 80 bytes of data.
+pub const MAX_DATA_CARRIER_SIZE: usize = 83;
+
+/// The version of TRUC (Topologically Restricted Until Confirmation) transactions, as per BIP431.
+pub const TRUC_VERSION: bitcoin::transaction::Version = bitcoin::transaction::Version(3);
+
+/// Maximum virtual size of a TRUC (version 3) transaction, as per BIP431.
+pub const TRUC_MAX_VSIZE: u64 = 10_000;
+
+/// Maximum virtual size of a TRUC (version 3) transaction spending an unconfirmed TRUC parent, as
+/// per BIP431.
+pub const TRUC_CHILD_MAX_VSIZE: u64 = 1_000;
+
+/// Long-term feerate used for coin selection considerations.
+pub const LONG_TERM_FEERATE: Feerate = Feerate::from_sat_per_vb(10);
 
 /// Assume that paying more than 1BTC in fee is a bug.
 pub const MAX_FEE: bitcoin::Amount = bitcoin::Amount::ONE_BTC;
 
 /// Assume that paying more than 1000sat/vb in feerate is a bug.
-pub const MAX_FEERATE: u64 = 1_000;
+pub const MAX_FEERATE: Feerate = Feerate::from_sat_per_vb(1_000);
+
+/// The minimum feerate of a transaction we create, which is the default minimum relay feerate of
+/// Bitcoin Core.
+pub const MIN_FEERATE: Feerate = Feerate::from_sat_per_vb(1);
+
+/// The minimum feerate of a transaction we create depending on whether it is a TRUC transaction.
+/// A TRUC transaction may pay less than [`MIN_FEERATE`], down to no fee at all, as it can be
+/// relayed in a package along with a child paying for it.
+pub fn min_feerate(truc: bool) -> Feerate {
+    if truc {
+        Feerate::default()
+    } else {
+        MIN_FEERATE
+    }
+}
+
+/// A feerate, in satoshis per 1000 virtual bytes. This allows for feerates which are a fraction
+/// of a satoshi per virtual byte, down to a thousandth of it.
+///
+/// It is represented (and parsed from) a decimal number of satoshis per virtual byte, such as
+/// `1.5`. It is serialized as a JSON number of satoshis per virtual byte.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Feerate(u64);
+
+impl Feerate {
+    pub const fn from_sat_per_kvb(sat_kvb: u64) -> Self {
+        Self(sat_kvb)
+    }
+
+    pub const fn from_sat_per_vb(sat_vb: u64) -> Self {
+        Self(sat_vb.saturating_mul(1_000))
+    }
+
+    /// The feerate of a transaction of this size (in virtual bytes) paying this fee (in sats),
+    /// rounded down. `None` if the size is null.
+    pub fn from_fee(fee: u64, vbytes: u64) -> Option<Self> {
+        fee.saturating_mul(1_000).checked_div(vbytes).map(Self)
+    }
+
+    pub const fn to_sat_per_kvb(self) -> u64 {
+        self.0
+    }
+
+    /// The feerate in satoshis per virtual byte, as used by the coin selection.
+    pub fn to_sat_per_vb_f32(self) -> f32 {
+        self.0 as f32 / 1_000.0
+    }
+
+    /// The fee (in sats), rounded up, paid by a transaction of this size (in virtual bytes).
+    pub fn fee(self, vbytes: u64) -> u64 {
+        self.0.saturating_mul(vbytes).div_ceil(1_000)
+    }
+
+    /// Add this many satoshis per virtual byte to the feerate.
+    pub const fn saturating_add_sat_per_vb(self, sat_vb: u64) -> Self {
+        Self(self.0.saturating_add(sat_vb.saturating_mul(1_000)))
+    }
+}
+
+impl fmt::Display for Feerate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (sat_vb, frac) = (self.0 / 1_000, self.0 % 1_000);
+        if frac == 0 {
+            write!(f, "{}", sat_vb)
+        } else {
+            let frac = format!("{:03}", frac);
+            write!(f, "{}.{}", sat_vb, frac.trim_end_matches('0'))
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseFeerateError(String);
+
+impl fmt::Display for ParseFeerateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Invalid feerate '{}': must be a number of sats/vb with at most 3 decimals.",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for ParseFeerateError {}
+
+impl str::FromStr for Feerate {
+    type Err = ParseFeerateError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || ParseFeerateError(s.to_string());
+        let (sat_vb, frac) = match s.split_once('.') {
+            Some((sat_vb, frac)) => (sat_vb, frac),
+            None => (s, "0"),
+        };
+        let is_digits = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+        if !is_digits(sat_vb) || !is_digits(frac) || frac.len() > 3 {
+            return Err(err());
+        }
+        let sat_vb: u64 = sat_vb.parse().map_err(|_| err())?;
+        let frac: u64 = format!("{:0<3}", frac).parse().map_err(|_| err())?;
+        sat_vb
+            .checked_mul(1_000)
+            .and_then(|sat_kvb| sat_kvb.checked_add(frac))
+            .map(Self)
+            .ok_or_else(err)
+    }
+}
+
+impl Serialize for Feerate {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if self.0 % 1_000 == 0 {
+            serializer.serialize_u64(self.0 / 1_000)
+        } else {
+            serializer.serialize_f64(self.0 as f64 / 1_000.0)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Feerate {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct FeerateVisitor;
+
+        impl serde::de::Visitor<'_> for FeerateVisitor {
+            type Value = Feerate;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "a feerate in sats/vb")
+            }
+
+            fn visit_u64<E: serde::de::Error>(self, v: u64) -> Result<Feerate, E> {
+                v.checked_mul(1_000)
+                    .map(Feerate)
+                    .ok_or_else(|| E::custom(ParseFeerateError(v.to_string())))
+            }
+
+            fn visit_i64<E: serde::de::Error>(self, v: i64) -> Result<Feerate, E> {
+                let v: u64 = v
+                    .try_into()
+                    .map_err(|_| E::custom(ParseFeerateError(v.to_string())))?;
+                self.visit_u64(v)
+            }
+
+            // The decimal representation of a float is the shortest one which round-trips, so
+            // this parses a number given with at most 3 decimals exactly.
+            fn visit_f64<E: serde::de::Error>(self, v: f64) -> Result<Feerate, E> {
+                self.visit_str(&v.to_string())
+            }
+
+            fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<Feerate, E> {
+                str::FromStr::from_str(v).map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_any(FeerateVisitor)
+    }
+}
 
 /// Do not set locktime if tip age in seconds is older than this.
 // See also https://github.com/bitcoin/bitcoin/blob/ecd23656db174adef61d3bd753d02698c3528192/src/wallet/spend.cpp#L906.
@@ -44,17 +217,75 @@ pub enum InsaneFeeInfo {
     NegativeFee,
     InvalidFeerate,
     TooHighFee(u64),
-    TooHighFeerate(u64),
+    TooHighFeerate(Feerate),
+}
+
+/// A violation of the topology restrictions of TRUC (version 3) transactions (BIP431).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrucViolation {
+    /// The TRUC transaction is larger than [`TRUC_MAX_VSIZE`].
+    TooLarge(u64),
+    /// The TRUC transaction spends an unconfirmed parent and is larger than
+    /// [`TRUC_CHILD_MAX_VSIZE`].
+    ChildTooLarge(u64),
+    /// The TRUC transaction spends coins from more than one unconfirmed transaction.
+    TooManyUnconfirmedParents(usize),
+    /// The TRUC transaction spends a coin from this unconfirmed non-TRUC transaction.
+    NonTrucParent(bitcoin::Txid),
+    /// The non-TRUC transaction spends a coin from this unconfirmed TRUC transaction.
+    TrucParent(bitcoin::Txid),
+}
+
+impl fmt::Display for TrucViolation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::TooLarge(vb) => write!(
+                f,
+                "transaction is {} vbytes, the maximum is {} vbytes",
+                vb, TRUC_MAX_VSIZE
+            ),
+            Self::ChildTooLarge(vb) => write!(
+                f,
+                "transaction spending an unconfirmed coin is {} vbytes, the maximum is {} vbytes",
+                vb, TRUC_CHILD_MAX_VSIZE
+            ),
+            Self::TooManyUnconfirmedParents(n) => write!(
+                f,
+                "transaction spends coins from {} unconfirmed transactions, the maximum is 1",
+                n
+            ),
+            Self::NonTrucParent(txid) => write!(
+                f,
+                "transaction spends a coin from unconfirmed non-TRUC transaction {}",
+                txid
+            ),
+            Self::TrucParent(txid) => write!(
+                f,
+                "non-TRUC transaction spends a coin from unconfirmed TRUC transaction {}",
+                txid
+            ),
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum SpendCreationError {
-    InvalidFeerate(/* sats/vb */ u64),
+    InvalidFeerate(Feerate),
     InvalidOutputValue(bitcoin::Amount),
     InsaneFees(InsaneFeeInfo),
     SanityCheckFailure(Psbt),
     FetchingTransaction(bitcoin::OutPoint),
     CoinSelection(InsufficientFunds),
+    /// The scriptPubKey of a destination isn't standard.
+    NonStandardScript(bitcoin::ScriptBuf),
+    /// The scriptPubKey of a data-carrier output is larger than [`MAX_DATA_CARRIER_SIZE`].
+    DataCarrierTooLarge(usize),
+    /// Standard transactions may only have a single data-carrier output.
+    MultipleDataCarriers,
+    /// The fee can't be deducted from the value of a data-carrier output.
+    SubtractFeeFromDataCarrier,
+    /// The transaction would not be relayed as it breaks the TRUC topology restrictions.
+    Truc(TrucViolation),
 }
 
 impl fmt::Display for SpendCreationError {
@@ -84,12 +315,42 @@ impl fmt::Display for SpendCreationError {
                 "BUG! Please report this. Failed sanity checks for PSBT '{}'.",
                 psbt
             ),
+            Self::NonStandardScript(script) => write!(
+                f,
+                "Output script '{}' is not standard and would not be relayed.",
+                script.to_hex_string()
+            ),
+            Self::DataCarrierTooLarge(size) => write!(
+                f,
+                "Data-carrier output script is {} bytes long, the maximum is {} bytes.",
+                size, MAX_DATA_CARRIER_SIZE
+            ),
+            Self::MultipleDataCarriers => write!(
+                f,
+                "A transaction may only have a single data-carrier (OP_RETURN) output."
+            ),
+            Self::SubtractFeeFromDataCarrier => write!(
+                f,
+                "Cannot subtract the fee from a data-carrier (OP_RETURN) output."
+            ),
+            Self::Truc(e) => write!(f, "TRUC topology restriction violated: {}.", e),
         }
     }
 }
 
 impl std::error::Error for SpendCreationError {}
 
+// Sanity check the value of an output to this destination.
+fn check_destination_value(
+    destination: &SpendDestination,
+    value: bitcoin::Amount,
+) -> Result<(), SpendCreationError> {
+    match destination {
+        SpendDestination::Address(_) => check_output_value(value),
+        SpendDestination::Script(script) => check_script_output(script, value),
+    }
+}
+
 // Sanity check the value of a transaction output.
 fn check_output_value(value: bitcoin::Amount) -> Result<(), SpendCreationError> {
     if value > bitcoin::Amount::MAX_MONEY || value.to_sat() < DUST_OUTPUT_SATS {
@@ -99,12 +360,89 @@ fn check_output_value(value: bitcoin::Amount) -> Result<(), SpendCreationError>
     }
 }
 
-// Apply some sanity checks on a created transaction's PSBT.
+// Whether this script is of a standard type for a transaction output, apart from data-carrier
+// outputs. Bare multisig is not considered as it is discouraged.
+fn is_standard_script(script: &bitcoin::Script) -> bool {
+    match script.witness_version() {
+        Some(bitcoin::WitnessVersion::V0) => script.is_p2wpkh() || script.is_p2wsh(),
+        Some(_) => true,
+        None => script.is_p2pk() || script.is_p2pkh() || script.is_p2sh(),
+    }
+}
+
+// Sanity check a raw-script destination and its value against the standardness rules.
+fn check_script_output(
+    script: &bitcoin::Script,
+    value: bitcoin::Amount,
+) -> Result<(), SpendCreationError> {
+    if script.is_op_return() {
+        // The data must be pushed, and it's most likely a mistake to burn coins.
+        if !bitcoin::Script::from_bytes(&script.as_bytes()[1..]).is_push_only() {
+            return Err(SpendCreationError::NonStandardScript(script.to_owned()));
+        }
+        if script.len() > MAX_DATA_CARRIER_SIZE {
+            return Err(SpendCreationError::DataCarrierTooLarge(script.len()));
+        }
+        if value != bitcoin::Amount::ZERO {
+            return Err(SpendCreationError::InvalidOutputValue(value));
+        }
+    } else {
+        if !is_standard_script(script) {
+            return Err(SpendCreationError::NonStandardScript(script.to_owned()));
+        }
+        if value > bitcoin::Amount::MAX_MONEY || value < script.minimal_non_dust() {
+            return Err(SpendCreationError::InvalidOutputValue(value));
+        }
+    }
+    Ok(())
+}
+
+// Check a transaction of this version and size abides by the TRUC topology restrictions given the
+// unconfirmed transactions it spends from, mapped to their version if known. Note we can't check
+// whether an unconfirmed TRUC parent already has another child: it would be replaced by this one.
+fn check_truc_topology(
+    version: bitcoin::transaction::Version,
+    tx_vb: u64,
+    unconfirmed_parents: &BTreeMap<bitcoin::Txid, Option<bitcoin::transaction::Version>>,
+) -> Result<(), TrucViolation> {
+    if version != TRUC_VERSION {
+        return match unconfirmed_parents
+            .iter()
+            .find(|(_, parent_version)| **parent_version == Some(TRUC_VERSION))
+        {
+            Some((txid, _)) => Err(TrucViolation::TrucParent(*txid)),
+            None => Ok(()),
+        };
+    }
+
+    if tx_vb > TRUC_MAX_VSIZE {
+        return Err(TrucViolation::TooLarge(tx_vb));
+    }
+    if unconfirmed_parents.len() > 1 {
+        return Err(TrucViolation::TooManyUnconfirmedParents(
+            unconfirmed_parents.len(),
+        ));
+    }
+    if let Some((txid, parent_version)) = unconfirmed_parents.iter().next() {
+        if *parent_version != Some(TRUC_VERSION) {
+            return Err(TrucViolation::NonTrucParent(*txid));
+        }
+        if tx_vb > TRUC_CHILD_MAX_VSIZE {
+            return Err(TrucViolation::ChildTooLarge(tx_vb));
+        }
+    }
+
+    Ok(())
+}
+
+// Apply some sanity checks on a created transaction's PSBT. The unconfirmed transactions it spends
+// from are given along with their version, if known.
 // TODO: add more sanity checks from revault_tx
 fn sanity_check_psbt(
     spent_desc: &descriptors::LianaDescriptor,
     psbt: &Psbt,
     use_primary_path: bool,
+    unconfirmed_parents: &BTreeMap<bitcoin::Txid, Option<bitcoin::transaction::Version>>,
 ) -> Result<(), SpendCreationError> {
     let tx = &psbt.unsigned_tx;
 
@@ -144,14 +482,12 @@ fn sanity_check_psbt(
 
     // Check the feerate isn't insane.
     let tx_vb = spent_desc.unsigned_tx_max_vbytes(tx, use_primary_path);
-    let feerate_sats_vb = abs_fee
-        .checked_div(tx_vb)
-        .ok_or(SpendCreationError::InsaneFees(
-            InsaneFeeInfo::InvalidFeerate,
-        ))?;
-    if !(1..=MAX_FEERATE).contains(&feerate_sats_vb) {
+    let feerate = Feerate::from_fee(abs_fee, tx_vb).ok_or(SpendCreationError::InsaneFees(
+        InsaneFeeInfo::InvalidFeerate,
+    ))?;
+    if !(min_feerate(tx.version == TRUC_VERSION)..=MAX_FEERATE).contains(&feerate) {
         return Err(SpendCreationError::InsaneFees(
-            InsaneFeeInfo::TooHighFeerate(feerate_sats_vb),
+            InsaneFeeInfo::TooHighFeerate(feerate),
         ));
     }
 
@@ -162,6 +498,10 @@ fn sanity_check_psbt(
         }
     }
 
+    // Make sure the transaction would be relayed if it is (or spends from) a TRUC transaction.
+    check_truc_topology(tx.version, tx_vb, unconfirmed_parents)
+        .map_err(SpendCreationError::Truc)?;
+
     Ok(())
 }
 
@@ -184,10 +524,52 @@ pub struct CandidateCoin {
     pub is_change: bool,
     /// Whether or not this coin must be selected by the coin selection algorithm.
     pub must_select: bool,
-    /// The nSequence field to set for an input spending this coin.
+    /// The nSequence field to set for an input spending this coin. Only set when spending
+    /// through a recovery path.
     pub sequence: Option<bitcoin::Sequence>,
     /// Information about in-mempool ancestors of the coin.
     pub ancestor_info: Option<AncestorInfo>,
+    /// The height of the block in which this coin was confirmed, if it is.
+    pub block_height: Option<i32>,
+    /// Identifier of the cluster of labels of this coin, if it is labelled. Coins whose labels are
+    /// in the same cluster (for instance they refer to the same counterparty) are considered
+    /// related by the [`CoinSelectionStrategy::Privacy`] strategy.
+    pub label_cluster: Option<u32>,
+}
+
+/// The strategy to use when automatically selecting coins for a transaction.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CoinSelectionStrategy {
+    /// Minimize the fees paid by the transaction, accounting for the cost of later spending the
+    /// change output at [`LONG_TERM_FEERATE`].
+    #[default]
+    LowestFee,
+    /// Minimize the waste of the selection given this long-term feerate. This consolidates coins
+    /// when the target feerate is lower than the long-term feerate, and spends as few of them as
+    /// possible otherwise.
+    Waste { long_term_feerate: Feerate },
+    /// Spend the coins confirmed first, to prevent the recovery paths from becoming available for
+    /// them. Unconfirmed coins are spent last.
+    OldestFirst,
+    /// Avoid linking unrelated coins together. Coins paying to the same address are always spent
+    /// together, and coins are taken from as few clusters of related coins (sharing an address, a
+    /// transaction or a label cluster) as possible. Among those, avoid creating a change output if
+    /// possible and otherwise spend as few coins as possible.
+    Privacy,
+    /// Spend the coins with the largest value first.
+    LargestFirst,
+}
+
+impl fmt::Display for CoinSelectionStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::LowestFee => write!(f, "lowest_fee"),
+            Self::Waste { .. } => write!(f, "waste"),
+            Self::OldestFirst => write!(f, "oldest_first"),
+            Self::Privacy => write!(f, "privacy"),
+            Self::LargestFirst => write!(f, "largest_first"),
+        }
+    }
 }
 
 /// A coin selection result.
@@ -205,6 +587,12 @@ pub struct CoinSelectionRes {
     pub max_change_amount: bitcoin::Amount,
     /// Fee added to pay for ancestors at the target feerate.
     pub fee_for_ancestors: bitcoin::Amount,
+    /// Fee needed for the transaction to reach the target feerate with this selection, including
+    /// the fee for ancestors. Any excess value not going to a change output comes on top of it.
+    pub fee: bitcoin::Amount,
+    /// Number of clusters of related coins the selected coins belong to. Only computed for the
+    /// [`CoinSelectionStrategy::Privacy`] strategy, set to 0 otherwise.
+    pub linked_clusters: usize,
 }
 
 /// Metric based on [`LowestFee`] that aims to minimize transaction fees
@@ -238,6 +626,290 @@ impl bdk_coin_select::BnbMetric for LowestFeeChangeCondition {
     }
 }
 
+/// Metric that aims to minimize the waste of the selection, that is the difference between the
+/// fee paid for the inputs at the target feerate and at the long-term feerate, plus either the
+/// excess given to fees or the cost of creating and later spending the change output.
+///
+/// If `must_have_change` is `true`, only solutions with change will be found.
+struct WasteChangeCondition {
+    target: Target,
+    long_term_feerate: FeeRate,
+    change_policy: ChangePolicy,
+    must_have_change: bool,
+}
+
+impl bdk_coin_select::BnbMetric for WasteChangeCondition {
+    fn score(&mut self, cs: &CoinSelector) -> Option<bdk_coin_select::float::Ordf32> {
+        let drain = cs.drain(self.target, self.change_policy);
+        if !cs.is_target_met_with_drain(self.target, drain)
+            || (drain.is_none() && self.must_have_change)
+        {
+            return None;
+        }
+        let waste = cs.waste(self.target, self.long_term_feerate, drain, 1.0);
+        Some(bdk_coin_select::float::Ordf32(waste))
+    }
+
+    fn bound(&mut self, cs: &CoinSelector) -> Option<bdk_coin_select::float::Ordf32> {
+        // The excess and the change costs are never negative. The waste of the inputs only
+        // decreases when adding more of them if the target feerate is lower than the long-term
+        // feerate, in which case the lowest possible waste is reached by selecting all of them.
+        let rate_diff = self.target.fee.rate.spwu() - self.long_term_feerate.spwu();
+        let input_weight = if rate_diff < 0.0 {
+            let mut all = cs.clone();
+            all.select_all();
+            all.input_weight()
+        } else {
+            cs.input_weight()
+        };
+        Some(bdk_coin_select::float::Ordf32(
+            input_weight as f32 * rate_diff,
+        ))
+    }
+
+    fn requires_ordering_by_descending_value_pwu(&self) -> bool {
+        true
+    }
+}
+
+// Group the candidates paying to the same address. Returns the indexes of the candidates in each
+// group.
+fn address_groups(candidate_coins: &[CandidateCoin]) -> Vec<Vec<usize>> {
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    let mut group_indexes = HashMap::<(bip32::ChildNumber, bool), usize>::new();
+    for (i, cand) in candidate_coins.iter().enumerate() {
+        match group_indexes.entry((cand.deriv_index, cand.is_change)) {
+            hash_map::Entry::Occupied(entry) => groups[*entry.get()].push(i),
+            hash_map::Entry::Vacant(entry) => {
+                entry.insert(groups.len());
+                groups.push(vec![i]);
+            }
+        }
+    }
+    groups
+}
+
+// Get the cluster of each group of candidates. Two groups are in the same cluster if they contain
+// coins created by the same transaction or in the same label cluster, directly or through other
+// groups. A cluster is identified by the index of one of its groups.
+fn group_clusters(candidate_coins: &[CandidateCoin], groups: &[Vec<usize>]) -> Vec<usize> {
+    // A union-find where each group points to another group of the same cluster, and the root of
+    // a cluster points to itself.
+    fn root(parents: &mut [usize], mut i: usize) -> usize {
+        while parents[i] != i {
+            parents[i] = parents[parents[i]];
+            i = parents[i];
+        }
+        i
+    }
+    let mut parents: Vec<usize> = (0..groups.len()).collect();
+    let mut txids = HashMap::<bitcoin::Txid, usize>::new();
+    let mut labels = HashMap::<u32, usize>::new();
+    for (g, group) in groups.iter().enumerate() {
+        for cand in group.iter().map(|i| &candidate_coins[*i]) {
+            let related = [
+                Some(*txids.entry(cand.outpoint.txid).or_insert(g)),
+                cand.label_cluster.map(|l| *labels.entry(l).or_insert(g)),
+            ];
+            for other in related.iter().flatten() {
+                let (a, b) = (root(&mut parents, g), root(&mut parents, *other));
+                parents[a] = b;
+            }
+        }
+    }
+    (0..groups.len()).map(|g| root(&mut parents, g)).collect()
+}
+
+// The parameters of a coin selection.
+struct Selection<'a> {
+    strategy: CoinSelectionStrategy,
+    target: Target,
+    change_policy: ChangePolicy,
+    long_term_feerate: FeeRate,
+    drain_weights: DrainWeights,
+    must_have_change: bool,
+    bnb_rounds: usize,
+    // For each candidate, whether it is unconfirmed and its confirmation height.
+    group_heights: &'a [(bool, Option<i32>)],
+}
+
+impl Selection<'_> {
+    // Run the coin selection algorithm. For the strategies minimizing a metric we use an
+    // opportunistic BnB and if it couldn't find any solution we fall back to selecting coins in
+    // the order of preference of the strategy.
+    fn run(&self, selector: &mut CoinSelector) -> Result<(), InsufficientFunds> {
+        let (target, change_policy) = (self.target, self.change_policy);
+        let bnb_res = match self.strategy {
+            // Without a feerate there is no fee to minimize, and the metrics assume a fee is paid.
+            CoinSelectionStrategy::LowestFee | CoinSelectionStrategy::Waste { .. }
+                if target.fee.rate == FeeRate::ZERO =>
+            {
+                None
+            }
+            CoinSelectionStrategy::LowestFee => {
+                let lowest_fee = LowestFee {
+                    target,
+                    long_term_feerate: self.long_term_feerate,
+                    change_policy,
+                };
+                let lowest_fee_change_cond = LowestFeeChangeCondition {
+                    lowest_fee,
+                    must_have_change: self.must_have_change,
+                };
+                Some(selector.run_bnb(lowest_fee_change_cond, self.bnb_rounds))
+            }
+            CoinSelectionStrategy::Waste { .. } => {
+                let waste_change_cond = WasteChangeCondition {
+                    target,
+                    long_term_feerate: self.long_term_feerate,
+                    change_policy,
+                    must_have_change: self.must_have_change,
+                };
+                Some(selector.run_bnb(waste_change_cond, self.bnb_rounds))
+            }
+            // A self-send always has a change output, don't bother looking for a changeless solution.
+            CoinSelectionStrategy::Privacy if !self.must_have_change => {
+                let changeless = Changeless {
+                    target,
+                    change_policy,
+                };
+                Some(selector.run_bnb(changeless, self.bnb_rounds))
+            }
+            _ => None,
+        };
+        if let Some(Err(e)) = &bnb_res {
+            log::debug!(
+                "Coin selection error: '{}'. Selecting coins in the order of the strategy '{}'...",
+                e.to_string(),
+                self.strategy
+            );
+        }
+        if matches!(bnb_res, Some(Ok(_))) {
+            return Ok(());
+        }
+        match self.strategy {
+            CoinSelectionStrategy::LowestFee | CoinSelectionStrategy::Waste { .. } => {
+                selector.sort_candidates_by_descending_value_pwu()
+            }
+            CoinSelectionStrategy::OldestFirst => {
+                selector.sort_candidates_by_key(|(i, _)| self.group_heights[i])
+            }
+            CoinSelectionStrategy::Privacy | CoinSelectionStrategy::LargestFirst => {
+                selector.sort_candidates_by_key(|(_, cand)| std::cmp::Reverse(cand.value))
+            }
+        }
+        // Select more coins until target is met and change condition satisfied.
+        loop {
+            let drain = selector.drain(target, change_policy);
+            if selector.is_target_met_with_drain(target, drain)
+                && (drain.is_some() || !self.must_have_change)
+            {
+                return Ok(());
+            }
+            if !selector.select_next() {
+                // If the solution must have change, we calculate how much is missing from the current
+                // selection in order for there to be a change output with the smallest possible value.
+                let drain = if self.must_have_change {
+                    bdk_coin_select::Drain {
+                        weights: self.drain_weights,
+                        value: DUST_OUTPUT_SATS,
+                    }
+                } else {
+                    drain
+                };
+                let missing = selector.excess(target, drain).unsigned_abs();
+                return Err(InsufficientFunds { missing });
+            }
+        }
+    }
+
+    // Run the coin selection algorithm using candidates from as few clusters as possible, given
+    // the cluster of each candidate. The clusters of the candidates already selected are always
+    // used. Returns the resulting selection along with the number of clusters it spends from.
+    fn run_by_cluster<'a>(
+        &self,
+        selector: &CoinSelector<'a>,
+        clusters: &[usize],
+    ) -> Result<(CoinSelector<'a>, usize), InsufficientFunds> {
+        let required: BTreeSet<usize> = selector
+            .selected_indices()
+            .iter()
+            .map(|i| clusters[*i])
+            .collect();
+        // The other clusters, by descending value.
+        let mut values = BTreeMap::<usize, u64>::new();
+        for (i, cluster) in clusters.iter().enumerate() {
+            if !required.contains(cluster) {
+                *values.entry(*cluster).or_default() += selector.candidate(i).value;
+            }
+        }
+        let mut others: Vec<(usize, u64)> = values.into_iter().collect();
+        others.sort_by(|a, b| b.1.cmp(&a.1));
+
+        let run_with = |allowed: &BTreeSet<usize>| {
+            let mut selector = selector.clone();
+            for (i, cluster) in clusters.iter().enumerate() {
+                if !allowed.contains(cluster) {
+                    selector.ban(i);
+                }
+            }
+            self.run(&mut selector).ok().map(|_| selector)
+        };
+        // First try not to link any other cluster, then to link a single one. In this case prefer
+        // solutions without change, and then those spending fewer coins.
+        let mut res = if required.is_empty() {
+            None
+        } else {
+            run_with(&required)
+        };
+        if res.is_none() {
+            res = others
+                .iter()
+                .filter_map(|(cluster, _)| {
+                    let mut allowed = required.clone();
+                    allowed.insert(*cluster);
+                    run_with(&allowed)
+                })
+                .min_by_key(|selector| {
+                    (
+                        selector.drain(self.target, self.change_policy).is_some(),
+                        selector
+                            .selected()
+                            .map(|(_, cand)| cand.input_count)
+                            .sum::<usize>(),
+                    )
+                });
+        }
+        // Otherwise link as few clusters as possible, starting with the largest ones.
+        if res.is_none() {
+            let mut allowed = required.clone();
+            for (cluster, _) in &others {
+                allowed.insert(*cluster);
+                res = run_with(&allowed);
+                if res.is_some() {
+                    break;
+                }
+            }
+        }
+        let selector = match res {
+            Some(selector) => selector,
+            None => {
+                // Not enough funds even using all the clusters. Get how much is missing.
+                let mut selector = selector.clone();
+                self.run(&mut selector)?;
+                selector
+            }
+        };
+        let n_clusters = selector
+            .selected_indices()
+            .iter()
+            .map(|i| clusters[*i])
+            .collect::<BTreeSet<_>>()
+            .len();
+        Ok((selector, n_clusters))
+    }
+}
+
 /// Select coins for spend.
 ///
 /// Returns the selected coins and the change amount, which could be zero.
@@ -249,9 +921,9 @@ impl bdk_coin_select::BnbMetric for LowestFeeChangeCondition {
 ///
 /// `change_txo` is the change output to add if needed (with any value).
 ///
-/// `feerate_vb` is the minimum feerate (in sats/vb). Note that the selected coins
-/// and change may result in a slightly lower feerate than this as the underlying
-/// function instead uses a minimum feerate of `feerate_vb / 4.0` sats/wu.
+/// `feerate` is the minimum feerate. Note that the selected coins and change may
+/// result in a slightly lower feerate than this as the underlying function instead
+/// uses a minimum feerate in sats/wu.
 ///
 /// If this is a replacement spend using RBF, then `replaced_fee` should be set to
 /// the total fees (in sats) of the transaction(s) being replaced, including any
@@ -263,14 +935,18 @@ impl bdk_coin_select::BnbMetric for LowestFeeChangeCondition {
 ///
 /// `must_have_change` indicates whether the transaction must have a change output.
 /// If `true`, the returned change amount will be positive.
+///
+/// `strategy` is the [`CoinSelectionStrategy`] used to choose among the candidates.
+#[allow(clippy::too_many_arguments)]
 fn select_coins_for_spend(
     candidate_coins: &[CandidateCoin],
     base_tx: bitcoin::Transaction,
     change_txo: bitcoin::TxOut,
-    feerate_vb: f32,
+    feerate: Feerate,
     replaced_fee: Option<u64>,
     max_sat_weight: u64,
     must_have_change: bool,
+    strategy: CoinSelectionStrategy,
 ) -> Result<CoinSelectionRes, InsufficientFunds> {
     let out_value_nochange = base_tx.output.iter().map(|o| o.value.to_sat()).sum();
     let out_weight_nochange = {
@@ -285,53 +961,87 @@ fn select_coins_for_spend(
     };
     let n_outputs_nochange = base_tx.output.len();
     let max_input_weight = TXIN_BASE_WEIGHT + max_sat_weight;
-    // Get feerate as u64 for calculation relating to ancestor below.
-    // We expect `feerate_vb` to be a positive integer, but take ceil()
-    // just in case to be sure we pay enough for ancestors.
-    let feerate_vb_u64 = feerate_vb.ceil() as u64;
     let witness_factor: u64 = WITNESS_SCALE_FACTOR
         .try_into()
         .expect("scale factor must fit in u64");
     // This will be used to store any extra weight added to candidates.
     let mut added_weights = HashMap::<bitcoin::OutPoint, u64>::with_capacity(candidate_coins.len());
-    let candidates: Vec<Candidate> = candidate_coins
+    let coin_weights: Vec<u64> = candidate_coins
         .iter()
-        .map(|cand| Candidate {
-            input_count: 1,
-            value: cand.amount.to_sat(),
-            weight: {
-                let extra = cand
-                    .ancestor_info
-                    .map(|info| {
-                        // The implied ancestor vsize if the fee had been paid at our target feerate.
-                        let ancestor_vsize_at_feerate = <u32 as Into<u64>>::into(info.fee)
-                            .checked_div(feerate_vb_u64)
-                            .expect("feerate is greater than zero");
-                        // If the actual ancestor vsize is bigger than the implied vsize, we will need to
-                        // pay the difference in order for the combined feerate to be at the target value.
-                        // We multiply the vsize by 4 to get the ancestor weight, which is an upper bound
-                        // on its true weight (vsize*4 - 3 <= weight <= vsize*4), to ensure we pay enough.
-                        // Note that if candidates share ancestors, we may add this difference more than
-                        // once in the resulting transaction.
-                        info.vsize
-                            .saturating_sub(ancestor_vsize_at_feerate)
-                            .checked_mul(witness_factor)
-                            .expect("weight difference must fit in u64")
-                    })
-                    .unwrap_or(0);
-                // Store the extra weight for this candidate for use later on.
-                // At the same time, make sure there are no duplicate outpoints.
-                assert!(added_weights.insert(cand.outpoint, extra).is_none());
-                max_input_weight
-                    .checked_add(extra)
-                    .expect("effective weight must fit in u64")
-            },
+        .map(|cand| {
+            // At a zero feerate (only allowed for TRUC transactions) there is no ancestor fee
+            // to make up for.
+            let extra = cand
+                .ancestor_info
+                .filter(|_| feerate.to_sat_per_kvb() > 0)
+                .map(|info| {
+                    // The implied ancestor vsize if the fee had been paid at our target feerate.
+                    let ancestor_vsize_at_feerate = <u32 as Into<u64>>::into(info.fee)
+                        .saturating_mul(1_000)
+                        .checked_div(feerate.to_sat_per_kvb())
+                        .expect("feerate is greater than zero");
+                    // If the actual ancestor vsize is bigger than the implied vsize, we will need to
+                    // pay the difference in order for the combined feerate to be at the target value.
+                    // We multiply the vsize by 4 to get the ancestor weight, which is an upper bound
+                    // on its true weight (vsize*4 - 3 <= weight <= vsize*4), to ensure we pay enough.
+                    // Note that if candidates share ancestors, we may add this difference more than
+                    // once in the resulting transaction.
+                    info.vsize
+                        .saturating_sub(ancestor_vsize_at_feerate)
+                        .checked_mul(witness_factor)
+                        .expect("weight difference must fit in u64")
+                })
+                .unwrap_or(0);
+            // Store the extra weight for this candidate for use later on.
+            // At the same time, make sure there are no duplicate outpoints.
+            assert!(added_weights.insert(cand.outpoint, extra).is_none());
+            max_input_weight
+                .checked_add(extra)
+                .expect("effective weight must fit in u64")
+        })
+        .collect();
+    // The candidates for the coin selection algorithm are groups of coins which are selected
+    // together. When preserving privacy coins paying to the same address are grouped together
+    // (to not partially spend an address), otherwise each coin is its own group.
+    let groups = if strategy == CoinSelectionStrategy::Privacy {
+        address_groups(candidate_coins)
+    } else {
+        (0..candidate_coins.len()).map(|i| vec![i]).collect()
+    };
+    let candidates: Vec<Candidate> = groups
+        .iter()
+        .map(|group| Candidate {
+            input_count: group.len(),
+            value: group
+                .iter()
+                .map(|i| candidate_coins[*i].amount.to_sat())
+                .sum(),
+            weight: group
+                .iter()
+                .map(|i| coin_weights[*i])
+                .try_fold(0u64, |sum, w| sum.checked_add(w))
+                .expect("effective weight must fit in u64"),
             is_segwit: true, // We only support receiving on Segwit scripts.
         })
         .collect();
+    // The confirmation height of the oldest coin in each group, used to sort them from the oldest
+    // to the most recent (unconfirmed coins last).
+    let group_heights: Vec<(bool, Option<i32>)> = groups
+        .iter()
+        .map(|group| {
+            group
+                .iter()
+                .map(|i| {
+                    let height = candidate_coins[*i].block_height;
+                    (height.is_none(), height)
+                })
+                .min()
+                .expect("groups are never empty")
+        })
+        .collect();
     let mut selector = CoinSelector::new(&candidates);
-    for (i, cand) in candidate_coins.iter().enumerate() {
-        if cand.must_select {
+    for (i, group) in groups.iter().enumerate() {
+        if group.iter().any(|c| candidate_coins[*c].must_select) {
             // It's fine because the index passed to `select` refers to the original candidates ordering
             // (and in any case the ordering of candidates is still the same in the coin selector).
             selector.select(i);
@@ -341,8 +1051,12 @@ fn select_coins_for_spend(
     // Now set the change policy. We use a policy which ensures no change output is created with a
     // lower value than our custom dust limit. NOTE: the change output weight must not account for
     // a potential difference in the size of the outputs count varint.
-    let feerate = FeeRate::from_sat_per_vb(feerate_vb);
-    let long_term_feerate = FeeRate::from_sat_per_vb(LONG_TERM_FEERATE_VB);
+    let long_term_feerate = match strategy {
+        CoinSelectionStrategy::Waste { long_term_feerate } => long_term_feerate,
+        _ => LONG_TERM_FEERATE,
+    };
+    let long_term_feerate = FeeRate::from_sat_per_vb(long_term_feerate.to_sat_per_vb_f32());
+    let feerate = FeeRate::from_sat_per_vb(feerate.to_sat_per_vb_f32());
     let change_output_weight = change_txo.weight().to_wu();
     let drain_weights = DrainWeights {
         output_weight: change_output_weight,
@@ -358,8 +1072,9 @@ fn select_coins_for_spend(
         long_term_feerate,
     );
 
-    // Finally, run the coin selection algorithm. We use an opportunistic BnB and if it couldn't
-    // find any solution we fall back to selecting coins by descending value.
+    // Finally, run the coin selection algorithm. For the strategies minimizing a metric we use an
+    // opportunistic BnB and if it couldn't find any solution we fall back to selecting coins in
+    // the order of preference of the strategy.
     let replace = replaced_fee.map(Replace::new);
     let target_fee = TargetFee {
         rate: feerate,
@@ -374,15 +1089,6 @@ fn select_coins_for_spend(
         fee: target_fee,
         outputs: target_outputs,
     };
-    let lowest_fee = LowestFee {
-        target,
-        long_term_feerate,
-        change_policy,
-    };
-    let lowest_fee_change_cond = LowestFeeChangeCondition {
-        lowest_fee,
-        must_have_change,
-    };
     // Scale down the number of rounds to perform if there is too many candidates. If the binary
     // isn't optimized, scale it down further to avoid lags in hot loops.
     let bnb_rounds = match candidate_coins.len() {
@@ -392,35 +1098,24 @@ fn select_coins_for_spend(
     };
     #[cfg(debug_assertions)]
     let bnb_rounds = bnb_rounds / 1_000;
-    if let Err(e) = selector.run_bnb(lowest_fee_change_cond, bnb_rounds) {
-        log::debug!(
-            "Coin selection error: '{}'. Selecting coins by descending value per weight unit...",
-            e.to_string()
-        );
-        selector.sort_candidates_by_descending_value_pwu();
-        // Select more coins until target is met and change condition satisfied.
-        loop {
-            let drain = selector.drain(target, change_policy);
-            if selector.is_target_met_with_drain(target, drain)
-                && (drain.is_some() || !must_have_change)
-            {
-                break;
-            }
-            if !selector.select_next() {
-                // If the solution must have change, we calculate how much is missing from the current
-                // selection in order for there to be a change output with the smallest possible value.
-                let drain = if must_have_change {
-                    bdk_coin_select::Drain {
-                        weights: drain_weights,
-                        value: DUST_OUTPUT_SATS,
-                    }
-                } else {
-                    drain
-                };
-                let missing = selector.excess(target, drain).unsigned_abs();
-                return Err(InsufficientFunds { missing });
-            }
-        }
+    let selection = Selection {
+        strategy,
+        target,
+        change_policy,
+        long_term_feerate,
+        drain_weights,
+        must_have_change,
+        bnb_rounds,
+        group_heights: &group_heights,
+    };
+    let mut linked_clusters = 0;
+    if strategy == CoinSelectionStrategy::Privacy {
+        let clusters = group_clusters(candidate_coins, &groups);
+        let (selector_res, n_clusters) = selection.run_by_cluster(&selector, &clusters)?;
+        selector = selector_res;
+        linked_clusters = n_clusters;
+    } else {
+        selection.run(&mut selector)?;
     }
     // By now, selection is complete and we can check how much change to give according to our policy.
     let drain = selector.drain(target, change_policy);
@@ -437,11 +1132,19 @@ fn select_coins_for_spend(
             .try_into()
             .expect("value is non-negative"),
     );
+    let excess: u64 = selector
+        .excess(target, drain)
+        .max(0)
+        .try_into()
+        .expect("value is non-negative");
+    let fee = bitcoin::Amount::from_sat(
+        selector.selected_value() - out_value_nochange - drain.value - excess,
+    );
     let mut total_added_weight: u64 = 0;
     let selected = selector
         .selected_indices()
         .iter()
-        .map(|i| candidate_coins[*i])
+        .flat_map(|i| groups[*i].iter().map(|c| candidate_coins[*c]))
         .inspect(|cand| {
             total_added_weight = total_added_weight
                 .checked_add(
@@ -460,6 +1163,8 @@ fn select_coins_for_spend(
         change_amount,
         max_change_amount,
         fee_for_ancestors,
+        fee,
+        linked_clusters,
     })
 }
 
@@ -523,28 +1228,125 @@ pub struct SpendOutputAddress {
     pub info: Option<AddrInfo>,
 }
 
+/// The recipient of an output of a transaction we create.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SpendDestination {
+    /// Pay to an address, which may be one of ours.
+    Address(SpendOutputAddress),
+    /// Pay to a raw scriptPubKey, for instance a data-carrier (OP_RETURN) output.
+    Script(bitcoin::ScriptBuf),
+}
+
+impl SpendDestination {
+    /// Create a data-carrier (OP_RETURN) output script pushing this data.
+    pub fn data(data: &[u8]) -> Result<Self, SpendCreationError> {
+        let push_bytes = <&bitcoin::script::PushBytes>::try_from(data)
+            .map_err(|_| SpendCreationError::DataCarrierTooLarge(data.len()))?;
+        Ok(Self::Script(bitcoin::ScriptBuf::new_op_return(push_bytes)))
+    }
+
+    pub fn script_pubkey(&self) -> bitcoin::ScriptBuf {
+        match self {
+            Self::Address(address) => address.addr.script_pubkey(),
+            Self::Script(script) => script.clone(),
+        }
+    }
+
+    fn addr_info(&self) -> Option<AddrInfo> {
+        match self {
+            Self::Address(address) => address.info,
+            Self::Script(_) => None,
+        }
+    }
+}
+
+impl From<SpendOutputAddress> for SpendDestination {
+    fn from(address: SpendOutputAddress) -> Self {
+        Self::Address(address)
+    }
+}
+
+/// An output of a transaction we create.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpendOutput {
+    pub destination: SpendDestination,
+    pub amount: bitcoin::Amount,
+    /// Whether to deduct the transaction fee from this output's amount instead of paying it from
+    /// the inputs. The fee is split equally among all the outputs for which this is set.
+    pub subtract_fee: bool,
+}
+
+impl SpendOutput {
+    pub fn new(destination: SpendDestination, amount: bitcoin::Amount) -> Self {
+        Self {
+            destination,
+            amount,
+            subtract_fee: false,
+        }
+    }
+}
+
+// The part of the fee paid by the i-th of `n` outputs the fee is subtracted from. The fee is split
+// equally and the first output pays the remainder, if any.
+fn fee_share(fee: u64, n: usize, i: usize) -> u64 {
+    let n = n as u64;
+    if i == 0 {
+        fee / n + fee % n
+    } else {
+        fee / n
+    }
+}
+
+// Set the value of the outputs at `indexes` in `tx` to the amount of the corresponding
+// destination minus their share of `fee`, and sanity check the resulting values.
+fn deduct_fee(
+    tx: &mut bitcoin::Transaction,
+    destinations: &[SpendOutput],
+    indexes: &[usize],
+    fee: u64,
+) -> Result<(), SpendCreationError> {
+    for (i, index) in indexes.iter().enumerate() {
+        let SpendOutput {
+            destination,
+            amount,
+            ..
+        } = &destinations[*index];
+        let share = bitcoin::Amount::from_sat(fee_share(fee, indexes.len(), i));
+        let value = amount
+            .checked_sub(share)
+            .ok_or(SpendCreationError::InvalidOutputValue(
+                bitcoin::Amount::ZERO,
+            ))?;
+        check_destination_value(destination, value)?;
+        tx.output[*index].value = value;
+    }
+    Ok(())
+}
+
 /// A trait for getting a wallet transaction by its txid.
 pub trait TxGetter {
     /// Get a wallet transaction. Allows for a cache by making the access mutable.
     fn get_tx(&mut self, txid: &bitcoin::Txid) -> Option<bitcoin::Transaction>;
 }
 
-/// Specify the fee requirements for a transaction. In both cases set a target feerate in satoshi
-/// per virtual byte. For RBF also set a minimum fee in satoshis for this transaction. See
+/// Specify the fee requirements for a transaction. In both cases set a target feerate. For RBF also set a minimum fee in satoshis for this transaction. See
 /// https://github.com/bitcoin/bitcoin/blob/master/doc/policy/mempool-replacements.md for more
 /// information about how it should be set.
 pub enum SpendTxFees {
-    /// The target feerate in sats/vb for this transaction.
-    Regular(u64),
-    /// The (target feerate in sats/vb, total fees in sats of transaction(s) to be replaced
-    /// including descendants) for this transaction.
-    Rbf(u64, u64),
+    /// The target feerate for this transaction.
+    Regular(Feerate),
+    /// The (target feerate, total fees in sats of transaction(s) to be replaced including
+    /// descendants) for this transaction.
+    Rbf(Feerate, u64),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub enum CreateSpendWarning {
     ChangeAddedToFee(u64),
     AdditionalFeeForAncestors(u64),
+    /// The coins could not be selected from a single cluster of related coins. This many clusters
+    /// are linked together by the transaction.
+    LinkedCoinClusters(usize),
 }
 
 impl fmt::Display for CreateSpendWarning {
@@ -567,6 +1369,13 @@ impl fmt::Display for CreateSpendWarning {
                 amt,
                 if *amt > 1 { "s" } else { "" },
             ),
+            CreateSpendWarning::LinkedCoinClusters(n) => write!(
+                f,
+                "Privacy: the selected coins could not be taken from a single group of related \
+                coins (sharing an address, a transaction or a label). This transaction links {} \
+                such groups together on-chain.",
+                n
+            ),
         }
     }
 }
@@ -581,9 +1390,10 @@ pub struct CreateSpendRes {
 }
 
 /// Create a PSBT for a transaction spending some, or all, of `candidate_coins` to `destinations`.
-/// Important information for signers will be populated. Will refuse to create outputs worth less
-/// than `DUST_OUTPUT_SATS`. Will refuse to create a transaction paying more than `MAX_FEE`
-/// satoshis in fees or whose feerate is larger than `MAX_FEERATE` sats/vb.
+/// Important information for signers will be populated. Will refuse to create outputs to an
+/// address worth less than `DUST_OUTPUT_SATS`, and raw-script outputs which would not be relayed
+/// by Bitcoin Core with its default policy. Will refuse to create a transaction paying more than
+/// `MAX_FEE` satoshis in fees or whose feerate is larger than `MAX_FEERATE`.
 ///
 /// More about the parameters:
 /// * `main_descriptor`: the multipath Liana descriptor, used to derive the addresses of the
@@ -591,29 +1401,37 @@ pub struct CreateSpendRes {
 /// * `secp`: necessary to derive data from the descriptor.
 /// * `tx_getter`: an interface to get the wallet transaction for the prevouts of the transaction.
 ///   Wouldn't be necessary if we only spent Taproot coins.
-/// * `destinations`: a list of addresses and amounts, one per recipient i.e. per output in the
-///   transaction created. If empty all the `candidate_coins` get spent and a single change output
+/// * `destinations`: a list of addresses or raw scripts and amounts, one per recipient i.e. per
+///   output in the transaction created. At most one of them may be a data-carrier (OP_RETURN)
+///   output, with a zero amount. If empty all the `candidate_coins` get spent and a single change output
 ///   is created to the provided `change_addr`. Can be used to sweep all, or some, coins from the
 ///   wallet.
 /// * `candidate_coins`: a list of coins to consider including as input of the transaction. If
 ///   `destinations` is empty, they will all be included as inputs of the transaction. Otherwise, a
 ///   coin selection algorithm will be run to spend the most efficient subset of them to meet the
 ///   `destinations` requirements.
-/// * `fees`: the target feerate (in sats/vb) and, if necessary, minimum absolute fee for this tx.
+/// * `fees`: the target feerate and, if necessary, minimum absolute fee for this tx.
+/// * `coin_selection`: the strategy to use when selecting among the `candidate_coins`. Irrelevant
+///   if all of them must be selected.
 /// * `change_addr`: the address to use for a change output if we need to create one. Can be set to
 ///   an external address (if combined with an empty list of `destinations` it's useful to sweep some
 ///   or all coins of a wallet to an external address).
 /// * `locktime`: the locktime to use for the transaction.
+/// * `truc`: whether to create a TRUC (version 3) transaction (BIP431) instead of a version 2 one.
+///   TRUC transactions are subject to topology restrictions which make fee bumping them through
+///   CPFP reliable. Any unconfirmed coin spent must be from a transaction of the same kind.
 #[allow(clippy::too_many_arguments)]
 pub fn create_spend(
     main_descriptor: &descriptors::LianaDescriptor,
     secp: &secp256k1::Secp256k1<secp256k1::VerifyOnly>,
     tx_getter: &mut impl TxGetter,
-    destinations: &[(SpendOutputAddress, bitcoin::Amount)],
+    destinations: &[SpendOutput],
     candidate_coins: &[CandidateCoin],
     fees: SpendTxFees,
+    coin_selection: CoinSelectionStrategy,
     change_addr: SpendOutputAddress,
     locktime: LockTime,
+    truc: bool,
 ) -> Result<CreateSpendRes, SpendCreationError> {
     // This method does quite a few things. In addition, we support different modes (coin control
     // vs automated coin selection, self-spend, sweep, etc..) which make the logic a bit more
@@ -630,18 +1448,22 @@ pub fn create_spend(
     // 4. Finalize the PSBT and sanity check it before returning it.
 
     let mut warnings = Vec::new();
-    let (feerate_vb, replaced_fee) = match fees {
+    let (feerate, replaced_fee) = match fees {
         SpendTxFees::Regular(feerate) => (feerate, None),
         SpendTxFees::Rbf(feerate, fee) => (feerate, Some(fee)),
     };
     let is_self_send = destinations.is_empty();
-    if feerate_vb < 1 {
-        return Err(SpendCreationError::InvalidFeerate(feerate_vb));
+    if feerate < min_feerate(truc) {
+        return Err(SpendCreationError::InvalidFeerate(feerate));
     }
 
     // Create transaction with no inputs and no outputs.
     let mut tx = bitcoin::Transaction {
-        version: bitcoin::transaction::Version::TWO,
+        version: if truc {
+            TRUC_VERSION
+        } else {
+            bitcoin::transaction::Version::TWO
+        },
         lock_time: locktime,
         input: Vec::with_capacity(candidate_coins.iter().filter(|c| c.must_select).count()),
         output: Vec::with_capacity(destinations.len()),
@@ -649,17 +1471,29 @@ pub fn create_spend(
     // Add the destinations outputs to the transaction and PSBT. At the same time
     // sanity check each output's value.
     let mut psbt_outs = Vec::with_capacity(destinations.len());
-    for (address, amount) in destinations {
-        check_output_value(*amount)?;
+    for SpendOutput {
+        destination,
+        amount,
+        subtract_fee,
+    } in destinations
+    {
+        let script_pubkey = destination.script_pubkey();
+        if *subtract_fee && script_pubkey.is_op_return() {
+            return Err(SpendCreationError::SubtractFeeFromDataCarrier);
+        }
+        // The value of the outputs we subtract the fee from is checked once it's deducted.
+        if !subtract_fee {
+            check_destination_value(destination, *amount)?;
+        }
 
         tx.output.push(bitcoin::TxOut {
             value: *amount,
-            script_pubkey: address.addr.script_pubkey(),
+            script_pubkey,
         });
         // If it's an address of ours, signal it as change to signing devices by adding the
         // BIP32 derivation path to the PSBT output.
         let mut psbt_out = PsbtOut::default();
-        if let Some(AddrInfo { index, is_change }) = address.info {
+        if let Some(AddrInfo { index, is_change }) = destination.addr_info() {
             let desc = if is_change {
                 main_descriptor.change_descriptor()
             } else {
@@ -671,6 +1505,15 @@ pub fn create_spend(
         psbt_outs.push(psbt_out);
     }
     assert_eq!(tx.output.is_empty(), is_self_send);
+    if tx
+        .output
+        .iter()
+        .filter(|o| o.script_pubkey.is_op_return())
+        .count()
+        > 1
+    {
+        return Err(SpendCreationError::MultipleDataCarriers);
+    }
 
     // Now compute whether we'll need a change output while automatically selecting coins to be
     // used as input if necessary.
@@ -680,48 +1523,91 @@ pub fn create_spend(
         value: bitcoin::Amount::MAX,
         script_pubkey: change_addr.addr.script_pubkey(),
     };
-    // If no candidates have relative locktime, then we should use the primary spending path.
+    // If no candidates have a sequence set, then we should use the primary spending path. A
+    // sequence is only set when spending through a recovery path, be it to enable its relative
+    // timelock or the transaction's nLockTime for an absolute one.
     // Note we set this value before actually selecting the coins, but we expect either all
-    // candidates or none to have relative locktime sequence so this is fine.
-    let use_primary_path = !candidate_coins
-        .iter()
-        .filter_map(|cand| cand.sequence)
-        .any(|seq| seq.is_relative_lock_time());
+    // candidates or none to have a sequence set so this is fine.
+    let use_primary_path = candidate_coins.iter().all(|cand| cand.sequence.is_none());
     // Now select the coins necessary using the provided candidates and determine whether
     // there is any leftover to create a change output.
+    // The indexes of the outputs we subtract the fee from, and the fee subtracted from them.
+    let sffo_indexes: Vec<usize> = destinations
+        .iter()
+        .enumerate()
+        .filter_map(|(i, out)| out.subtract_fee.then_some(i))
+        .collect();
+    let mut subtracted_fee: u64 = 0;
     let CoinSelectionRes {
         selected,
-        change_amount,
-        max_change_amount,
+        mut change_amount,
+        mut max_change_amount,
         fee_for_ancestors,
-    } = {
+        fee,
+        linked_clusters,
+    } = loop {
         // At this point the transaction still has no input and no change output, as expected
         // by the coins selection helper function.
         assert!(tx.input.is_empty());
         assert_eq!(tx.output.len(), destinations.len());
-        // TODO: Introduce general conversion error type.
-        let feerate_vb: f32 = {
-            let fr: u16 = feerate_vb.try_into().map_err(|_| {
-                SpendCreationError::InsaneFees(InsaneFeeInfo::TooHighFeerate(feerate_vb))
-            })?;
-            fr
-        }
-        .into();
         let max_sat_wu = main_descriptor
             .max_sat_weight(use_primary_path)
             .try_into()
             .expect("Weight must fit in a u64");
-        select_coins_for_spend(
+        let res = select_coins_for_spend(
             candidate_coins,
             tx.clone(),
             change_txo.clone(),
-            feerate_vb,
+            feerate,
             replaced_fee,
             max_sat_wu,
             is_self_send,
-        )
-        .map_err(SpendCreationError::CoinSelection)?
+            coin_selection,
+        );
+        if sffo_indexes.is_empty() {
+            break res.map_err(SpendCreationError::CoinSelection)?;
+        }
+        // When subtracting the fee from the outputs, we start by deducting nothing and increase
+        // the deduction until it covers the fee needed by the selection. The deduction only ever
+        // increases so this terminates, at the latest when the outputs become too small.
+        match res {
+            Ok(res) if res.fee.to_sat() <= subtracted_fee => break res,
+            Ok(res) => subtracted_fee = res.fee.to_sat(),
+            Err(InsufficientFunds { missing }) => subtracted_fee += missing,
+        }
+        deduct_fee(&mut tx, destinations, &sffo_indexes, subtracted_fee)?;
     };
+    // We may have deducted more than the fee needed by the selection. Give the difference back to
+    // the outputs, taking it from the change if there is one and it remains above the dust limit.
+    // Otherwise the change is dropped and the excess value that would go to fees is given back.
+    if !sffo_indexes.is_empty() {
+        let over_deducted = bitcoin::Amount::from_sat(subtracted_fee - fee.to_sat());
+        let refund = match change_amount.checked_sub(over_deducted) {
+            Some(change) if change.to_sat() >= DUST_OUTPUT_SATS => {
+                change_amount = change;
+                over_deducted.to_sat()
+            }
+            _ => {
+                // The change output isn't part of the transaction yet, so its value is included
+                // in the excess.
+                let out_value: u64 = tx.output.iter().map(|o| o.value.to_sat()).sum();
+                let in_value: u64 = selected.iter().map(|c| c.amount.to_sat()).sum();
+                let excess = in_value - out_value - fee.to_sat();
+                change_amount = bitcoin::Amount::ZERO;
+                let refund = cmp::min(excess, subtracted_fee);
+                max_change_amount = max_change_amount
+                    .checked_sub(bitcoin::Amount::from_sat(refund))
+                    .unwrap_or(bitcoin::Amount::ZERO);
+                refund
+            }
+        };
+        deduct_fee(
+            &mut tx,
+            destinations,
+            &sffo_indexes,
+            subtracted_fee - refund,
+        )?;
+    }
     // If necessary, add a change output.
     // For a self-send, coin selection will only find solutions with change and will otherwise
     // return an error. In any case, the PSBT sanity check will catch a transaction with no outputs.
@@ -757,10 +1643,20 @@ pub fn create_spend(
             fee_for_ancestors.to_sat(),
         ));
     }
+    if linked_clusters > 1 {
+        warnings.push(CreateSpendWarning::LinkedCoinClusters(linked_clusters));
+    }
 
-    // Iterate through selected coins and add necessary information to the PSBT inputs.
+    // Iterate through selected coins and add necessary information to the PSBT inputs. Record
+    // the version of the unconfirmed transactions we spend from for the TRUC checks.
     let mut psbt_ins = Vec::with_capacity(selected.len());
+    let mut unconfirmed_parents = BTreeMap::new();
     for cand in &selected {
+        if cand.block_height.is_none() {
+            if let btree_map::Entry::Vacant(entry) = unconfirmed_parents.entry(cand.outpoint.txid) {
+                entry.insert(tx_getter.get_tx(&cand.outpoint.txid).map(|tx| tx.version));
+            }
+        }
         let sequence = cand
             .sequence
             .unwrap_or(bitcoin::Sequence::ENABLE_RBF_NO_LOCKTIME);
@@ -795,7 +1691,12 @@ pub fn create_spend(
         inputs: psbt_ins,
         outputs: psbt_outs,
     };
-    sanity_check_psbt(main_descriptor, &psbt, use_primary_path)?;
+    sanity_check_psbt(
+        main_descriptor,
+        &psbt,
+        use_primary_path,
+        &unconfirmed_parents,
+    )?;
     // TODO: maybe check for common standardness rules (max size, ..)?
 
     Ok(CreateSpendRes {
@@ -809,9 +1710,82 @@ pub fn create_spend(
 mod tests {
     use super::*;
 
-    use std::time::Duration;
+    use std::{str::FromStr, time::Duration};
+
+    use miniscript::bitcoin::{
+        absolute::{Height, LockTime},
+        hashes::Hash,
+        transaction::Version,
+        Amount, OutPoint, ScriptBuf, Transaction, TxOut, Txid, WScriptHash,
+    };
+
+    // The candidates used to test all the coin selection strategies, as (value, block height).
+    const CANDIDATES: [(u64, Option<i32>); 6] = [
+        (1_000_000, Some(100)),
+        (300_000, Some(500)),
+        (250_000, Some(50)),
+        (2_000_000, None),
+        (120_000, Some(300)),
+        (201_000, Some(400)),
+    ];
+
+    fn candidates() -> Vec<CandidateCoin> {
+        CANDIDATES
+            .iter()
+            .enumerate()
+            .map(|(i, (value, block_height))| CandidateCoin {
+                outpoint: OutPoint::new(Txid::from_byte_array([i as u8; 32]), 0),
+                amount: Amount::from_sat(*value),
+                deriv_index: bip32::ChildNumber::from_normal_idx(i as u32).unwrap(),
+                is_change: false,
+                must_select: false,
+                sequence: None,
+                ancestor_info: None,
+                block_height: *block_height,
+                label_cluster: None,
+            })
+            .collect()
+    }
+
+    // Select coins among these candidates for a transaction with a single output of this value.
+    fn select(
+        candidates: &[CandidateCoin],
+        value: u64,
+        feerate_vb: u64,
+        strategy: CoinSelectionStrategy,
+    ) -> Result<CoinSelectionRes, InsufficientFunds> {
+        let script_pubkey = ScriptBuf::new_p2wsh(&WScriptHash::all_zeros());
+        let base_tx = Transaction {
+            version: Version::TWO,
+            lock_time: LockTime::ZERO,
+            input: Vec::new(),
+            output: vec![TxOut {
+                value: Amount::from_sat(value),
+                script_pubkey: script_pubkey.clone(),
+            }],
+        };
+        let change_txo = TxOut {
+            value: Amount::ZERO,
+            script_pubkey,
+        };
+        select_coins_for_spend(
+            candidates,
+            base_tx,
+            change_txo,
+            Feerate::from_sat_per_vb(feerate_vb),
+            None,
+            300,
+            false,
+            strategy,
+        )
+    }
 
-    use miniscript::bitcoin::absolute::{Height, LockTime};
+    // The values of the selected coins, sorted by descending value.
+    fn selected_values(res: &CoinSelectionRes) -> Vec<u64> {
+        let mut values: Vec<u64> = res.selected.iter().map(|c| c.amount.to_sat()).collect();
+        values.sort_by(|a, b| b.cmp(a));
+        values
+    }
 
     #[test]
     fn test_anti_fee_sniping_locktime() {
@@ -922,4 +1896,431 @@ mod tests {
             LockTime::from_height(1).unwrap() // subtract 90
         );
     }
+
+    #[test]
+    fn coin_selection_strategies() {
+        let cands = candidates();
+
+        // A coin almost exactly matches the target. It's the cheapest selection, and it doesn't need
+        // a change output.
+        let res = select(&cands, 200_000, 2, CoinSelectionStrategy::LowestFee).unwrap();
+        assert_eq!(selected_values(&res), vec![201_000]);
+        assert_eq!(res.change_amount, Amount::ZERO);
+        let res = select(&cands, 200_000, 2, CoinSelectionStrategy::Privacy).unwrap();
+        assert_eq!(selected_values(&res), vec![201_000]);
+        assert_eq!(res.change_amount, Amount::ZERO);
+
+        // When the long-term feerate is higher than the target feerate, waste minimization
+        // consolidates coins. Otherwise it spends as few of them as possible.
+        let strategy = CoinSelectionStrategy::Waste {
+            long_term_feerate: Feerate::from_sat_per_vb(50),
+        };
+        let res = select(&cands, 200_000, 2, strategy).unwrap();
+        assert!(res.selected.len() >= 4, "{:?}", selected_values(&res));
+        assert!(res.change_amount > Amount::ZERO);
+        let strategy = CoinSelectionStrategy::Waste {
+            long_term_feerate: Feerate::from_sat_per_vb(1),
+        };
+        let res = select(&cands, 200_000, 2, strategy).unwrap();
+        assert_eq!(res.selected.len(), 1);
+
+        // The coin confirmed first is spent first, even if others would be cheaper.
+        let res = select(&cands, 200_000, 2, CoinSelectionStrategy::OldestFirst).unwrap();
+        assert_eq!(selected_values(&res), vec![250_000]);
+        assert!(res.change_amount > Amount::ZERO);
+        // Unconfirmed coins are only spent once all confirmed coins are.
+        let res = select(&cands, 1_800_000, 2, CoinSelectionStrategy::OldestFirst).unwrap();
+        assert!(res.selected.iter().all(|c| c.block_height.is_some()));
+        assert_eq!(res.selected.len(), 5);
+        let res = select(&cands, 2_500_000, 2, CoinSelectionStrategy::OldestFirst).unwrap();
+        assert_eq!(res.selected.len(), 6);
+
+        // The largest coins are spent first.
+        let res = select(&cands, 200_000, 2, CoinSelectionStrategy::LargestFirst).unwrap();
+        assert_eq!(selected_values(&res), vec![2_000_000]);
+        let res = select(&cands, 2_500_000, 2, CoinSelectionStrategy::LargestFirst).unwrap();
+        assert_eq!(selected_values(&res), vec![2_000_000, 1_000_000]);
+
+        // Without a changeless solution, the privacy strategy spends as few coins as possible.
+        let no_exact_match: Vec<_> = cands
+            .iter()
+            .filter(|c| c.amount.to_sat() != 201_000)
+            .cloned()
+            .collect();
+        let res = select(&no_exact_match, 200_000, 2, CoinSelectionStrategy::Privacy).unwrap();
+        assert_eq!(res.selected.len(), 1);
+        assert!(res.change_amount > Amount::ZERO);
+        let res = select(
+            &no_exact_match,
+            2_500_000,
+            2,
+            CoinSelectionStrategy::Privacy,
+        )
+        .unwrap();
+        assert_eq!(res.selected.len(), 2);
+
+        // Coins which must be selected always are, whatever the strategy.
+        let mut must_select = cands.clone();
+        must_select[4].must_select = true;
+        for strategy in [
+            CoinSelectionStrategy::LowestFee,
+            CoinSelectionStrategy::Waste {
+                long_term_feerate: Feerate::from_sat_per_vb(1),
+            },
+            CoinSelectionStrategy::OldestFirst,
+            CoinSelectionStrategy::Privacy,
+            CoinSelectionStrategy::LargestFirst,
+        ] {
+            let res = select(&must_select, 200_000, 2, strategy).unwrap();
+            assert!(
+                res.selected.contains(&must_select[4]),
+                "{}: {:?}",
+                strategy,
+                selected_values(&res)
+            );
+
+            // And none of them can spend more than the candidates' value.
+            let missing = select(&cands, 4_000_000, 2, strategy).unwrap_err().missing;
+            assert!(missing > 4_000_000 - 3_871_000, "{}", strategy);
+        }
+    }
+
+    #[test]
+    fn privacy_coin_selection() {
+        // Whether the selection spends either all or none of these coins.
+        fn all_or_none(res: &CoinSelectionRes, coins: &[&CandidateCoin]) -> bool {
+            let n_selected = coins.iter().filter(|c| res.selected.contains(c)).count();
+            n_selected == 0 || n_selected == coins.len()
+        }
+
+        // Coins paying to the same address are always spent together.
+        let mut cands = candidates();
+        cands[5].deriv_index = cands[4].deriv_index;
+        for target in [100_000, 200_000, 310_000, 1_500_000, 3_000_000] {
+            let res = select(&cands, target, 2, CoinSelectionStrategy::Privacy).unwrap();
+            assert!(
+                all_or_none(&res, &[&cands[4], &cands[5]]),
+                "{}: {:?}",
+                target,
+                selected_values(&res)
+            );
+        }
+        // Whereas other strategies don't care.
+        let res = select(&cands, 200_000, 2, CoinSelectionStrategy::LowestFee).unwrap();
+        assert_eq!(selected_values(&res), vec![201_000]);
+
+        // When no single coin is enough, prefer spending coins which are already linked together
+        // by a label cluster.
+        let mut cands: Vec<_> = candidates()
+            .into_iter()
+            .filter(|c| c.amount.to_sat() < 1_000_000)
+            .collect();
+        cands[0].label_cluster = Some(1);
+        cands[1].label_cluster = Some(1);
+        let res = select(&cands, 500_000, 2, CoinSelectionStrategy::Privacy).unwrap();
+        assert_eq!(selected_values(&res), vec![300_000, 250_000]);
+        assert_eq!(res.linked_clusters, 1);
+        // Or by the transaction which created them.
+        cands[1].label_cluster = None;
+        cands[2].outpoint.txid = cands[3].outpoint.txid;
+        cands[2].outpoint.vout = 1;
+        let res = select(&cands, 300_000, 2, CoinSelectionStrategy::Privacy).unwrap();
+        assert_eq!(selected_values(&res), vec![201_000, 120_000]);
+        assert_eq!(res.linked_clusters, 1);
+        // A coin which is part of both links the two clusters together.
+        cands[2].label_cluster = Some(1);
+        let res = select(&cands, 600_000, 2, CoinSelectionStrategy::Privacy).unwrap();
+        assert_eq!(selected_values(&res), vec![300_000, 201_000, 120_000]);
+        assert_eq!(res.linked_clusters, 1);
+
+        // If there is no way around it, the number of linked clusters is reported.
+        let res = select(&cands, 800_000, 2, CoinSelectionStrategy::Privacy).unwrap();
+        assert_eq!(res.selected.len(), 4);
+        assert_eq!(res.linked_clusters, 2);
+        // It is not computed for other strategies.
+        let res = select(&cands, 800_000, 2, CoinSelectionStrategy::LowestFee).unwrap();
+        assert_eq!(res.linked_clusters, 0);
+    }
+
+    #[test]
+    fn feerate_parsing() {
+        for (s, sat_kvb) in [
+            ("1", 1_000),
+            ("0", 0),
+            ("1.5", 1_500),
+            ("0.1", 100),
+            ("2.25", 2_250),
+            ("12.001", 12_001),
+            ("1.500", 1_500),
+        ] {
+            let feerate = Feerate::from_str(s).unwrap();
+            assert_eq!(feerate.to_sat_per_kvb(), sat_kvb);
+            assert_eq!(
+                Feerate::from_str(&feerate.to_string()).unwrap(),
+                feerate,
+                "{}",
+                s
+            );
+        }
+        assert_eq!(Feerate::from_sat_per_kvb(1_500).to_string(), "1.5");
+        assert_eq!(Feerate::from_sat_per_kvb(12_001).to_string(), "12.001");
+        assert_eq!(Feerate::from_sat_per_vb(3).to_string(), "3");
+
+        for s in [
+            "", ".", "1.", ".5", "-1", "1.0001", "1,5", "1e3", " 1", "abc",
+        ] {
+            Feerate::from_str(s).unwrap_err();
+        }
+        Feerate::from_str(&u64::MAX.to_string()).unwrap_err();
+
+        // Serialized as a number of sats/vb, deserialized from a number or a string.
+        let feerate = Feerate::from_sat_per_kvb(1_500);
+        assert_eq!(serde_json::to_string(&feerate).unwrap(), "1.5");
+        assert_eq!(
+            serde_json::to_string(&Feerate::from_sat_per_vb(2)).unwrap(),
+            "2"
+        );
+        for json in ["1.5", "\"1.5\""] {
+            assert_eq!(serde_json::from_str::<Feerate>(json).unwrap(), feerate);
+        }
+        assert_eq!(
+            serde_json::from_str::<Feerate>("0.001").unwrap(),
+            Feerate::from_sat_per_kvb(1)
+        );
+        assert_eq!(
+            serde_json::from_str::<Feerate>("7").unwrap(),
+            Feerate::from_sat_per_vb(7)
+        );
+        for json in ["-1", "0.0001", "true"] {
+            serde_json::from_str::<Feerate>(json).unwrap_err();
+        }
+
+        // Fees are rounded up, feerates computed from a fee are rounded down.
+        assert_eq!(feerate.fee(141), 212);
+        assert_eq!(
+            Feerate::from_fee(212, 141),
+            Some(Feerate::from_sat_per_kvb(1_503))
+        );
+        assert_eq!(Feerate::from_fee(212, 0), None);
+    }
+
+    struct NoTxGetter;
+
+    impl TxGetter for NoTxGetter {
+        fn get_tx(&mut self, _: &Txid) -> Option<Transaction> {
+            None
+        }
+    }
+
+    #[test]
+    fn subtract_fee_refund() {
+        let secp = secp256k1::Secp256k1::verification_only();
+        let desc = descriptors::LianaDescriptor::from_str("tr([8344c025]xpub661MyMwAqRbcG2SYC6YSRsUGvcSxXEZm1kjiQRTEaAqart1PQk1N1hVTTEsGfaBx6xQ5gDYXXtbourodE6ZE5qZTnaMgmehNs8GGEEY9YK6/<0;1>/*,and_v(v:pk([158fd0ef]xpub661MyMwAqRbcF2KsCnvJ4mqWXXrwd3799wCyQrLk2iNDC6CfK8UcfnABdeTpXyoJnBhRTybmtBLDAuTuHye1eQMq43BSLtR2miA6t9KqmWU/<0;1>/*),older(4242)))#zy3kddhj").unwrap();
+        let change_index = bip32::ChildNumber::from_normal_idx(0).unwrap();
+        let change_addr = SpendOutputAddress {
+            addr: desc
+                .change_descriptor()
+                .derive(change_index, &secp)
+                .address(bitcoin::Network::Bitcoin),
+            info: Some(AddrInfo {
+                index: change_index,
+                is_change: true,
+            }),
+        };
+        let recipient = bitcoin::Address::from_str("bc1qvklensptw5lk7d470ds60pcpsr0psdpgyvwepv")
+            .unwrap()
+            .assume_checked();
+        let create = |candidates: &[u64], amount: u64, feerate: u64| {
+            let candidates: Vec<_> = candidates
+                .iter()
+                .enumerate()
+                .map(|(i, value)| CandidateCoin {
+                    outpoint: OutPoint::new(Txid::from_byte_array([i as u8; 32]), 0),
+                    amount: Amount::from_sat(*value),
+                    deriv_index: bip32::ChildNumber::from_normal_idx(i as u32).unwrap(),
+                    is_change: false,
+                    must_select: false,
+                    sequence: None,
+                    ancestor_info: None,
+                    block_height: Some(100),
+                    label_cluster: None,
+                })
+                .collect();
+            let destination = SpendOutput {
+                destination: SpendDestination::Address(SpendOutputAddress {
+                    addr: recipient.clone(),
+                    info: None,
+                }),
+                amount: Amount::from_sat(amount),
+                subtract_fee: true,
+            };
+            create_spend(
+                &desc,
+                &secp,
+                &mut NoTxGetter,
+                &[destination],
+                &candidates,
+                SpendTxFees::Regular(Feerate::from_sat_per_vb(feerate)),
+                CoinSelectionStrategy::LargestFirst,
+                change_addr.clone(),
+                LockTime::ZERO,
+                false,
+            )
+            .unwrap()
+        };
+        // The value of the inputs of the created transaction minus the value of its outputs.
+        let tx_fee = |psbt: &Psbt| {
+            let in_value: Amount = psbt
+                .inputs
+                .iter()
+                .map(|i| i.witness_utxo.as_ref().unwrap().value)
+                .sum();
+            let out_value: Amount = psbt.unsigned_tx.output.iter().map(|o| o.value).sum();
+            in_value - out_value
+        };
+
+        // Paying the full amount needs both coins, but once the fee is deducted from it the largest
+        // coin is enough and leaves some change. The fee deducted in excess is taken back from the
+        // change: the recipient pays exactly the fee.
+        let res = create(&[700_000, 77_000], 680_000, 500);
+        let tx = &res.psbt.unsigned_tx;
+        assert_eq!(tx.input.len(), 1);
+        assert!(res.has_change);
+        assert_eq!(tx.output.len(), 2);
+        let fee = tx_fee(&res.psbt);
+        assert_eq!(tx.output[0].value, Amount::from_sat(680_000) - fee);
+        assert_eq!(tx.output[1].value, Amount::from_sat(700_000 - 680_000));
+        assert!(fee.to_sat() >= 500 * tx.vsize() as u64);
+
+        // Same, but taking back the excess from the change would make it dust. It is dropped and
+        // the excess is given back to the recipient, the remainder going to fees.
+        let res = create(&[700_000, 16_000], 695_750, 100);
+        let tx = &res.psbt.unsigned_tx;
+        assert_eq!(tx.input.len(), 1);
+        assert!(!res.has_change);
+        assert_eq!(tx.output.len(), 1);
+        assert!(tx.output[0].value < Amount::from_sat(695_750));
+        assert!(tx_fee(&res.psbt).to_sat() >= 100 * tx.vsize() as u64);
+    }
+
+    #[test]
+    fn truc_zero_feerate_unconfirmed_candidate() {
+        // Returns a TRUC transaction for any txid.
+        struct TrucTxGetter;
+        impl TxGetter for TrucTxGetter {
+            fn get_tx(&mut self, _: &Txid) -> Option<Transaction> {
+                Some(Transaction {
+                    version: TRUC_VERSION,
+                    lock_time: LockTime::ZERO,
+                    input: Vec::new(),
+                    output: Vec::new(),
+                })
+            }
+        }
+
+        let secp = secp256k1::Secp256k1::verification_only();
+        let desc = descriptors::LianaDescriptor::from_str("tr([8344c025]xpub661MyMwAqRbcG2SYC6YSRsUGvcSxXEZm1kjiQRTEaAqart1PQk1N1hVTTEsGfaBx6xQ5gDYXXtbourodE6ZE5qZTnaMgmehNs8GGEEY9YK6/<0;1>/*,and_v(v:pk([158fd0ef]xpub661MyMwAqRbcF2KsCnvJ4mqWXXrwd3799wCyQrLk2iNDC6CfK8UcfnABdeTpXyoJnBhRTybmtBLDAuTuHye1eQMq43BSLtR2miA6t9KqmWU/<0;1>/*),older(4242)))#zy3kddhj").unwrap();
+        let change_index = bip32::ChildNumber::from_normal_idx(0).unwrap();
+        let change_addr = SpendOutputAddress {
+            addr: desc
+                .change_descriptor()
+                .derive(change_index, &secp)
+                .address(bitcoin::Network::Bitcoin),
+            info: Some(AddrInfo {
+                index: change_index,
+                is_change: true,
+            }),
+        };
+        // An unconfirmed coin, manually selected, whose ancestor pays a low fee.
+        let candidate = CandidateCoin {
+            outpoint: OutPoint::new(Txid::from_byte_array([1; 32]), 0),
+            amount: Amount::from_sat(100_000),
+            deriv_index: bip32::ChildNumber::from_normal_idx(0).unwrap(),
+            is_change: false,
+            must_select: true,
+            sequence: None,
+            ancestor_info: Some(AncestorInfo { vsize: 200, fee: 0 }),
+            block_height: None,
+            label_cluster: None,
+        };
+
+        // A zero-fee TRUC sweep of this coin doesn't need to pay for its ancestor.
+        let res = create_spend(
+            &desc,
+            &secp,
+            &mut TrucTxGetter,
+            &[],
+            &[candidate],
+            SpendTxFees::Regular(Feerate::default()),
+            CoinSelectionStrategy::LargestFirst,
+            change_addr,
+            LockTime::ZERO,
+            true,
+        )
+        .unwrap();
+        let tx = &res.psbt.unsigned_tx;
+        assert_eq!(tx.version, TRUC_VERSION);
+        assert_eq!(tx.input.len(), 1);
+        assert_eq!(tx.output.len(), 1);
+        assert_eq!(tx.output[0].value, Amount::from_sat(100_000));
+        assert!(!res
+            .warnings
+            .iter()
+            .any(|w| matches!(w, CreateSpendWarning::AdditionalFeeForAncestors(_))));
+    }
+
+    #[test]
+    fn truc_topology() {
+        let (v2, v3) = (Version::TWO, TRUC_VERSION);
+        let parent_a = Txid::from_byte_array([1; 32]);
+        let parent_b = Txid::from_byte_array([2; 32]);
+        let parents = |parents: &[(Txid, Option<Version>)]| {
+            parents.iter().copied().collect::<BTreeMap<_, _>>()
+        };
+
+        // Non-TRUC transactions are only restricted in that they can't spend from an unconfirmed
+        // TRUC transaction.
+        check_truc_topology(v2, 20_000, &parents(&[])).unwrap();
+        check_truc_topology(v2, 500, &parents(&[(parent_a, Some(v2)), (parent_b, None)])).unwrap();
+        assert_eq!(
+            check_truc_topology(
+                v2,
+                500,
+                &parents(&[(parent_a, Some(v2)), (parent_b, Some(v3))])
+            ),
+            Err(TrucViolation::TrucParent(parent_b))
+        );
+
+        // TRUC transactions are limited in size, more so if they spend an unconfirmed coin.
+        check_truc_topology(v3, TRUC_MAX_VSIZE, &parents(&[])).unwrap();
+        assert_eq!(
+            check_truc_topology(v3, TRUC_MAX_VSIZE + 1, &parents(&[])),
+            Err(TrucViolation::TooLarge(TRUC_MAX_VSIZE + 1))
+        );
+        check_truc_topology(v3, TRUC_CHILD_MAX_VSIZE, &parents(&[(parent_a, Some(v3))])).unwrap();
+        assert_eq!(
+            check_truc_topology(
+                v3,
+                TRUC_CHILD_MAX_VSIZE + 1,
+                &parents(&[(parent_a, Some(v3))])
+            ),
+            Err(TrucViolation::ChildTooLarge(TRUC_CHILD_MAX_VSIZE + 1))
+        );
+
+        // They may only spend from a single unconfirmed transaction, itself TRUC.
+        assert_eq!(
+            check_truc_topology(
+                v3,
+                500,
+                &parents(&[(parent_a, Some(v3)), (parent_b, Some(v3))])
+            ),
+            Err(TrucViolation::TooManyUnconfirmedParents(2))
+        );
+        for parent_version in [Some(v2), None] {
+            assert_eq!(
+                check_truc_topology(v3, 500, &parents(&[(parent_a, parent_version)])),
+                Err(TrucViolation::NonTrucParent(parent_a))
+            );
+        }
+    }
 }
@@ -1,9 +1,10 @@
 #![cfg(not(target_os = "windows"))]
 
+use liana::batch;
 use lianad::config::{config_folder_path, Config};
 
 use std::{
-    env,
+    env, fs,
     io::{Read, Write},
     path::PathBuf,
     process,
@@ -13,10 +14,18 @@ use serde_json::Value as Json;
 
 use std::os::unix::net::UnixStream;
 
+// A command to create a spend paying a batch of payments read from a CSV or JSON file. The path to
+// the file takes the place of the destinations parameter of 'createspend'.
+const BATCH_SPEND_COMMAND: &str = "createbatchspend";
+
 // Exits with error
-fn show_usage() {
+fn show_usage() -> ! {
     eprintln!("Usage:");
     eprintln!(" liana-cli [--conf conf_path] [--raw] <command> [<param 1> <param 2> ...]");
+    eprintln!(
+        " liana-cli [--conf conf_path] [--raw] {} <payments file> [<createspend param 2> ...]",
+        BATCH_SPEND_COMMAND
+    );
     process::exit(1);
 }
 
@@ -68,9 +77,38 @@ fn from_str_hack(token: String) -> Json {
     }
 }
 
-fn rpc_request(method: String, params: Vec<String>) -> Json {
+// Read the payments from the file at the given path and turn them into the list of destinations of
+// a 'createspend' request.
+fn batch_destinations(config: &Config, path: &str) -> Json {
+    let content = fs::read_to_string(path).unwrap_or_else(|e| {
+        eprintln!("Error reading payments file '{}': {}", path, e);
+        process::exit(1);
+    });
+    let payments =
+        batch::parse_batch(&content, config.bitcoin_config.network).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            process::exit(1);
+        });
+    Json::Array(
+        payments
+            .into_iter()
+            .map(|payment| {
+                let mut entry = serde_json::json!({
+                    "destination": payment.address.to_string(),
+                    "amount": payment.amount.to_sat(),
+                });
+                if let Some(label) = payment.label {
+                    entry["label"] = Json::String(label);
+                }
+                entry
+            })
+            .collect(),
+    )
+}
+
+fn rpc_request(method: String, params: Vec<Json>) -> Json {
     let method = Json::String(method);
-    let params = Json::Array(params.into_iter().map(from_str_hack).collect::<Vec<Json>>());
+    let params = Json::Array(params);
     let mut object = serde_json::Map::<String, Json>::new();
     object.insert("jsonrpc".to_string(), Json::String("2.0".to_string()));
     object.insert(
@@ -83,13 +121,17 @@ fn rpc_request(method: String, params: Vec<String>) -> Json {
     Json::Object(object)
 }
 
-fn socket_file(conf_file: Option<PathBuf>) -> PathBuf {
-    let config = Config::from_file(conf_file).unwrap_or_else(|e| {
+fn config(conf_file: Option<PathBuf>) -> Config {
+    Config::from_file(conf_file).unwrap_or_else(|e| {
         eprintln!("Error getting config: {}", e);
         process::exit(1);
-    });
+    })
+}
+
+fn socket_file(config: &Config) -> PathBuf {
     let data_dir = config
         .data_dir
+        .clone()
         .unwrap_or_else(|| config_folder_path().unwrap());
     let data_dir = data_dir.to_str().expect("Datadir is valid unicode");
 
@@ -120,8 +162,21 @@ fn trimmed(mut vec: Vec<u8>, bytes_read: usize) -> Vec<u8> {
 fn main() {
     let args = env::args().collect();
     let (conf_file, raw, method, params) = parse_args(args);
-    let request = rpc_request(method, params);
-    let socket_file = socket_file(conf_file);
+    let config = config(conf_file);
+    let request = if method == BATCH_SPEND_COMMAND {
+        let mut params = params.into_iter();
+        let path = params.next().unwrap_or_else(|| {
+            eprintln!("Missing payments file.");
+            show_usage()
+        });
+        let params = std::iter::once(batch_destinations(&config, &path))
+            .chain(params.map(from_str_hack))
+            .collect();
+        rpc_request("createspend".to_string(), params)
+    } else {
+        rpc_request(method, params.into_iter().map(from_str_hack).collect())
+    };
+    let socket_file = socket_file(&config);
     let mut raw_response = vec![0; 256];
 
     let mut socket = UnixStream::connect(&socket_file).unwrap_or_else(|e| {
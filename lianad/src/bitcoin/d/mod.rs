@@ -7,7 +7,7 @@ use crate::{
     bitcoin::{Block, BlockChainTip},
     config,
 };
-use liana::descriptors::LianaDescriptor;
+use liana::{descriptors::LianaDescriptor, spend::Feerate};
 use utils::{block_before_date, roundup_progress};
 
 use std::{
@@ -62,6 +62,8 @@ pub enum BitcoindError {
     NetworkMismatch(String /*config*/, String /*bitcoind*/),
     StartRescan,
     RescanPastPruneHeight,
+    /// Bitcoind did not accept a package of transactions, with the reason.
+    PackageRejected(String),
 }
 
 impl BitcoindError {
@@ -155,6 +157,9 @@ impl std::fmt::Display for BitcoindError {
                     "Trying to rescan the block chain past the prune block height."
                 )
             }
+            BitcoindError::PackageRejected(reason) => {
+                write!(f, "Package rejected: {}", reason)
+            }
         }
     }
 }
@@ -1054,6 +1059,39 @@ impl BitcoinD {
         Ok(())
     }
 
+    /// Submit a package of transactions, a child and its unconfirmed parents in topological order,
+    /// to bitcoind's mempool. The transactions are broadcast if the package is accepted.
+    pub fn submit_package(&self, txs: &[bitcoin::Transaction]) -> Result<(), BitcoindError> {
+        let txs_hex: Vec<Json> = txs
+            .iter()
+            .map(|tx| bitcoin::consensus::encode::serialize_hex(tx).into())
+            .collect();
+        let res =
+            self.make_fallible_node_request("submitpackage", params!(Json::Array(txs_hex)))?;
+        // Before version 28.0, bitcoind would return an RPC error if the package was rejected.
+        match res.get("package_msg").and_then(Json::as_str) {
+            None | Some("success") => Ok(()),
+            Some(msg) => {
+                // Report the reason the individual transactions were rejected, if any.
+                let tx_errors: Vec<&str> = res
+                    .get("tx-results")
+                    .and_then(Json::as_object)
+                    .map(|results| {
+                        results
+                            .values()
+                            .filter_map(|res| res.get("error").and_then(Json::as_str))
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                Err(BitcoindError::PackageRejected(if tx_errors.is_empty() {
+                    msg.to_string()
+                } else {
+                    format!("{} ({})", msg, tx_errors.join(", "))
+                }))
+            }
+        }
+    }
+
     // For the given descriptor strings check if they are imported at this timestamp in the
     // watchonly wallet.
     fn check_descs_timestamp(
@@ -1207,6 +1245,20 @@ impl BitcoinD {
         }
     }
 
+    /// Get bitcoind's estimate of the feerate needed for a transaction to confirm within this
+    /// number of blocks. Returns `None` if bitcoind does not have enough data to estimate it.
+    pub fn estimate_smart_fee(&self, target: u16) -> Option<Feerate> {
+        let res = self
+            .make_fallible_node_request("estimatesmartfee", params!(Json::Number(target.into())))
+            .map_err(|e| log::error!("Error querying fee estimate for {} blocks: {}", target, e))
+            .ok()?;
+        // The feerate is in BTC/kvB. It is absent if there was not enough data.
+        let btc_kvb = res.get("feerate").and_then(Json::as_f64)?;
+        bitcoin::Amount::from_btc(btc_kvb)
+            .ok()
+            .map(|sat_kvb| Feerate::from_sat_per_kvb(sat_kvb.to_sat()))
+    }
+
     /// Get the list of txids spending those outpoints in mempool.
     pub fn mempool_txs_spending_prevouts(
         &self,
@@ -18,6 +18,7 @@ use crate::{
     bitcoin::{electrum::utils::tip_from_block_id, BlockChainTip, MempoolEntry, MempoolEntryFees},
     config,
 };
+use liana::spend::Feerate;
 
 // Default batch size to use when making requests to the Electrum server.
 const DEFAULT_BATCH_SIZE: usize = 200;
@@ -113,6 +114,42 @@ impl Client {
             .map(|bh| bh.time)
     }
 
+    pub fn median_time_past(&self, height: i32) -> Result<u32, Error> {
+        let height = height_usize_from_i32(height);
+        let start_height = height.saturating_sub(10);
+        let mut times: Vec<u32> = self
+            .0
+            .inner
+            .block_headers(start_height, height + 1 - start_height)
+            .map_err(Error::Server)?
+            .headers
+            .iter()
+            .map(|bh| bh.time)
+            .collect();
+        times.sort_unstable();
+        Ok(times[times.len() / 2])
+    }
+
+    /// Get the server's estimate of the feerate needed for a transaction to confirm within each of
+    /// these numbers of blocks. `None` for the targets the server could not estimate.
+    pub fn fee_estimates(&self, targets: &[u16]) -> Result<Vec<Option<Feerate>>, Error> {
+        let targets: Vec<usize> = targets.iter().map(|t| (*t).into()).collect();
+        Ok(self
+            .0
+            .inner
+            .batch_estimate_fee(&targets)
+            .map_err(Error::Server)?
+            .into_iter()
+            // The feerate is in BTC/kvB. It is -1 if the server could not estimate it.
+            .map(|btc_kvb| {
+                bitcoin::Amount::from_btc(btc_kvb)
+                    .ok()
+                    .filter(|sat_kvb| *sat_kvb > bitcoin::Amount::ZERO)
+                    .map(|sat_kvb| Feerate::from_sat_per_kvb(sat_kvb.to_sat()))
+            })
+            .collect())
+    }
+
     /// Returns a reference to the wrapped `BdkElectrumClient`.
     pub fn bdk_electrum_client(&self) -> &BdkElectrumClient<electrum_client::Client> {
         &self.0
@@ -8,7 +8,7 @@ pub mod poller;
 
 use crate::bitcoin::d::{BitcoindError, CachedTxGetter, LSBlockEntry};
 pub use d::{MempoolEntry, MempoolEntryFees, SyncProgress};
-use liana::descriptors;
+use liana::{descriptors, spend::Feerate};
 
 use std::{fmt, sync};
 
@@ -57,6 +57,11 @@ pub trait BitcoinInterface: Send {
     /// Get the timestamp set in the best block's header.
     fn tip_time(&self) -> Option<u32>;
 
+    /// Get the median time past of the block at this height in the best chain, that is the median
+    /// of the timestamps of this block and the 10 preceding ones. This is the time against which
+    /// time-based timelocks are checked.
+    fn median_time_past(&self, height: i32) -> Option<u32>;
+
     /// Check whether this former tip is part of the current best chain.
     fn is_in_chain(&self, tip: &BlockChainTip) -> bool;
 
@@ -105,6 +110,11 @@ pub trait BitcoinInterface: Send {
     /// Broadcast this transaction to the Bitcoin P2P network
     fn broadcast_tx(&self, tx: &bitcoin::Transaction) -> Result<(), String>;
 
+    /// Submit this package of transactions to the mempool and broadcast them. The package must
+    /// be a child and its unconfirmed parents, in topological order (parents first). This allows
+    /// for the child to pay for parents whose feerate is below the mempool's minimum.
+    fn submit_package(&self, txs: &[bitcoin::Transaction]) -> Result<(), String>;
+
     /// Trigger a rescan of the block chain for transactions related to this descriptor since
     /// the given date.
     fn start_rescan(
@@ -133,6 +143,10 @@ pub trait BitcoinInterface: Send {
     ///
     /// Returns `None` if the transaction is not in the mempool.
     fn mempool_entry(&self, txid: &bitcoin::Txid) -> Option<MempoolEntry>;
+
+    /// Get an estimate of the feerate needed for a transaction to confirm within each of these
+    /// numbers of blocks. `None` for the targets which could not be estimated.
+    fn fee_estimates(&self, targets: &[u16]) -> Vec<Option<Feerate>>;
 }
 
 impl BitcoinInterface for d::BitcoinD {
@@ -362,6 +376,16 @@ impl BitcoinInterface for d::BitcoinD {
         }
     }
 
+    fn submit_package(&self, txs: &[bitcoin::Transaction]) -> Result<(), String> {
+        match self.submit_package(txs) {
+            Ok(()) => Ok(()),
+            Err(BitcoindError::Server(e)) => Err(e.to_string()),
+            Err(BitcoindError::PackageRejected(e)) => Err(e),
+            // We assume the Bitcoin backend doesn't fail, so it must be a JSONRPC error.
+            Err(e) => panic!("Unexpected Bitcoin error when submitting package: '{}'.", e),
+        }
+    }
+
     fn start_rescan(
         &mut self,
         desc: &descriptors::LianaDescriptor,
@@ -385,6 +409,11 @@ impl BitcoinInterface for d::BitcoinD {
         Some(self.get_block_stats(tip.hash)?.time)
     }
 
+    fn median_time_past(&self, height: i32) -> Option<u32> {
+        let hash = self.get_block_hash(height)?;
+        Some(self.get_block_stats(hash)?.median_time_past)
+    }
+
     fn wallet_transaction(
         &self,
         txid: &bitcoin::Txid,
@@ -402,6 +431,13 @@ impl BitcoinInterface for d::BitcoinD {
     fn mempool_entry(&self, txid: &bitcoin::Txid) -> Option<MempoolEntry> {
         self.mempool_entry(txid)
     }
+
+    fn fee_estimates(&self, targets: &[u16]) -> Vec<Option<Feerate>> {
+        targets
+            .iter()
+            .map(|target| self.estimate_smart_fee(*target))
+            .collect()
+    }
 }
 
 impl BitcoinInterface for electrum::Electrum {
@@ -543,6 +579,10 @@ impl BitcoinInterface for electrum::Electrum {
         }
     }
 
+    fn submit_package(&self, _txs: &[bitcoin::Transaction]) -> Result<(), String> {
+        Err("Package relay is not supported by the Electrum backend.".to_string())
+    }
+
     fn wallet_transaction(
         &self,
         txid: &bitcoin::Txid,
@@ -589,6 +629,17 @@ impl BitcoinInterface for electrum::Electrum {
     fn tip_time(&self) -> Option<u32> {
         self.client().tip_time().ok()
     }
+
+    fn median_time_past(&self, height: i32) -> Option<u32> {
+        self.client().median_time_past(height).ok()
+    }
+
+    fn fee_estimates(&self, targets: &[u16]) -> Vec<Option<Feerate>> {
+        self.client().fee_estimates(targets).unwrap_or_else(|e| {
+            log::error!("Error querying fee estimates: {}", e);
+            vec![None; targets.len()]
+        })
+    }
 }
 
 // FIXME: do we need to repeat the entire trait implementation? Isn't there a nicer way?
@@ -660,6 +711,10 @@ impl BitcoinInterface for sync::Arc<sync::Mutex<dyn BitcoinInterface + 'static>>
         self.lock().unwrap().broadcast_tx(tx)
     }
 
+    fn submit_package(&self, txs: &[bitcoin::Transaction]) -> Result<(), String> {
+        self.lock().unwrap().submit_package(txs)
+    }
+
     fn start_rescan(
         &mut self,
         desc: &descriptors::LianaDescriptor,
@@ -680,6 +735,10 @@ impl BitcoinInterface for sync::Arc<sync::Mutex<dyn BitcoinInterface + 'static>>
         self.lock().unwrap().tip_time()
     }
 
+    fn median_time_past(&self, height: i32) -> Option<u32> {
+        self.lock().unwrap().median_time_past(height)
+    }
+
     fn wallet_transaction(
         &self,
         txid: &bitcoin::Txid,
@@ -694,6 +753,10 @@ impl BitcoinInterface for sync::Arc<sync::Mutex<dyn BitcoinInterface + 'static>>
     fn mempool_entry(&self, txid: &bitcoin::Txid) -> Option<MempoolEntry> {
         self.lock().unwrap().mempool_entry(txid)
     }
+
+    fn fee_estimates(&self, targets: &[u16]) -> Vec<Option<Feerate>> {
+        self.lock().unwrap().fee_estimates(targets)
+    }
 }
 
 // FIXME: We could avoid this type (and all the conversions entailing allocations) if bitcoind
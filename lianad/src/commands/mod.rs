@@ -7,7 +7,7 @@ mod utils;
 use crate::{
     bitcoin::BitcoinInterface,
     database::{Coin, DatabaseConnection, DatabaseInterface},
-    miniscript::bitcoin::absolute::LockTime,
+    miniscript::bitcoin::{absolute::LockTime, relative},
     poller::PollerMessage,
     DaemonControl, VERSION,
 };
@@ -15,36 +15,46 @@ use crate::{
 pub use crate::database::{CoinStatus, LabelItem};
 
 use liana::{
-    descriptors,
+    batch, bsms, descriptors, psbt_analysis,
     spend::{
-        self, create_spend, AddrInfo, AncestorInfo, CandidateCoin, CreateSpendRes,
-        SpendCreationError, SpendOutputAddress, SpendTxFees, TxGetter,
+        self, create_spend, AddrInfo, AncestorInfo, CandidateCoin, CoinSelectionStrategy,
+        CreateSpendRes, Feerate, SpendCreationError, SpendDestination, SpendOutput,
+        SpendOutputAddress, SpendTxFees, TxGetter, MAX_FEERATE, MIN_FEERATE, TRUC_VERSION,
     },
 };
 
 use utils::{
-    deser_addr_assume_checked, deser_amount_from_sats, deser_fromstr, deser_hex, ser_amount,
-    ser_hex, ser_to_string,
+    deser_addr_assume_checked, deser_amount_from_sats, deser_fromstr, deser_hex, deser_psbt,
+    ser_amount, ser_hex, ser_to_string,
 };
 
 use std::{
     collections::{hash_map, HashMap, HashSet},
     convert::TryInto,
     fmt,
+    str::FromStr,
     sync::{self, mpsc},
     time::SystemTime,
 };
 
 use miniscript::{
-    bitcoin::{self, address, bip32, psbt::Psbt},
+    bitcoin::{
+        self, address, bip32,
+        hex::{DisplayHex, FromHex},
+        psbt::Psbt,
+    },
     psbt::PsbtExt,
 };
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize, Serializer};
+
+/// The confirmation targets, in number of blocks, for which we give fee estimates. That's roughly
+/// the next block, one hour and one day.
+pub const FEE_ESTIMATE_TARGETS: [u16; 3] = [1, 6, 144];
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum CommandError {
     NoOutpointForSelfSend,
-    InvalidFeerate(/* sats/vb */ u64),
+    InvalidFeerate(Feerate),
     UnknownOutpoint(bitcoin::OutPoint),
     AlreadySpent(bitcoin::OutPoint),
     ImmatureCoinbase(bitcoin::OutPoint),
@@ -53,7 +63,7 @@ pub enum CommandError {
     InsufficientFunds(
         /* in value */ bitcoin::Amount,
         /* out value */ Option<bitcoin::Amount>,
-        /* target feerate */ u64,
+        /* target feerate */ Feerate,
     ),
     UnknownSpend(bitcoin::Txid),
     // FIXME: when upgrading Miniscript put the actual error there
@@ -64,10 +74,21 @@ pub enum CommandError {
     /// An error that might occur in the racy rescan triggering logic.
     RescanTrigger(String),
     RecoveryNotAvailable,
+    /// There is no recovery path behind this absolute timelock in the descriptor.
+    UnknownAbsoluteTimelock(u32),
+    /// This value does not encode a (non-disabled) relative timelock.
+    InvalidRelativeTimelock(u32),
+    /// Both a relative and an absolute timelock were provided for a recovery path.
+    ConflictingTimelocks,
     /// Overflowing or unhardened derivation index.
     InvalidDerivationIndex,
     RbfError(RbfErrorInfo),
+    CpfpError(CpfpErrorInfo),
     EmptyFilterList,
+    /// The input at this index of an imported PSBT does not spend one of our coins.
+    ForeignInput(usize, bitcoin::OutPoint),
+    /// The input at this index of an imported PSBT commits to a different coin than ours.
+    InputMismatch(usize, bitcoin::OutPoint),
 }
 
 impl fmt::Display for CommandError {
@@ -116,11 +137,34 @@ impl fmt::Display for CommandError {
                 f,
                 "No coin currently spendable through this timelocked recovery path."
             ),
+            Self::UnknownAbsoluteTimelock(tl) => write!(
+                f,
+                "No recovery path with absolute timelock '{}' in the descriptor.",
+                tl
+            ),
+            Self::InvalidRelativeTimelock(tl) => {
+                write!(f, "Invalid relative timelock '{}'.", tl)
+            }
+            Self::ConflictingTimelocks => write!(
+                f,
+                "A recovery path must be specified by either its relative or absolute timelock, not both."
+            ),
             Self::InvalidDerivationIndex => {
                 write!(f, "Unhardened or overflowing BIP32 derivation index.")
             }
             Self::RbfError(e) => write!(f, "RBF error: '{}'.", e),
+            Self::CpfpError(e) => write!(f, "CPFP error: '{}'.", e),
             Self::EmptyFilterList => write!(f, "Filter list is empty, should supply None instead."),
+            Self::ForeignInput(i, op) => write!(
+                f,
+                "Input #{} spends '{}', which is not a coin of this wallet.",
+                i, op
+            ),
+            Self::InputMismatch(i, op) => write!(
+                f,
+                "Input #{} commits to a different value or script than the wallet's coin at '{}'.",
+                i, op
+            ),
         }
     }
 }
@@ -137,7 +181,7 @@ impl From<SpendCreationError> for CommandError {
 pub enum RbfErrorInfo {
     MissingFeerate,
     SuperfluousFeerate,
-    TooLowFeerate(u64, u64),
+    TooLowFeerate(Feerate, Feerate),
     NotSignaling,
 }
 
@@ -158,6 +202,29 @@ impl fmt::Display for RbfErrorInfo {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CpfpErrorInfo {
+    AlreadyConfirmed(bitcoin::OutPoint),
+    NotInMempool(bitcoin::Txid),
+    TooLowFeerate(Feerate, Feerate),
+}
+
+impl fmt::Display for CpfpErrorInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Self::AlreadyConfirmed(op) => write!(f, "Coin at '{}' is already confirmed.", op),
+            Self::NotInMempool(txid) => {
+                write!(f, "Transaction '{}' is not in the mempool.", txid)
+            }
+            Self::TooLowFeerate(r, m) => write!(
+                f,
+                "Feerate {} must be higher than the current feerate {} of the unconfirmed transaction and its ancestors.",
+                r, m
+            ),
+        }
+    }
+}
+
 /// A wallet transaction getter which fetches the transaction from our database backend with a cache
 /// to avoid needless redundant calls. Note the cache holds an Option<> so we also avoid redundant
 /// calls when the txid isn't known by our database backend.
@@ -173,6 +240,13 @@ impl<'a> DbTxGetter<'a> {
             cache: HashMap::new(),
         }
     }
+
+    /// Whether this wallet transaction is a TRUC (version 3) transaction.
+    pub fn is_truc(&mut self, txid: &bitcoin::Txid) -> bool {
+        self.get_tx(txid)
+            .map(|tx| tx.version == TRUC_VERSION)
+            .unwrap_or(false)
+    }
 }
 
 impl<'a> TxGetter for DbTxGetter<'a> {
@@ -204,6 +278,8 @@ fn coin_to_candidate(
         must_select,
         sequence,
         ancestor_info,
+        block_height: coin.block_info.map(|b| b.height),
+        label_cluster: None,
     }
 }
 
@@ -270,6 +346,79 @@ impl DaemonControl {
         }
     }
 
+    // Get the label cluster of each of these coins. Two coins are in the same cluster if their
+    // labels (of the coin itself, of its address or of its transaction) have a value in common,
+    // directly or through other coins. Coins without any label aren't part of any cluster.
+    fn label_clusters(
+        &self,
+        db_conn: &mut Box<dyn DatabaseConnection>,
+        coins: &[CandidateCoin],
+    ) -> HashMap<bitcoin::OutPoint, u32> {
+        let coins_items: Vec<(bitcoin::OutPoint, [LabelItem; 3])> = coins
+            .iter()
+            .map(|coin| {
+                let desc = if coin.is_change {
+                    self.config.main_descriptor.change_descriptor()
+                } else {
+                    self.config.main_descriptor.receive_descriptor()
+                };
+                let address = desc
+                    .derive(coin.deriv_index, &self.secp)
+                    .address(self.config.bitcoin_config.network);
+                (
+                    coin.outpoint,
+                    [
+                        LabelItem::from(coin.outpoint),
+                        LabelItem::from(address),
+                        LabelItem::from(coin.outpoint.txid),
+                    ],
+                )
+            })
+            .collect();
+        let labels = db_conn.labels(
+            &coins_items
+                .iter()
+                .flat_map(|(_, items)| items.iter().cloned())
+                .collect(),
+        );
+        let coins_values: Vec<(bitcoin::OutPoint, Vec<&String>)> = coins_items
+            .iter()
+            .map(|(op, items)| {
+                let values = items
+                    .iter()
+                    .filter_map(|item| labels.get(&item.to_string()))
+                    .collect();
+                (*op, values)
+            })
+            .collect();
+
+        // Assign a cluster to each label value, merging the clusters of the values of each coin.
+        let mut value_clusters = HashMap::<&String, u32>::new();
+        let mut next_cluster = 0;
+        for (_, values) in &coins_values {
+            let clusters: HashSet<u32> = values
+                .iter()
+                .filter_map(|v| value_clusters.get(v).copied())
+                .collect();
+            let cluster = clusters.iter().min().copied().unwrap_or_else(|| {
+                next_cluster += 1;
+                next_cluster
+            });
+            for c in value_clusters.values_mut() {
+                if clusters.contains(c) {
+                    *c = cluster;
+                }
+            }
+            for value in values {
+                value_clusters.insert(value, cluster);
+            }
+        }
+        coins_values
+            .iter()
+            .filter_map(|(op, values)| values.first().map(|v| (*op, value_clusters[v])))
+            .collect()
+    }
+
     // If we detect the given address as ours, and it has a higher derivation index than our next
     // derivation index, update our next derivation index to the one after the address'.
     fn maybe_increase_next_deriv_index(
@@ -331,6 +480,53 @@ impl DaemonControl {
         }
     }
 
+    /// Get an estimate of the feerate needed for a transaction to confirm within each of the
+    /// [`FEE_ESTIMATE_TARGETS`]. If the Bitcoin backend has no estimate for a target (for instance
+    /// on a test network without enough transaction history), the minimum feerate is given instead.
+    pub fn estimate_fees(&self) -> EstimateFeesResult {
+        let estimates = FEE_ESTIMATE_TARGETS
+            .iter()
+            .zip(self.bitcoin.fee_estimates(&FEE_ESTIMATE_TARGETS))
+            .map(|(target, feerate)| FeeEstimate {
+                target: *target,
+                feerate: feerate
+                    .unwrap_or(MIN_FEERATE)
+                    .clamp(MIN_FEERATE, MAX_FEERATE),
+                estimated: feerate.is_some(),
+            })
+            .collect();
+        EstimateFeesResult { estimates }
+    }
+
+    /// Get a structured summary of the spending policy of the wallet's descriptor. When the block
+    /// heights of absolute timelocks are reached is estimated from the current chain tip.
+    pub fn explain_policy(&self) -> descriptors::PolicyExplanation {
+        let mut explanation = self.config.main_descriptor.explain();
+        let tip_height = self
+            .db
+            .connection()
+            .chain_tip()
+            .and_then(|tip| tip.height.try_into().ok());
+        if let (Some(tip_height), Some(tip_time)) = (tip_height, self.bitcoin.tip_time()) {
+            explanation.estimate_dates(tip_height, tip_time.into());
+        }
+        explanation
+    }
+
+    /// Get the BIP-129 descriptor record for the wallet's descriptor, encrypted with the given
+    /// token. This lets the Signers of a multi-party wallet verify its descriptor and first address.
+    pub fn get_bsms_record(&self, token: &bsms::Token) -> GetBsmsRecordResult {
+        let record = bsms::DescriptorRecord::new(
+            self.config.main_descriptor.clone(),
+            self.config.bitcoin_config.network,
+            &self.secp,
+        );
+        GetBsmsRecordResult {
+            record: record.encrypt(token),
+            first_address: record.first_address().clone(),
+        }
+    }
+
     /// Get a new deposit address. This will always generate a new deposit address, regardless of
     /// whether it was actually used.
     pub fn get_new_address(&self) -> GetAddressResult {
@@ -455,29 +651,49 @@ impl DaemonControl {
 
     pub fn create_spend(
         &self,
-        destinations: &HashMap<bitcoin::Address<bitcoin::address::NetworkUnchecked>, u64>,
+        destinations: &[Recipient],
         coins_outpoints: &[bitcoin::OutPoint],
-        feerate_vb: u64,
+        feerate: Feerate,
         change_address: Option<bitcoin::Address<bitcoin::address::NetworkUnchecked>>,
+        options: CreateSpendOptions,
     ) -> Result<CreateSpendResult, CommandError> {
+        let CreateSpendOptions {
+            coin_selection,
+            truc,
+        } = options;
         let is_self_send = destinations.is_empty();
         // For self-send, the coins must be specified.
         if is_self_send && coins_outpoints.is_empty() {
             return Err(CommandError::NoOutpointForSelfSend);
         }
-        if feerate_vb < 1 {
-            return Err(CommandError::InvalidFeerate(feerate_vb));
+        if feerate < spend::min_feerate(truc) {
+            return Err(CommandError::InvalidFeerate(feerate));
         }
         let mut db_conn = self.db.connection();
         let mut tx_getter = DbTxGetter::new(&self.db);
 
-        // Prepare the destination addresses.
+        // Prepare the destinations.
         let mut destinations_checked = Vec::with_capacity(destinations.len());
-        for (address, value_sat) in destinations {
-            let address = self.validate_address(address.clone())?;
-            let amount = bitcoin::Amount::from_sat(*value_sat);
-            let address = self.spend_addr(&mut db_conn, address);
-            destinations_checked.push((address, amount));
+        for Recipient {
+            destination,
+            amount,
+            subtract_fee,
+            ..
+        } in destinations
+        {
+            let destination = match destination {
+                Destination::Address(address) => {
+                    let address = self.validate_address(address.clone())?;
+                    self.spend_addr(&mut db_conn, address).into()
+                }
+                Destination::Data(data) => SpendDestination::data(data)?,
+                Destination::Script(script) => SpendDestination::Script(script.clone()),
+            };
+            destinations_checked.push(SpendOutput {
+                destination,
+                amount: bitcoin::Amount::from_sat(*amount),
+                subtract_fee: *subtract_fee,
+            });
         }
 
         // The change address to be used if a change output needs to be created. It may be
@@ -494,17 +710,20 @@ impl DaemonControl {
         // If no coins have been specified, then coins will be selected automatically for
         // the spend from a set of optional candidates.
         // Otherwise, only the specified coins will be used, all as mandatory candidates.
-        let candidate_coins: Vec<CandidateCoin> = if coins_outpoints.is_empty() {
+        let mut candidate_coins: Vec<CandidateCoin> = if coins_outpoints.is_empty() {
             // From our unconfirmed coins, we only include those that are from self
             // since unconfirmed external deposits are more at risk of being dropped
             // unexpectedly from the mempool as they are beyond the user's control.
+            // A TRUC transaction may spend from at most a single unconfirmed TRUC transaction,
+            // so don't consider unconfirmed coins at all when creating one. Likewise, a non-TRUC
+            // transaction may not spend from an unconfirmed TRUC transaction.
             db_conn
                 .coins(&[CoinStatus::Unconfirmed, CoinStatus::Confirmed], &[])
                 .into_iter()
                 .filter_map(|(op, c)| {
                     if c.block_info.is_some() {
                         Some((c, None)) // confirmed coins have no ancestor info
-                    } else if c.is_from_self {
+                    } else if c.is_from_self && !truc && !tx_getter.is_truc(&op.txid) {
                         // In case the mempool_entry is None, the coin will be included without
                         // any ancestor info.
                         Some((
@@ -577,6 +796,14 @@ impl DaemonControl {
                 .collect()
         };
 
+        // The privacy-preserving coin selection avoids linking coins with unrelated labels.
+        if coin_selection == CoinSelectionStrategy::Privacy {
+            let clusters = self.label_clusters(&mut db_conn, &candidate_coins);
+            for cand in candidate_coins.iter_mut() {
+                cand.label_cluster = clusters.get(&cand.outpoint).copied();
+            }
+        }
+
         // Create the PSBT. If there was no error in doing so make sure to update our next
         // derivation index in case any address in the transaction outputs was ours and from the
         // future.
@@ -592,9 +819,11 @@ impl DaemonControl {
             &mut tx_getter,
             &destinations_checked,
             &candidate_coins,
-            SpendTxFees::Regular(feerate_vb),
+            SpendTxFees::Regular(feerate),
+            coin_selection,
             change_address,
             locktime,
+            truc,
         ) {
             Ok(res) => res,
             Err(SpendCreationError::CoinSelection(e)) => {
@@ -604,20 +833,41 @@ impl DaemonControl {
                 return Err(e.into());
             }
         };
-        for (addr, _) in destinations_checked {
-            self.maybe_increase_next_deriv_index(&mut db_conn, &addr.info);
+        for SpendOutput { destination, .. } in destinations_checked {
+            if let SpendDestination::Address(addr) = destination {
+                self.maybe_increase_next_deriv_index(&mut db_conn, &addr.info);
+            }
         }
         if has_change {
             self.maybe_increase_next_deriv_index(&mut db_conn, &change_info);
         }
+        // The outputs of the transaction are in the same order as the destinations. Return their
+        // labels, for them to be stored along with the PSBT once it gets saved.
+        let txid = psbt.unsigned_tx.compute_txid();
+        let labels = destinations
+            .iter()
+            .zip(0..)
+            .filter_map(|(recipient, vout)| {
+                let label = recipient.label.clone()?;
+                Some((bitcoin::OutPoint::new(txid, vout).to_string(), label))
+            })
+            .collect();
 
         Ok(CreateSpendResult::Success {
             psbt,
+            labels,
             warnings: warnings.iter().map(|w| w.to_string()).collect(),
         })
     }
 
-    pub fn update_spend(&self, mut psbt: Psbt) -> Result<(), CommandError> {
+    /// Store the PSBT of a Spend transaction, merging its signatures with those of the stored
+    /// PSBT for the same transaction if any. The given labels are stored atomically along with it,
+    /// as with [`DaemonControl::update_labels`].
+    pub fn update_spend(
+        &self,
+        mut psbt: Psbt,
+        labels: &HashMap<LabelItem, Option<String>>,
+    ) -> Result<(), CommandError> {
         let mut db_conn = self.db.connection();
         let tx = &psbt.unsigned_tx;
 
@@ -670,11 +920,145 @@ impl DaemonControl {
         }
 
         // Finally, insert (or update) the PSBT in database.
-        db_conn.store_spend(&psbt);
+        db_conn.store_spend(&psbt, labels);
 
         Ok(())
     }
 
+    /// Import a PSBT created by another wallet and store it as a draft. All its inputs must spend
+    /// our coins. The information needed by signers which is missing from the PSBT is filled in
+    /// from our descriptor. Proprietary fields in our own namespace are dropped, we don't trust
+    /// another wallet to have set them.
+    pub fn import_psbt(&self, mut psbt: Psbt) -> Result<ImportPsbtResult, CommandError> {
+        let mut db_conn = self.db.connection();
+        let mut tx_getter = DbTxGetter::new(&self.db);
+        let is_taproot = self.config.main_descriptor.is_taproot();
+
+        let outpoints: Vec<bitcoin::OutPoint> = psbt
+            .unsigned_tx
+            .input
+            .iter()
+            .map(|txin| txin.previous_output)
+            .collect();
+        let coins = db_conn.coins_by_outpoints(&outpoints);
+        for (i, (op, psbt_in)) in outpoints.iter().zip(psbt.inputs.iter_mut()).enumerate() {
+            let coin = coins.get(op).ok_or(CommandError::ForeignInput(i, *op))?;
+            if coin.is_immature {
+                return Err(CommandError::ImmatureCoinbase(*op));
+            }
+            if coin.spend_block.is_some() {
+                return Err(CommandError::AlreadySpent(*op));
+            }
+
+            // Make sure the PSBT input doesn't commit to a different coin than ours, as signers
+            // would sign for the wrong amount.
+            let coin_desc = self.derived_desc(coin);
+            let txout = bitcoin::TxOut {
+                value: coin.amount,
+                script_pubkey: coin_desc.script_pubkey(),
+            };
+            if psbt_in
+                .witness_utxo
+                .as_ref()
+                .map(|utxo| utxo != &txout)
+                .unwrap_or(false)
+            {
+                return Err(CommandError::InputMismatch(i, *op));
+            }
+            if let Some(prev_tx) = &psbt_in.non_witness_utxo {
+                if prev_tx.compute_txid() != op.txid
+                    || prev_tx.output.get(op.vout as usize) != Some(&txout)
+                {
+                    return Err(CommandError::InputMismatch(i, *op));
+                }
+            }
+
+            coin_desc.update_psbt_in(psbt_in);
+            psbt_in.witness_utxo = Some(txout);
+            if !is_taproot && psbt_in.non_witness_utxo.is_none() {
+                psbt_in.non_witness_utxo = tx_getter.get_tx(&op.txid);
+            }
+        }
+
+        psbt.proprietary.retain(|key, _| key.prefix != b"liana");
+        for psbt_in in psbt.inputs.iter_mut() {
+            psbt_in.proprietary.retain(|key, _| key.prefix != b"liana");
+        }
+
+        // Outputs paying to our own addresses get their BIP32 derivations too, so signers can
+        // recognize them.
+        for (txo, psbt_out) in psbt.unsigned_tx.output.iter().zip(psbt.outputs.iter_mut()) {
+            psbt_out.proprietary.retain(|key, _| key.prefix != b"liana");
+            let info = bitcoin::Address::from_script(
+                &txo.script_pubkey,
+                self.config.bitcoin_config.network,
+            )
+            .ok()
+            .and_then(|addr| self.addr_info(&mut db_conn, &addr));
+            if let Some(AddrInfo { index, is_change }) = info {
+                let desc = if is_change {
+                    self.config.main_descriptor.change_descriptor()
+                } else {
+                    self.config.main_descriptor.receive_descriptor()
+                };
+                desc.derive(index, &self.secp)
+                    .update_change_psbt_out(psbt_out);
+            }
+        }
+
+        let txid = psbt.unsigned_tx.compute_txid();
+        self.update_spend(psbt, &HashMap::new())?;
+        let psbt = db_conn.spend_tx(&txid).expect("The PSBT was just stored.");
+        Ok(ImportPsbtResult { psbt })
+    }
+
+    /// Analyze a PSBT, which may have been created by another wallet, against our descriptor,
+    /// our coins and the current state of the chain.
+    pub fn analyze_psbt(
+        &self,
+        psbt: &Psbt,
+        key_aliases: HashMap<bip32::Fingerprint, String>,
+    ) -> psbt_analysis::PsbtReport {
+        let mut db_conn = self.db.connection();
+
+        let outpoints: Vec<bitcoin::OutPoint> = psbt
+            .unsigned_tx
+            .input
+            .iter()
+            .map(|txin| txin.previous_output)
+            .collect();
+        let mut mtp_cache = HashMap::new();
+        let coins = db_conn
+            .coins_by_outpoints(&outpoints)
+            .into_iter()
+            .map(|(op, coin)| {
+                let block_prev_mtp = coin.block_info.and_then(|b| {
+                    *mtp_cache
+                        .entry(b.height)
+                        .or_insert_with(|| self.bitcoin.median_time_past(b.height - 1))
+                });
+                let known_coin = psbt_analysis::KnownCoin {
+                    amount: coin.amount,
+                    derivation_index: coin.derivation_index,
+                    is_change: coin.is_change,
+                    block_height: coin.block_info.and_then(|b| b.height.try_into().ok()),
+                    block_prev_mtp,
+                };
+                (op, known_coin)
+            })
+            .collect();
+
+        let tip_height = self.bitcoin.chain_tip().height;
+        let ctx = psbt_analysis::AnalysisContext {
+            network: self.config.bitcoin_config.network,
+            tip_height: tip_height.try_into().ok(),
+            tip_mtp: self.bitcoin.median_time_past(tip_height),
+            coins,
+            key_aliases,
+        };
+        psbt_analysis::analyze_psbt(&self.config.main_descriptor, psbt, &ctx, &self.secp)
+    }
+
     pub fn update_labels(&self, items: &HashMap<LabelItem, Option<String>>) {
         let mut db_conn = self.db.connection();
         db_conn.update_labels(items);
@@ -720,13 +1104,11 @@ impl DaemonControl {
         db_conn.delete_spend(txid);
     }
 
-    /// Finalize and broadcast this stored Spend transaction.
-    pub fn broadcast_spend(&self, txid: &bitcoin::Txid) -> Result<(), CommandError> {
-        let mut db_conn = self.db.connection();
-
-        // First, try to finalize the spending transaction with the elements contained
-        // in the PSBT.
-        let mut spend_psbt = db_conn
+    // Finalize this stored Spend transaction with the elements contained in its PSBT.
+    fn finalize_spend(&self, txid: &bitcoin::Txid) -> Result<bitcoin::Transaction, CommandError> {
+        let mut spend_psbt = self
+            .db
+            .connection()
             .spend_tx(txid)
             .ok_or(CommandError::UnknownSpend(*txid))?;
         spend_psbt.finalize_mut(&self.secp).map_err(|e| {
@@ -737,16 +1119,12 @@ impl DaemonControl {
                     .unwrap_or_default(),
             )
         })?;
-
-        // Then, broadcast it (or try to, we never know if we are not going to hit an
-        // error at broadcast time).
         // These checks are already performed at Spend creation time. TODO: a belt-and-suspenders is still worth it though.
-        let final_tx = spend_psbt.extract_tx_unchecked_fee_rate();
-        self.bitcoin
-            .broadcast_tx(&final_tx)
-            .map_err(CommandError::TxBroadcast)?;
+        Ok(spend_psbt.extract_tx_unchecked_fee_rate())
+    }
 
-        // Finally, update our state with the changes from this transaction.
+    // Update our state with the changes from broadcast transactions.
+    fn poll_now(&self) {
         let (tx, rx) = mpsc::sync_channel(0);
         if let Err(e) = self.poller_sender.send(PollerMessage::PollNow(tx)) {
             log::error!("Error requesting update from poller: {}", e);
@@ -754,6 +1132,37 @@ impl DaemonControl {
         if let Err(e) = rx.recv() {
             log::error!("Error receiving completion signal from poller: {}", e);
         }
+    }
+
+    /// Finalize and broadcast this stored Spend transaction.
+    pub fn broadcast_spend(&self, txid: &bitcoin::Txid) -> Result<(), CommandError> {
+        // First, try to finalize the spending transaction. Then, broadcast it (or try to, we never
+        // know if we are not going to hit an error at broadcast time).
+        let final_tx = self.finalize_spend(txid)?;
+        self.bitcoin
+            .broadcast_tx(&final_tx)
+            .map_err(CommandError::TxBroadcast)?;
+
+        // Finally, update our state with the changes from this transaction.
+        self.poll_now();
+
+        Ok(())
+    }
+
+    /// Finalize these stored Spend transactions and submit them as a package. They must be a child
+    /// transaction and its unconfirmed parents, parents first. This allows the child to pay for
+    /// parents whose feerate is too low for them to be relayed on their own, such as TRUC
+    /// transactions paying no fee.
+    pub fn broadcast_package(&self, txids: &[bitcoin::Txid]) -> Result<(), CommandError> {
+        let final_txs = txids
+            .iter()
+            .map(|txid| self.finalize_spend(txid))
+            .collect::<Result<Vec<_>, _>>()?;
+        self.bitcoin
+            .submit_package(&final_txs)
+            .map_err(CommandError::TxBroadcast)?;
+
+        self.poll_now();
 
         Ok(())
     }
@@ -777,19 +1186,19 @@ impl DaemonControl {
     /// - the RBF transaction may include additional confirmed coins as inputs if required
     ///   in order to pay the higher fee (this applies also when replacing a self-send).
     ///
-    /// `feerate_vb` is the target feerate for the RBF transaction (in sat/vb). If `None`, it will be set
-    /// to 1 sat/vb larger than the feerate of the previous transaction, which is the minimum value allowed
-    /// when using RBF.
+    /// `feerate` is the target feerate for the RBF transaction. If `None`, it will be set to 1 sat/vb
+    /// larger than the feerate of the previous transaction, which is the minimum value allowed when
+    /// using RBF.
     pub fn rbf_psbt(
         &self,
         txid: &bitcoin::Txid,
         is_cancel: bool,
-        feerate_vb: Option<u64>,
+        feerate: Option<Feerate>,
     ) -> Result<CreateSpendResult, CommandError> {
         let mut db_conn = self.db.connection();
         let mut tx_getter = DbTxGetter::new(&self.db);
 
-        if is_cancel && feerate_vb.is_some() {
+        if is_cancel && feerate.is_some() {
             return Err(CommandError::RbfError(RbfErrorInfo::SuperfluousFeerate));
         }
 
@@ -803,6 +1212,8 @@ impl DaemonControl {
                 .and_then(|_| tx_getter.get_tx(txid))
                 .ok_or(CommandError::UnknownSpend(*txid))?
         };
+        // The replacement is a TRUC transaction if the replaced one is.
+        let is_truc = prev_tx.version == TRUC_VERSION;
         if !prev_tx.is_explicitly_rbf() {
             return Err(CommandError::RbfError(RbfErrorInfo::NotSignaling));
         }
@@ -833,21 +1244,16 @@ impl DaemonControl {
         // https://github.com/bitcoin/bitcoin/blob/master/doc/policy/mempool-replacements.md). By
         // default (ie if the transaction we are replacing was dropped from the mempool) there is
         // no minimum absolute fee and the minimum feerate is 1, the minimum relay feerate.
-        let (min_feerate_vb, descendant_fees) = self
+        let (min_feerate, descendant_fees) = self
             .bitcoin
             .mempool_spenders(&prev_outpoints)
             .into_iter()
             .fold(
-                (1, bitcoin::Amount::from_sat(0)),
+                (MIN_FEERATE, bitcoin::Amount::from_sat(0)),
                 |(min_feerate, descendant_fee), entry| {
-                    let entry_feerate = entry
-                        .fees
-                        .base
-                        .checked_div(entry.vsize)
+                    let entry_feerate = Feerate::from_fee(entry.fees.base.to_sat(), entry.vsize)
                         .expect("Can't have a null vsize or tx would be invalid")
-                        .to_sat()
-                        .checked_add(1)
-                        .expect("Can't overflow or tx would be invalid");
+                        .saturating_add_sat_per_vb(1);
                     (
                         std::cmp::max(min_feerate, entry_feerate),
                         descendant_fee + entry.fees.descendant,
@@ -856,32 +1262,41 @@ impl DaemonControl {
             );
         // Check replacement transaction's target feerate, if set, is high enough,
         // and otherwise set it to the min feerate found above.
-        let feerate_vb = if is_cancel {
-            min_feerate_vb
+        let feerate = if is_cancel {
+            min_feerate
         } else {
-            feerate_vb.ok_or(CommandError::RbfError(RbfErrorInfo::MissingFeerate))?
+            feerate.ok_or(CommandError::RbfError(RbfErrorInfo::MissingFeerate))?
         };
-        if feerate_vb < min_feerate_vb {
+        if feerate < min_feerate {
             return Err(CommandError::RbfError(RbfErrorInfo::TooLowFeerate(
-                feerate_vb,
-                min_feerate_vb,
+                feerate,
+                min_feerate,
             )));
         }
-        // Get info about prev outputs to determine replacement outputs.
+        // Get info about prev outputs to determine replacement outputs. Outputs which don't pay to
+        // an address (for instance data-carrier outputs) are kept as they are.
+        let mut prev_scripts = Vec::new();
         let prev_derivs: Vec<_> = prev_tx
             .output
             .iter()
-            .map(|txo| {
-                let address = bitcoin::Address::from_script(
+            .filter_map(|txo| {
+                match bitcoin::Address::from_script(
                     &txo.script_pubkey,
                     self.config.bitcoin_config.network,
-                )
-                .expect("address already used in finalized transaction");
-                (
-                    address.clone(),
-                    txo.value,
-                    db_conn.derivation_index_by_address(&address),
-                )
+                ) {
+                    Ok(address) => Some((
+                        address.clone(),
+                        txo.value,
+                        db_conn.derivation_index_by_address(&address),
+                    )),
+                    Err(_) => {
+                        prev_scripts.push(SpendOutput::new(
+                            SpendDestination::Script(txo.script_pubkey.clone()),
+                            txo.value,
+                        ));
+                        None
+                    }
+                }
             })
             .collect();
         // Set the previous change address to that of the change output with the largest value
@@ -901,16 +1316,20 @@ impl DaemonControl {
         // If not cancel, use all previous outputs as destinations, except for
         // the output corresponding to the change address we found above.
         // If cancel, the replacement will not have any destinations, only a change output.
-        let destinations = if !is_cancel {
+        let destinations: Vec<_> = if !is_cancel {
             prev_derivs
                 .into_iter()
                 .filter_map(|(addr, amt, _)| {
                     if prev_change_address.as_ref() != Some(&addr) {
-                        Some((self.spend_addr(&mut db_conn, addr), amt))
+                        Some(SpendOutput::new(
+                            self.spend_addr(&mut db_conn, addr).into(),
+                            amt,
+                        ))
                     } else {
                         None
                     }
                 })
+                .chain(prev_scripts)
                 .collect()
         } else {
             Vec::new()
@@ -971,9 +1390,11 @@ impl DaemonControl {
                 &mut tx_getter,
                 &destinations,
                 &candidate_coins,
-                SpendTxFees::Rbf(feerate_vb, replaced_fee),
+                SpendTxFees::Rbf(feerate, replaced_fee),
+                CoinSelectionStrategy::default(),
                 change_address.clone(),
                 locktime,
+                is_truc,
             ) {
                 Ok(CreateSpendRes {
                     psbt,
@@ -982,8 +1403,10 @@ impl DaemonControl {
                 }) => {
                     // In case of success, make sure to update our next derivation index if any address
                     // used in the transaction outputs was from the future.
-                    for (addr, _) in destinations {
-                        self.maybe_increase_next_deriv_index(&mut db_conn, &addr.info);
+                    for SpendOutput { destination, .. } in destinations {
+                        if let SpendDestination::Address(addr) = destination {
+                            self.maybe_increase_next_deriv_index(&mut db_conn, &addr.info);
+                        }
                     }
                     if has_change {
                         self.maybe_increase_next_deriv_index(&mut db_conn, &change_address.info);
@@ -991,6 +1414,7 @@ impl DaemonControl {
 
                     return Ok(CreateSpendResult::Success {
                         psbt,
+                        labels: HashMap::new(),
                         warnings: warnings.iter().map(|w| w.to_string()).collect(),
                     });
                 }
@@ -1015,6 +1439,65 @@ impl DaemonControl {
         }
     }
 
+    /// Create a transaction spending an unconfirmed coin, along with any additional coins, back to
+    /// a change address. The fee of the transaction is set such as the package it forms with the
+    /// unconfirmed ancestors of the coin has the given feerate. This allows to bump the fee of a
+    /// transaction we do not control, such as an incoming payment (Child Pays For Parent).
+    pub fn create_cpfp(
+        &self,
+        outpoint: &bitcoin::OutPoint,
+        extra_outpoints: &[bitcoin::OutPoint],
+        feerate: Feerate,
+    ) -> Result<CreateSpendResult, CommandError> {
+        let coin = self
+            .db
+            .connection()
+            .coins(&[], &[*outpoint])
+            .remove(outpoint)
+            .ok_or(CommandError::UnknownOutpoint(*outpoint))?;
+        if coin.block_info.is_some() {
+            return Err(CommandError::CpfpError(CpfpErrorInfo::AlreadyConfirmed(
+                *outpoint,
+            )));
+        }
+        let entry = self
+            .bitcoin
+            .mempool_entry(&outpoint.txid)
+            .ok_or(CommandError::CpfpError(CpfpErrorInfo::NotInMempool(
+                outpoint.txid,
+            )))?;
+        // There is no point in a child which would lower the feerate of the package.
+        let package_feerate = Feerate::from_fee(entry.fees.ancestor.to_sat(), entry.ancestor_vsize)
+            .unwrap_or_default();
+        if feerate <= package_feerate {
+            return Err(CommandError::CpfpError(CpfpErrorInfo::TooLowFeerate(
+                feerate,
+                package_feerate,
+            )));
+        }
+
+        // Spending the coins with no destination creates a single change output. The ancestors
+        // of the unconfirmed coins are accounted for when computing the fee. The child of a TRUC
+        // transaction must itself be TRUC.
+        let mut coins = vec![*outpoint];
+        for op in extra_outpoints {
+            if !coins.contains(op) {
+                coins.push(*op);
+            }
+        }
+        let truc = DbTxGetter::new(&self.db).is_truc(&outpoint.txid);
+        self.create_spend(
+            &[],
+            &coins,
+            feerate,
+            None,
+            CreateSpendOptions {
+                truc,
+                ..CreateSpendOptions::default()
+            },
+        )
+    }
+
     /// Trigger a rescan of the block chain for transactions involving our main descriptor between
     /// the given date and the current tip.
     /// The date must be after the genesis block time and before the current tip blocktime.
@@ -1074,17 +1557,25 @@ impl DaemonControl {
     /// currently available to a provided address with the provided feerate.
     ///
     /// The `timelock` parameter can be used to specify which recovery path to use. By default,
-    /// we'll use the first recovery path available.
+    /// we'll use the first recovery path available, as per
+    /// [`descriptors::LianaDescriptor::first_relative_timelock`]. It is the relative timelock as
+    /// encoded in the nSequence (i.e. the value in the `older()` fragment), so it may also
+    /// designate a recovery path behind a time-based relative timelock.
     ///
     /// Note that not all coins may be spendable through a single recovery path at the same time.
     pub fn create_recovery(
         &self,
         address: bitcoin::Address<address::NetworkUnchecked>,
-        feerate_vb: u64,
-        timelock: Option<u16>,
+        feerate: Feerate,
+        timelock: Option<u32>,
+        absolute_timelock: Option<u32>,
+        truc: bool,
     ) -> Result<CreateRecoveryResult, CommandError> {
-        if feerate_vb < 1 {
-            return Err(CommandError::InvalidFeerate(feerate_vb));
+        if feerate < spend::min_feerate(truc) {
+            return Err(CommandError::InvalidFeerate(feerate));
+        }
+        if timelock.is_some() && absolute_timelock.is_some() {
+            return Err(CommandError::ConflictingTimelocks);
         }
         let mut tx_getter = DbTxGetter::new(&self.db);
         let mut db_conn = self.db.connection();
@@ -1093,35 +1584,114 @@ impl DaemonControl {
         // Query the coins that we can spend through the specified recovery path (if no recovery
         // path specified, use the first available one) from the database.
         let current_height = self.bitcoin.chain_tip().height;
-        let timelock =
-            timelock.unwrap_or_else(|| self.config.main_descriptor.first_timelock_value());
-        let height_delta: i32 = timelock.into();
-        let sweepable_coins: Vec<_> = db_conn
-            .coins(&[CoinStatus::Confirmed], &[])
-            .into_values()
-            .filter_map(|c| {
-                // We are interested in coins available at the *next* block
-                if c.block_info
-                    .map(|b| current_height + 1 >= b.height + height_delta)
+        let confirmed_coins = db_conn.coins(&[CoinStatus::Confirmed], &[]).into_values();
+        let (sweepable_coins, locktime): (Vec<_>, _) = if let Some(abs_tl) = absolute_timelock {
+            // An absolute timelock does not depend on when the coins were confirmed: either the
+            // recovery path is available for all coins, or for none.
+            let abs_tl = miniscript::AbsLockTime::from_consensus(abs_tl)
+                .ok()
+                .filter(|tl| {
+                    self.config
+                        .main_descriptor
+                        .policy()
+                        .conditional_paths()
+                        .contains_key(&descriptors::PathCondition::Absolute(*tl))
+                })
+                .ok_or(CommandError::UnknownAbsoluteTimelock(abs_tl))?;
+            // The transaction must be valid in the *next* block. For a height this means the
+            // timelock is at most the current height, for a time it must be strictly lower than
+            // the median time past of the current tip.
+            let is_available = if abs_tl.is_block_height() {
+                i64::from(abs_tl.to_consensus_u32()) <= i64::from(current_height)
+            } else {
+                self.bitcoin
+                    .median_time_past(current_height)
+                    .map(|mtp| abs_tl.to_consensus_u32() < mtp)
                     .unwrap_or(false)
-                {
-                    Some(coin_to_candidate(
+            };
+            if !is_available {
+                return Err(CommandError::RecoveryNotAvailable);
+            }
+            let coins = confirmed_coins
+                .map(|c| {
+                    coin_to_candidate(
                         &c,
                         /*must_select=*/ true,
-                        /*sequence=*/ Some(bitcoin::Sequence::from_height(timelock)),
+                        /*sequence=*/ Some(bitcoin::Sequence::ENABLE_RBF_NO_LOCKTIME),
                         /*ancestor_info=*/ None,
-                    ))
-                } else {
-                    None
+                    )
+                })
+                .collect();
+            // Keep anti fee-sniping if it doesn't prevent using the recovery path.
+            let abs_tl: LockTime = abs_tl.into();
+            let anti_fee_sniping = self.anti_fee_sniping_locktime();
+            let locktime = if abs_tl.is_implied_by(anti_fee_sniping) {
+                anti_fee_sniping
+            } else {
+                abs_tl
+            };
+            (coins, locktime)
+        } else {
+            let timelock = match timelock {
+                Some(tl) => relative::LockTime::from_consensus(tl)
+                    .map_err(|_| CommandError::InvalidRelativeTimelock(tl))?,
+                None => self.config.main_descriptor.first_relative_timelock(),
+            };
+            let coins = match timelock {
+                relative::LockTime::Blocks(height) => {
+                    let height_delta: i32 = height.value().into();
+                    confirmed_coins
+                        .filter(|c| {
+                            // We are interested in coins available at the *next* block
+                            c.block_info
+                                .map(|b| current_height + 1 >= b.height + height_delta)
+                                .unwrap_or(false)
+                        })
+                        .collect::<Vec<_>>()
                 }
-            })
-            .collect();
+                relative::LockTime::Time(time) => {
+                    // As per BIP68, a coin's age is measured from the median time past of the
+                    // block preceding the one it was confirmed in. A transaction is valid in the
+                    // *next* block if this plus the timelock is at most the median time past of
+                    // the current tip.
+                    let time_delta = u32::from(time.value()) * 512;
+                    let tip_mtp = self
+                        .bitcoin
+                        .median_time_past(current_height)
+                        .ok_or(CommandError::RecoveryNotAvailable)?;
+                    let mut mtp_cache = HashMap::new();
+                    confirmed_coins
+                        .filter(|c| {
+                            c.block_info
+                                .and_then(|b| {
+                                    *mtp_cache.entry(b.height).or_insert_with(|| {
+                                        self.bitcoin.median_time_past(b.height - 1)
+                                    })
+                                })
+                                .map(|coin_mtp| coin_mtp + time_delta <= tip_mtp)
+                                .unwrap_or(false)
+                        })
+                        .collect::<Vec<_>>()
+                }
+            };
+            let coins = coins
+                .iter()
+                .map(|c| {
+                    coin_to_candidate(
+                        c,
+                        /*must_select=*/ true,
+                        /*sequence=*/ Some(timelock.to_sequence()),
+                        /*ancestor_info=*/ None,
+                    )
+                })
+                .collect();
+            (coins, self.anti_fee_sniping_locktime())
+        };
         if sweepable_coins.is_empty() {
             return Err(CommandError::RecoveryNotAvailable);
         }
 
         let sweep_addr_info = sweep_addr.info;
-        let locktime = self.anti_fee_sniping_locktime();
         let CreateSpendRes {
             psbt, has_change, ..
         } = create_spend(
@@ -1130,9 +1700,11 @@ impl DaemonControl {
             &mut tx_getter,
             &[], // No destination, only the change address.
             &sweepable_coins,
-            SpendTxFees::Regular(feerate_vb),
+            SpendTxFees::Regular(feerate),
+            CoinSelectionStrategy::default(),
             sweep_addr,
             locktime,
+            truc,
         )?;
         if has_change {
             self.maybe_increase_next_deriv_index(&mut db_conn, &sweep_addr_info);
@@ -1163,6 +1735,20 @@ pub struct GetInfoResult {
     pub last_poll_timestamp: Option<u32>,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeeEstimate {
+    /// The confirmation target, in number of blocks.
+    pub target: u16,
+    pub feerate: Feerate,
+    /// Whether the feerate was estimated by the Bitcoin backend, or is the minimum feerate.
+    pub estimated: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EstimateFeesResult {
+    pub estimates: Vec<FeeEstimate>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GetAddressResult {
     #[serde(deserialize_with = "deser_addr_assume_checked")]
@@ -1179,6 +1765,14 @@ impl GetAddressResult {
     }
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetBsmsRecordResult {
+    /// The descriptor record, hex encoded if it is encrypted.
+    pub record: String,
+    #[serde(deserialize_with = "deser_addr_assume_checked")]
+    pub first_address: bitcoin::Address,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GetLabelsResult {
     pub labels: HashMap<String, String>,
@@ -1242,43 +1836,166 @@ pub struct ListCoinsResult {
     pub coins: Vec<ListCoinsEntry>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
-#[serde(untagged)]
-pub enum CreateSpendResult {
-    Success {
-        #[serde(serialize_with = "ser_to_string", deserialize_with = "deser_fromstr")]
-        psbt: Psbt,
-        warnings: Vec<String>,
-    },
-    InsufficientFunds {
-        missing: u64,
-    },
+/// The recipient of an output of a transaction created with [`DaemonControl::create_spend`].
+///
+/// It is represented as a string: either an address, `raw(<hex>)` for an output paying to a raw
+/// scriptPubKey or `data(<hex>)` for a data-carrier (OP_RETURN) output pushing this data.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Destination {
+    Address(bitcoin::Address<address::NetworkUnchecked>),
+    Data(Vec<u8>),
+    Script(bitcoin::ScriptBuf),
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ListSpendEntry {
-    #[serde(serialize_with = "ser_to_string", deserialize_with = "deser_fromstr")]
-    pub psbt: Psbt,
-    pub updated_at: Option<u32>,
+impl From<bitcoin::Address<address::NetworkUnchecked>> for Destination {
+    fn from(address: bitcoin::Address<address::NetworkUnchecked>) -> Self {
+        Self::Address(address)
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ListSpendResult {
-    pub spend_txs: Vec<ListSpendEntry>,
+impl fmt::Display for Destination {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Address(address) => write!(f, "{}", address.assume_checked_ref()),
+            Self::Data(data) => write!(f, "data({})", data.as_hex()),
+            Self::Script(script) => write!(f, "raw({})", script.to_hex_string()),
+        }
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ListTransactionsResult {
-    pub transactions: Vec<TransactionInfo>,
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DestinationParseError {
+    Address(address::ParseError),
+    Hex(bitcoin::hex::HexToBytesError),
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct TransactionInfo {
-    #[serde(serialize_with = "ser_hex", deserialize_with = "deser_hex")]
-    pub tx: bitcoin::Transaction,
-    pub height: Option<i32>,
-    pub time: Option<u32>,
-}
+impl fmt::Display for DestinationParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Address(e) => write!(f, "Invalid address: {}", e),
+            Self::Hex(e) => write!(f, "Invalid hex: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for DestinationParseError {}
+
+impl FromStr for Destination {
+    type Err = DestinationParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(hex) = s.strip_prefix("data(").and_then(|s| s.strip_suffix(')')) {
+            Vec::from_hex(hex)
+                .map(Self::Data)
+                .map_err(DestinationParseError::Hex)
+        } else if let Some(hex) = s.strip_prefix("raw(").and_then(|s| s.strip_suffix(')')) {
+            bitcoin::ScriptBuf::from_hex(hex)
+                .map(Self::Script)
+                .map_err(DestinationParseError::Hex)
+        } else {
+            bitcoin::Address::from_str(s)
+                .map(Self::Address)
+                .map_err(DestinationParseError::Address)
+        }
+    }
+}
+
+impl Serialize for Destination {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        s.collect_str(self)
+    }
+}
+
+/// Optional parameters of [`DaemonControl::create_spend`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CreateSpendOptions {
+    /// The strategy to use when automatically selecting coins.
+    pub coin_selection: CoinSelectionStrategy,
+    /// Whether to create a TRUC (version 3) transaction.
+    pub truc: bool,
+}
+
+/// An output of a transaction created with [`DaemonControl::create_spend`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recipient {
+    pub destination: Destination,
+    /// Value of the output in satoshis.
+    pub amount: u64,
+    /// Whether to deduct (part of) the transaction fee from the value of this output.
+    pub subtract_fee: bool,
+    /// A label for this output. It is returned along with the created PSBT, to be stored with it.
+    pub label: Option<String>,
+}
+
+impl Recipient {
+    pub fn new(destination: impl Into<Destination>, amount: u64) -> Self {
+        Self {
+            destination: destination.into(),
+            amount,
+            subtract_fee: false,
+            label: None,
+        }
+    }
+}
+
+impl From<batch::Payment> for Recipient {
+    fn from(payment: batch::Payment) -> Self {
+        Self {
+            label: payment.label,
+            ..Self::new(
+                payment.address.as_unchecked().clone(),
+                payment.amount.to_sat(),
+            )
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum CreateSpendResult {
+    Success {
+        #[serde(serialize_with = "ser_to_string", deserialize_with = "deser_fromstr")]
+        psbt: Psbt,
+        /// The labels of the outputs, by outpoint, to be stored along with the PSBT.
+        #[serde(default)]
+        labels: HashMap<String, String>,
+        warnings: Vec<String>,
+    },
+    InsufficientFunds {
+        missing: u64,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListSpendEntry {
+    #[serde(serialize_with = "ser_to_string", deserialize_with = "deser_psbt")]
+    pub psbt: Psbt,
+    pub updated_at: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportPsbtResult {
+    #[serde(serialize_with = "ser_to_string", deserialize_with = "deser_psbt")]
+    pub psbt: Psbt,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListSpendResult {
+    pub spend_txs: Vec<ListSpendEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListTransactionsResult {
+    pub transactions: Vec<TransactionInfo>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransactionInfo {
+    #[serde(serialize_with = "ser_hex", deserialize_with = "deser_hex")]
+    pub tx: bitcoin::Transaction,
+    pub height: Option<i32>,
+    pub time: Option<u32>,
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct CreateRecoveryResult {
@@ -1289,17 +2006,30 @@ pub struct CreateRecoveryResult {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{bitcoin::Block, database::BlockInfo, testutils::*};
-    use liana::spend::InsaneFeeInfo;
+    use crate::{
+        bitcoin::{Block, MempoolEntry, MempoolEntryFees},
+        database::BlockInfo,
+        testutils::*,
+    };
+    use liana::spend::{InsaneFeeInfo, TrucViolation};
 
     use bitcoin::{
         bip32::{self, ChildNumber},
         blockdata::transaction::{TxIn, TxOut, Version as TxVersion},
+        hashes::Hash,
         locktime::absolute,
         Amount, OutPoint, ScriptBuf, Sequence, Transaction, Txid, Witness,
     };
     use std::{collections::BTreeMap, str::FromStr};
 
+    // Convert a map of destinations to value into the outputs of a transaction to create.
+    fn recipients(destinations: &HashMap<Destination, u64>) -> Vec<Recipient> {
+        destinations
+            .iter()
+            .map(|(destination, amount)| Recipient::new(destination.clone(), *amount))
+            .collect()
+    }
+
     #[test]
     fn getinfo() {
         let ms = DummyLiana::new(DummyBitcoind::new(), DummyDatabase::new());
@@ -1308,6 +2038,82 @@ mod tests {
         ms.shutdown();
     }
 
+    #[test]
+    fn estimatefees() {
+        // The backend may only be able to estimate some of the targets, the others default to the
+        // minimum feerate. Estimates out of our bounds are clamped.
+        let mut bitcoind = DummyBitcoind::new();
+        bitcoind
+            .fee_estimates
+            .insert(1, Feerate::from_sat_per_vb(2_000));
+        bitcoind
+            .fee_estimates
+            .insert(6, Feerate::from_sat_per_kvb(12_345));
+        let ms = DummyLiana::new(bitcoind, DummyDatabase::new());
+        let estimates = ms.control().estimate_fees().estimates;
+        assert_eq!(
+            estimates,
+            vec![
+                FeeEstimate {
+                    target: 1,
+                    feerate: MAX_FEERATE,
+                    estimated: true,
+                },
+                FeeEstimate {
+                    target: 6,
+                    feerate: Feerate::from_sat_per_kvb(12_345),
+                    estimated: true,
+                },
+                FeeEstimate {
+                    target: 144,
+                    feerate: MIN_FEERATE,
+                    estimated: false,
+                },
+            ]
+        );
+        ms.shutdown();
+    }
+
+    #[test]
+    fn getbsmsrecord() {
+        let ms = DummyLiana::new(DummyBitcoind::new(), DummyDatabase::new());
+        let control = &ms.control();
+        let secp = bitcoin::secp256k1::Secp256k1::verification_only();
+        let network = control.config.bitcoin_config.network;
+
+        // Without encryption the record is returned in clear, and its first address is the one
+        // of the wallet at index 0.
+        let res = control.get_bsms_record(&bsms::Token::no_encryption());
+        assert!(res.record.starts_with("BSMS 1.0\n"));
+        let record = bsms::DescriptorRecord::parse(&res.record, network, &secp).unwrap();
+        assert_eq!(record.descriptor(), &control.config.main_descriptor);
+        assert_eq!(
+            res.first_address,
+            control
+                .config
+                .main_descriptor
+                .receive_descriptor()
+                .derive(0.into(), &secp)
+                .address(network)
+        );
+
+        // It can only be decrypted with the same token.
+        let token = bsms::Token::generate().unwrap();
+        let res = control.get_bsms_record(&token);
+        assert!(!res.record.contains("BSMS"));
+        let record = bsms::DescriptorRecord::decrypt(&token, &res.record, network, &secp).unwrap();
+        assert_eq!(record.descriptor(), &control.config.main_descriptor);
+        bsms::DescriptorRecord::decrypt(
+            &bsms::Token::generate().unwrap(),
+            &res.record,
+            network,
+            &secp,
+        )
+        .unwrap_err();
+
+        ms.shutdown();
+    }
+
     #[test]
     fn getnewaddress() {
         let ms = DummyLiana::new(DummyBitcoind::new(), DummyDatabase::new());
@@ -1463,29 +2269,53 @@ mod tests {
         let dummy_addr =
             bitcoin::Address::from_str("bc1qnsexk3gnuyayu92fc3tczvc7k62u22a22ua2kv").unwrap();
         let dummy_value = 10_000;
-        let mut destinations = <HashMap<bitcoin::Address<address::NetworkUnchecked>, u64>>::new();
+        let mut destinations = <HashMap<Destination, u64>>::new();
         assert_eq!(
-            control.create_spend(&destinations, &[], 1, None),
+            control.create_spend(
+                &recipients(&destinations),
+                &[],
+                Feerate::from_sat_per_vb(1),
+                None,
+                CreateSpendOptions::default(),
+            ),
             Err(CommandError::NoOutpointForSelfSend)
         );
         destinations = [(dummy_addr.clone(), dummy_value)]
             .iter()
-            .cloned()
+            .map(|(addr, value)| (addr.clone().into(), *value))
             .collect();
         // Insufficient funds for coin selection.
         assert!(matches!(
-            control.create_spend(&destinations, &[], 1, None),
+            control.create_spend(
+                &recipients(&destinations),
+                &[],
+                Feerate::from_sat_per_vb(1),
+                None,
+                CreateSpendOptions::default(),
+            ),
             Ok(CreateSpendResult::InsufficientFunds { .. }),
         ));
         assert_eq!(
-            control.create_spend(&destinations, &[dummy_op], 0, None),
-            Err(CommandError::InvalidFeerate(0))
+            control.create_spend(
+                &recipients(&destinations),
+                &[dummy_op],
+                Feerate::from_sat_per_vb(0),
+                None,
+                CreateSpendOptions::default(),
+            ),
+            Err(CommandError::InvalidFeerate(Feerate::from_sat_per_vb(0)))
         );
 
         // The coin doesn't exist. If we create a new unspent one at this outpoint with a much
         // higher value, we'll get a Spend transaction with a change output.
         assert_eq!(
-            control.create_spend(&destinations, &[dummy_op], 1, None),
+            control.create_spend(
+                &recipients(&destinations),
+                &[dummy_op],
+                Feerate::from_sat_per_vb(1),
+                None,
+                CreateSpendOptions::default(),
+            ),
             Err(CommandError::UnknownOutpoint(dummy_op))
         );
         db_conn.new_unspent_coins(&[Coin {
@@ -1502,11 +2332,23 @@ mod tests {
         // If we try to use coin selection, the unconfirmed not-from-self coin will not be used
         // as a candidate and so we get a coin selection error due to insufficient funds.
         assert!(matches!(
-            control.create_spend(&destinations, &[], 1, None),
+            control.create_spend(
+                &recipients(&destinations),
+                &[],
+                Feerate::from_sat_per_vb(1),
+                None,
+                CreateSpendOptions::default(),
+            ),
             Ok(CreateSpendResult::InsufficientFunds { .. }),
         ));
-        let (psbt, warnings) = if let CreateSpendResult::Success { psbt, warnings } = control
-            .create_spend(&destinations, &[dummy_op], 1, None)
+        let (psbt, warnings) = if let CreateSpendResult::Success { psbt, warnings, .. } = control
+            .create_spend(
+                &recipients(&destinations),
+                &[dummy_op],
+                Feerate::from_sat_per_vb(1),
+                None,
+                CreateSpendOptions::default(),
+            )
             .unwrap()
         {
             (psbt, warnings)
@@ -1536,7 +2378,13 @@ mod tests {
         // At 2sats/vb, it's twice that.
         assert_eq!(tx.output[1].value.to_sat(), 89_839);
         let psbt = if let CreateSpendResult::Success { psbt, .. } = control
-            .create_spend(&destinations, &[dummy_op], 2, None)
+            .create_spend(
+                &recipients(&destinations),
+                &[dummy_op],
+                Feerate::from_sat_per_vb(2),
+                None,
+                CreateSpendOptions::default(),
+            )
             .unwrap()
         {
             psbt
@@ -1546,25 +2394,66 @@ mod tests {
         let tx = psbt.unsigned_tx;
         assert_eq!(tx.output[1].value.to_sat(), 89_678);
 
+        // Feerates may be a fraction of a sat/vb. At 1.5sat/vb the fee is rounded up to 242 sats.
+        let psbt = if let CreateSpendResult::Success { psbt, .. } = control
+            .create_spend(
+                &recipients(&destinations),
+                &[dummy_op],
+                Feerate::from_sat_per_kvb(1_500),
+                None,
+                CreateSpendOptions::default(),
+            )
+            .unwrap()
+        {
+            psbt
+        } else {
+            panic!("expect successful spend creation")
+        };
+        assert_eq!(psbt.unsigned_tx.output[1].value.to_sat(), 89_758);
+
         // A feerate of 555 won't trigger the sanity checks (they were previously not taking the
         // satisfaction size into account and overestimating the feerate).
         control
-            .create_spend(&destinations, &[dummy_op], 555, None)
+            .create_spend(
+                &recipients(&destinations),
+                &[dummy_op],
+                Feerate::from_sat_per_vb(555),
+                None,
+                CreateSpendOptions::default(),
+            )
             .unwrap();
 
         // If we ask for a too high feerate, or a too large/too small output, it'll fail.
         assert!(matches!(
-            control.create_spend(&destinations, &[dummy_op], 10_000, None),
+            control.create_spend(
+                &recipients(&destinations),
+                &[dummy_op],
+                Feerate::from_sat_per_vb(10_000),
+                None,
+                CreateSpendOptions::default(),
+            ),
             Ok(CreateSpendResult::InsufficientFunds { .. }),
         ));
-        *destinations.get_mut(&dummy_addr).unwrap() = 100_001;
+        *destinations.get_mut(&dummy_addr.clone().into()).unwrap() = 100_001;
         assert!(matches!(
-            control.create_spend(&destinations, &[dummy_op], 1, None),
+            control.create_spend(
+                &recipients(&destinations),
+                &[dummy_op],
+                Feerate::from_sat_per_vb(1),
+                None,
+                CreateSpendOptions::default(),
+            ),
             Ok(CreateSpendResult::InsufficientFunds { .. }),
         ));
-        *destinations.get_mut(&dummy_addr).unwrap() = 4_500;
+        *destinations.get_mut(&dummy_addr.clone().into()).unwrap() = 4_500;
         assert_eq!(
-            control.create_spend(&destinations, &[dummy_op], 1, None),
+            control.create_spend(
+                &recipients(&destinations),
+                &[dummy_op],
+                Feerate::from_sat_per_vb(1),
+                None,
+                CreateSpendOptions::default(),
+            ),
             Err(CommandError::SpendCreation(
                 SpendCreationError::InvalidOutputValue(bitcoin::Amount::from_sat(4_500))
             ))
@@ -1573,20 +2462,318 @@ mod tests {
         // If we ask to create an output for an address from another network, it will fail.
         let invalid_addr =
             bitcoin::Address::from_str("tb1qfufcrdyarcg5eph608c6l8vktrc9re6agu4se2").unwrap();
-        let invalid_destinations: HashMap<bitcoin::Address<address::NetworkUnchecked>, u64> =
-            [(invalid_addr, dummy_value)].iter().cloned().collect();
+        let invalid_destinations: HashMap<Destination, u64> = [(invalid_addr, dummy_value)]
+            .iter()
+            .map(|(addr, value)| (addr.clone().into(), *value))
+            .collect();
         assert!(matches!(
-            control.create_spend(&invalid_destinations, &[dummy_op], 1, None),
+            control.create_spend(
+                &recipients(&invalid_destinations),
+                &[dummy_op],
+                Feerate::from_sat_per_vb(1),
+                None,
+                CreateSpendOptions::default(),
+            ),
             Err(CommandError::Address(
                 address::error::ParseError::NetworkValidation { .. }
             ))
         ));
 
+        // We can attach data to a payment. It must be pushed in a single data-carrier output with
+        // no value, within the standard size limit.
+        let data_dest = Destination::from_str("data(6c69616e61)").unwrap();
+        assert_eq!(data_dest.to_string(), "data(6c69616e61)");
+        let mut data_destinations: HashMap<Destination, u64> = [
+            (dummy_addr.clone().into(), dummy_value),
+            (data_dest.clone(), 0),
+        ]
+        .iter()
+        .cloned()
+        .collect();
+        let psbt = if let CreateSpendResult::Success { psbt, .. } = control
+            .create_spend(
+                &recipients(&data_destinations),
+                &[dummy_op],
+                Feerate::from_sat_per_vb(1),
+                None,
+                CreateSpendOptions::default(),
+            )
+            .unwrap()
+        {
+            psbt
+        } else {
+            panic!("expect successful spend creation")
+        };
+        let tx = psbt.unsigned_tx;
+        assert_eq!(tx.output.len(), 3);
+        let data_txo = tx
+            .output
+            .iter()
+            .find(|txo| txo.script_pubkey.is_op_return())
+            .unwrap();
+        assert_eq!(data_txo.value, bitcoin::Amount::ZERO);
+        assert_eq!(data_txo.script_pubkey.as_bytes()[2..], b"liana"[..]);
+        data_destinations.insert(data_dest.clone(), 1);
+        assert_eq!(
+            control.create_spend(
+                &recipients(&data_destinations),
+                &[dummy_op],
+                Feerate::from_sat_per_vb(1),
+                None,
+                CreateSpendOptions::default(),
+            ),
+            Err(CommandError::SpendCreation(
+                SpendCreationError::InvalidOutputValue(bitcoin::Amount::from_sat(1))
+            ))
+        );
+        data_destinations.insert(data_dest, 0);
+        data_destinations.insert(Destination::Data(vec![0; 80]), 0);
+        assert_eq!(
+            control.create_spend(
+                &recipients(&data_destinations),
+                &[dummy_op],
+                Feerate::from_sat_per_vb(1),
+                None,
+                CreateSpendOptions::default(),
+            ),
+            Err(CommandError::SpendCreation(
+                SpendCreationError::MultipleDataCarriers
+            ))
+        );
+        let large_data_destinations: HashMap<Destination, u64> =
+            [(Destination::Data(vec![0; 81]), 0)]
+                .iter()
+                .cloned()
+                .collect();
+        assert_eq!(
+            control.create_spend(
+                &recipients(&large_data_destinations),
+                &[dummy_op],
+                Feerate::from_sat_per_vb(1),
+                None,
+                CreateSpendOptions::default(),
+            ),
+            Err(CommandError::SpendCreation(
+                SpendCreationError::DataCarrierTooLarge(84)
+            ))
+        );
+
+        // We can also pay to a raw script. It only has to be standard and above the dust
+        // threshold of the network.
+        let wsh_script = ScriptBuf::new_p2wsh(&bitcoin::WScriptHash::all_zeros());
+        let script_dest =
+            Destination::from_str(&format!("raw({})", wsh_script.to_hex_string())).unwrap();
+        assert_eq!(script_dest, Destination::Script(wsh_script.clone()));
+        let mut script_destinations: HashMap<Destination, u64> =
+            [(script_dest.clone(), 1_000)].iter().cloned().collect();
+        let psbt = if let CreateSpendResult::Success { psbt, .. } = control
+            .create_spend(
+                &recipients(&script_destinations),
+                &[dummy_op],
+                Feerate::from_sat_per_vb(1),
+                None,
+                CreateSpendOptions::default(),
+            )
+            .unwrap()
+        {
+            psbt
+        } else {
+            panic!("expect successful spend creation")
+        };
+        assert!(psbt
+            .unsigned_tx
+            .output
+            .iter()
+            .any(|txo| txo.script_pubkey == wsh_script && txo.value.to_sat() == 1_000));
+        script_destinations.insert(script_dest, 329);
+        assert_eq!(
+            control.create_spend(
+                &recipients(&script_destinations),
+                &[dummy_op],
+                Feerate::from_sat_per_vb(1),
+                None,
+                CreateSpendOptions::default(),
+            ),
+            Err(CommandError::SpendCreation(
+                SpendCreationError::InvalidOutputValue(bitcoin::Amount::from_sat(329))
+            ))
+        );
+        let op_true = bitcoin::ScriptBuf::from_bytes(vec![0x51]);
+        let nonstandard_destinations: HashMap<Destination, u64> =
+            [(Destination::Script(op_true.clone()), 10_000)]
+                .iter()
+                .cloned()
+                .collect();
+        assert_eq!(
+            control.create_spend(
+                &recipients(&nonstandard_destinations),
+                &[dummy_op],
+                Feerate::from_sat_per_vb(1),
+                None,
+                CreateSpendOptions::default(),
+            ),
+            Err(CommandError::SpendCreation(
+                SpendCreationError::NonStandardScript(op_true)
+            ))
+        );
+
+        // The fee may be subtracted from the outputs instead of being paid by the inputs. This
+        // allows sending the whole value of the coin.
+        let sffo_recipient = |destination: Destination, amount| Recipient {
+            subtract_fee: true,
+            ..Recipient::new(destination, amount)
+        };
+        let sweep_recipients = [sffo_recipient(dummy_addr.clone().into(), 100_000)];
+        let psbt = if let CreateSpendResult::Success { psbt, warnings, .. } = control
+            .create_spend(
+                &sweep_recipients,
+                &[dummy_op],
+                Feerate::from_sat_per_vb(1),
+                None,
+                CreateSpendOptions::default(),
+            )
+            .unwrap()
+        {
+            assert!(warnings.is_empty());
+            psbt
+        } else {
+            panic!("expect successful spend creation")
+        };
+        assert_eq!(psbt.unsigned_tx.output.len(), 1);
+        let sweep_fee = 100_000 - psbt.unsigned_tx.output[0].value.to_sat();
+        let sweep_vsize = control
+            .config
+            .main_descriptor
+            .unsigned_tx_max_vbytes(&psbt.unsigned_tx, true);
+        assert!(sweep_fee >= sweep_vsize && sweep_fee < sweep_vsize + 10);
+        // The fee is split equally among the outputs it is subtracted from. The wallet pays exactly
+        // the value of the outputs, the rest goes back to the change output.
+        let sffo_recipients = [
+            sffo_recipient(dummy_addr.clone().into(), 40_000),
+            sffo_recipient(Destination::Script(wsh_script.clone()), 40_000),
+        ];
+        let psbt = if let CreateSpendResult::Success { psbt, .. } = control
+            .create_spend(
+                &sffo_recipients,
+                &[dummy_op],
+                Feerate::from_sat_per_vb(2),
+                None,
+                CreateSpendOptions::default(),
+            )
+            .unwrap()
+        {
+            psbt
+        } else {
+            panic!("expect successful spend creation")
+        };
+        let tx = &psbt.unsigned_tx;
+        assert_eq!(tx.output.len(), 3);
+        assert_eq!(tx.output[2].value.to_sat(), 20_000);
+        let (value_a, value_b) = (tx.output[0].value.to_sat(), tx.output[1].value.to_sat());
+        assert!(value_a < 40_000 && value_b < 40_000);
+        assert!(value_a.abs_diff(value_b) <= 1);
+        let sffo_fee = 80_000 - value_a - value_b;
+        let sffo_vsize = control
+            .config
+            .main_descriptor
+            .unsigned_tx_max_vbytes(tx, true);
+        assert!(sffo_fee >= 2 * sffo_vsize && sffo_fee < 2 * sffo_vsize + 10);
+        // An output can't be reduced below the dust threshold by the fee.
+        assert!(matches!(
+            control.create_spend(
+                &[sffo_recipient(dummy_addr.clone().into(), 5_500)],
+                &[dummy_op],
+                Feerate::from_sat_per_vb(10),
+                None,
+                CreateSpendOptions::default(),
+            ),
+            Err(CommandError::SpendCreation(
+                SpendCreationError::InvalidOutputValue(..)
+            ))
+        ));
+        // Nor from a data-carrier output.
+        assert_eq!(
+            control.create_spend(
+                &[
+                    Recipient::new(dummy_addr.clone(), dummy_value),
+                    sffo_recipient(Destination::Data(b"liana".to_vec()), 0),
+                ],
+                &[dummy_op],
+                Feerate::from_sat_per_vb(1),
+                None,
+                CreateSpendOptions::default(),
+            ),
+            Err(CommandError::SpendCreation(
+                SpendCreationError::SubtractFeeFromDataCarrier
+            ))
+        );
+
+        // The same address may be paid more than once. Each output may have its own label, which
+        // is returned along with the PSBT and stored once it gets saved.
+        let labelled_recipients = [
+            Recipient {
+                label: Some("Invoice 1".to_string()),
+                ..Recipient::new(dummy_addr.clone(), 20_000)
+            },
+            Recipient {
+                label: Some("Invoice 2".to_string()),
+                ..Recipient::new(dummy_addr.clone(), 30_000)
+            },
+        ];
+        let (psbt, labels) = if let CreateSpendResult::Success { psbt, labels, .. } = control
+            .create_spend(
+                &labelled_recipients,
+                &[dummy_op],
+                Feerate::from_sat_per_vb(1),
+                None,
+                CreateSpendOptions::default(),
+            )
+            .unwrap()
+        {
+            (psbt, labels)
+        } else {
+            panic!("expect successful spend creation")
+        };
+        let tx = &psbt.unsigned_tx;
+        assert_eq!(tx.output.len(), 3);
+        assert_eq!(tx.output[0].script_pubkey, tx.output[1].script_pubkey);
+        assert_eq!(tx.output[0].value.to_sat(), 20_000);
+        assert_eq!(tx.output[1].value.to_sat(), 30_000);
+        assert!(psbt.outputs.iter().all(|o| o.proprietary.is_empty()));
+        let txid = tx.compute_txid();
+        assert_eq!(labels.len(), 2);
+        assert_eq!(labels[&format!("{}:0", txid)], "Invoice 1");
+        assert_eq!(labels[&format!("{}:1", txid)], "Invoice 2");
+        // Nothing is stored until the PSBT is saved.
+        let outpoints: HashSet<LabelItem> = (0..3)
+            .map(|vout| bitcoin::OutPoint::new(txid, vout).into())
+            .collect();
+        assert!(control.get_labels(&outpoints).labels.is_empty());
+        let label_items: HashMap<LabelItem, Option<String>> = labels
+            .iter()
+            .map(|(item, label)| {
+                (
+                    LabelItem::from_str(item, bitcoin::Network::Testnet).unwrap(),
+                    Some(label.clone()),
+                )
+            })
+            .collect();
+        control.update_spend(psbt.clone(), &label_items).unwrap();
+        assert_eq!(control.get_labels(&outpoints).labels, labels);
+        assert!(db_conn.spend_tx(&txid).is_some());
+        db_conn.delete_spend(&txid);
+
         // If we ask for a large, but valid, output we won't get a change output. 95_000 because we
         // won't create an output lower than 5k sats.
-        *destinations.get_mut(&dummy_addr).unwrap() = 95_000;
-        let (psbt, warnings) = if let CreateSpendResult::Success { psbt, warnings } = control
-            .create_spend(&destinations, &[dummy_op], 1, None)
+        *destinations.get_mut(&dummy_addr.clone().into()).unwrap() = 95_000;
+        let (psbt, warnings) = if let CreateSpendResult::Success { psbt, warnings, .. } = control
+            .create_spend(
+                &recipients(&destinations),
+                &[dummy_op],
+                Feerate::from_sat_per_vb(1),
+                None,
+                CreateSpendOptions::default(),
+            )
             .unwrap()
         {
             (psbt, warnings)
@@ -1613,9 +2800,15 @@ mod tests {
         );
 
         // Increase the target value by the change amount and the warning will disappear.
-        *destinations.get_mut(&dummy_addr).unwrap() = 95_000 + 4_839;
-        let (psbt, warnings) = if let CreateSpendResult::Success { psbt, warnings } = control
-            .create_spend(&destinations, &[dummy_op], 1, None)
+        *destinations.get_mut(&dummy_addr.clone().into()).unwrap() = 95_000 + 4_839;
+        let (psbt, warnings) = if let CreateSpendResult::Success { psbt, warnings, .. } = control
+            .create_spend(
+                &recipients(&destinations),
+                &[dummy_op],
+                Feerate::from_sat_per_vb(1),
+                None,
+                CreateSpendOptions::default(),
+            )
             .unwrap()
         {
             (psbt, warnings)
@@ -1627,10 +2820,16 @@ mod tests {
         assert!(warnings.is_empty());
 
         // Now increase target also by the extra fee that was paying for change and we can still create the spend.
-        *destinations.get_mut(&dummy_addr).unwrap() =
+        *destinations.get_mut(&dummy_addr.clone().into()).unwrap() =
             95_000 + 4_830 + /* fee for change output */ 43;
-        let (psbt, warnings) = if let CreateSpendResult::Success { psbt, warnings } = control
-            .create_spend(&destinations, &[dummy_op], 1, None)
+        let (psbt, warnings) = if let CreateSpendResult::Success { psbt, warnings, .. } = control
+            .create_spend(
+                &recipients(&destinations),
+                &[dummy_op],
+                Feerate::from_sat_per_vb(1),
+                None,
+                CreateSpendOptions::default(),
+            )
             .unwrap()
         {
             (psbt, warnings)
@@ -1642,18 +2841,30 @@ mod tests {
         assert!(warnings.is_empty());
 
         // Now increase the target by 1 more sat and we will have insufficient funds.
-        *destinations.get_mut(&dummy_addr).unwrap() =
+        *destinations.get_mut(&dummy_addr.clone().into()).unwrap() =
             95_000 + 4_839 + /* fee for change output */ 43 + 1;
         assert_eq!(
-            control.create_spend(&destinations, &[dummy_op], 1, None),
+            control.create_spend(
+                &recipients(&destinations),
+                &[dummy_op],
+                Feerate::from_sat_per_vb(1),
+                None,
+                CreateSpendOptions::default(),
+            ),
             Ok(CreateSpendResult::InsufficientFunds { missing: 1 }),
         );
 
         // Now decrease the target so that the lost change is just 1 sat.
-        *destinations.get_mut(&dummy_addr).unwrap() =
+        *destinations.get_mut(&dummy_addr.clone().into()).unwrap() =
             100_000 - /* fee without change */ 118 - /* extra fee for change output */ 43 - 1;
         let warnings = if let CreateSpendResult::Success { warnings, .. } = control
-            .create_spend(&destinations, &[dummy_op], 1, None)
+            .create_spend(
+                &recipients(&destinations),
+                &[dummy_op],
+                Feerate::from_sat_per_vb(1),
+                None,
+                CreateSpendOptions::default(),
+            )
             .unwrap()
         {
             warnings
@@ -1671,10 +2882,16 @@ mod tests {
         );
 
         // Now decrease the target value so that we have enough for a change output.
-        *destinations.get_mut(&dummy_addr).unwrap() =
+        *destinations.get_mut(&dummy_addr.clone().into()).unwrap() =
             95_000 - /* fee without change */ 118 - /* extra fee for change output */ 43;
-        let (psbt, warnings) = if let CreateSpendResult::Success { psbt, warnings } = control
-            .create_spend(&destinations, &[dummy_op], 1, None)
+        let (psbt, warnings) = if let CreateSpendResult::Success { psbt, warnings, .. } = control
+            .create_spend(
+                &recipients(&destinations),
+                &[dummy_op],
+                Feerate::from_sat_per_vb(1),
+                None,
+                CreateSpendOptions::default(),
+            )
             .unwrap()
         {
             (psbt, warnings)
@@ -1687,10 +2904,16 @@ mod tests {
         assert!(warnings.is_empty());
 
         // Now increase the target by 1 and we'll get a warning again, this time for 1 less than the dust threshold.
-        *destinations.get_mut(&dummy_addr).unwrap() =
+        *destinations.get_mut(&dummy_addr.clone().into()).unwrap() =
             95_000 - /* fee without change */ 118 - /* extra fee for change output */ 43 + 1;
         let warnings = if let CreateSpendResult::Success { warnings, .. } = control
-            .create_spend(&destinations, &[dummy_op], 1, None)
+            .create_spend(
+                &recipients(&destinations),
+                &[dummy_op],
+                Feerate::from_sat_per_vb(1),
+                None,
+                CreateSpendOptions::default(),
+            )
             .unwrap()
         {
             warnings
@@ -1716,13 +2939,25 @@ mod tests {
             .unwrap(),
         )]);
         assert_eq!(
-            control.create_spend(&destinations, &[dummy_op], 1, None),
+            control.create_spend(
+                &recipients(&destinations),
+                &[dummy_op],
+                Feerate::from_sat_per_vb(1),
+                None,
+                CreateSpendOptions::default(),
+            ),
             Err(CommandError::AlreadySpent(dummy_op))
         );
         // If we try to use coin selection, the spent coin will not be used as a candidate
         // and so we get a coin selection error due to insufficient funds.
         assert!(matches!(
-            control.create_spend(&destinations, &[], 1, None),
+            control.create_spend(
+                &recipients(&destinations),
+                &[],
+                Feerate::from_sat_per_vb(1),
+                None,
+                CreateSpendOptions::default(),
+            ),
             Ok(CreateSpendResult::InsufficientFunds { .. }),
         ));
 
@@ -1743,9 +2978,15 @@ mod tests {
             is_from_self: false,
         }]);
         assert_eq!(
-            control.create_spend(&destinations, &[dummy_op_dup], 1_001, None),
+            control.create_spend(
+                &recipients(&destinations),
+                &[dummy_op_dup],
+                Feerate::from_sat_per_vb(1_001),
+                None,
+                CreateSpendOptions::default(),
+            ),
             Err(CommandError::SpendCreation(SpendCreationError::InsaneFees(
-                InsaneFeeInfo::TooHighFeerate(1_001)
+                InsaneFeeInfo::TooHighFeerate(Feerate::from_sat_per_vb(1_001))
             )))
         );
 
@@ -1768,14 +3009,26 @@ mod tests {
         db_conn.new_unspent_coins(&[unconfirmed_coin]);
         // Coin selection error due to insufficient funds.
         assert!(matches!(
-            control.create_spend(&destinations, &[], 1, None),
+            control.create_spend(
+                &recipients(&destinations),
+                &[],
+                Feerate::from_sat_per_vb(1),
+                None,
+                CreateSpendOptions::default(),
+            ),
             Ok(CreateSpendResult::InsufficientFunds { .. }),
         ));
         // Set destination amount equal to value of confirmed coins.
-        *destinations.get_mut(&dummy_addr).unwrap() = 80_000;
+        *destinations.get_mut(&dummy_addr.clone().into()).unwrap() = 80_000;
         // Coin selection error occurs due to insufficient funds to pay fee.
         assert!(matches!(
-            control.create_spend(&destinations, &[], 1, None),
+            control.create_spend(
+                &recipients(&destinations),
+                &[],
+                Feerate::from_sat_per_vb(1),
+                None,
+                CreateSpendOptions::default(),
+            ),
             Ok(CreateSpendResult::InsufficientFunds { .. }),
         ));
         let confirmed_op_2 = bitcoin::OutPoint {
@@ -1798,8 +3051,15 @@ mod tests {
             is_from_self: false,
         }]);
         // First, create a transaction using auto coin selection.
-        let psbt = if let CreateSpendResult::Success { psbt, .. } =
-            control.create_spend(&destinations, &[], 1, None).unwrap()
+        let psbt = if let CreateSpendResult::Success { psbt, .. } = control
+            .create_spend(
+                &recipients(&destinations),
+                &[],
+                Feerate::from_sat_per_vb(1),
+                None,
+                CreateSpendOptions::default(),
+            )
+            .unwrap()
         {
             psbt
         } else {
@@ -1824,7 +3084,13 @@ mod tests {
 
         // Create a second transaction using manual coin selection.
         let psbt = if let CreateSpendResult::Success { psbt, .. } = control
-            .create_spend(&destinations, &[confirmed_op_1, confirmed_op_2], 1, None)
+            .create_spend(
+                &recipients(&destinations),
+                &[confirmed_op_1, confirmed_op_2],
+                Feerate::from_sat_per_vb(1),
+                None,
+                CreateSpendOptions::default(),
+            )
             .unwrap()
         {
             psbt
@@ -1857,7 +3123,13 @@ mod tests {
         unconfirmed_coin_2.is_change = false;
         db_conn.new_unspent_coins(&[unconfirmed_coin_2]);
         assert!(matches!(
-            control.create_spend(&destinations, &[], 1, None),
+            control.create_spend(
+                &recipients(&destinations),
+                &[],
+                Feerate::from_sat_per_vb(1),
+                None,
+                CreateSpendOptions::default(),
+            ),
             Ok(CreateSpendResult::InsufficientFunds { .. }),
         ));
         // 2. not from self and change
@@ -1866,7 +3138,13 @@ mod tests {
         unconfirmed_coin_2.is_change = true;
         db_conn.new_unspent_coins(&[unconfirmed_coin_2]);
         assert!(matches!(
-            control.create_spend(&destinations, &[], 1, None),
+            control.create_spend(
+                &recipients(&destinations),
+                &[],
+                Feerate::from_sat_per_vb(1),
+                None,
+                CreateSpendOptions::default(),
+            ),
             Ok(CreateSpendResult::InsufficientFunds { .. }),
         ));
 
@@ -1883,10 +3161,11 @@ mod tests {
         .unwrap();
         let psbt = if let CreateSpendResult::Success { psbt, .. } = control
             .create_spend(
-                &destinations,
+                &recipients(&destinations),
                 &[confirmed_op_1, confirmed_op_2],
-                1,
+                Feerate::from_sat_per_vb(1),
                 Some(change_address.as_unchecked().clone()),
+                CreateSpendOptions::default(),
             )
             .unwrap()
         {
@@ -1924,14 +3203,25 @@ mod tests {
             spend_block: None,
             is_from_self: false,
         }]);
-        let empty_dest = &HashMap::<bitcoin::Address<address::NetworkUnchecked>, u64>::new();
         assert!(matches!(
-            control.create_spend(empty_dest, &[confirmed_op_3], 5, None),
+            control.create_spend(
+                &[],
+                &[confirmed_op_3],
+                Feerate::from_sat_per_vb(5),
+                None,
+                CreateSpendOptions::default(),
+            ),
             Ok(CreateSpendResult::InsufficientFunds { .. }),
         ));
         // If we use a lower fee, the self-send will succeed.
         let psbt = if let CreateSpendResult::Success { psbt, .. } = control
-            .create_spend(empty_dest, &[confirmed_op_3], 1, None)
+            .create_spend(
+                &[],
+                &[confirmed_op_3],
+                Feerate::from_sat_per_vb(1),
+                None,
+                CreateSpendOptions::default(),
+            )
             .unwrap()
         {
             psbt
@@ -1965,13 +3255,290 @@ mod tests {
             is_from_self: false,
         }]);
         assert_eq!(
-            control.create_spend(&destinations, &[imma_op], 1_001, None),
+            control.create_spend(
+                &recipients(&destinations),
+                &[imma_op],
+                Feerate::from_sat_per_vb(1_001),
+                None,
+                CreateSpendOptions::default(),
+            ),
             Err(CommandError::ImmatureCoinbase(imma_op))
         );
 
         ms.shutdown();
     }
 
+    #[test]
+    fn create_cpfp() {
+        let parent_tx = bitcoin::Transaction {
+            version: TxVersion::TWO,
+            lock_time: absolute::LockTime::Blocks(absolute::Height::ZERO),
+            input: vec![],
+            output: vec![],
+        };
+        let other_tx = bitcoin::Transaction {
+            version: TxVersion::TWO,
+            lock_time: absolute::LockTime::Blocks(absolute::Height::from_consensus(1).unwrap()),
+            input: vec![],
+            output: vec![],
+        };
+        let parent_op = bitcoin::OutPoint::new(parent_tx.compute_txid(), 0);
+        let unconfirmed_op = bitcoin::OutPoint::new(other_tx.compute_txid(), 0);
+        let confirmed_op = bitcoin::OutPoint::new(other_tx.compute_txid(), 1);
+
+        // The parent pays 1sat/vb and is in the mempool.
+        let mut bitcoind = DummyBitcoind::new();
+        bitcoind.mempool_entries.insert(
+            parent_op.txid,
+            MempoolEntry {
+                vsize: 200,
+                ancestor_vsize: 200,
+                fees: MempoolEntryFees {
+                    base: Amount::from_sat(200),
+                    ancestor: Amount::from_sat(200),
+                    descendant: Amount::from_sat(200),
+                },
+            },
+        );
+        let ms = DummyLiana::new(bitcoind, DummyDatabase::new());
+        let control = &ms.control();
+        let mut db_conn = control.db().lock().unwrap().connection();
+        db_conn.new_txs(&[parent_tx, other_tx]);
+
+        let feerate = Feerate::from_sat_per_vb(10);
+        assert_eq!(
+            control.create_cpfp(&parent_op, &[], feerate),
+            Err(CommandError::UnknownOutpoint(parent_op))
+        );
+        let coin = |outpoint, derivation_index: u32, block_info| Coin {
+            outpoint,
+            is_immature: false,
+            block_info,
+            amount: bitcoin::Amount::from_sat(100_000),
+            derivation_index: bip32::ChildNumber::from(derivation_index),
+            is_change: false,
+            spend_txid: None,
+            spend_block: None,
+            is_from_self: false,
+        };
+        db_conn.new_unspent_coins(&[
+            coin(parent_op, 13, None),
+            coin(unconfirmed_op, 14, None),
+            coin(
+                confirmed_op,
+                15,
+                Some(BlockInfo {
+                    height: 174500,
+                    time: 174500,
+                }),
+            ),
+        ]);
+
+        // The coin must be unconfirmed and its transaction in the mempool. And the target feerate
+        // must be higher than the one of the parent.
+        assert_eq!(
+            control.create_cpfp(&confirmed_op, &[], feerate),
+            Err(CommandError::CpfpError(CpfpErrorInfo::AlreadyConfirmed(
+                confirmed_op
+            )))
+        );
+        assert_eq!(
+            control.create_cpfp(&unconfirmed_op, &[], feerate),
+            Err(CommandError::CpfpError(CpfpErrorInfo::NotInMempool(
+                unconfirmed_op.txid
+            )))
+        );
+        assert_eq!(
+            control.create_cpfp(&parent_op, &[], Feerate::from_sat_per_vb(1)),
+            Err(CommandError::CpfpError(CpfpErrorInfo::TooLowFeerate(
+                Feerate::from_sat_per_vb(1),
+                Feerate::from_sat_per_vb(1)
+            )))
+        );
+
+        // The child spends the coin back to a change output. Its fee makes up for the parent's.
+        let psbt = match control.create_cpfp(&parent_op, &[], feerate).unwrap() {
+            CreateSpendResult::Success { psbt, .. } => psbt,
+            res => panic!("unexpected result {:?}", res),
+        };
+        let tx = &psbt.unsigned_tx;
+        assert_eq!(tx.input.len(), 1);
+        assert_eq!(tx.input[0].previous_output, parent_op);
+        assert_eq!(tx.output.len(), 1);
+        assert!(!psbt.outputs[0].bip32_derivation.is_empty());
+        let fee = 100_000 - tx.output[0].value.to_sat();
+        let fee_for_parent = 10 * 200 - 200;
+        assert!(fee > fee_for_parent, "{}", fee);
+        assert!(fee < fee_for_parent + 10 * 200, "{}", fee);
+
+        // Additional coins may be spent along with the unconfirmed one.
+        let psbt = match control
+            .create_cpfp(&parent_op, &[confirmed_op, parent_op], feerate)
+            .unwrap()
+        {
+            CreateSpendResult::Success { psbt, .. } => psbt,
+            res => panic!("unexpected result {:?}", res),
+        };
+        assert_eq!(psbt.unsigned_tx.input.len(), 2);
+        assert_eq!(psbt.unsigned_tx.output.len(), 1);
+
+        ms.shutdown();
+    }
+
+    #[test]
+    fn create_truc_spend() {
+        let truc_tx = bitcoin::Transaction {
+            version: TRUC_VERSION,
+            lock_time: absolute::LockTime::Blocks(absolute::Height::ZERO),
+            input: vec![],
+            output: vec![],
+        };
+        let other_tx = bitcoin::Transaction {
+            version: TxVersion::TWO,
+            lock_time: absolute::LockTime::Blocks(absolute::Height::ZERO),
+            input: vec![],
+            output: vec![],
+        };
+        let truc_op = bitcoin::OutPoint::new(truc_tx.compute_txid(), 0);
+        let unconfirmed_op = bitcoin::OutPoint::new(other_tx.compute_txid(), 0);
+        let confirmed_op = bitcoin::OutPoint::new(other_tx.compute_txid(), 1);
+
+        let mut bitcoind = DummyBitcoind::new();
+        for txid in [truc_op.txid, unconfirmed_op.txid] {
+            bitcoind.mempool_entries.insert(
+                txid,
+                MempoolEntry {
+                    vsize: 200,
+                    ancestor_vsize: 200,
+                    fees: MempoolEntryFees {
+                        base: Amount::from_sat(200),
+                        ancestor: Amount::from_sat(200),
+                        descendant: Amount::from_sat(200),
+                    },
+                },
+            );
+        }
+        let ms = DummyLiana::new(bitcoind, DummyDatabase::new());
+        let control = &ms.control();
+        let mut db_conn = control.db().lock().unwrap().connection();
+        db_conn.new_txs(&[truc_tx, other_tx]);
+        let coin = |outpoint, derivation_index: u32, block_info| Coin {
+            outpoint,
+            is_immature: false,
+            block_info,
+            amount: bitcoin::Amount::from_sat(100_000),
+            derivation_index: bip32::ChildNumber::from(derivation_index),
+            is_change: false,
+            spend_txid: None,
+            spend_block: None,
+            is_from_self: false,
+        };
+        db_conn.new_unspent_coins(&[
+            coin(truc_op, 13, None),
+            coin(unconfirmed_op, 14, None),
+            coin(
+                confirmed_op,
+                15,
+                Some(BlockInfo {
+                    height: 174500,
+                    time: 174500,
+                }),
+            ),
+        ]);
+        let feerate = Feerate::from_sat_per_vb(10);
+        let spend = |coins: &[bitcoin::OutPoint], truc| {
+            control
+                .create_spend(
+                    &[],
+                    coins,
+                    feerate,
+                    None,
+                    CreateSpendOptions {
+                        truc,
+                        ..CreateSpendOptions::default()
+                    },
+                )
+                .map(|res| match res {
+                    CreateSpendResult::Success { psbt, .. } => psbt.unsigned_tx,
+                    res => panic!("unexpected result {:?}", res),
+                })
+        };
+
+        // A TRUC transaction may spend confirmed coins, or coins from a single unconfirmed TRUC
+        // transaction.
+        assert_eq!(spend(&[confirmed_op], true).unwrap().version, TRUC_VERSION);
+        assert_eq!(
+            spend(&[confirmed_op], false).unwrap().version,
+            TxVersion::TWO
+        );
+        assert_eq!(
+            spend(&[confirmed_op, truc_op], true).unwrap().version,
+            TRUC_VERSION
+        );
+
+        // But not coins from an unconfirmed non-TRUC transaction, while a non-TRUC transaction
+        // may not spend coins from an unconfirmed TRUC transaction.
+        assert_eq!(
+            spend(&[unconfirmed_op], true),
+            Err(CommandError::SpendCreation(SpendCreationError::Truc(
+                TrucViolation::NonTrucParent(unconfirmed_op.txid)
+            )))
+        );
+        assert_eq!(
+            spend(&[truc_op, unconfirmed_op], true),
+            Err(CommandError::SpendCreation(SpendCreationError::Truc(
+                TrucViolation::TooManyUnconfirmedParents(2)
+            )))
+        );
+        assert_eq!(
+            spend(&[truc_op], false),
+            Err(CommandError::SpendCreation(SpendCreationError::Truc(
+                TrucViolation::TrucParent(truc_op.txid)
+            )))
+        );
+
+        // A TRUC transaction may pay no fee at all, as it can be relayed in a package along with a
+        // child paying for it. A non-TRUC transaction may not.
+        let zero_fee_spend = |truc| {
+            control.create_spend(
+                &[],
+                &[confirmed_op],
+                Feerate::default(),
+                None,
+                CreateSpendOptions {
+                    truc,
+                    ..CreateSpendOptions::default()
+                },
+            )
+        };
+        match zero_fee_spend(true).unwrap() {
+            CreateSpendResult::Success { psbt, .. } => {
+                assert_eq!(psbt.unsigned_tx.version, TRUC_VERSION);
+                assert_eq!(psbt.unsigned_tx.output.len(), 1);
+                assert_eq!(psbt.unsigned_tx.output[0].value, Amount::from_sat(100_000));
+            }
+            res => panic!("unexpected result {:?}", res),
+        }
+        assert_eq!(
+            zero_fee_spend(false).unwrap_err(),
+            CommandError::InvalidFeerate(Feerate::default())
+        );
+
+        // The child used to bump the fee of a TRUC transaction is itself TRUC.
+        let psbt = match control.create_cpfp(&truc_op, &[], feerate).unwrap() {
+            CreateSpendResult::Success { psbt, .. } => psbt,
+            res => panic!("unexpected result {:?}", res),
+        };
+        assert_eq!(psbt.unsigned_tx.version, TRUC_VERSION);
+        let psbt = match control.create_cpfp(&unconfirmed_op, &[], feerate).unwrap() {
+            CreateSpendResult::Success { psbt, .. } => psbt,
+            res => panic!("unexpected result {:?}", res),
+        };
+        assert_eq!(psbt.unsigned_tx.version, TxVersion::TWO);
+
+        ms.shutdown();
+    }
+
     #[test]
     fn update_spend() {
         let dummy_op_a = bitcoin::OutPoint::from_str(
@@ -2030,23 +3597,27 @@ mod tests {
             bitcoin::Address::from_str("bc1q39srgatmkp6k2ne3l52yhkjprdvunvspqydmkx").unwrap();
         let dummy_value_a = 50_000;
         let dummy_value_b = 60_000;
-        let destinations_a: HashMap<bitcoin::Address<address::NetworkUnchecked>, u64> =
-            [(dummy_addr_a.clone(), dummy_value_a)]
-                .iter()
-                .cloned()
-                .collect();
-        let destinations_b: HashMap<bitcoin::Address<address::NetworkUnchecked>, u64> =
-            [(dummy_addr_b.clone(), dummy_value_b)]
-                .iter()
-                .cloned()
-                .collect();
-        let destinations_c: HashMap<bitcoin::Address<address::NetworkUnchecked>, u64> =
+        let destinations_a: HashMap<Destination, u64> = [(dummy_addr_a.clone(), dummy_value_a)]
+            .iter()
+            .map(|(addr, value)| (addr.clone().into(), *value))
+            .collect();
+        let destinations_b: HashMap<Destination, u64> = [(dummy_addr_b.clone(), dummy_value_b)]
+            .iter()
+            .map(|(addr, value)| (addr.clone().into(), *value))
+            .collect();
+        let destinations_c: HashMap<Destination, u64> =
             [(dummy_addr_a, dummy_value_a), (dummy_addr_b, dummy_value_b)]
                 .iter()
-                .cloned()
+                .map(|(addr, value)| (addr.clone().into(), *value))
                 .collect();
         let mut psbt_a = if let CreateSpendResult::Success { psbt, .. } = control
-            .create_spend(&destinations_a, &[dummy_op_a], 1, None)
+            .create_spend(
+                &recipients(&destinations_a),
+                &[dummy_op_a],
+                Feerate::from_sat_per_vb(1),
+                None,
+                CreateSpendOptions::default(),
+            )
             .unwrap()
         {
             psbt
@@ -2055,7 +3626,13 @@ mod tests {
         };
         let txid_a = psbt_a.unsigned_tx.compute_txid();
         let psbt_b = if let CreateSpendResult::Success { psbt, .. } = control
-            .create_spend(&destinations_b, &[dummy_op_b], 10, None)
+            .create_spend(
+                &recipients(&destinations_b),
+                &[dummy_op_b],
+                Feerate::from_sat_per_vb(10),
+                None,
+                CreateSpendOptions::default(),
+            )
             .unwrap()
         {
             psbt
@@ -2064,7 +3641,13 @@ mod tests {
         };
         let txid_b = psbt_b.unsigned_tx.compute_txid();
         let psbt_c = if let CreateSpendResult::Success { psbt, .. } = control
-            .create_spend(&destinations_c, &[dummy_op_a, dummy_op_b], 100, None)
+            .create_spend(
+                &recipients(&destinations_c),
+                &[dummy_op_a, dummy_op_b],
+                Feerate::from_sat_per_vb(100),
+                None,
+                CreateSpendOptions::default(),
+            )
             .unwrap()
         {
             psbt
@@ -2074,11 +3657,17 @@ mod tests {
         let txid_c = psbt_c.unsigned_tx.compute_txid();
 
         // We can store and query them all
-        control.update_spend(psbt_a.clone()).unwrap();
+        control
+            .update_spend(psbt_a.clone(), &HashMap::new())
+            .unwrap();
         assert_eq!(db_conn.spend_tx(&txid_a).unwrap(), psbt_a);
-        control.update_spend(psbt_b.clone()).unwrap();
+        control
+            .update_spend(psbt_b.clone(), &HashMap::new())
+            .unwrap();
         assert_eq!(db_conn.spend_tx(&txid_b).unwrap(), psbt_b);
-        control.update_spend(psbt_c.clone()).unwrap();
+        control
+            .update_spend(psbt_c.clone(), &HashMap::new())
+            .unwrap();
         assert_eq!(db_conn.spend_tx(&txid_c).unwrap(), psbt_c);
 
         // As well as update them, with or without new signatures
@@ -2090,11 +3679,17 @@ mod tests {
             .unwrap(),
             sig,
         );
-        control.update_spend(psbt_a.clone()).unwrap();
+        control
+            .update_spend(psbt_a.clone(), &HashMap::new())
+            .unwrap();
         assert_eq!(db_conn.spend_tx(&txid_a).unwrap(), psbt_a);
-        control.update_spend(psbt_b.clone()).unwrap();
+        control
+            .update_spend(psbt_b.clone(), &HashMap::new())
+            .unwrap();
         assert_eq!(db_conn.spend_tx(&txid_b).unwrap(), psbt_b);
-        control.update_spend(psbt_c.clone()).unwrap();
+        control
+            .update_spend(psbt_c.clone(), &HashMap::new())
+            .unwrap();
         assert_eq!(db_conn.spend_tx(&txid_c).unwrap(), psbt_c);
 
         // We can't store a PSBT spending an external coin
@@ -2104,13 +3699,219 @@ mod tests {
         .unwrap();
         psbt_a.unsigned_tx.input[0].previous_output = external_op;
         assert_eq!(
-            control.update_spend(psbt_a),
+            control.update_spend(psbt_a, &HashMap::new()),
             Err(CommandError::UnknownOutpoint(external_op))
         );
 
         ms.shutdown();
     }
 
+    #[test]
+    fn analyze_psbt() {
+        let dummy_op = bitcoin::OutPoint::from_str(
+            "3753a1d74c0af8dd0a0f3b763c14faf3bd9ed03cbdf33337a074fb0e9f6c7810:0",
+        )
+        .unwrap();
+        let ms = DummyLiana::new(DummyBitcoind::new(), DummyDatabase::new());
+        let control = &ms.control();
+        let mut db_conn = control.db().lock().unwrap().connection();
+        db_conn.new_unspent_coins(&[Coin {
+            outpoint: dummy_op,
+            is_immature: false,
+            block_info: Some(BlockInfo {
+                height: 95,
+                time: 1_700_000_000,
+            }),
+            amount: bitcoin::Amount::from_sat(100_000),
+            derivation_index: bip32::ChildNumber::from(13),
+            is_change: false,
+            spend_txid: None,
+            spend_block: None,
+            is_from_self: false,
+        }]);
+
+        let dummy_addr =
+            bitcoin::Address::from_str("bc1qnsexk3gnuyayu92fc3tczvc7k62u22a22ua2kv").unwrap();
+        let destinations: HashMap<Destination, u64> =
+            [(dummy_addr.into(), 50_000)].iter().cloned().collect();
+        let mut psbt = if let CreateSpendResult::Success { psbt, .. } = control
+            .create_spend(
+                &recipients(&destinations),
+                &[dummy_op],
+                Feerate::from_sat_per_vb(2),
+                None,
+                CreateSpendOptions::default(),
+            )
+            .unwrap()
+        {
+            psbt
+        } else {
+            panic!("expect successful spend creation")
+        };
+
+        // The input is ours and can be spent through the primary path. One output pays back to
+        // our change address.
+        let fg = bip32::Fingerprint::from_str("aabbccdd").unwrap();
+        let aliases: HashMap<_, _> = [(fg, "Owner".to_string())].iter().cloned().collect();
+        let report = control.analyze_psbt(&psbt, aliases.clone());
+        assert_eq!(report.txid, psbt.unsigned_tx.compute_txid());
+        let ours = report.inputs[0].ours.as_ref().unwrap();
+        assert_eq!(ours.derivation_index, 13);
+        assert_eq!(ours.spending_paths.len(), 1);
+        assert_eq!(
+            ours.spending_paths[0].unsigned_keys[0].alias.as_deref(),
+            Some("Owner")
+        );
+        assert!(report
+            .outputs
+            .iter()
+            .any(|o| o.kind == psbt_analysis::OutputKind::Change));
+        assert_eq!(
+            report.fee.map(|fee| fee.to_sat()),
+            Some(
+                100_000
+                    - psbt
+                        .unsigned_tx
+                        .output
+                        .iter()
+                        .map(|o| o.value.to_sat())
+                        .sum::<u64>()
+            )
+        );
+        assert!(report.feerate.unwrap() >= Feerate::from_sat_per_vb(2));
+        assert!(report.warnings.is_empty());
+
+        // Using the recovery path, the coin confirmed at height 95 is far from mature at the
+        // current height of 100.
+        psbt.unsigned_tx.input[0].sequence = bitcoin::Sequence::from_height(10_000);
+        let report = control.analyze_psbt(&psbt, aliases);
+        let ours = report.inputs[0].ours.as_ref().unwrap();
+        assert_eq!(
+            ours.spending_paths[1].timelock,
+            psbt_analysis::TimelockStatus::ImmatureBlocks { remaining: 9_994 }
+        );
+    }
+
+    #[test]
+    fn import_psbt() {
+        let dummy_op = bitcoin::OutPoint::from_str(
+            "3753a1d74c0af8dd0a0f3b763c14faf3bd9ed03cbdf33337a074fb0e9f6c7810:0",
+        )
+        .unwrap();
+        let ms = DummyLiana::new(DummyBitcoind::new(), DummyDatabase::new());
+        let control = &ms.control();
+        let mut db_conn = control.db().lock().unwrap().connection();
+        db_conn.new_unspent_coins(&[Coin {
+            outpoint: dummy_op,
+            is_immature: false,
+            block_info: None,
+            amount: bitcoin::Amount::from_sat(100_000),
+            derivation_index: bip32::ChildNumber::from(13),
+            is_change: false,
+            spend_txid: None,
+            spend_block: None,
+            is_from_self: false,
+        }]);
+
+        // Create a PSBT and strip it from all the information another wallet would not have.
+        let dummy_addr =
+            bitcoin::Address::from_str("bc1qnsexk3gnuyayu92fc3tczvc7k62u22a22ua2kv").unwrap();
+        let destinations: HashMap<Destination, u64> =
+            [(dummy_addr.into(), 50_000)].iter().cloned().collect();
+        let psbt = if let CreateSpendResult::Success { psbt, .. } = control
+            .create_spend(
+                &recipients(&destinations),
+                &[dummy_op],
+                Feerate::from_sat_per_vb(2),
+                None,
+                CreateSpendOptions::default(),
+            )
+            .unwrap()
+        {
+            psbt
+        } else {
+            panic!("expect successful spend creation")
+        };
+        let mut external_psbt = psbt.clone();
+        external_psbt.inputs[0] = Default::default();
+        for psbt_out in external_psbt.outputs.iter_mut() {
+            *psbt_out = Default::default();
+        }
+        let txid = psbt.unsigned_tx.compute_txid();
+        assert!(db_conn.spend_tx(&txid).is_none());
+
+        // Proprietary fields in our namespace set by another wallet are dropped, others are kept.
+        let liana_key = bitcoin::psbt::raw::ProprietaryKey {
+            prefix: b"liana".to_vec(),
+            subtype: 0x00,
+            key: Vec::new(),
+        };
+        let other_key = bitcoin::psbt::raw::ProprietaryKey {
+            prefix: b"other".to_vec(),
+            ..liana_key.clone()
+        };
+        let mut tagged_psbt = external_psbt.clone();
+        tagged_psbt
+            .proprietary
+            .insert(liana_key.clone(), b"Invoice 1".to_vec());
+        tagged_psbt.outputs[0]
+            .proprietary
+            .insert(liana_key.clone(), b"Invoice 1".to_vec());
+        tagged_psbt.outputs[0]
+            .proprietary
+            .insert(other_key.clone(), b"Invoice 1".to_vec());
+        tagged_psbt.inputs[0]
+            .proprietary
+            .insert(liana_key, b"Invoice 1".to_vec());
+        let imported = control.import_psbt(tagged_psbt).unwrap().psbt;
+        assert!(imported.proprietary.is_empty());
+        assert!(imported.inputs[0].proprietary.is_empty());
+        assert_eq!(
+            imported.outputs[0].proprietary.keys().collect::<Vec<_>>(),
+            vec![&other_key]
+        );
+        let outpoints: HashSet<LabelItem> = (0..2)
+            .map(|vout| bitcoin::OutPoint::new(txid, vout).into())
+            .collect();
+        assert!(control.get_labels(&outpoints).labels.is_empty());
+        db_conn.delete_spend(&txid);
+
+        // Once imported, the information was filled back in and the PSBT is stored.
+        let imported = control.import_psbt(external_psbt.clone()).unwrap().psbt;
+        assert_eq!(imported.inputs[0].witness_utxo, psbt.inputs[0].witness_utxo);
+        assert_eq!(
+            imported.inputs[0].bip32_derivation,
+            psbt.inputs[0].bip32_derivation
+        );
+        assert_eq!(
+            imported.inputs[0].witness_script,
+            psbt.inputs[0].witness_script
+        );
+        assert_eq!(db_conn.spend_tx(&txid), Some(imported));
+
+        // An input committing to a different value is rejected.
+        let mut mismatch_psbt = external_psbt.clone();
+        let mut txout = psbt.inputs[0].witness_utxo.clone().unwrap();
+        txout.value = bitcoin::Amount::from_sat(1_000_000);
+        mismatch_psbt.inputs[0].witness_utxo = Some(txout);
+        assert_eq!(
+            control.import_psbt(mismatch_psbt).unwrap_err(),
+            CommandError::InputMismatch(0, dummy_op)
+        );
+
+        // An input which doesn't spend one of our coins is rejected.
+        let foreign_op = bitcoin::OutPoint {
+            txid: dummy_op.txid,
+            vout: 1,
+        };
+        let mut foreign_psbt = external_psbt;
+        foreign_psbt.unsigned_tx.input[0].previous_output = foreign_op;
+        assert_eq!(
+            control.import_psbt(foreign_psbt).unwrap_err(),
+            CommandError::ForeignInput(0, foreign_op)
+        );
+    }
+
     #[test]
     fn rbf_psbt() {
         let dummy_op_a = bitcoin::OutPoint::from_str(
@@ -2150,7 +3951,7 @@ mod tests {
             Err(CommandError::UnknownSpend(dummy_txid_a))
         );
         // Store the spend.
-        db_conn.store_spend(&dummy_psbt_a);
+        db_conn.store_spend(&dummy_psbt_a, &HashMap::new());
         // Now add the coin to DB, but as spent.
         db_conn.new_unspent_coins(&[Coin {
             outpoint: dummy_op_a,
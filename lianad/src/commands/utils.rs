@@ -1,6 +1,7 @@
 use std::str::FromStr;
 
-use miniscript::bitcoin::{self, consensus, hashes::hex::FromHex};
+use liana::psbt_v2;
+use miniscript::bitcoin::{self, consensus, hashes::hex::FromHex, psbt::Psbt};
 use serde::{de, Deserialize, Deserializer, Serializer};
 
 pub fn deser_fromstr<'de, D, T>(deserializer: D) -> Result<T, D::Error>
@@ -20,6 +21,15 @@ pub fn ser_to_string<T: std::fmt::Display, S: Serializer>(
     s.serialize_str(&field.to_string())
 }
 
+/// Deserialize a base64-encoded PSBT of either version 0 or version 2.
+pub fn deser_psbt<'de, D>(deserializer: D) -> Result<Psbt, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let string = String::deserialize(deserializer)?;
+    psbt_v2::from_base64(&string).map_err(de::Error::custom)
+}
+
 /// Deserialize an address from string, assuming the network was checked.
 pub fn deser_addr_assume_checked<'de, D>(deserializer: D) -> Result<bitcoin::Address, D::Error>
 where
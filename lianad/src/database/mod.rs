@@ -154,8 +154,9 @@ pub trait DatabaseConnection {
 
     fn spend_tx(&mut self, txid: &bitcoin::Txid) -> Option<Psbt>;
 
-    /// Insert a new Spend transaction or replace an existing one.
-    fn store_spend(&mut self, psbt: &Psbt);
+    /// Insert a new Spend transaction or replace an existing one. Atomically update the labels
+    /// for the given items (as key), as with [`DatabaseConnection::update_labels`].
+    fn store_spend(&mut self, psbt: &Psbt, labels: &HashMap<LabelItem, Option<String>>);
 
     /// List all existing Spend transactions, along with an optional last update timestamp.
     fn list_spend(&mut self) -> Vec<(Psbt, Option<u32>)>;
@@ -343,8 +344,8 @@ impl DatabaseConnection for SqliteConn {
         self.db_spend(txid).map(|db_spend| db_spend.psbt)
     }
 
-    fn store_spend(&mut self, psbt: &Psbt) {
-        self.store_spend(psbt)
+    fn store_spend(&mut self, psbt: &Psbt, labels: &HashMap<LabelItem, Option<String>>) {
+        self.store_spend(psbt, labels)
     }
 
     fn list_spend(&mut self) -> Vec<(Psbt, Option<u32>)> {
@@ -216,6 +216,32 @@ impl SqliteDb {
 // We only support single wallet. The id of the wallet row is always 1.
 const WALLET_ID: i64 = 1;
 
+// Set, for a set of items (as key), their label (as value). A `None` value deletes the label.
+fn set_labels(
+    db_tx: &rusqlite::Transaction,
+    items: &HashMap<LabelItem, Option<String>>,
+) -> rusqlite::Result<()> {
+    for (labelled, kind, value) in items.iter().map(|(a, v)| match a {
+        LabelItem::Address(a) => (a.to_string(), DbLabelledKind::Address, v),
+        LabelItem::Txid(a) => (a.to_string(), DbLabelledKind::Txid, v),
+        LabelItem::OutPoint(a) => (a.to_string(), DbLabelledKind::OutPoint, v),
+    }) {
+        if let Some(value) = value {
+            db_tx.execute(
+                "INSERT INTO labels (wallet_id, item, item_kind, value) VALUES (?1, ?2, ?3, ?4) \
+                ON CONFLICT DO UPDATE SET value=excluded.value",
+                rusqlite::params![WALLET_ID, labelled, kind as i64, value],
+            )?;
+        } else {
+            db_tx.execute(
+                "DELETE FROM labels WHERE wallet_id = ?1 AND item = ?2",
+                rusqlite::params![WALLET_ID, labelled],
+            )?;
+        }
+    }
+    Ok(())
+}
+
 pub struct SqliteConn {
     conn: rusqlite::Connection,
 }
@@ -609,8 +635,8 @@ impl SqliteConn {
         .pop()
     }
 
-    /// Insert a new Spend transaction or replace an existing one.
-    pub fn store_spend(&mut self, psbt: &Psbt) {
+    /// Insert a new Spend transaction or replace an existing one, along with the given labels.
+    pub fn store_spend(&mut self, psbt: &Psbt, labels: &HashMap<LabelItem, Option<String>>) {
         let txid = &psbt.unsigned_tx.compute_txid()[..].to_vec();
 
         db_exec(&mut self.conn, |db_tx| {
@@ -619,7 +645,7 @@ impl SqliteConn {
                  ON CONFLICT DO UPDATE SET psbt=excluded.psbt",
                 rusqlite::params![psbt.serialize(), txid, curr_timestamp()],
             )?;
-            Ok(())
+            set_labels(db_tx, labels)
         })
         .expect("Db must not fail");
     }
@@ -635,32 +661,7 @@ impl SqliteConn {
     }
 
     pub fn update_labels(&mut self, items: &HashMap<LabelItem, Option<String>>) {
-        db_exec(&mut self.conn, |db_tx| {
-            for (labelled, kind, value) in items
-                .iter()
-                .map(|(a, v)| {
-                     match a {
-                         LabelItem::Address(a) =>(a.to_string(), DbLabelledKind::Address, v),
-                         LabelItem::Txid(a) =>(a.to_string(), DbLabelledKind::Txid, v),
-                         LabelItem::OutPoint(a) =>(a.to_string(), DbLabelledKind::OutPoint, v),
-                     }
-                }) {
-                if let Some(value) = value {
-                    db_tx.execute(
-                        "INSERT INTO labels (wallet_id, item, item_kind, value) VALUES (?1, ?2, ?3, ?4) \
-                        ON CONFLICT DO UPDATE SET value=excluded.value",
-                        rusqlite::params![WALLET_ID, labelled, kind as i64, value],
-                    )?;
-                } else {
-                    db_tx.execute(
-                        "DELETE FROM labels WHERE wallet_id = ?1 AND item = ?2",
-                        rusqlite::params![WALLET_ID, labelled],
-                    )?;
-                }
-            }
-            Ok(())
-        })
-        .expect("Db must not fail")
+        db_exec(&mut self.conn, |db_tx| set_labels(db_tx, items)).expect("Db must not fail")
     }
 
     pub fn db_labels(&mut self, items: &HashSet<LabelItem>) -> Vec<DbLabel> {
@@ -3179,7 +3180,7 @@ CREATE TABLE labels (
         // have no associated timestamp.
         {
             let mut conn = db.connection().unwrap();
-            conn.store_spend(&second_psbt);
+            conn.store_spend(&second_psbt, &HashMap::new());
             let db_spends = conn.list_spend();
             let first_spend = db_spends
                 .iter()
@@ -1,5 +1,5 @@
 use crate::{
-    commands::{CoinStatus, LabelItem},
+    commands::{CoinStatus, CreateSpendOptions, Destination, LabelItem, Recipient},
     jsonrpc::rpc::{Error, Params, Request, Response},
     DaemonControl,
 };
@@ -10,23 +10,85 @@ use std::{
     str::FromStr,
 };
 
-use miniscript::bitcoin::{self, psbt::Psbt, Txid};
+use liana::{
+    bsms,
+    psbt_v2::{self, PsbtVersion},
+    spend::{self, CoinSelectionStrategy, Feerate},
+};
+use miniscript::bitcoin::{self, bip32, psbt::Psbt, Txid};
+use serde::Deserialize;
+
+// Parse a feerate in sats/vb. It may be an integer or a decimal number.
+fn parse_feerate(value: &serde_json::Value) -> Option<Feerate> {
+    if !value.is_number() {
+        return None;
+    }
+    Feerate::deserialize(value).ok()
+}
+
+// Parse an entry of the list form of the 'destinations' parameter of 'createspend'.
+fn recipient(entry: &serde_json::Value) -> Result<Recipient, Error> {
+    let invalid = |field: &str| {
+        Error::invalid_params(format!(
+            "Invalid 'destinations' parameter: invalid or missing '{}' field.",
+            field
+        ))
+    };
+    let destination = entry
+        .get("destination")
+        .and_then(|d| d.as_str())
+        .and_then(|d| Destination::from_str(d).ok())
+        .ok_or_else(|| invalid("destination"))?;
+    let amount = entry
+        .get("amount")
+        .and_then(|a| a.as_u64())
+        .ok_or_else(|| invalid("amount"))?;
+    let subtract_fee = entry
+        .get("subtract_fee")
+        .map(|s| s.as_bool().ok_or_else(|| invalid("subtract_fee")))
+        .transpose()?
+        .unwrap_or(false);
+    let label = entry
+        .get("label")
+        .map(|l| {
+            l.as_str()
+                .filter(|l| l.len() <= 100)
+                .map(|l| l.to_string())
+                .ok_or_else(|| invalid("label"))
+        })
+        .transpose()?;
+    Ok(Recipient {
+        destination,
+        amount,
+        subtract_fee,
+        label,
+    })
+}
 
 fn create_spend(control: &DaemonControl, params: Params) -> Result<serde_json::Value, Error> {
+    // The destinations are either an ordered list of outputs or a map from destination to value.
     let destinations = params
         .get(0, "destinations")
-        .ok_or_else(|| Error::invalid_params("Missing 'destinations' parameter."))?
-        .as_object()
-        .and_then(|obj| {
-            obj.into_iter()
-                .map(|(k, v)| {
-                    let addr = bitcoin::Address::from_str(k).ok()?;
-                    let amount: u64 = v.as_i64()?.try_into().ok()?;
-                    Some((addr, amount))
-                })
-                .collect::<Option<HashMap<bitcoin::Address<bitcoin::address::NetworkUnchecked>, u64>>>()
-        })
-        .ok_or_else(|| Error::invalid_params("Invalid 'destinations' parameter."))?;
+        .ok_or_else(|| Error::invalid_params("Missing 'destinations' parameter."))?;
+    let mut recipients = if let Some(entries) = destinations.as_array() {
+        entries
+            .iter()
+            .map(recipient)
+            .collect::<Result<Vec<_>, _>>()?
+    } else {
+        destinations
+            .as_object()
+            .and_then(|obj| {
+                obj.into_iter()
+                    .map(|(k, v)| {
+                        let destination = Destination::from_str(k).ok()?;
+                        let amount: u64 = v.as_i64()?.try_into().ok()?;
+                        Some(Recipient::new(destination, amount))
+                    })
+                    .collect::<Option<Vec<Recipient>>>()
+            })
+            .ok_or_else(|| Error::invalid_params("Invalid 'destinations' parameter."))?
+    };
     let outpoints = params
         .get(1, "outpoints")
         .ok_or_else(|| Error::invalid_params("Missing 'outpoints' parameter."))?
@@ -41,11 +103,12 @@ fn create_spend(control: &DaemonControl, params: Params) -> Result<serde_json::V
                 .collect::<Option<Vec<bitcoin::OutPoint>>>()
         })
         .ok_or_else(|| Error::invalid_params("Invalid 'outpoints' parameter."))?;
-    let feerate: u64 = params
+    let feerate = params
         .get(2, "feerate")
-        .ok_or_else(|| Error::invalid_params("Missing 'feerate' parameter."))?
-        .as_u64()
-        .ok_or_else(|| Error::invalid_params("Invalid 'feerate' parameter."))?;
+        .ok_or_else(|| Error::invalid_params("Missing 'feerate' parameter."))
+        .and_then(|fr| {
+            parse_feerate(fr).ok_or_else(|| Error::invalid_params("Invalid 'feerate' parameter."))
+        })?;
     let change_address: Option<bitcoin::Address<bitcoin::address::NetworkUnchecked>> = params
         .get(3, "change_address")
         .map(|addr| {
@@ -57,23 +120,169 @@ fn create_spend(control: &DaemonControl, params: Params) -> Result<serde_json::V
             })
         })
         .transpose()?;
+    let long_term_feerate: Option<Feerate> = params
+        .get(5, "long_term_feerate")
+        .map(|fr| {
+            parse_feerate(fr)
+                .filter(|fr| *fr > Feerate::default())
+                .ok_or_else(|| Error::invalid_params("Invalid 'long_term_feerate' parameter."))
+        })
+        .transpose()?;
+    let coin_selection = params
+        .get(4, "coin_selection")
+        .map(|strategy| {
+            match strategy.as_str() {
+                Some("lowest_fee") => Some(CoinSelectionStrategy::LowestFee),
+                Some("waste") => Some(CoinSelectionStrategy::Waste {
+                    long_term_feerate: long_term_feerate.unwrap_or(spend::LONG_TERM_FEERATE),
+                }),
+                Some("oldest_first") => Some(CoinSelectionStrategy::OldestFirst),
+                Some("privacy") => Some(CoinSelectionStrategy::Privacy),
+                Some("largest_first") => Some(CoinSelectionStrategy::LargestFirst),
+                _ => None,
+            }
+            .ok_or_else(|| Error::invalid_params("Invalid 'coin_selection' parameter."))
+        })
+        .transpose()?
+        .unwrap_or_default();
+    if long_term_feerate.is_some() && !matches!(coin_selection, CoinSelectionStrategy::Waste { .. })
+    {
+        return Err(Error::invalid_params(
+            "The 'long_term_feerate' parameter is only valid with the 'waste' coin selection.",
+        ));
+    }
 
-    let res = control.create_spend(&destinations, &outpoints, feerate, change_address)?;
+    let subtract_fee_from = params
+        .get(6, "subtract_fee_from")
+        .map(|dests| {
+            dests
+                .as_array()
+                .and_then(|arr| {
+                    arr.iter()
+                        .map(|dest| dest.as_str().and_then(|d| Destination::from_str(d).ok()))
+                        .collect::<Option<HashSet<Destination>>>()
+                })
+                .ok_or_else(|| Error::invalid_params("Invalid 'subtract_fee_from' parameter."))
+        })
+        .transpose()?
+        .unwrap_or_default();
+    for destination in subtract_fee_from {
+        let mut found = false;
+        for recipient in recipients
+            .iter_mut()
+            .filter(|r| r.destination == destination)
+        {
+            recipient.subtract_fee = true;
+            found = true;
+        }
+        if !found {
+            return Err(Error::invalid_params(format!(
+                "Invalid 'subtract_fee_from' parameter: '{}' is not a destination of the transaction.",
+                destination
+            )));
+        }
+    }
+
+    let truc = params
+        .get(7, "truc")
+        .map(|truc| {
+            truc.as_bool()
+                .ok_or_else(|| Error::invalid_params("Invalid 'truc' parameter."))
+        })
+        .transpose()?
+        .unwrap_or(false);
+
+    let res = control.create_spend(
+        &recipients,
+        &outpoints,
+        feerate,
+        change_address,
+        CreateSpendOptions {
+            coin_selection,
+            truc,
+        },
+    )?;
     Ok(serde_json::json!(&res))
 }
 
+fn get_bsms_record(
+    control: &DaemonControl,
+    params: Option<Params>,
+) -> Result<serde_json::Value, Error> {
+    let token = params
+        .as_ref()
+        .and_then(|p| p.get(0, "token"))
+        .map(|token| {
+            token
+                .as_str()
+                .and_then(|t| bsms::Token::from_str(t).ok())
+                .ok_or_else(|| Error::invalid_params("Invalid 'token' parameter."))
+        })
+        .transpose()?
+        .unwrap_or_else(bsms::Token::no_encryption);
+    Ok(serde_json::json!(&control.get_bsms_record(&token)))
+}
+
 fn update_spend(control: &DaemonControl, params: Params) -> Result<serde_json::Value, Error> {
     let psbt: Psbt = params
         .get(0, "psbt")
         .ok_or_else(|| Error::invalid_params("Missing 'psbt' parameter."))?
         .as_str()
-        .and_then(|s| Psbt::from_str(s).ok())
+        .and_then(|s| psbt_v2::from_base64(s).ok())
         .ok_or_else(|| Error::invalid_params("Invalid 'psbt' parameter."))?;
-    control.update_spend(psbt)?;
+    let labels = params
+        .get(1, "labels")
+        .map(|labels| labels_param(control, labels))
+        .transpose()?
+        .unwrap_or_default();
+    control.update_spend(psbt, &labels)?;
 
     Ok(serde_json::json!({}))
 }
 
+fn import_psbt(control: &DaemonControl, params: Params) -> Result<serde_json::Value, Error> {
+    let psbt: Psbt = params
+        .get(0, "psbt")
+        .ok_or_else(|| Error::invalid_params("Missing 'psbt' parameter."))?
+        .as_str()
+        .and_then(|s| psbt_v2::from_base64(s).ok())
+        .ok_or_else(|| Error::invalid_params("Invalid 'psbt' parameter."))?;
+    let res = control.import_psbt(psbt)?;
+
+    Ok(serde_json::json!(&res))
+}
+
+fn analyze_psbt(control: &DaemonControl, params: Params) -> Result<serde_json::Value, Error> {
+    let psbt: Psbt = params
+        .get(0, "psbt")
+        .ok_or_else(|| Error::invalid_params("Missing 'psbt' parameter."))?
+        .as_str()
+        .and_then(|s| psbt_v2::from_base64(s).ok())
+        .ok_or_else(|| Error::invalid_params("Invalid 'psbt' parameter."))?;
+    let mut key_aliases = HashMap::new();
+    if let Some(aliases) = params.get(1, "key_aliases") {
+        for (fg, alias) in aliases
+            .as_object()
+            .ok_or_else(|| Error::invalid_params("Invalid 'key_aliases' parameter."))?
+            .iter()
+        {
+            let fg = bip32::Fingerprint::from_str(fg).map_err(|_| {
+                Error::invalid_params(format!(
+                    "Invalid 'key_aliases.{}' parameter: must be a key fingerprint",
+                    fg
+                ))
+            })?;
+            let alias = alias.as_str().ok_or_else(|| {
+                Error::invalid_params(format!("Invalid 'key_aliases.{}' value.", fg))
+            })?;
+            key_aliases.insert(fg, alias.to_string());
+        }
+    }
+    let res = control.analyze_psbt(&psbt, key_aliases);
+
+    Ok(serde_json::json!(&res))
+}
+
 fn delete_spend(control: &DaemonControl, params: Params) -> Result<serde_json::Value, Error> {
     let txid = params
         .get(0, "txid")
@@ -98,6 +307,23 @@ fn broadcast_spend(control: &DaemonControl, params: Params) -> Result<serde_json
     Ok(serde_json::json!({}))
 }
 
+fn broadcast_package(control: &DaemonControl, params: Params) -> Result<serde_json::Value, Error> {
+    let txids = params
+        .get(0, "txids")
+        .ok_or_else(|| Error::invalid_params("Missing 'txids' parameter."))?
+        .as_array()
+        .and_then(|arr| {
+            arr.iter()
+                .map(|entry| entry.as_str().and_then(|s| bitcoin::Txid::from_str(s).ok()))
+                .collect::<Option<Vec<bitcoin::Txid>>>()
+        })
+        .filter(|txids| !txids.is_empty())
+        .ok_or_else(|| Error::invalid_params("Invalid 'txids' parameter."))?;
+    control.broadcast_package(&txids)?;
+
+    Ok(serde_json::json!({}))
+}
+
 fn rbf_psbt(control: &DaemonControl, params: Params) -> Result<serde_json::Value, Error> {
     let txid = params
         .get(0, "txid")
@@ -110,16 +336,48 @@ fn rbf_psbt(control: &DaemonControl, params: Params) -> Result<serde_json::Value
         .ok_or_else(|| Error::invalid_params("Missing 'is_cancel' parameter."))?
         .as_bool()
         .ok_or_else(|| Error::invalid_params("Invalid 'is_cancel' parameter."))?;
-    let feerate_vb: Option<u64> = if let Some(feerate) = params.get(2, "feerate") {
-        Some(
-            feerate
-                .as_u64()
-                .ok_or_else(|| Error::invalid_params("Invalid 'feerate' parameter."))?,
-        )
-    } else {
-        None
-    };
-    let res = control.rbf_psbt(&txid, is_cancel, feerate_vb)?;
+    let feerate: Option<Feerate> = params
+        .get(2, "feerate")
+        .map(|fr| {
+            parse_feerate(fr).ok_or_else(|| Error::invalid_params("Invalid 'feerate' parameter."))
+        })
+        .transpose()?;
+    let res = control.rbf_psbt(&txid, is_cancel, feerate)?;
+    Ok(serde_json::json!(&res))
+}
+
+fn create_cpfp(control: &DaemonControl, params: Params) -> Result<serde_json::Value, Error> {
+    let outpoint = params
+        .get(0, "outpoint")
+        .ok_or_else(|| Error::invalid_params("Missing 'outpoint' parameter."))?
+        .as_str()
+        .and_then(|s| bitcoin::OutPoint::from_str(s).ok())
+        .ok_or_else(|| Error::invalid_params("Invalid 'outpoint' parameter."))?;
+    let feerate = params
+        .get(1, "feerate")
+        .ok_or_else(|| Error::invalid_params("Missing 'feerate' parameter."))
+        .and_then(|fr| {
+            parse_feerate(fr).ok_or_else(|| Error::invalid_params("Invalid 'feerate' parameter."))
+        })?;
+    let extra_outpoints = params
+        .get(2, "extra_outpoints")
+        .map(|outpoints| {
+            outpoints
+                .as_array()
+                .and_then(|arr| {
+                    arr.iter()
+                        .map(|entry| {
+                            entry
+                                .as_str()
+                                .and_then(|e| bitcoin::OutPoint::from_str(e).ok())
+                        })
+                        .collect::<Option<Vec<bitcoin::OutPoint>>>()
+                })
+                .ok_or_else(|| Error::invalid_params("Invalid 'extra_outpoints' parameter."))
+        })
+        .transpose()?
+        .unwrap_or_default();
+    let res = control.create_cpfp(&outpoint, &extra_outpoints, feerate)?;
     Ok(serde_json::json!(&res))
 }
 
@@ -230,7 +488,7 @@ fn list_spendtxs(
     control: &DaemonControl,
     params: Option<Params>,
 ) -> Result<serde_json::Value, Error> {
-    let txids: Option<Vec<bitcoin::Txid>> = if let Some(p) = params {
+    let txids: Option<Vec<bitcoin::Txid>> = if let Some(p) = &params {
         let tx_ids = p.get(0, "txids");
         if let Some(ids) = tx_ids {
             let ids: Vec<Txid> = ids
@@ -249,7 +507,25 @@ fn list_spendtxs(
         None
     };
 
-    Ok(serde_json::json!(&control.list_spend(txids)?))
+    let psbt_version = get_opt_u32(&params, 1, "psbt_version")?
+        .map(|v| {
+            PsbtVersion::from_number(v.into())
+                .ok_or_else(|| Error::invalid_params("Invalid 'psbt_version' parameter."))
+        })
+        .transpose()?
+        .unwrap_or_default();
+
+    let res = control.list_spend(txids)?;
+    let mut json_res = serde_json::json!(&res);
+    // PSBTs are serialized as version 0 by default. Re-encode them if another version was asked.
+    if psbt_version != PsbtVersion::V0 {
+        if let Some(entries) = json_res["spend_txs"].as_array_mut() {
+            for (entry, json_entry) in res.spend_txs.iter().zip(entries.iter_mut()) {
+                json_entry["psbt"] = psbt_v2::to_base64(&entry.psbt, psbt_version).into();
+            }
+        }
+    }
+    Ok(json_res)
 }
 
 fn list_transactions(control: &DaemonControl, params: Params) -> Result<serde_json::Value, Error> {
@@ -285,12 +561,13 @@ fn create_recovery(control: &DaemonControl, params: Params) -> Result<serde_json
         .as_str()
         .and_then(|s| bitcoin::Address::from_str(s).ok())
         .ok_or_else(|| Error::invalid_params("Invalid 'address' parameter."))?;
-    let feerate: u64 = params
+    let feerate = params
         .get(1, "feerate")
-        .ok_or_else(|| Error::invalid_params("Missing 'feerate' parameter."))?
-        .as_u64()
-        .ok_or_else(|| Error::invalid_params("Invalid 'feerate' parameter."))?;
-    let timelock: Option<u16> = params
+        .ok_or_else(|| Error::invalid_params("Missing 'feerate' parameter."))
+        .and_then(|fr| {
+            parse_feerate(fr).ok_or_else(|| Error::invalid_params("Invalid 'feerate' parameter."))
+        })?;
+    let timelock: Option<u32> = params
         .get(2, "timelock")
         .map(|tl| {
             tl.as_u64()
@@ -298,16 +575,35 @@ fn create_recovery(control: &DaemonControl, params: Params) -> Result<serde_json
                 .ok_or_else(|| Error::invalid_params("Invalid 'timelock' parameter."))
         })
         .transpose()?;
+    let absolute_timelock: Option<u32> = params
+        .get(3, "absolute_timelock")
+        .map(|tl| {
+            tl.as_u64()
+                .and_then(|tl| tl.try_into().ok())
+                .ok_or_else(|| Error::invalid_params("Invalid 'absolute_timelock' parameter."))
+        })
+        .transpose()?;
+
+    let truc = params
+        .get(4, "truc")
+        .map(|truc| {
+            truc.as_bool()
+                .ok_or_else(|| Error::invalid_params("Invalid 'truc' parameter."))
+        })
+        .transpose()?
+        .unwrap_or(false);
 
-    let res = control.create_recovery(address, feerate, timelock)?;
+    let res = control.create_recovery(address, feerate, timelock, absolute_timelock, truc)?;
     Ok(serde_json::json!(&res))
 }
 
-fn update_labels(control: &DaemonControl, params: Params) -> Result<serde_json::Value, Error> {
+// Parse a map of labelled items to their label (or `null` to delete it).
+fn labels_param(
+    control: &DaemonControl,
+    labels: &serde_json::Value,
+) -> Result<HashMap<LabelItem, Option<String>>, Error> {
     let mut items = HashMap::new();
-    for (item, value) in params
-        .get(0, "labels")
-        .ok_or_else(|| Error::invalid_params("Missing 'labels' parameter."))?
+    for (item, value) in labels
         .as_object()
         .ok_or_else(|| Error::invalid_params("Invalid 'labels' parameter."))?
         .iter()
@@ -330,6 +626,14 @@ fn update_labels(control: &DaemonControl, params: Params) -> Result<serde_json::
             })?;
         items.insert(item, value);
     }
+    Ok(items)
+}
+
+fn update_labels(control: &DaemonControl, params: Params) -> Result<serde_json::Value, Error> {
+    let labels = params
+        .get(0, "labels")
+        .ok_or_else(|| Error::invalid_params("Missing 'labels' parameter."))?;
+    let items = labels_param(control, labels)?;
 
     control.update_labels(&items);
     Ok(serde_json::json!({}))
@@ -373,6 +677,18 @@ pub fn handle_request(control: &mut DaemonControl, req: Request) -> Result<Respo
                 .ok_or_else(|| Error::invalid_params("Missing 'txid' parameter."))?;
             broadcast_spend(control, params)?
         }
+        "broadcastpackage" => {
+            let params = req
+                .params
+                .ok_or_else(|| Error::invalid_params("Missing 'txids' parameter."))?;
+            broadcast_package(control, params)?
+        }
+        "createcpfp" => {
+            let params = req.params.ok_or_else(|| {
+                Error::invalid_params("Missing 'outpoint' and 'feerate' parameters.")
+            })?;
+            create_cpfp(control, params)?
+        }
         "createrecovery" => {
             let params = req.params.ok_or_else(|| {
                 Error::invalid_params("Missing 'address' and 'feerate' parameters.")
@@ -387,6 +703,18 @@ pub fn handle_request(control: &mut DaemonControl, req: Request) -> Result<Respo
             })?;
             create_spend(control, params)?
         }
+        "importpsbt" => {
+            let params = req
+                .params
+                .ok_or_else(|| Error::invalid_params("Missing 'psbt' parameter."))?;
+            import_psbt(control, params)?
+        }
+        "analyzepsbt" => {
+            let params = req
+                .params
+                .ok_or_else(|| Error::invalid_params("Missing 'psbt' parameter."))?;
+            analyze_psbt(control, params)?
+        }
         "delspendtx" => {
             let params = req
                 .params
@@ -399,7 +727,10 @@ pub fn handle_request(control: &mut DaemonControl, req: Request) -> Result<Respo
             })?;
             rbf_psbt(control, params)?
         }
+        "estimatefees" => serde_json::json!(&control.estimate_fees()),
         "getinfo" => serde_json::json!(&control.get_info()),
+        "explainpolicy" => serde_json::json!(&control.explain_policy()),
+        "getbsmsrecord" => get_bsms_record(control, req.params)?,
         "getnewaddress" => serde_json::json!(&control.get_new_address()),
         "listcoins" => {
             let params = req.params;
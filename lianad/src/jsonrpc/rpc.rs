@@ -162,8 +162,14 @@ impl From<commands::CommandError> for Error {
             | commands::CommandError::AlreadyRescanning
             | commands::CommandError::InvalidDerivationIndex
             | commands::CommandError::RbfError(..)
+            | commands::CommandError::CpfpError(..)
             | commands::CommandError::EmptyFilterList
-            | commands::CommandError::RecoveryNotAvailable => {
+            | commands::CommandError::RecoveryNotAvailable
+            | commands::CommandError::UnknownAbsoluteTimelock(..)
+            | commands::CommandError::InvalidRelativeTimelock(..)
+            | commands::CommandError::ConflictingTimelocks
+            | commands::CommandError::ForeignInput(..)
+            | commands::CommandError::InputMismatch(..) => {
                 Error::new(ErrorCode::InvalidParams, e.to_string())
             }
             commands::CommandError::RescanTrigger(..) => {
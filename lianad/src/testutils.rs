@@ -6,7 +6,7 @@ use crate::{
     },
     DaemonControl, DaemonHandle,
 };
-use liana::descriptors;
+use liana::{descriptors, spend::Feerate};
 
 use std::convert::TryInto;
 use std::{
@@ -24,6 +24,8 @@ use miniscript::{
 
 pub struct DummyBitcoind {
     pub txs: HashMap<Txid, (Transaction, Option<Block>)>,
+    pub fee_estimates: HashMap<u16, Feerate>,
+    pub mempool_entries: HashMap<Txid, MempoolEntry>,
 }
 
 impl DummyBitcoind {}
@@ -32,6 +34,8 @@ impl DummyBitcoind {
     pub fn new() -> Self {
         Self {
             txs: HashMap::new(),
+            fee_estimates: HashMap::new(),
+            mempool_entries: HashMap::new(),
         }
     }
 }
@@ -112,6 +116,10 @@ impl BitcoinInterface for DummyBitcoind {
         todo!()
     }
 
+    fn submit_package(&self, _: &[bitcoin::Transaction]) -> Result<(), String> {
+        todo!()
+    }
+
     fn start_rescan(&mut self, _: &descriptors::LianaDescriptor, _: u32) -> Result<(), String> {
         todo!()
     }
@@ -128,6 +136,10 @@ impl BitcoinInterface for DummyBitcoind {
         None
     }
 
+    fn median_time_past(&self, _: i32) -> Option<u32> {
+        None
+    }
+
     fn wallet_transaction(
         &self,
         txid: &bitcoin::Txid,
@@ -139,8 +151,15 @@ impl BitcoinInterface for DummyBitcoind {
         Vec::new()
     }
 
-    fn mempool_entry(&self, _: &bitcoin::Txid) -> Option<MempoolEntry> {
-        None
+    fn mempool_entry(&self, txid: &bitcoin::Txid) -> Option<MempoolEntry> {
+        self.mempool_entries.get(txid).cloned()
+    }
+
+    fn fee_estimates(&self, targets: &[u16]) -> Vec<Option<Feerate>> {
+        targets
+            .iter()
+            .map(|target| self.fee_estimates.get(target).copied())
+            .collect()
     }
 }
 
@@ -151,6 +170,7 @@ struct DummyDbState {
     coins: HashMap<bitcoin::OutPoint, Coin>,
     txs: HashMap<bitcoin::Txid, bitcoin::Transaction>,
     spend_txs: HashMap<bitcoin::Txid, (Psbt, Option<u32>)>,
+    labels: HashMap<String, String>,
     timestamp: u32,
     rescan_timestamp: Option<u32>,
     last_poll_timestamp: Option<u32>,
@@ -185,6 +205,7 @@ impl DummyDatabase {
                 coins: HashMap::new(),
                 txs: HashMap::new(),
                 spend_txs: HashMap::new(),
+                labels: HashMap::new(),
                 timestamp: now,
                 rescan_timestamp: None,
                 last_poll_timestamp: None,
@@ -380,13 +401,14 @@ impl DatabaseConnection for DummyDatabase {
             .collect()
     }
 
-    fn store_spend(&mut self, psbt: &Psbt) {
+    fn store_spend(&mut self, psbt: &Psbt, labels: &HashMap<LabelItem, Option<String>>) {
         let txid = psbt.unsigned_tx.compute_txid();
         self.db
             .write()
             .unwrap()
             .spend_txs
             .insert(txid, (psbt.clone(), None));
+        self.update_labels(labels);
     }
 
     fn spend_tx(&mut self, txid: &bitcoin::Txid) -> Option<Psbt> {
@@ -437,12 +459,26 @@ impl DatabaseConnection for DummyDatabase {
         self.db.write().unwrap().last_poll_timestamp = Some(timestamp);
     }
 
-    fn update_labels(&mut self, _items: &HashMap<LabelItem, Option<String>>) {
-        todo!()
+    fn update_labels(&mut self, items: &HashMap<LabelItem, Option<String>>) {
+        let labels = &mut self.db.write().unwrap().labels;
+        for (item, value) in items {
+            if let Some(value) = value {
+                labels.insert(item.to_string(), value.clone());
+            } else {
+                labels.remove(&item.to_string());
+            }
+        }
     }
 
-    fn labels(&mut self, _items: &HashSet<LabelItem>) -> HashMap<String, String> {
-        todo!()
+    fn labels(&mut self, items: &HashSet<LabelItem>) -> HashMap<String, String> {
+        let labels = &self.db.read().unwrap().labels;
+        items
+            .iter()
+            .filter_map(|item| {
+                let item = item.to_string();
+                labels.get(&item).map(|value| (item, value.clone()))
+            })
+            .collect()
     }
 
     fn list_txids(&mut self, start: u32, end: u32, limit: u64) -> Vec<bitcoin::Txid> {
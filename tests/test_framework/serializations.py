@@ -680,23 +680,39 @@ class PSBT:
         self.o = o if o is not None else []
         self.tx = None
 
+    def version(self):
+        if PSBT_GLOBAL_VERSION not in self.g.map:
+            return 0
+        return struct.unpack("<I", self.g.map[PSBT_GLOBAL_VERSION])[0]
+
     def deserialize(self, f):
         assert f.read(5) == b"psbt\xff"
         self.g = from_binary(PSBTMap, f)
-        assert 0 in self.g.map
-        self.tx = from_binary(CTransaction, self.g.map[0])
-        self.i = [from_binary(PSBTMap, f) for _ in self.tx.vin]
-        self.o = [from_binary(PSBTMap, f) for _ in self.tx.vout]
+        if self.version() == 2:
+            # BIP370 PSBTs don't contain the unsigned transaction.
+            assert 0 not in self.g.map
+            self.tx = None
+            n_inputs = deser_compact_size(BytesIO(self.g.map[PSBT_GLOBAL_INPUT_COUNT]))
+            n_outputs = deser_compact_size(BytesIO(self.g.map[PSBT_GLOBAL_OUTPUT_COUNT]))
+        else:
+            assert 0 in self.g.map
+            self.tx = from_binary(CTransaction, self.g.map[0])
+            n_inputs, n_outputs = len(self.tx.vin), len(self.tx.vout)
+        self.i = [from_binary(PSBTMap, f) for _ in range(n_inputs)]
+        self.o = [from_binary(PSBTMap, f) for _ in range(n_outputs)]
         return self
 
     def serialize(self):
         assert isinstance(self.g, PSBTMap)
         assert isinstance(self.i, list) and all(isinstance(x, PSBTMap) for x in self.i)
         assert isinstance(self.o, list) and all(isinstance(x, PSBTMap) for x in self.o)
-        assert 0 in self.g.map
-        tx = from_binary(CTransaction, self.g.map[0])
-        assert len(tx.vin) == len(self.i)
-        assert len(tx.vout) == len(self.o)
+        if self.version() == 2:
+            assert 0 not in self.g.map
+        else:
+            assert 0 in self.g.map
+            tx = from_binary(CTransaction, self.g.map[0])
+            assert len(tx.vin) == len(self.i)
+            assert len(tx.vout) == len(self.o)
 
         psbt = [x.serialize() for x in [self.g] + self.i + self.o]
         return b"psbt\xff" + b"".join(psbt)
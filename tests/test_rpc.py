@@ -6,8 +6,12 @@ import time
 from fixtures import *
 from test_framework.serializations import (
     PSBT,
+    PSBT_GLOBAL_UNSIGNED_TX,
     PSBT_IN_PARTIAL_SIG,
     PSBT_IN_NON_WITNESS_UTXO,
+    PSBT_IN_PREVIOUS_TXID,
+    PSBT_IN_WITNESS_UTXO,
+    PSBT_OUT_SCRIPT,
 )
 from test_framework.utils import (
     wait_for,
@@ -38,6 +42,47 @@ def test_getinfo(lianad):
     assert res["last_poll_timestamp"] > last_poll_timestamp
 
 
+def test_estimatefees(lianad):
+    estimates = lianad.rpc.estimatefees()["estimates"]
+    assert [e["target"] for e in estimates] == [1, 6, 144]
+    for estimate in estimates:
+        assert 1 <= estimate["feerate"] <= 1_000
+        # There usually isn't enough data on regtest for the backend to estimate fees, in which
+        # case the minimum feerate is given.
+        if not estimate["estimated"]:
+            assert estimate["feerate"] == 1
+
+
+def test_explainpolicy(lianad):
+    res = lianad.rpc.explainpolicy()
+    assert res["is_taproot"] == USE_TAPROOT
+    primary, recovery = res["paths"][0], res["paths"][1:]
+    assert primary["condition"] == {"type": "none"}
+    assert primary["threshold"] >= 1
+    assert all("fingerprint" in k and "origin_path" in k for k in primary["keys"])
+    assert len(recovery) > 0
+    assert recovery[0]["condition"]["type"] == "relative_blocks"
+    assert all(p["max_sat_weight"] >= primary["max_sat_weight"] for p in recovery)
+
+
+def test_getbsmsrecord(lianad):
+    first_addr = lianad.rpc.listaddresses(0, 1)["addresses"][0]["receive"]
+    res = lianad.rpc.getbsmsrecord()
+    lines = res["record"].split("\n")
+    assert lines[0] == "BSMS 1.0"
+    assert "/**" in lines[1] and "#" not in lines[1]
+    assert lines[2] == "/0/*,/1/*"
+    assert lines[3] == first_addr == res["first_address"]
+    assert lianad.rpc.getbsmsrecord("00")["record"] == res["record"]
+
+    # With a token the record is encrypted.
+    res = lianad.rpc.getbsmsrecord("a54044308ceac9b7")
+    assert "BSMS" not in res["record"]
+    assert res["first_address"] == first_addr
+    with pytest.raises(RpcError, match="Invalid 'token' parameter."):
+        lianad.rpc.getbsmsrecord("a540")
+
+
 def test_getaddress(lianad):
     res = lianad.rpc.getnewaddress()
     assert "address" in res
@@ -590,6 +635,141 @@ def test_update_spend(lianad, bitcoind):
     assert psbt_merged.i[0].map[PSBT_IN_PARTIAL_SIG][dummy_pk_b] == dummy_sig_b
 
 
+def test_update_spend_psbt_v2(lianad, bitcoind):
+    # Create a Spend PSBT and store it.
+    addr = lianad.rpc.getnewaddress()["address"]
+    bitcoind.rpc.sendtoaddress(addr, 0.2567)
+    wait_for(lambda: len(lianad.rpc.listcoins()["coins"]) > 0)
+    outpoints = [c["outpoint"] for c in lianad.rpc.listcoins()["coins"]]
+    destinations = {
+        bitcoind.rpc.getnewaddress(): 200_000,
+    }
+    res = lianad.rpc.createspend(destinations, outpoints, 6)
+    lianad.rpc.updatespend(res["psbt"])
+
+    # It can be exported as a version 2 PSBT.
+    list_res = lianad.rpc.listspendtxs(psbt_version=2)["spend_txs"]
+    assert len(list_res) == 1
+    psbt_v2 = PSBT.from_base64(list_res[0]["psbt"])
+    assert psbt_v2.version() == 2
+    assert PSBT_GLOBAL_UNSIGNED_TX not in psbt_v2.g.map
+    assert all(PSBT_IN_PREVIOUS_TXID in psbt_in.map for psbt_in in psbt_v2.i)
+    assert all(PSBT_OUT_SCRIPT in psbt_out.map for psbt_out in psbt_v2.o)
+    assert lianad.rpc.listspendtxs(psbt_version=0)["spend_txs"][0]["psbt"] == res["psbt"]
+    with pytest.raises(RpcError, match="Invalid 'psbt_version' parameter."):
+        lianad.rpc.listspendtxs(psbt_version=1)
+
+    # A signature added to the version 2 PSBT is merged into the stored one.
+    dummy_pk = bytes.fromhex(
+        "0375e00eb72e29da82b89367947f29ef34afb75e8654f6ea368e0acdfd92976b7c"
+    )
+    dummy_sig = bytes.fromhex(
+        "304402202b925395cfeaa0171a7a92982bb4891acc4a312cbe7691d8375d36796d5b570a0220378a8ab42832848e15d1aedded5fb360fedbdd6c39226144e527f0f1e19d539801"
+    )
+    psbt_v2.i[0].map[PSBT_IN_PARTIAL_SIG] = {dummy_pk: dummy_sig}
+    lianad.rpc.updatespend(psbt_v2.to_base64())
+    list_res = lianad.rpc.listspendtxs()["spend_txs"]
+    assert len(list_res) == 1
+    psbt = PSBT.from_base64(list_res[0]["psbt"])
+    assert psbt.version() == 0
+    assert psbt.i[0].map[PSBT_IN_PARTIAL_SIG][dummy_pk] == dummy_sig
+
+
+def test_analyze_psbt(lianad, bitcoind):
+    # Create a Spend PSBT for a confirmed coin of ours.
+    addr = lianad.rpc.getnewaddress()["address"]
+    txid = bitcoind.rpc.sendtoaddress(addr, 0.2567)
+    bitcoind.generate_block(1, wait_for_mempool=txid)
+    wait_for(
+        lambda: len(
+            [c for c in lianad.rpc.listcoins()["coins"] if c["block_height"] is not None]
+        )
+        > 0
+    )
+    outpoints = [c["outpoint"] for c in lianad.rpc.listcoins()["coins"]]
+    destinations = {
+        bitcoind.rpc.getnewaddress(): 200_000,
+    }
+    res = lianad.rpc.createspend(destinations, outpoints, 6)
+    psbt = PSBT.from_base64(res["psbt"])
+
+    # The input is ours, and is missing a signature for the primary path.
+    prim_fg = xpub_fingerprint(lianad.signer.primary_hd)
+    report = lianad.rpc.analyzepsbt(res["psbt"], key_aliases={prim_fg: "Alice"})
+    assert report["txid"] == psbt.tx.txid().hex()
+    assert report["signals_rbf"]
+    assert len(report["inputs"]) == 1
+    ours = report["inputs"][0]["ours"]
+    assert ours is not None
+    assert report["inputs"][0]["value"] == 25_670_000
+    assert len(ours["spending_paths"]) == 1
+    primary = ours["spending_paths"][0]
+    assert primary["path"] == {"type": "primary"}
+    assert primary["missing_signatures"] == 1
+    assert primary["unsigned_keys"] == [{"fingerprint": prim_fg, "alias": "Alice"}]
+    assert sorted(o["kind"] for o in report["outputs"]) == ["change", "external"]
+    assert report["fee"] == 25_670_000 - sum(o["value"] for o in report["outputs"])
+    assert report["feerate"] >= 6
+    assert report["warnings"] == []
+
+    # Once signed, no signature is missing anymore. The PSBT may also be given as version 2.
+    signed_psbt = lianad.signer.sign_psbt(psbt)
+    lianad.rpc.updatespend(signed_psbt.to_base64())
+    psbt_v2 = lianad.rpc.listspendtxs(psbt_version=2)["spend_txs"][0]["psbt"]
+    report = lianad.rpc.analyzepsbt(psbt_v2)
+    primary = report["inputs"][0]["ours"]["spending_paths"][0]
+    assert primary["missing_signatures"] == 0
+    assert primary["unsigned_keys"] == []
+
+    # Invalid parameters are rejected.
+    with pytest.raises(RpcError, match="Invalid 'psbt' parameter."):
+        lianad.rpc.analyzepsbt("not a psbt")
+    with pytest.raises(RpcError, match="must be a key fingerprint"):
+        lianad.rpc.analyzepsbt(res["psbt"], key_aliases={"aabb": "Bob"})
+
+
+def test_import_psbt(lianad, bitcoind):
+    # Receive a coin, and create a PSBT spending it using another wallet. It doesn't contain any
+    # information about the coin it spends.
+    addr = lianad.rpc.getnewaddress()["address"]
+    bitcoind.rpc.sendtoaddress(addr, 0.2567)
+    wait_for(lambda: len(lianad.rpc.listcoins()["coins"]) > 0)
+    coin = lianad.rpc.listcoins()["coins"][0]
+    op_txid, op_vout = coin["outpoint"].split(":")
+    dest_addr = bitcoind.rpc.getnewaddress()
+    external_psbt = bitcoind.rpc.createpsbt(
+        [{"txid": op_txid, "vout": int(op_vout)}], [{dest_addr: 0.2566}]
+    )
+    psbt = PSBT.from_base64(external_psbt)
+    assert PSBT_IN_WITNESS_UTXO not in psbt.i[0].map
+    spend_txid = psbt.tx.txid().hex()
+
+    # Once imported, it's stored as a draft with the information needed to sign it.
+    res = lianad.rpc.importpsbt(external_psbt)
+    imported = PSBT.from_base64(res["psbt"])
+    assert PSBT_IN_WITNESS_UTXO in imported.i[0].map
+    list_res = lianad.rpc.listspendtxs()["spend_txs"]
+    assert len(list_res) == 1
+    assert list_res[0]["psbt"] == res["psbt"]
+
+    # It can be signed and broadcast.
+    signed_psbt = lianad.signer.sign_psbt(imported)
+    lianad.rpc.updatespend(signed_psbt.to_base64())
+    lianad.rpc.broadcastspend(spend_txid)
+    wait_for(lambda: spend_txid in bitcoind.rpc.getrawmempool())
+
+    # A PSBT spending a coin which isn't ours is rejected.
+    foreign_coin = bitcoind.rpc.listunspent()[0]
+    foreign_psbt = bitcoind.rpc.createpsbt(
+        [{"txid": foreign_coin["txid"], "vout": foreign_coin["vout"]}],
+        [{dest_addr: 0.001}],
+    )
+    with pytest.raises(RpcError, match="is not a coin of this wallet"):
+        lianad.rpc.importpsbt(foreign_psbt)
+    with pytest.raises(RpcError, match="Invalid 'psbt' parameter."):
+        lianad.rpc.importpsbt("not a psbt")
+
+
 def test_broadcast_spend(lianad, bitcoind):
     # Create a new coin and a spending tx for it.
     addr = lianad.rpc.getnewaddress()["address"]
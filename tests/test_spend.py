@@ -514,6 +514,142 @@ def test_coin_selection_changeless(lianad, bitcoind):
     assert psbt.tx.vin[0].prevout.hash == txid_a
 
 
+def test_coin_selection_strategies(lianad, bitcoind):
+    """We can choose the coin selection strategy."""
+    # Get an old small coin, then a more recent large one.
+    txid_old = bitcoind.rpc.sendtoaddress(lianad.rpc.getnewaddress()["address"], 0.0005)
+    bitcoind.generate_block(10, wait_for_mempool=txid_old)
+    txid_new = bitcoind.rpc.sendtoaddress(lianad.rpc.getnewaddress()["address"], 0.01)
+    bitcoind.generate_block(1, wait_for_mempool=txid_new)
+    wait_for(lambda: len(lianad.rpc.listcoins(["confirmed"])["coins"]) == 2)
+    txid_old = uint256_from_str(bytes.fromhex(txid_old)[::-1])
+    txid_new = uint256_from_str(bytes.fromhex(txid_new)[::-1])
+    destinations = {bitcoind.rpc.getnewaddress(): 30_000}
+
+    # Spend the oldest coin first, or the largest one.
+    res = lianad.rpc.createspend(
+        destinations=destinations,
+        outpoints=[],
+        feerate=1,
+        coin_selection="oldest_first",
+    )
+    psbt = PSBT.from_base64(res["psbt"])
+    assert [i.prevout.hash for i in psbt.tx.vin] == [txid_old]
+    res = lianad.rpc.createspend(
+        destinations=destinations,
+        outpoints=[],
+        feerate=1,
+        coin_selection="largest_first",
+    )
+    psbt = PSBT.from_base64(res["psbt"])
+    assert [i.prevout.hash for i in psbt.tx.vin] == [txid_new]
+
+    # Consolidate coins when minimizing waste with a long-term feerate higher than the feerate.
+    res = lianad.rpc.createspend(
+        destinations=destinations,
+        outpoints=[],
+        feerate=1,
+        coin_selection="waste",
+        long_term_feerate=100,
+    )
+    assert len(PSBT.from_base64(res["psbt"]).tx.vin) == 2
+
+    # Invalid parameters.
+    with pytest.raises(RpcError, match="Invalid 'coin_selection' parameter."):
+        lianad.rpc.createspend(
+            destinations=destinations, outpoints=[], feerate=1, coin_selection="random"
+        )
+    with pytest.raises(RpcError, match="only valid with the 'waste' coin selection"):
+        lianad.rpc.createspend(
+            destinations=destinations,
+            outpoints=[],
+            feerate=1,
+            coin_selection="privacy",
+            long_term_feerate=100,
+        )
+
+
+def test_privacy_coin_selection(lianad, bitcoind):
+    """The privacy coin selection avoids linking coins with unrelated labels."""
+    # Get two coins we label as coming from the same counterparty, and an unlabelled one.
+    outpoints = []
+    for amount in (0.0002, 0.0002, 0.0003):
+        addr = lianad.rpc.getnewaddress()["address"]
+        txid = bitcoind.rpc.sendtoaddress(addr, amount)
+        bitcoind.generate_block(1, wait_for_mempool=txid)
+        wait_for(
+            lambda: len(lianad.rpc.listcoins(["confirmed"])["coins"])
+            == len(outpoints) + 1
+        )
+        coin = next(
+            c
+            for c in lianad.rpc.listcoins(["confirmed"])["coins"]
+            if c["outpoint"] not in outpoints
+        )
+        outpoints.append(coin["outpoint"])
+    lianad.rpc.updatelabels({outpoints[0]: "Alice", outpoints[1]: "Alice"})
+
+    # No coin is enough on its own. Spend the two coins which are already related.
+    destinations = {bitcoind.rpc.getnewaddress(): 35_000}
+    res = lianad.rpc.createspend(
+        destinations=destinations, outpoints=[], feerate=1, coin_selection="privacy"
+    )
+    psbt = PSBT.from_base64(res["psbt"])
+    spent = {f"{i.prevout.hash:064x}:{i.prevout.n}" for i in psbt.tx.vin}
+    assert spent == set(outpoints[:2])
+    assert len(res["warnings"]) == 0
+
+    # When unrelated coins must be spent together, we are warned about it.
+    destinations = {bitcoind.rpc.getnewaddress(): 60_000}
+    res = lianad.rpc.createspend(
+        destinations=destinations, outpoints=[], feerate=1, coin_selection="privacy"
+    )
+    assert len(PSBT.from_base64(res["psbt"]).tx.vin) == 3
+    assert len(res["warnings"]) == 1
+    assert "links 2 such groups together" in res["warnings"][0]
+
+
+def test_data_and_script_outputs(lianad, bitcoind):
+    """We can embed data in a transaction and pay to a raw script."""
+    txid = bitcoind.rpc.sendtoaddress(lianad.rpc.getnewaddress()["address"], 0.01)
+    bitcoind.generate_block(1, wait_for_mempool=txid)
+    wait_for(lambda: len(lianad.rpc.listcoins(["confirmed"])["coins"]) == 1)
+
+    # Pay to an external address along with a commitment, and to a raw P2WSH script below the
+    # 5k sats minimum we otherwise enforce.
+    data = "deadbeef" * 8
+    script = "0020" + "00" * 32
+    destinations = {
+        bitcoind.rpc.getnewaddress(): 100_000,
+        f"data({data})": 0,
+        f"raw({script})": 1_000,
+    }
+    res = lianad.rpc.createspend(destinations, [], 2)
+    psbt = PSBT.from_base64(res["psbt"])
+    scripts = {o.scriptPubKey.hex(): o.nValue for o in psbt.tx.vout}
+    assert scripts[f"6a20{data}"] == 0
+    assert scripts[script] == 1_000
+    txid = sign_and_broadcast_psbt(lianad, psbt)
+    wait_for(lambda: txid in bitcoind.rpc.getrawmempool())
+
+    # The data-carrier and raw-script outputs are kept when replacing the transaction.
+    res = lianad.rpc.rbfpsbt(txid, False, 3)
+    psbt = PSBT.from_base64(res["psbt"])
+    scripts = {o.scriptPubKey.hex(): o.nValue for o in psbt.tx.vout}
+    assert scripts[f"6a20{data}"] == 0
+    assert scripts[script] == 1_000
+
+    # Only a single data-carrier output, of standard size and without value, may be created.
+    with pytest.raises(RpcError, match="single data-carrier"):
+        lianad.rpc.createspend({"data(00)": 0, "data(01)": 0}, [], 2)
+    with pytest.raises(RpcError, match="maximum is 83 bytes"):
+        lianad.rpc.createspend({f"data({'00' * 81})": 0}, [], 2)
+    with pytest.raises(RpcError, match="Invalid output value"):
+        lianad.rpc.createspend({"data(00)": 1_000}, [], 2)
+    with pytest.raises(RpcError, match="is not standard"):
+        lianad.rpc.createspend({"raw(51)": 10_000}, [], 2)
+
+
 def test_sweep(lianad, bitcoind):
     """
     Test we can leverage the change_address parameter to partially or completely sweep
@@ -615,3 +751,202 @@ def test_tr_multisig_2_of_2_feerate_is_met(feerate, lianad_multisig_2_of_2, bitc
     spend_weight = res["weight"]
     assert spend_weight == 646
     assert spend_fee == math.ceil(646.0 / 4.0) * feerate
+
+
+def test_subtract_fee_from_outputs(lianad, bitcoind):
+    """The fee may be deducted from the value of the outputs."""
+    txid = bitcoind.rpc.sendtoaddress(lianad.rpc.getnewaddress()["address"], 0.01)
+    bitcoind.generate_block(1, wait_for_mempool=txid)
+    wait_for(lambda: len(lianad.rpc.listcoins(["confirmed"])["coins"]) == 1)
+
+    # Split the fee among two recipients, we pay exactly the value of the outputs.
+    addr_a, addr_b = bitcoind.rpc.getnewaddress(), bitcoind.rpc.getnewaddress()
+    destinations = {addr_a: 300_000, addr_b: 300_000}
+    res = lianad.rpc.createspend(
+        destinations=destinations,
+        outpoints=[],
+        feerate=2,
+        subtract_fee_from=[addr_a, addr_b],
+    )
+    psbt = PSBT.from_base64(res["psbt"])
+    values = sorted(o.nValue for o in psbt.tx.vout)
+    assert values[2] == 1_000_000 - 600_000
+    assert values[0] < 300_000 and values[1] - values[0] <= 1
+
+    # The fee may not be subtracted from an unknown destination.
+    with pytest.raises(RpcError, match="is not a destination of the transaction"):
+        lianad.rpc.createspend(
+            destinations=destinations,
+            outpoints=[],
+            feerate=2,
+            subtract_fee_from=[bitcoind.rpc.getnewaddress()],
+        )
+
+    # With the list form of the destinations, the fee is deducted from all the outputs paying to
+    # the given destinations.
+    destinations = [
+        {"destination": addr_a, "amount": 200_000},
+        {"destination": addr_a, "amount": 200_000},
+        {"destination": addr_b, "amount": 200_000},
+    ]
+    res = lianad.rpc.createspend(
+        destinations=destinations,
+        outpoints=[],
+        feerate=2,
+        subtract_fee_from=[addr_a],
+    )
+    psbt = PSBT.from_base64(res["psbt"])
+    values = [o.nValue for o in psbt.tx.vout]
+    assert values[0] < 200_000 and values[1] - values[0] <= 1
+    assert values[2] == 200_000 and values[3] == 1_000_000 - 600_000
+
+    # Individual outputs may also be marked, even if another one pays to the same destination.
+    destinations = [
+        {"destination": addr_a, "amount": 200_000},
+        {"destination": addr_a, "amount": 200_000, "subtract_fee": True},
+    ]
+    res = lianad.rpc.createspend(destinations=destinations, outpoints=[], feerate=2)
+    psbt = PSBT.from_base64(res["psbt"])
+    values = [o.nValue for o in psbt.tx.vout]
+    assert values[0] == 200_000 and values[1] < 200_000
+    assert values[2] == 1_000_000 - 400_000
+
+    # We can send the whole value of our coin to an address.
+    destinations = {addr_a: 1_000_000}
+    res = lianad.rpc.createspend(
+        destinations=destinations, outpoints=[], feerate=2, subtract_fee_from=[addr_a]
+    )
+    psbt = PSBT.from_base64(res["psbt"])
+    assert len(psbt.tx.vout) == 1
+    txid = sign_and_broadcast_psbt(lianad, psbt)
+    wait_for(lambda: txid in bitcoind.rpc.getrawmempool())
+    entry = bitcoind.rpc.getmempoolentry(txid)
+    fee = round(entry["fees"]["base"] * COIN)
+    assert psbt.tx.vout[0].nValue == 1_000_000 - fee
+    assert fee >= 2 * entry["vsize"]
+
+
+def test_ordered_outputs_with_labels(lianad, bitcoind):
+    """We can pay the same address twice, and label the outputs when creating the spend."""
+    txid = bitcoind.rpc.sendtoaddress(lianad.rpc.getnewaddress()["address"], 0.01)
+    bitcoind.generate_block(1, wait_for_mempool=txid)
+    wait_for(lambda: len(lianad.rpc.listcoins(["confirmed"])["coins"]) == 1)
+
+    addr = bitcoind.rpc.getnewaddress()
+    destinations = [
+        {"destination": addr, "amount": 200_000, "label": "Invoice 1"},
+        {"destination": addr, "amount": 300_000, "label": "Invoice 2"},
+    ]
+    res = lianad.rpc.createspend(destinations, [], 2)
+    psbt = PSBT.from_base64(res["psbt"])
+    assert [o.nValue for o in psbt.tx.vout[:2]] == [200_000, 300_000]
+    assert psbt.tx.vout[0].scriptPubKey == psbt.tx.vout[1].scriptPubKey
+
+    # The labels are returned along with the PSBT, and only stored once it is saved.
+    txid = psbt.tx.txid().hex()
+    outpoints = [f"{txid}:{i}" for i in range(len(psbt.tx.vout))]
+    labels = {
+        outpoints[0]: "Invoice 1",
+        outpoints[1]: "Invoice 2",
+    }
+    assert res["labels"] == labels
+    assert lianad.rpc.getlabels(outpoints)["labels"] == {}
+    lianad.rpc.updatespend(res["psbt"], res["labels"])
+    assert lianad.rpc.getlabels(outpoints)["labels"] == labels
+
+    with pytest.raises(RpcError, match="invalid or missing 'amount' field"):
+        lianad.rpc.createspend([{"destination": addr}], [], 2)
+
+
+def test_cpfp(lianad, bitcoind):
+    """We can bump the fee of an incoming payment by spending the coin it created."""
+    addr = lianad.rpc.getnewaddress()["address"]
+    deposit_txid = bitcoind.rpc.sendtoaddress(addr, 0.01)
+    wait_for(lambda: len(lianad.rpc.listcoins(["unconfirmed"])["coins"]) == 1)
+    outpoint = lianad.rpc.listcoins(["unconfirmed"])["coins"][0]["outpoint"]
+
+    # The target feerate must be higher than the one of the parent.
+    entry = bitcoind.rpc.getmempoolentry(deposit_txid)
+    parent_feerate = entry["fees"]["ancestor"] * COIN / entry["ancestorsize"]
+    with pytest.raises(RpcError, match="must be higher than the current feerate"):
+        lianad.rpc.createcpfp(outpoint, math.floor(parent_feerate))
+
+    # Create the child, spending the coin back to one of our change addresses.
+    target_feerate = math.ceil(parent_feerate) + 10
+    res = lianad.rpc.createcpfp(outpoint, target_feerate)
+    psbt = PSBT.from_base64(res["psbt"])
+    assert len(psbt.tx.vin) == 1 and len(psbt.tx.vout) == 1
+    child_txid = sign_and_broadcast_psbt(lianad, psbt)
+
+    # The package of the parent and the child pays the target feerate.
+    entry = bitcoind.rpc.getmempoolentry(child_txid)
+    package_feerate = entry["fees"]["ancestor"] * COIN / entry["ancestorsize"]
+    assert target_feerate <= package_feerate < target_feerate + 1
+
+    # A confirmed coin can't be used.
+    bitcoind.generate_block(1, wait_for_mempool=child_txid)
+    wait_for(lambda: len(lianad.rpc.listcoins(["confirmed"])["coins"]) == 1)
+    outpoint = lianad.rpc.listcoins(["confirmed"])["coins"][0]["outpoint"]
+    with pytest.raises(RpcError, match="is already confirmed"):
+        lianad.rpc.createcpfp(outpoint, target_feerate)
+
+
+def test_truc_spend(lianad, bitcoind):
+    """We can create TRUC transactions and bump their fee with a package."""
+    addr = lianad.rpc.getnewaddress()["address"]
+    txid = bitcoind.rpc.sendtoaddress(addr, 0.01)
+    bitcoind.generate_block(1, wait_for_mempool=txid)
+    wait_for(lambda: len(lianad.rpc.listcoins(["confirmed"])["coins"]) == 1)
+    outpoint = lianad.rpc.listcoins(["confirmed"])["coins"][0]["outpoint"]
+
+    # Create a TRUC self-send and broadcast it.
+    res = lianad.rpc.createspend(
+        destinations={}, outpoints=[outpoint], feerate=1, truc=True
+    )
+    psbt = PSBT.from_base64(res["psbt"])
+    assert psbt.tx.nVersion == 3
+    parent_txid = sign_and_broadcast_psbt(lianad, psbt)
+    assert bitcoind.rpc.getrawtransaction(parent_txid, True)["version"] == 3
+    wait_for(lambda: len(lianad.rpc.listcoins(["unconfirmed"])["coins"]) == 1)
+    change_outpoint = lianad.rpc.listcoins(["unconfirmed"])["coins"][0]["outpoint"]
+
+    # A non-TRUC transaction can't spend its unconfirmed output.
+    with pytest.raises(RpcError, match="TRUC topology restriction violated"):
+        lianad.rpc.createspend({}, [change_outpoint], 2)
+
+    # The child bumping its fee is TRUC too. Submit both as a package.
+    res = lianad.rpc.createcpfp(change_outpoint, 10)
+    psbt = PSBT.from_base64(res["psbt"])
+    assert psbt.tx.nVersion == 3
+    child_txid = psbt.tx.txid().hex()
+    lianad.rpc.updatespend(lianad.signer.sign_psbt(psbt).to_base64())
+    # Package relay is only available with bitcoind, on all networks since version 28.0.
+    if (
+        BITCOIN_BACKEND_TYPE is BitcoinBackendType.Bitcoind
+        and bitcoind.rpc.getnetworkinfo()["version"] >= 280000
+    ):
+        lianad.rpc.broadcastpackage([parent_txid, child_txid])
+    else:
+        lianad.rpc.broadcastspend(child_txid)
+    entry = bitcoind.rpc.getmempoolentry(child_txid)
+    assert entry["ancestorcount"] == 2
+    with pytest.raises(RpcError, match="Unknown spend transaction"):
+        lianad.rpc.broadcastpackage([parent_txid, "00" * 32])
+
+    # A TRUC transaction may pay no fee at all, in which case it can't be relayed on its own.
+    bitcoind.generate_block(1, wait_for_mempool=child_txid)
+    wait_for(lambda: len(lianad.rpc.listcoins(["confirmed"])["coins"]) == 1)
+    coin = lianad.rpc.listcoins(["confirmed"])["coins"][0]
+    outpoint = coin["outpoint"]
+    with pytest.raises(RpcError, match="Invalid feerate"):
+        lianad.rpc.createspend({}, [outpoint], 0)
+    res = lianad.rpc.createspend(
+        destinations={}, outpoints=[outpoint], feerate=0, truc=True
+    )
+    psbt = PSBT.from_base64(res["psbt"])
+    assert psbt.tx.nVersion == 3
+    assert sum(o.nValue for o in psbt.tx.vout) == coin["amount"]
+    txid = psbt.tx.txid().hex()
+    lianad.rpc.updatespend(lianad.signer.sign_psbt(psbt).to_base64())
+    with pytest.raises(RpcError):
+        lianad.rpc.broadcastspend(txid)